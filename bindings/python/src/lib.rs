@@ -27,7 +27,7 @@ fn serialize_splitter(py: Python, obj: &Option<PyObject>) -> PyResult<Vec<u8>> {
 fn deserialize_splitter(py: Python, bytes: Vec<u8>) -> PyResult<Option<PyObject>> {
     let pybytes = PyBytes::new(py, &bytes);
     let obj: &PyAny = py.import("pickle")?.call_method1("loads", (pybytes,))?;
-    Ok(obj.extract::<Option<_>>()?)
+    obj.extract::<Option<_>>()
 }
 
 fn get_resource(code: &str, name: &str) -> PyResult<impl Read> {
@@ -54,7 +54,7 @@ fn get_resource(code: &str, name: &str) -> PyResult<impl Read> {
     }
 
     // ... otherwise, request the data from the URL ...
-    let bytes = reqwest::blocking::get(&format!(
+    let bytes = reqwest::blocking::get(format!(
         "https://github.com/bminixhofer/nlprule/releases/download/{}/{}_{}",
         env!("CARGO_PKG_VERSION"),
         code,
@@ -172,7 +172,7 @@ impl SplitOn {
     fn new(split_chars: Option<Vec<&str>>) -> PyResult<Self> {
         Ok(SplitOn {
             split_chars: split_chars
-                .unwrap_or_else(Vec::new)
+                .unwrap_or_default()
                 .iter()
                 .map(|x| {
                     let chars: Vec<_> = x.chars().collect();
@@ -197,7 +197,7 @@ impl SplitOn {
             let mut start = 0;
 
             for (i, c) in text.char_indices() {
-                if self.split_chars.iter().any(|x| *x == c) {
+                if self.split_chars.contains(&c) {
                     let end = i + c.len_utf8();
                     sentences.push(&text[start..end]);
                     start = end;
@@ -283,7 +283,7 @@ impl PyTagger {
     /// Returns:
     ///     group_members (List[str]): The words in the dictionary with the same lemma.
     fn get_group_members(&self, lemma: &str) -> Vec<&str> {
-        self.tagger.get_group_members(&lemma.to_string())
+        self.tagger.get_group_members(lemma)
     }
 }
 
@@ -323,6 +323,21 @@ impl PyToken {
         self.token.char_span
     }
 
+    #[getter]
+    fn grapheme_span(&self) -> (usize, usize) {
+        self.token.grapheme_span
+    }
+
+    #[getter]
+    fn utf16_span(&self) -> (usize, usize) {
+        self.token.utf16_span
+    }
+
+    #[getter]
+    fn space_before(&self) -> &str {
+        &self.token.space_before
+    }
+
     #[getter]
     fn data(&self) -> Vec<(&str, &str)> {
         self.token
@@ -801,7 +816,7 @@ impl PyRules {
             let tokenizer = tokenizer.tokenizer();
 
             self.rules
-                .suggest(&sentence, &tokenizer)
+                .suggest(&sentence, tokenizer)
                 .into_iter()
                 .map(|x| PyCell::new(py, PySuggestion::from(x)))
                 .collect::<PyResult<Vec<_>>>()
@@ -826,7 +841,7 @@ impl PyRules {
                 for sentence in sentences.iter() {
                     let suggestions = self
                         .rules
-                        .suggest(&sentence, &tokenizer)
+                        .suggest(sentence, tokenizer)
                         .into_iter()
                         .map(|mut x| {
                             x.start += offset;
@@ -857,7 +872,7 @@ impl PyRules {
             let tokenizer = self.tokenizer.borrow(py);
             let tokenizer = tokenizer.tokenizer();
 
-            let suggestions = self.rules.suggest(&sentence, &tokenizer);
+            let suggestions = self.rules.suggest(&sentence, tokenizer);
             Ok(apply_suggestions(&sentence, &suggestions))
         })
     }
@@ -877,7 +892,7 @@ impl PyRules {
                 Ok(sentences
                     .iter()
                     .map(|x| {
-                        let suggestions = self.rules.suggest(&x, &tokenizer);
+                        let suggestions = self.rules.suggest(x, tokenizer);
                         apply_suggestions(x, &suggestions)
                     })
                     .collect::<Vec<_>>()
@@ -0,0 +1,94 @@
+use clap::Clap;
+use nlprule::tokenizer::Tokenizer;
+use std::time::Instant;
+
+#[derive(Clap)]
+#[clap(
+    version = "1.0",
+    author = "Benjamin Minixhofer <bminixhofer@gmail.com>"
+)]
+struct Opts {
+    #[clap(long, short)]
+    tokenizer: String,
+    /// Path to a text file. Its content is repeated until the text is at least `min-size` bytes long.
+    #[clap(long)]
+    text: String,
+    #[clap(long, default_value = "5000000")]
+    min_size: usize,
+    /// Part-of-speech tag to count in the tokens to compare matching tags by string and by ID.
+    #[clap(long, default_value = "SENT_END")]
+    tag: String,
+}
+
+fn main() {
+    env_logger::init();
+    let opts = Opts::parse();
+
+    let mut tokenizer = Tokenizer::new(&opts.tokenizer).unwrap();
+    let content = std::fs::read_to_string(&opts.text).unwrap();
+    assert!(!content.is_empty(), "text must not be empty");
+
+    let mut text = String::new();
+    while text.len() < opts.min_size {
+        text.push_str(&content);
+    }
+
+    println!("Text size: {} bytes", text.len());
+
+    // tagging without disambiguation, so the effect of caching the tags of repeated words is not hidden
+    let cache_tags = tokenizer.options().cache_tags;
+    for &cache in &[true, false] {
+        tokenizer.options_mut().cache_tags = cache;
+
+        let start = Instant::now();
+        let n_tokens = tokenizer.tokenize(&text).len();
+        println!(
+            "Tagging with cache_tags = {}: {} tokens in {:.2?}",
+            cache,
+            n_tokens,
+            start.elapsed()
+        );
+    }
+    tokenizer.options_mut().cache_tags = cache_tags;
+
+    let start = Instant::now();
+    let serial = tokenizer.tokenize_sentences(&text);
+    println!(
+        "Serial: {} sentences in {:.2?}",
+        serial.len(),
+        start.elapsed()
+    );
+
+    let start = Instant::now();
+    let parallel = tokenizer.tokenize_parallel(&text);
+    println!(
+        "Parallel: {} sentences in {:.2?}",
+        parallel.len(),
+        start.elapsed()
+    );
+
+    let tags = || serial.iter().flatten().flat_map(|x| x.word.tags.iter());
+
+    let start = Instant::now();
+    let count = tags().filter(|x| x.pos.as_str() == opts.tag).count();
+    println!(
+        "Matching {} by string: {} tags in {:.2?}",
+        opts.tag,
+        count,
+        start.elapsed()
+    );
+
+    let pos = tokenizer
+        .tagger()
+        .tag_store()
+        .id(&opts.tag)
+        .expect("tag must be known to the tagger");
+    let start = Instant::now();
+    let count = tags().filter(|x| x.pos == pos).count();
+    println!(
+        "Matching {} by ID: {} tags in {:.2?}",
+        opts.tag,
+        count,
+        start.elapsed()
+    );
+}
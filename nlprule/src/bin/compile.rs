@@ -1,9 +1,9 @@
 use clap::Clap;
-use nlprule::compile::{compile, BuildOptions};
+use nlprule::compile::{compile, BuildOptions, Error};
 
-fn main() {
+fn main() -> Result<(), Error> {
     env_logger::init();
     let opts = BuildOptions::parse();
 
-    compile(&opts);
+    compile(&opts)
 }
@@ -1,4 +1,7 @@
-use std::hash::{Hash, Hasher};
+use std::{
+    collections::HashSet,
+    hash::{Hash, Hasher},
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -9,12 +12,99 @@ use crate::{
         DisambiguationRule, MatchGraph, Rule,
     },
     rules::{Rules, RulesOptions},
-    tokenizer::{chunk, Tokenizer, TokenizerOptions},
+    tokenizer::{
+        chunk,
+        srx::{SrxRule, SrxSegmenter},
+        Tokenizer, TokenizerOptions,
+    },
     types::*,
-    utils::parallelism::MaybeParallelIterator,
 };
 
-use super::parse_structure::BuildInfo;
+#[cfg(feature = "rayon")]
+use crate::utils::parallelism::MaybeParallelIterator;
+
+use super::{parse_structure::BuildInfo, Error, LoadReport, RuleReport, RuleStatus};
+
+impl SrxSegmenter {
+    /// Reads the rules for the given language code from an SRX file. The language code is matched against
+    /// the `languagepattern` of the language maps. If the SRX header has `cascade="yes"` the rules of all matching
+    /// language maps are used in order, otherwise only those of the first matching one.
+    /// Rules with regular expressions which are not supported are skipped.
+    pub fn from_xml<P: AsRef<std::path::Path>>(path: P, lang_code: &str) -> Result<Self, Error> {
+        use log::warn;
+
+        let path = path.as_ref();
+        let xml = std::fs::read_to_string(path).map_err(|source| Error::XmlNotFound {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let parse_error = |message: String| Error::XmlParse {
+            path: path.to_path_buf(),
+            message,
+            line: None,
+            column: None,
+        };
+
+        let document = roxmltree::Document::parse(&xml).map_err(|x| parse_error(x.to_string()))?;
+        let root = document.root_element();
+
+        let cascade = root
+            .descendants()
+            .find(|x| x.has_tag_name("header"))
+            .and_then(|x| x.attribute("cascade"))
+            == Some("yes");
+
+        let mut rule_names = Vec::new();
+        for map in root.descendants().filter(|x| x.has_tag_name("languagemap")) {
+            let (pattern, name) = match (
+                map.attribute("languagepattern"),
+                map.attribute("languagerulename"),
+            ) {
+                (Some(pattern), Some(name)) => (pattern, name),
+                _ => {
+                    return Err(parse_error(
+                        "languagemap must have a pattern and a rule name".into(),
+                    ))
+                }
+            };
+
+            let regex = onig::Regex::new(&format!("^(?:{})$", pattern)).map_err(|x| {
+                parse_error(format!("invalid language pattern {:?}: {}", pattern, x))
+            })?;
+            if regex.is_match(lang_code) {
+                rule_names.push(name);
+                if !cascade {
+                    break;
+                }
+            }
+        }
+
+        let mut rules = Vec::new();
+        for name in rule_names {
+            let language_rules = root.descendants().filter(|x| {
+                x.has_tag_name("languagerule") && x.attribute("languagerulename") == Some(name)
+            });
+
+            for rule in language_rules.flat_map(|x| x.children().filter(|x| x.has_tag_name("rule")))
+            {
+                let text_of = |tag: &str| {
+                    rule.children()
+                        .find(|x| x.has_tag_name(tag))
+                        .and_then(|x| x.text())
+                        .unwrap_or("")
+                };
+                let is_break = rule.attribute("break") != Some("no");
+
+                match SrxRule::new(is_break, text_of("beforebreak"), text_of("afterbreak")) {
+                    Ok(rule) => rules.push(rule),
+                    Err(error) => warn!("skipping SRX rule of {}: {}", name, error),
+                }
+            }
+        }
+
+        Ok(SrxSegmenter::new(rules))
+    }
+}
 
 impl TextMatcher {
     pub fn new(matcher: Matcher, info: &mut BuildInfo) -> Self {
@@ -34,11 +124,15 @@ impl TextMatcher {
             } else {
                 let data: Vec<_> = info.tagger().word_store().iter().collect();
 
+                #[cfg(feature = "rayon")]
+                let data = data.into_maybe_par_iter();
+                #[cfg(not(feature = "rayon"))]
+                let data = data.into_iter();
+
                 let set: DefaultHashSet<u32> = data
-                    .into_maybe_par_iter()
                     .filter_map(|(word, id)| {
-                        if matcher.is_match(word.as_str(), &graph, None) {
-                            Some(*id)
+                        if matcher.is_match(word, &graph, None) {
+                            Some(id)
                         } else {
                             None
                         }
@@ -65,8 +159,8 @@ impl PosMatcher {
         let mut mask = vec![false; info.tagger().tag_store().len()];
         let graph = MatchGraph::default();
 
-        for (word, id) in info.tagger().tag_store().iter() {
-            mask[*id as usize] = matcher.is_match(word.as_str(), &graph, None);
+        for pos in info.tagger().tag_store().iter() {
+            mask[*pos.id() as usize] = matcher.is_match(pos.as_str(), &graph, None);
         }
 
         PosMatcher { mask }
@@ -78,11 +172,11 @@ impl Rules {
         path: P,
         build_info: &mut BuildInfo,
         options: RulesOptions,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         use log::warn;
         use std::collections::HashMap;
 
-        let rules = super::parse_structure::read_rules(path);
+        let rules = super::parse_structure::read_rules(path)?;
         let mut errors: HashMap<String, usize> = HashMap::new();
 
         let rules: Vec<_> = rules
@@ -152,73 +246,120 @@ impl Rules {
             warn!("Errors constructing Rules: {:#?}", &errors);
         }
 
-        Rules { rules }
+        Ok(Rules { rules })
     }
 }
 
 impl Tokenizer {
+    /// Creates a tokenizer from a disambiguation file. Fails with the first rule which can not be read or
+    /// compiled unless `options.allow_errors` is set, in which case these rules are skipped with a warning.
+    /// Rules which use unsupported features are skipped unless `options.strict` is set, in which case they are
+    /// treated like rules which can not be compiled. See [load_report][Tokenizer::load_report] for the status
+    /// of each rule.
     pub fn from_xml<P: AsRef<std::path::Path>>(
         path: P,
         build_info: &mut BuildInfo,
         chunker: Option<chunk::Chunker>,
         options: TokenizerOptions,
-    ) -> Result<Self, Box<dyn std::error::Error>> {
+    ) -> Result<Self, Error> {
         use log::warn;
 
-        let rules = super::parse_structure::read_disambiguation_rules(path);
-        let mut error = None;
+        let tokenizer = Tokenizer::from_xml_lenient(path, build_info, chunker, options)?;
+        let options = tokenizer.options();
+
+        for rule in &tokenizer.load_report.rules {
+            let is_error = match rule.status {
+                RuleStatus::Failed(_) => true,
+                RuleStatus::Skipped(_) => options.strict,
+                RuleStatus::Loaded | RuleStatus::Excluded => false,
+            };
+
+            if let (true, Some(error)) = (is_error, rule.error()) {
+                if options.allow_errors {
+                    warn!("Error constructing Disambiguator: {}", error)
+                } else {
+                    return Err(Error::Disambiguator(error));
+                }
+            }
+        }
 
-        let rules: Vec<_> = rules
-            .into_iter()
-            .filter_map(|x| match x {
-                Ok((rule_structure, group, _)) => {
-                    let id = rule_structure.id.as_ref().map_or_else(
-                        || {
-                            let group = group.expect("must have group if ID not set");
-                            format!("{}.{}", group.id, group.n)
-                        },
-                        |x| x.clone(),
-                    );
+        Ok(tokenizer)
+    }
 
-                    match DisambiguationRule::from_rule_structure(rule_structure, build_info) {
-                        Ok(mut rule) => {
-                            if error.is_none()
-                                && (options.ids.is_empty() || options.ids.contains(&id))
-                                && !options.ignore_ids.contains(&id)
-                            {
-                                rule.id = id;
+    /// Like [from_xml][Tokenizer::from_xml], but skips all rules which can not be loaded regardless of
+    /// `options.allow_errors` and `options.strict`. Missing files and malformed XML still fail.
+    pub fn from_xml_lenient<P: AsRef<std::path::Path>>(
+        path: P,
+        build_info: &mut BuildInfo,
+        chunker: Option<chunk::Chunker>,
+        options: TokenizerOptions,
+    ) -> Result<Self, Error> {
+        use log::debug;
 
-                                Some(rule)
-                            } else {
-                                None
+        let rules = super::parse_structure::read_disambiguation_rules(path)?;
+        let mut report = LoadReport::default();
+
+        let rules: Vec<_> = rules
+            .into_iter()
+            .filter_map(|(x, location)| {
+                let (id, status, rule) = match x {
+                    Ok((rule_structure, group, _)) => {
+                        let id = rule_structure.id.as_ref().map_or_else(
+                            || {
+                                let group = group.expect("must have group if ID not set");
+                                format!("{}.{}", group.id, group.n)
+                            },
+                            |x| x.clone(),
+                        );
+
+                        match DisambiguationRule::from_rule_structure(rule_structure, build_info) {
+                            Ok(mut rule) => {
+                                if (options.ids.is_empty() || options.ids.contains(&id))
+                                    && !options.ignore_ids.contains(&id)
+                                {
+                                    rule.id = id.clone();
+
+                                    (Some(id), RuleStatus::Loaded, Some(rule))
+                                } else {
+                                    (Some(id), RuleStatus::Excluded, None)
+                                }
                             }
-                        }
-                        Err(x) => {
-                            error = Some(format!("[Rule] {}", x));
-                            None
+                            Err(crate::Error::Unimplemented(x)) => {
+                                debug!(
+                                    "Skipping disambiguation rule {}: {} is not supported",
+                                    id, x
+                                );
+                                (Some(id), RuleStatus::Skipped(x), None)
+                            }
+                            Err(x) => (Some(id), RuleStatus::Failed(format!("[Rule] {}", x)), None),
                         }
                     }
-                }
-                Err(x) => {
-                    error = Some(format!("[Structure] {}", x));
-                    None
-                }
+                    Err(x) => (
+                        location.id,
+                        RuleStatus::Failed(format!("[Structure] {}", x)),
+                        None,
+                    ),
+                };
+
+                report.rules.push(RuleReport {
+                    id,
+                    line: location.line,
+                    column: location.column,
+                    status,
+                });
+                rule
             })
             .collect();
 
-        if let Some(x) = error {
-            if options.allow_errors {
-                warn!("Error constructing Disambiguator: {}", x)
-            } else {
-                return Err(format!("Error constructing Disambiguator: {}", x).into());
-            }
-        }
-
         Ok(Tokenizer {
             tagger: build_info.tagger().clone(),
             chunker,
             rules,
+            abbreviations: HashSet::new(),
+            segmenter: None,
             options,
+            multiword_tagger: None,
+            load_report: report,
         })
     }
 }
@@ -243,7 +384,7 @@ impl From<ModelData> for chunk::Model {
 }
 
 impl chunk::Chunker {
-    pub fn from_json<R: std::io::Read>(reader: R) -> chunk::Chunker {
+    pub fn from_json<R: std::io::Read>(reader: R) -> Result<chunk::Chunker, serde_json::Error> {
         #[derive(Serialize, Deserialize)]
         struct ChunkData {
             token_model: ModelData,
@@ -252,8 +393,8 @@ impl chunk::Chunker {
             chunk_model: ModelData,
         }
 
-        let chunk_data: ChunkData = serde_json::from_reader(reader).unwrap();
-        chunk::Chunker {
+        let chunk_data: ChunkData = serde_json::from_reader(reader)?;
+        Ok(chunk::Chunker {
             token_model: chunk::MaxentTokenizer {
                 model: chunk_data.token_model.into(),
             },
@@ -264,7 +405,7 @@ impl chunk::Chunker {
             chunk_model: chunk::MaxentChunker {
                 model: chunk_data.chunk_model.into(),
             },
-        }
+        })
     }
 }
 
@@ -324,7 +465,7 @@ mod composition {
         pub fn and(atoms: Vec<Atom>) -> Atom {
             let mut atoms: Vec<_> = atoms
                 .into_iter()
-                .filter(|x| !matches!(x, Atom::TrueAtom { .. }))
+                .filter(|x| !matches!(x, Atom::True(_)))
                 .collect();
 
             if atoms.is_empty() {
@@ -341,7 +482,7 @@ mod composition {
         pub fn or(atoms: Vec<Atom>) -> Atom {
             let mut atoms: Vec<_> = atoms
                 .into_iter()
-                .filter(|x| !matches!(x, Atom::FalseAtom { .. }))
+                .filter(|x| !matches!(x, Atom::False(_)))
                 .collect();
 
             if atoms.is_empty() {
@@ -357,8 +498,8 @@ mod composition {
     impl NotAtom {
         pub fn not(atom: Atom) -> Atom {
             match atom {
-                Atom::TrueAtom { .. } => FalseAtom::default().into(),
-                Atom::FalseAtom { .. } => TrueAtom::default().into(),
+                Atom::True(_) => FalseAtom::default().into(),
+                Atom::False(_) => TrueAtom::default().into(),
                 x => (NotAtom { atom: Box::new(x) }).into(),
             }
         }
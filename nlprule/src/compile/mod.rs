@@ -3,14 +3,22 @@ use std::{
     fs::{read_to_string, File},
     hash::{Hash, Hasher},
     io::{BufReader, BufWriter},
+    path::PathBuf,
     sync::Arc,
 };
 
 use clap::Clap;
+use thiserror::Error;
 
 use crate::{
     rules::{Rules, RulesOptions},
-    tokenizer::{chunk::Chunker, tag::Tagger, Tokenizer, TokenizerOptions},
+    tokenizer::{
+        chunk::Chunker,
+        multiword::MultiwordTagger,
+        srx::SrxSegmenter,
+        tag::{Casing, Folding, Tagger},
+        Tokenizer, TokenizerOptions,
+    },
     types::DefaultHasher,
 };
 
@@ -20,6 +28,161 @@ mod impls;
 mod parse_structure;
 mod structure;
 
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("XML file {path:?} could not be opened: {source}")]
+    XmlNotFound {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// `line` and `column` are 1-based and refer to the position of the problem if known.
+    #[error("error parsing XML file {path:?}: {message}")]
+    XmlParse {
+        path: PathBuf,
+        message: String,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+    #[error("error constructing disambiguator: {0}")]
+    Disambiguator(DisambiguationError),
+    #[error(transparent)]
+    Nlprule(#[from] crate::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Serialization(#[from] bincode::Error),
+}
+
+/// A rule of a disambiguation file which could not be loaded, see [Tokenizer::from_xml].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisambiguationError {
+    /// The ID of the rule, or of the rule group if the group could not be read.
+    pub id: Option<String>,
+    /// The 1-based line of the rule (or of its group) in the file.
+    pub line: Option<usize>,
+    /// The 1-based column of the rule (or of its group) in the file.
+    pub column: Option<usize>,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl std::fmt::Display for DisambiguationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut location = Vec::new();
+        if let Some(id) = &self.id {
+            location.push(format!("rule {}", id));
+        }
+        if let Some(line) = self.line {
+            location.push(format!("line {}", line));
+        }
+        if let Some(column) = self.column {
+            location.push(format!("column {}", column));
+        }
+
+        if location.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", location.join(", "), self.message)
+        }
+    }
+}
+
+/// What happened to a rule of a disambiguation file, see [LoadReport].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleStatus {
+    /// The rule is used by the tokenizer.
+    Loaded,
+    /// The rule is not used because of the `ids` or `ignore_ids` of the [TokenizerOptions].
+    Excluded,
+    /// The rule uses the given feature which is not supported.
+    Skipped(String),
+    /// The rule could not be read or compiled because of the given error.
+    Failed(String),
+}
+
+/// A rule (or a rule group which could not be read) of a disambiguation file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleReport {
+    /// The ID of the rule, or of the rule group if the group could not be read.
+    pub id: Option<String>,
+    /// The 1-based line of the rule (or of its group) in the file.
+    pub line: Option<usize>,
+    /// The 1-based column of the rule (or of its group) in the file.
+    pub column: Option<usize>,
+    pub status: RuleStatus,
+}
+
+impl RuleReport {
+    /// The rule as error if it was skipped or failed.
+    pub fn error(&self) -> Option<DisambiguationError> {
+        let message = match &self.status {
+            RuleStatus::Loaded | RuleStatus::Excluded => return None,
+            RuleStatus::Skipped(feature) => format!("{} is not supported", feature),
+            RuleStatus::Failed(message) => message.clone(),
+        };
+
+        Some(DisambiguationError {
+            id: self.id.clone(),
+            line: self.line,
+            column: self.column,
+            message,
+        })
+    }
+}
+
+/// The status of each rule of the disambiguation file a tokenizer was created from, see [Tokenizer::load_report].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadReport {
+    /// The rules in the order of the file.
+    pub rules: Vec<RuleReport>,
+}
+
+impl LoadReport {
+    fn count(&self, predicate: impl Fn(&RuleStatus) -> bool) -> usize {
+        self.rules.iter().filter(|x| predicate(&x.status)).count()
+    }
+
+    /// The number of rules used by the tokenizer.
+    pub fn loaded(&self) -> usize {
+        self.count(|x| *x == RuleStatus::Loaded)
+    }
+
+    /// The number of rules excluded by the options.
+    pub fn excluded(&self) -> usize {
+        self.count(|x| *x == RuleStatus::Excluded)
+    }
+
+    /// The number of rules which use unsupported features.
+    pub fn skipped(&self) -> usize {
+        self.count(|x| matches!(x, RuleStatus::Skipped(_)))
+    }
+
+    /// The number of rules which could not be read or compiled.
+    pub fn failed(&self) -> usize {
+        self.count(|x| matches!(x, RuleStatus::Failed(_)))
+    }
+
+    /// The skipped and failed rules as errors.
+    pub fn errors(&self) -> Vec<DisambiguationError> {
+        self.rules.iter().filter_map(|x| x.error()).collect()
+    }
+}
+
+impl std::fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "loaded: {}, excluded: {}, skipped: {}, failed: {}",
+            self.loaded(),
+            self.excluded(),
+            self.skipped(),
+            self.failed()
+        )
+    }
+}
+
 #[derive(Clap)]
 #[clap(
     version = env!("CARGO_PKG_VERSION"),
@@ -42,6 +205,22 @@ pub struct BuildOptions {
     pub chunker_path: Option<String>,
     #[clap(long)]
     pub common_words_path: Option<String>,
+    /// File with one abbreviation per line after which no sentence boundary is assumed.
+    #[clap(long)]
+    pub abbreviations_path: Option<String>,
+    /// SRX file with rules for sentence segmentation. Unicode sentence segmentation is used if not set.
+    #[clap(long)]
+    pub srx_path: Option<String>,
+    /// File with one multiword expression and its tag, separated by a tab, per line (`multiwords.txt` in LanguageTool).
+    #[clap(long)]
+    pub multiwords_path: Option<String>,
+    /// File with one word and its number of occurrences per line. Tokens have no frequencies if not set or missing.
+    #[clap(long)]
+    pub frequency_path: Option<String>,
+    /// File with one tag and its description, separated by a tab, per line. Tags have no descriptions if not set
+    /// or missing.
+    #[clap(long)]
+    pub tagset_path: Option<String>,
     #[clap(long)]
     pub regex_cache_path: String,
     #[clap(long)]
@@ -50,39 +229,88 @@ pub struct BuildOptions {
     pub out_rules_path: String,
 }
 
-pub fn compile(opts: &BuildOptions) {
-    let common_words = opts
-        .common_words_path
-        .as_ref()
-        .map_or_else(HashSet::new, |path| {
-            read_to_string(path)
-                .unwrap()
-                .lines()
-                .map(|x| x.to_string())
-                .collect()
-        });
+/// Reads multiword expressions in the LanguageTool format: one expression and its tag separated by a tab per line,
+/// lines starting with `#` are comments.
+fn read_multiwords(content: &str) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter(|x| !x.trim().is_empty() && !x.starts_with('#'))
+        .filter_map(|x| {
+            let mut parts = x.split('\t');
+            match (parts.next(), parts.next()) {
+                (Some(text), Some(tag)) => Some((text.trim().to_string(), tag.trim().to_string())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Reads abbreviations in the LanguageTool format: one per line, lines starting with `#` are comments.
+fn read_abbreviations(content: &str) -> HashSet<String> {
+    content
+        .lines()
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty() && !x.starts_with('#'))
+        .map(|x| x.to_string())
+        .collect()
+}
+
+pub fn compile(opts: &BuildOptions) -> Result<(), Error> {
+    let common_words = if let Some(path) = &opts.common_words_path {
+        read_to_string(path)?
+            .lines()
+            .map(|x| x.to_string())
+            .collect()
+    } else {
+        HashSet::new()
+    };
 
-    let tokenizer_options: TokenizerOptions =
-        serde_json::from_str(&read_to_string(&opts.tokenizer_config_path).unwrap()).unwrap();
+    let mut tokenizer_options: TokenizerOptions =
+        serde_json::from_str(&read_to_string(&opts.tokenizer_config_path)?)?;
     let rules_options: RulesOptions =
-        serde_json::from_str(&read_to_string(&opts.rules_config_path).unwrap()).unwrap();
+        serde_json::from_str(&read_to_string(&opts.rules_config_path)?)?;
 
-    let tagger = Tagger::from_dumps(
+    let multiwords = if let Some(path) = &opts.multiwords_path {
+        read_multiwords(&read_to_string(path)?)
+    } else {
+        Vec::new()
+    };
+    // the tags of multiwords have to be in the tagger
+    for tag in MultiwordTagger::required_tags(&multiwords) {
+        if !tokenizer_options.extra_tags.contains(&tag) {
+            tokenizer_options.extra_tags.push(tag);
+        }
+    }
+
+    let mut tagger = Tagger::from_dumps(
         &opts.tag_paths,
         &opts.tag_remove_paths,
         &tokenizer_options.extra_tags,
         &common_words,
-    )
-    .unwrap();
+    )?
+    .with_casing(Casing::for_language(&tokenizer_options.lang_code))
+    .with_folding(if tokenizer_options.fold_diacritics {
+        Some(Folding::for_language(&tokenizer_options.lang_code))
+    } else {
+        None
+    });
+
+    if let Some(path) = &opts.frequency_path {
+        tagger.load_frequencies(path)?;
+    }
+
+    if let Some(path) = &opts.tagset_path {
+        tagger.load_tagset(path)?;
+    }
 
     let mut hasher = DefaultHasher::default();
     let mut word_store = tagger.word_store().iter().collect::<Vec<_>>();
-    word_store.sort_by(|a, b| a.1.cmp(b.1));
+    word_store.sort_by_key(|x| x.1);
     word_store.hash(&mut hasher);
     let word_store_hash = hasher.finish();
 
     let regex_cache = if let Ok(file) = File::open(&opts.regex_cache_path) {
-        let cache: RegexCache = bincode::deserialize_from(BufReader::new(file)).unwrap();
+        let cache: RegexCache = bincode::deserialize_from(BufReader::new(file))?;
         if *cache.word_hash() == word_store_hash {
             cache
         } else {
@@ -94,28 +322,1372 @@ pub fn compile(opts: &BuildOptions) {
 
     let mut build_info = BuildInfo::new(Arc::new(tagger), regex_cache);
 
-    let tokenizer = Tokenizer::from_xml(
+    let chunker = if let Some(path) = &opts.chunker_path {
+        let reader = BufReader::new(File::open(path)?);
+        Some(Chunker::from_json(reader)?)
+    } else {
+        None
+    };
+
+    let mut tokenizer = Tokenizer::from_xml(
         &opts.disambiguation_path,
         &mut build_info,
-        if let Some(path) = &opts.chunker_path {
-            let reader = BufReader::new(File::open(path).unwrap());
-            let chunker = Chunker::from_json(reader);
-            Some(chunker)
-        } else {
-            None
-        },
+        chunker,
         tokenizer_options,
-    )
-    .unwrap();
+    )?;
+
+    if let Some(path) = &opts.abbreviations_path {
+        tokenizer.abbreviations = read_abbreviations(&read_to_string(path)?);
+    }
+
+    if !multiwords.is_empty() {
+        tokenizer.multiword_tagger = Some(MultiwordTagger::new(&multiwords, &tokenizer.tagger)?);
+    }
+
+    if let Some(path) = &opts.srx_path {
+        tokenizer.segmenter = Some(SrxSegmenter::from_xml(
+            path,
+            &tokenizer.options().lang_code,
+        )?);
+    }
+
+    let f = BufWriter::new(File::create(&opts.out_tokenizer_path)?);
+    bincode::serialize_into(f, &tokenizer)?;
+
+    let rules = Rules::from_xml(&opts.grammar_path, &mut build_info, rules_options)?;
+
+    let f = BufWriter::new(File::create(&opts.regex_cache_path)?);
+    bincode::serialize_into(f, build_info.mut_regex_cache())?;
+
+    let f = BufWriter::new(File::create(&opts.out_rules_path)?);
+    bincode::serialize_into(f, &rules)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::{fs, path::Path};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("nlprule_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn path_str(path: &Path) -> &str {
+        path.to_str().unwrap()
+    }
+
+    /// Builds a tokenizer from a disambiguation XML string and a tagger dump with one `word\tlemma\ttag` entry per line.
+    pub(crate) fn tokenizer_from_xml(name: &str, dump: &str, xml: &str) -> Tokenizer {
+        tokenizer_with_extra_tags(name, dump, xml, &[])
+    }
+
+    /// Like [tokenizer_from_xml], with additional tags in the tagger.
+    fn tokenizer_with_extra_tags(
+        name: &str,
+        dump: &str,
+        xml: &str,
+        extra_tags: &[String],
+    ) -> Tokenizer {
+        let dir = temp_dir(name);
+        let dump_path = dir.join("dump.txt");
+        let xml_path = dir.join("disambiguation.xml");
+        fs::write(&dump_path, dump).unwrap();
+        fs::write(&xml_path, xml).unwrap();
+
+        let tagger = Tagger::from_dumps(
+            &[path_str(&dump_path)],
+            &[] as &[&str],
+            extra_tags,
+            &HashSet::new(),
+        )
+        .unwrap();
+        let mut build_info = BuildInfo::new(Arc::new(tagger), RegexCache::new(0));
+
+        Tokenizer::from_xml(
+            &xml_path,
+            &mut build_info,
+            None,
+            TokenizerOptions::default(),
+        )
+        .unwrap()
+    }
+
+    fn tags_of(tokenizer: &Tokenizer, text: &str, char_start: usize) -> Vec<String> {
+        let tokens = tokenizer.disambiguate(tokenizer.tokenize(text));
+        let token = tokens.iter().find(|x| x.char_span.0 == char_start).unwrap();
+
+        let mut tags: Vec<_> = token
+            .word
+            .tags
+            .iter()
+            .map(|x| x.pos.0.to_string())
+            .collect();
+        tags.sort();
+        tags
+    }
+
+    /// The readings of the token as "lemma/tag", sorted.
+    fn readings_of(tokenizer: &Tokenizer, text: &str, char_start: usize) -> Vec<String> {
+        let tokens = tokenizer.disambiguate(tokenizer.tokenize(text));
+        let token = tokens.iter().find(|x| x.char_span.0 == char_start).unwrap();
+
+        let mut readings: Vec<_> = token
+            .word
+            .tags
+            .iter()
+            .map(|x| format!("{}/{}", x.lemma.as_ref(), x.pos.0))
+            .collect();
+        readings.sort();
+        readings
+    }
+
+    #[test]
+    fn missing_disambiguation_xml() {
+        let path = temp_dir("missing_xml").join("disambiguation.xml");
+
+        match parse_structure::read_disambiguation_rules(&path) {
+            Err(Error::XmlNotFound { path: err_path, .. }) => assert_eq!(err_path, path),
+            x => panic!("unexpected result: {:?}", x.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn malformed_disambiguation_xml() {
+        let path = temp_dir("malformed_xml").join("disambiguation.xml");
+        fs::write(&path, "<rules><rule id=\"A\"></rules>").unwrap();
+
+        match parse_structure::read_disambiguation_rules(&path) {
+            Err(Error::XmlParse { path: err_path, .. }) => assert_eq!(err_path, path),
+            x => panic!("unexpected result: {:?}", x.map(|_| ())),
+        }
+    }
+
+    fn disambiguation_fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/disambiguation")
+            .join(name)
+    }
+
+    fn german_build_info(name: &str) -> BuildInfo {
+        let dump_path = temp_dir(name).join("dump.txt");
+        fs::write(
+            &dump_path,
+            "Die\tdie\tART:DEF\nDie\tdie\tPRO:DEM\nKatze\tKatze\tSUB\n",
+        )
+        .unwrap();
+
+        let tagger = Tagger::from_dumps(
+            &[path_str(&dump_path)],
+            &[] as &[&str],
+            &[] as &[&str],
+            &HashSet::new(),
+        )
+        .unwrap();
+        BuildInfo::new(Arc::new(tagger), RegexCache::new(0))
+    }
+
+    #[test]
+    fn truncated_disambiguation_xml() {
+        let path = disambiguation_fixture("truncated.xml");
+        let mut build_info = german_build_info("truncated_xml");
+
+        match Tokenizer::from_xml(&path, &mut build_info, None, TokenizerOptions::default()) {
+            Err(Error::XmlParse {
+                path: err_path,
+                line,
+                column,
+                ..
+            }) => {
+                assert_eq!(err_path, path);
+                assert_eq!(line, Some(5));
+                assert!(column.is_some());
+            }
+            x => panic!("unexpected result: {:?}", x.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn broken_disambiguation_rules() {
+        let path = disambiguation_fixture("broken_rules.xml");
+        let mut build_info = german_build_info("broken_rules");
+
+        match Tokenizer::from_xml(&path, &mut build_info, None, TokenizerOptions::default()) {
+            Err(Error::Disambiguator(error)) => {
+                assert_eq!(error.id.as_deref(), Some("UNKNOWN_ELEMENT"));
+                assert_eq!(error.line, Some(3));
+                assert!(error.message.contains("frobnicate"), "{}", error);
+            }
+            x => panic!("unexpected result: {:?}", x.map(|_| ())),
+        }
+
+        let tokenizer =
+            Tokenizer::from_xml_lenient(&path, &mut build_info, None, TokenizerOptions::default())
+                .unwrap();
+
+        let locations: Vec<_> = tokenizer
+            .load_report()
+            .errors()
+            .into_iter()
+            .map(|x| (x.id, x.line, x.column.is_some()))
+            .collect();
+        assert_eq!(
+            locations,
+            vec![
+                (Some("UNKNOWN_ELEMENT".to_string()), Some(3), true),
+                (Some("BAD_REGEX.0".to_string()), Some(9), true)
+            ]
+        );
+
+        // the valid rule after the broken ones is still used
+        assert_eq!(tags_of(&tokenizer, "Die Katze", 0), vec!["ART:DEF"]);
+
+        let options = TokenizerOptions {
+            allow_errors: true,
+            ..TokenizerOptions::default()
+        };
+        let tokenizer = Tokenizer::from_xml(&path, &mut build_info, None, options).unwrap();
+        assert_eq!(tags_of(&tokenizer, "Die Katze", 0), vec!["ART:DEF"]);
+    }
+
+    #[test]
+    fn unsupported_disambiguation_rules() {
+        let path = disambiguation_fixture("unsupported.xml");
+        let mut build_info = german_build_info("unsupported_rules");
 
-    let f = BufWriter::new(File::create(&opts.out_tokenizer_path).unwrap());
-    bincode::serialize_into(f, &tokenizer).unwrap();
+        let tokenizer =
+            Tokenizer::from_xml(&path, &mut build_info, None, TokenizerOptions::default()).unwrap();
+        let report = tokenizer.load_report();
 
-    let rules = Rules::from_xml(&opts.grammar_path, &mut build_info, rules_options);
+        let statuses: Vec<_> = report
+            .rules
+            .iter()
+            .map(|x| (x.id.as_deref(), x.status.clone()))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (Some("ARTICLE_BEFORE_NOUN"), RuleStatus::Loaded),
+                (
+                    Some("EXOTIC_FILTER"),
+                    RuleStatus::Skipped("filter ExoticFilter".into())
+                ),
+            ]
+        );
+        assert_eq!(
+            report.to_string(),
+            "loaded: 1, excluded: 0, skipped: 1, failed: 0"
+        );
+        assert_eq!(tags_of(&tokenizer, "Die Katze", 0), vec!["ART:DEF"]);
 
-    let f = BufWriter::new(File::create(&opts.regex_cache_path).unwrap());
-    bincode::serialize_into(f, build_info.mut_regex_cache()).unwrap();
+        let strict = TokenizerOptions {
+            strict: true,
+            ..TokenizerOptions::default()
+        };
+        match Tokenizer::from_xml(&path, &mut build_info, None, strict.clone()) {
+            Err(Error::Disambiguator(error)) => {
+                assert_eq!(error.id.as_deref(), Some("EXOTIC_FILTER"));
+                assert_eq!(error.line, Some(12));
+            }
+            x => panic!("unexpected result: {:?}", x.map(|_| ())),
+        }
+
+        let options = TokenizerOptions {
+            allow_errors: true,
+            ignore_ids: vec!["ARTICLE_BEFORE_NOUN".into()],
+            ..strict
+        };
+        let tokenizer = Tokenizer::from_xml(&path, &mut build_info, None, options).unwrap();
+        assert_eq!(
+            tokenizer.load_report().to_string(),
+            "loaded: 0, excluded: 1, skipped: 1, failed: 0"
+        );
+    }
+
+    #[test]
+    fn unreadable_dump() {
+        let dir = temp_dir("unreadable_dump");
+        let present = dir.join("present.txt");
+        fs::write(&present, "word\tlemma\tTAG\n").unwrap();
+        let missing = dir.join("missing.txt");
+
+        let result = Tagger::from_dumps(
+            &[path_str(&present), path_str(&missing)],
+            &[] as &[&str],
+            &[] as &[&str],
+            &HashSet::new(),
+        );
+
+        match result {
+            Err(crate::Error::DumpUnreadable { path, .. }) => {
+                assert_eq!(path, path_str(&missing))
+            }
+            x => panic!("unexpected result: {:?}", x.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn postag_regexp_matches_inflections() {
+        let tokenizer = tokenizer_from_xml(
+            "postag_regexp",
+            "sie\tsie\tPRO:PER:NOM:SIN:FEM\nsie\tsie\tPRO:PER:NOM:PLU\n\
+             läuft\tlaufen\tVER:3:SIN:PRÄ:NON\n\
+             lief\tlaufen\tVER:1:SIN:PRT:NON\nlief\tlaufen\tVER:3:SIN:PRT:NON\n\
+             laufen\tlaufen\tVER:3:PLU:PRÄ:NON\nlaufen\tlaufen\tVER:INF:NON\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="de">
+    <rule id="SIE_BEFORE_SINGULAR_VERB" name="sie before a singular verb is singular">
+        <pattern>
+            <marker>
+                <token>sie</token>
+            </marker>
+            <token postag="VER:.*:SIN.*" postag_regexp="yes"/>
+        </pattern>
+        <disambig action="filter" postag="PRO:PER:NOM:SIN.*"/>
+    </rule>
+    <rule id="SIE_BEFORE_OTHER_VERB" name="sie before a verb which is not singular is plural">
+        <pattern>
+            <marker>
+                <token>sie</token>
+            </marker>
+            <token postag="VER:.*:SIN.*" postag_regexp="yes" negate_pos="yes"/>
+        </pattern>
+        <disambig action="filter" postag="PRO:PER:NOM:PLU"/>
+    </rule>
+</rules>"#,
+        );
+
+        // the verbs are not at the end of the sentence, the SENT_END reading would match the negated postag
+        assert_eq!(
+            tags_of(&tokenizer, "Ob sie läuft, ist unklar", 3),
+            vec!["PRO:PER:NOM:SIN:FEM"]
+        );
+        assert_eq!(
+            tags_of(&tokenizer, "Ob sie lief, ist unklar", 3),
+            vec!["PRO:PER:NOM:SIN:FEM"]
+        );
+        assert_eq!(
+            tags_of(&tokenizer, "Ob sie laufen, ist unklar", 3),
+            vec!["PRO:PER:NOM:PLU"]
+        );
+    }
+
+    /// A tokenizer with a rule which disambiguates "Die" as article if it is followed by the `token`.
+    fn article_before(name: &str, token: &str) -> Tokenizer {
+        tokenizer_from_xml(
+            name,
+            "Die\tdie\tART:DEF\nDie\tdie\tPRO:DEM\nKatze\tKatze\tSUB\nHund\tHund\tSUB\n\
+             schläft\tschlafen\tVER\n",
+            &format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="de">
+    <rule id="ARTICLE" name="die before the token is an article">
+        <pattern>
+            <marker>
+                <token>Die</token>
+            </marker>
+            {}
+        </pattern>
+        <disambig action="filter" postag="ART:DEF"/>
+    </rule>
+</rules>"#,
+                token
+            ),
+        )
+    }
+
+    fn is_article(tokenizer: &Tokenizer, text: &str) -> bool {
+        // the period ends the sentence, the SENT_END reading of the last token would match negated postags
+        tags_of(tokenizer, &format!("{}.", text), 0) == vec!["ART:DEF"]
+    }
+
+    #[test]
+    fn negated_text() {
+        let tokenizer = article_before("negated_text", r#"<token negate="yes">Katze</token>"#);
+
+        assert!(is_article(&tokenizer, "Die Hund"));
+        assert!(is_article(&tokenizer, "Die schläft"));
+        assert!(!is_article(&tokenizer, "Die Katze"));
+    }
+
+    #[test]
+    fn negated_postag() {
+        let tokenizer = article_before(
+            "negated_postag",
+            r#"<token postag="VER" negate_pos="yes"/>"#,
+        );
+
+        assert!(is_article(&tokenizer, "Die Katze"));
+        assert!(is_article(&tokenizer, "Die Hund"));
+        assert!(!is_article(&tokenizer, "Die schläft"));
+    }
+
+    #[test]
+    fn negated_text_and_postag() {
+        // both conditions are negated independently and must hold
+        let tokenizer = article_before(
+            "negated_text_and_postag",
+            r#"<token postag="SUB" negate_pos="yes" negate="yes">Katze</token>"#,
+        );
+
+        assert!(is_article(&tokenizer, "Die schläft"));
+        assert!(!is_article(&tokenizer, "Die Hund"));
+        assert!(!is_article(&tokenizer, "Die Katze"));
+
+        // the text is negated, the postag is not
+        let tokenizer = article_before(
+            "negated_text_not_postag",
+            r#"<token postag="SUB" negate="yes">Katze</token>"#,
+        );
+
+        assert!(is_article(&tokenizer, "Die Hund"));
+        assert!(!is_article(&tokenizer, "Die schläft"));
+        assert!(!is_article(&tokenizer, "Die Katze"));
+    }
+
+    #[test]
+    fn exception_in_negated_token() {
+        let tokenizer = article_before(
+            "exception_in_negated_token",
+            r#"<token negate="yes">Katze<exception>Hund</exception></token>"#,
+        );
+
+        assert!(is_article(&tokenizer, "Die schläft"));
+        assert!(!is_article(&tokenizer, "Die Hund"));
+        assert!(!is_article(&tokenizer, "Die Katze"));
+    }
+
+    #[test]
+    fn skip_bridges_tokens_up_to_exception() {
+        let tokenizer = tokenizer_from_xml(
+            "skip",
+            "die\tdie\tART:DEF\ndie\tdie\tPRO:DEM\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="de">
+    <rule id="DASS_DIE" name="die after dass is a pronoun">
+        <pattern>
+            <token skip="2">dass<exception scope="next">nicht</exception></token>
+            <marker>
+                <token>die</token>
+            </marker>
+        </pattern>
+        <disambig action="filter" postag="PRO:DEM"/>
+    </rule>
+</rules>"#,
+        );
+
+        assert_eq!(tags_of(&tokenizer, "dass die", 5), vec!["PRO:DEM"]);
+        assert_eq!(tags_of(&tokenizer, "dass es die", 8), vec!["PRO:DEM"]);
+        assert_eq!(tags_of(&tokenizer, "dass es so die", 11), vec!["PRO:DEM"]);
+
+        // too many tokens in between
+        assert_eq!(
+            tags_of(&tokenizer, "dass es so oft die", 15),
+            vec!["ART:DEF", "PRO:DEM"]
+        );
+        // the exception must not be skipped
+        assert_eq!(
+            tags_of(&tokenizer, "dass es nicht die", 14),
+            vec!["ART:DEF", "PRO:DEM"]
+        );
+    }
+
+    #[test]
+    fn optional_and_repeated_tokens() {
+        let tokenizer = tokenizer_from_xml(
+            "min_max",
+            "die\tdie\tART:DEF\ndie\tdie\tPRO:DEM\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="de">
+    <rule id="WEIL_JA_DIE" name="die after weil is an article">
+        <pattern>
+            <token>weil</token>
+            <token min="0" max="2">ja</token>
+            <marker>
+                <token>die</token>
+            </marker>
+        </pattern>
+        <disambig action="filter" postag="ART:DEF"/>
+    </rule>
+</rules>"#,
+        );
+
+        assert_eq!(tags_of(&tokenizer, "weil die", 5), vec!["ART:DEF"]);
+        assert_eq!(tags_of(&tokenizer, "weil ja die", 8), vec!["ART:DEF"]);
+        assert_eq!(tags_of(&tokenizer, "weil ja ja die", 11), vec!["ART:DEF"]);
+        assert_eq!(
+            tags_of(&tokenizer, "weil ja ja ja die", 14),
+            vec!["ART:DEF", "PRO:DEM"]
+        );
+    }
+
+    #[test]
+    fn exception_with_previous_scope() {
+        let tokenizer = tokenizer_from_xml(
+            "exception_previous",
+            "can\tcan\tMD\ncan\tcan\tNN\ngo\tgo\tVB\nthe\tthe\tDT\nI\tI\tPRP\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="CAN_MODAL" name="can before a verb is a modal, but not after a determiner">
+        <pattern>
+            <marker>
+                <token>can<exception scope="previous" postag="DT"/></token>
+            </marker>
+            <token postag="VB"/>
+        </pattern>
+        <disambig action="filter" postag="MD"/>
+    </rule>
+</rules>"#,
+        );
+
+        assert_eq!(tags_of(&tokenizer, "I can go", 2), vec!["MD"]);
+        assert_eq!(tags_of(&tokenizer, "the can go", 4), vec!["MD", "NN"]);
+    }
+
+    #[test]
+    fn exceptions_with_regexp_and_inflected() {
+        let tokenizer = tokenizer_from_xml(
+            "exception_inflected",
+            "like\tlike\tIN\nlike\tlike\tVB\nthe\tthe\tDT\nwould\twould\tMD\n\
+             does\tdo\tVBZ\nlooks\tlook\tVBZ\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="LIKE_PREPOSITION" name="like before a determiner is a preposition">
+        <pattern>
+            <marker>
+                <token>like<exception scope="previous" regexp="yes">would|should</exception><exception scope="previous" inflected="yes">do</exception></token>
+            </marker>
+            <token postag="DT"/>
+        </pattern>
+        <disambig action="filter" postag="IN"/>
+    </rule>
+</rules>"#,
+        );
+
+        assert_eq!(tags_of(&tokenizer, "it looks like the sea", 9), vec!["IN"]);
+        assert_eq!(
+            tags_of(&tokenizer, "I would like the cake", 8),
+            vec!["IN", "VB"]
+        );
+        assert_eq!(
+            tags_of(&tokenizer, "she does like the cake", 9),
+            vec!["IN", "VB"]
+        );
+    }
+
+    #[test]
+    fn case_sensitive_exception() {
+        let tokenizer = tokenizer_from_xml(
+            "exception_case_sensitive",
+            "US\tUS\tNNP\nUS\twe\tPRP\nus\tUS\tNNP\nus\twe\tPRP\nthe\tthe\tDT\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="US_COUNTRY" name="US after the is the country">
+        <pattern>
+            <token>the</token>
+            <marker>
+                <token>us<exception case_sensitive="yes">us</exception></token>
+            </marker>
+        </pattern>
+        <disambig action="filter" postag="NNP"/>
+    </rule>
+</rules>"#,
+        );
+
+        assert_eq!(tags_of(&tokenizer, "in the US", 7), vec!["NNP"]);
+        assert_eq!(tags_of(&tokenizer, "in the us", 7), vec!["NNP", "PRP"]);
+    }
+
+    #[test]
+    fn case_sensitive_tokens() {
+        let build = |name: &str, pattern: &str| {
+            tokenizer_from_xml(
+                name,
+                "US\tUS\tNNP\nUS\twe\tPRP\nus\tUS\tNNP\nus\twe\tPRP\nthe\tthe\tDT\n",
+                &format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="US_COUNTRY" name="US after the is the country">
+        {}
+        <disambig action="filter" postag="NNP"/>
+    </rule>
+</rules>"#,
+                    pattern
+                ),
+            )
+        };
+
+        let cases = [
+            (
+                "case_sensitive_token",
+                r#"<pattern>
+            <token>the</token>
+            <marker><token case_sensitive="yes">US</token></marker>
+        </pattern>"#,
+                true,
+            ),
+            (
+                "case_sensitive_pattern",
+                r#"<pattern case_sensitive="yes">
+            <token>the</token>
+            <marker><token>US</token></marker>
+        </pattern>"#,
+                true,
+            ),
+            (
+                "case_insensitive_token",
+                r#"<pattern case_sensitive="yes">
+            <token>the</token>
+            <marker><token case_sensitive="no">US</token></marker>
+        </pattern>"#,
+                false,
+            ),
+            (
+                // the exception is case sensitive like its token, so it only excludes "us"
+                "case_sensitive_exception",
+                r#"<pattern>
+            <token>the</token>
+            <marker><token case_sensitive="yes" regexp="yes">US|us<exception>us</exception></token></marker>
+        </pattern>"#,
+                true,
+            ),
+        ];
+
+        for (name, pattern, case_sensitive) in cases.iter() {
+            let tokenizer = build(name, pattern);
+
+            assert_eq!(tags_of(&tokenizer, "in the US", 7), vec!["NNP"], "{}", name);
+            let expected = if *case_sensitive {
+                vec!["NNP", "PRP"]
+            } else {
+                vec!["NNP"]
+            };
+            assert_eq!(tags_of(&tokenizer, "in the us", 7), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn inflected_tokens() {
+        let tokenizer = tokenizer_with_extra_tags(
+            "inflected",
+            "ich\tich\tPRO\ndu\tdu\tPRO\ner\ter\tPRO\nwir\twir\tPRO\n\
+             gehe\tgehen\tVER:1:SIN\ngehst\tgehen\tVER:2:SIN\nging\tgehen\tVER:3:SIN\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="de">
+    <rule id="GEHEN" name="any form of gehen">
+        <pattern>
+            <token inflected="yes">gehen</token>
+        </pattern>
+        <disambig action="add"><wd lemma="gehen" pos="GEHEN"/></disambig>
+    </rule>
+    <rule id="GEHEN_3" name="the third person of gehen">
+        <pattern>
+            <token inflected="yes" postag="VER:3.*" postag_regexp="yes">gehen</token>
+        </pattern>
+        <disambig action="add"><wd lemma="gehen" pos="GEHEN:3"/></disambig>
+    </rule>
+</rules>"#,
+            &["GEHEN".to_string(), "GEHEN:3".to_string()],
+        );
+
+        assert_eq!(
+            tags_of(&tokenizer, "ich gehe", 4),
+            vec!["GEHEN", "VER:1:SIN"]
+        );
+        assert_eq!(
+            tags_of(&tokenizer, "du gehst", 3),
+            vec!["GEHEN", "VER:2:SIN"]
+        );
+        assert_eq!(
+            tags_of(&tokenizer, "er ging", 3),
+            vec!["GEHEN", "GEHEN:3", "VER:3:SIN"]
+        );
+        // an unknown word has no lemma from the dictionary, even if its text is the lemma
+        assert!(tags_of(&tokenizer, "wir gehen", 4).is_empty());
+    }
+
+    #[test]
+    fn chunk_conditions() {
+        let build = |name: &str, attribute: &str| {
+            tokenizer_from_xml(
+                name,
+                "dog\tdog\tNN\ndog\tdog\tVB\n",
+                &format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="DOG_NP" name="dog in a noun phrase is a noun">
+        <pattern>
+            <token {}>dog</token>
+        </pattern>
+        <disambig action="filter" postag="NN"/>
+    </rule>
+</rules>"#,
+                    attribute
+                ),
+            )
+        };
+
+        // no chunker is loaded, so the chunks are set manually
+        let tags_with_chunk = |tokenizer: &Tokenizer, chunk: Option<&str>| {
+            let mut tokens = tokenizer.tokenize("the dog");
+            let token = tokens.iter_mut().find(|x| x.char_span.0 == 4).unwrap();
+            token.chunks = chunk.into_iter().map(|x| x.to_string()).collect();
+
+            let tokens = tokenizer.disambiguate(tokens);
+            let token = tokens.iter().find(|x| x.char_span.0 == 4).unwrap();
+            let mut tags: Vec<_> = token
+                .word
+                .tags
+                .iter()
+                .map(|x| x.pos.0.to_string())
+                .collect();
+            tags.sort();
+            tags
+        };
+
+        for (name, attribute) in [
+            ("chunk", r#"chunk="B-NP""#),
+            ("chunk_re", r#"chunk_re="[BI]-NP""#),
+        ]
+        .iter()
+        {
+            let tokenizer = build(name, attribute);
+
+            assert_eq!(
+                tags_with_chunk(&tokenizer, None),
+                vec!["NN", "VB"],
+                "{}",
+                name
+            );
+            assert_eq!(
+                tags_with_chunk(&tokenizer, Some("B-VP")),
+                vec!["NN", "VB"],
+                "{}",
+                name
+            );
+            assert_eq!(
+                tags_with_chunk(&tokenizer, Some("B-NP")),
+                vec!["NN"],
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn grammar_rules_match_rule_chunks() {
+        let mut tokenizer = tokenizer_from_xml(
+            "rule_chunks",
+            "The\tthe\tDT\ndog\tdog\tNN\ndogs\tdog\tNNS\nis\tbe\tVBZ\nhere\there\tRB\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+</rules>"#,
+        );
+
+        let path = temp_dir("rule_chunks").join("grammar.xml");
+        fs::write(
+            &path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <category id="GRAMMAR" name="Grammar">
+        <rule id="PLURAL_IS" name="plural noun phrase followed by is">
+            <pattern>
+                <token chunk="E-NP-plural"/>
+                <marker>
+                    <token>is</token>
+                </marker>
+            </pattern>
+            <message>Did you mean <suggestion>are</suggestion>?</message>
+            <example correction="are">The dogs <marker>is</marker> here.</example>
+        </rule>
+    </category>
+</rules>"#,
+        )
+        .unwrap();
+        let mut build_info = BuildInfo::new(tokenizer.tagger().clone(), RegexCache::new(0));
+        let rules = Rules::from_xml(&path, &mut build_info, RulesOptions::default()).unwrap();
+        assert_eq!(rules.rules().len(), 1);
+
+        // without a chunker, no tokens have chunks
+        assert!(rules.suggest("The dogs is here.", &tokenizer).is_empty());
 
-    let f = BufWriter::new(File::create(&opts.out_rules_path).unwrap());
-    bincode::serialize_into(f, &rules).unwrap();
+        tokenizer.options_mut().rule_chunker = true;
+        let suggestions = rules.suggest("The dogs is here.", &tokenizer);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].replacements, vec!["are"]);
+        assert!(rules.suggest("The dog is here.", &tokenizer).is_empty());
+    }
+
+    #[test]
+    fn actions_only_change_marked_tokens() {
+        let tokenizer = |name: &str, disambig: &str| {
+            tokenizer_with_extra_tags(
+                name,
+                "foo\tfoo\tF1\nfoo\tfoo\tF2\nbar\tbar\tB1\nbar\tbar\tB2\n\
+                 baz\tbaz\tZ1\nbaz\tbaz\tZ2\n",
+                &format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="MIDDLE" name="only the middle token is marked">
+        <pattern>
+            <token>foo</token>
+            <marker>
+                <token>bar</token>
+            </marker>
+            <token>baz</token>
+        </pattern>
+        {}
+    </rule>
+</rules>"#,
+                    disambig
+                ),
+                &["B3".to_string()],
+            )
+        };
+
+        let cases = [
+            (
+                "marker_filter",
+                r#"<disambig action="filter" postag="B1"/>"#,
+                vec!["B1"],
+            ),
+            (
+                "marker_remove",
+                r#"<disambig action="remove" postag="B1"/>"#,
+                vec!["B2"],
+            ),
+            (
+                "marker_add",
+                r#"<disambig action="add"><wd lemma="bar" pos="B3"/></disambig>"#,
+                vec!["B1", "B2", "B3"],
+            ),
+            (
+                "marker_replace",
+                r#"<disambig action="replace"><wd lemma="bar" pos="B3"/></disambig>"#,
+                vec!["B3"],
+            ),
+        ];
+
+        for (name, disambig, expected) in cases.iter() {
+            let tokenizer = tokenizer(name, disambig);
+
+            assert_eq!(&tags_of(&tokenizer, "foo bar baz", 4), expected, "{}", name);
+            assert_eq!(tags_of(&tokenizer, "foo bar baz", 0), vec!["F1", "F2"]);
+            assert_eq!(tags_of(&tokenizer, "foo bar baz", 8), vec!["Z1", "Z2"]);
+        }
+    }
+
+    #[test]
+    fn word_data_actions() {
+        let tokenizer = |name: &str, disambig: &str| {
+            tokenizer_with_extra_tags(
+                name,
+                "saw\tsee\tVBD\nsaw\tsaw\tNN\nsaw\tsaw\tVB\n",
+                &format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="SAW" name="the readings of saw">
+        <pattern>
+            <token>saw</token>
+        </pattern>
+        {}
+    </rule>
+</rules>"#,
+                    disambig
+                ),
+                &["NNS".to_string()],
+            )
+        };
+
+        let cases = [
+            (
+                "wd_add",
+                r#"<disambig action="add"><wd lemma="saw" pos="NNS"/></disambig>"#,
+                vec!["saw/NN", "saw/NNS", "saw/VB", "see/VBD"],
+            ),
+            (
+                "wd_remove_lemma",
+                r#"<disambig action="remove"><wd lemma="see"/></disambig>"#,
+                vec!["saw/NN", "saw/VB"],
+            ),
+            (
+                "wd_remove_pos",
+                r#"<disambig action="remove"><wd pos="VB"/></disambig>"#,
+                vec!["saw/NN", "see/VBD"],
+            ),
+            (
+                "wd_remove_lemma_and_pos",
+                r#"<disambig action="remove"><wd lemma="saw" pos="NN"/></disambig>"#,
+                vec!["saw/VB", "see/VBD"],
+            ),
+            (
+                "wd_replace",
+                r#"<disambig action="replace"><wd lemma="saw" pos="NNS"/></disambig>"#,
+                vec!["saw/NNS"],
+            ),
+        ];
+
+        for (name, disambig, expected) in cases.iter() {
+            let tokenizer = tokenizer(name, disambig);
+            assert_eq!(&readings_of(&tokenizer, "I saw", 2), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn match_references_in_actions() {
+        let build = |name: &str, disambig: &str| {
+            tokenizer_with_extra_tags(
+                name,
+                "saw\tsee\tVBD\nsaw\tsaw\tNN\ndog\tdog\tNN\n",
+                &format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="SAW_DOG" name="refers to the first token">
+        <pattern>
+            <token>saw</token>
+            <marker>
+                <token>dog</token>
+            </marker>
+        </pattern>
+        {}
+    </rule>
+</rules>"#,
+                    disambig
+                ),
+                &["NNS".to_string()],
+            )
+        };
+
+        // the lemma is the text of the first token
+        let tokenizer = build(
+            "reference_lemma",
+            r#"<disambig action="replace"><wd lemma="\1" pos="NNS"/></disambig>"#,
+        );
+        assert_eq!(readings_of(&tokenizer, "Saw dog", 4), vec!["Saw/NNS"]);
+
+        let tokenizer = build(
+            "reference_lemma_converted",
+            r#"<disambig action="replace"><wd lemma="\1" pos="NNS" case_conversion="alllower"/></disambig>"#,
+        );
+        assert_eq!(readings_of(&tokenizer, "Saw dog", 4), vec!["saw/NNS"]);
+
+        // the tags are copied from the readings of the first token
+        let tokenizer = build(
+            "reference_pos",
+            r#"<disambig action="replace"><wd lemma="dog" pos="\1"/></disambig>"#,
+        );
+        assert_eq!(
+            readings_of(&tokenizer, "saw dog", 4),
+            vec!["dog/NN", "dog/VBD"]
+        );
+        assert_eq!(
+            readings_of(&tokenizer, "saw dog", 0),
+            vec!["saw/NN", "see/VBD"]
+        );
+    }
+
+    #[test]
+    fn match_reference_outside_pattern() {
+        let path = temp_dir("match_reference_outside_pattern").join("disambiguation.xml");
+        fs::write(
+            &path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="de">
+    <rule id="REFERENCE_OUTSIDE" name="the pattern has one token">
+        <pattern>
+            <token>Katze</token>
+        </pattern>
+        <disambig action="replace"><wd lemma="\2" pos="SUB"/></disambig>
+    </rule>
+</rules>"#,
+        )
+        .unwrap();
+        let mut build_info = german_build_info("match_reference_outside_pattern");
+
+        match Tokenizer::from_xml(&path, &mut build_info, None, TokenizerOptions::default()) {
+            Err(Error::Disambiguator(error)) => {
+                assert_eq!(error.id.as_deref(), Some("REFERENCE_OUTSIDE"));
+                assert!(error.message.contains("outside the pattern"), "{}", error);
+            }
+            x => panic!("unexpected result: {:?}", x.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn word_data_without_pos() {
+        let path = temp_dir("word_data_without_pos").join("disambiguation.xml");
+        fs::write(
+            &path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="de">
+    <rule id="ADD_WITHOUT_POS" name="only remove can match any pos">
+        <pattern>
+            <token>Katze</token>
+        </pattern>
+        <disambig action="add"><wd lemma="Katze"/></disambig>
+    </rule>
+</rules>"#,
+        )
+        .unwrap();
+        let mut build_info = german_build_info("word_data_without_pos");
+
+        match Tokenizer::from_xml(&path, &mut build_info, None, TokenizerOptions::default()) {
+            Err(Error::Disambiguator(error)) => {
+                assert_eq!(error.id.as_deref(), Some("ADD_WITHOUT_POS"));
+                assert!(error.message.contains("without `pos`"), "{}", error);
+            }
+            x => panic!("unexpected result: {:?}", x.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn unify_keeps_agreeing_readings() {
+        let tokenizer = tokenizer_from_xml(
+            "unify",
+            "mit\tmit\tPRP\n\
+             kleinen\tklein\tADJ:DAT:PLU:NEU\nkleinen\tklein\tADJ:AKK:SIN:MAS\n\
+             kleinen\tklein\tADJ:GEN:SIN:MAS\n\
+             Kindern\tKind\tSUB:DAT:PLU:NEU\n\
+             Hund\tHund\tSUB:NOM:SIN:MAS\nHund\tHund\tSUB:AKK:SIN:MAS\nHund\tHund\tSUB:DAT:SIN:MAS\n\
+             Frau\tFrau\tSUB:NOM:SIN:FEM\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="de">
+    <unification feature="case">
+        <equivalence type="nom">
+            <token postag="(ADJ|SUB):NOM.*" postag_regexp="yes"/>
+        </equivalence>
+        <equivalence type="akk">
+            <token postag="(ADJ|SUB):AKK.*" postag_regexp="yes"/>
+        </equivalence>
+        <equivalence type="dat">
+            <token postag="(ADJ|SUB):DAT.*" postag_regexp="yes"/>
+        </equivalence>
+        <equivalence type="gen">
+            <token postag="(ADJ|SUB):GEN.*" postag_regexp="yes"/>
+        </equivalence>
+    </unification>
+    <rule id="ADJ_SUB_CASE" name="an adjective before a noun agrees with it in case">
+        <pattern>
+            <marker>
+                <unify>
+                    <feature id="case"/>
+                    <token postag="ADJ.*" postag_regexp="yes"/>
+                    <token postag="SUB.*" postag_regexp="yes"/>
+                </unify>
+            </marker>
+        </pattern>
+        <disambig action="unify"/>
+    </rule>
+</rules>"#,
+        );
+
+        // the noun is only dative
+        let text = "mit kleinen Kindern";
+        assert_eq!(tags_of(&tokenizer, text, 4), vec!["ADJ:DAT:PLU:NEU"]);
+        assert_eq!(tags_of(&tokenizer, text, 12), vec!["SUB:DAT:PLU:NEU"]);
+
+        // accusative and dative agree
+        let text = "einen kleinen Hund";
+        assert_eq!(
+            tags_of(&tokenizer, text, 6),
+            vec!["ADJ:AKK:SIN:MAS", "ADJ:DAT:PLU:NEU"]
+        );
+        assert_eq!(
+            tags_of(&tokenizer, text, 14),
+            vec!["SUB:AKK:SIN:MAS", "SUB:DAT:SIN:MAS"]
+        );
+
+        // no agreement, nothing is changed
+        let text = "einer kleinen Frau";
+        assert_eq!(
+            tags_of(&tokenizer, text, 6),
+            vec!["ADJ:AKK:SIN:MAS", "ADJ:DAT:PLU:NEU", "ADJ:GEN:SIN:MAS"]
+        );
+        assert_eq!(tags_of(&tokenizer, text, 14), vec!["SUB:NOM:SIN:FEM"]);
+    }
+
+    #[test]
+    fn partial_pos_tag_filter() {
+        let build = |name: &str, extra_args: &str| {
+            tokenizer_from_xml(
+                name,
+                "the\tthe\tDT\nbuilding\tbuilding\tNN\nbuilding\tbuild\tVBG\nbuild\tbuild\tVB\n\
+                 evening\tevening\tNN\nevening\teven\tVBG\neven\teven\tRB\n",
+                &format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="ING_NOUN" name="an -ing word after the is a noun if it is derived from a verb">
+        <pattern>
+            <token>the</token>
+            <marker>
+                <token regexp="yes">.+ing</token>
+            </marker>
+        </pattern>
+        <filter class="org.languagetool.rules.en.NoDisambiguationEnglishPartialPosTagFilter" args="no:2 regexp:(.+)ing postag_regexp:VB.*{}"/>
+        <disambig action="filter" postag="NN"/>
+    </rule>
+</rules>"#,
+                    extra_args
+                ),
+            )
+        };
+
+        let tokenizer = build("partial_pos_tag_filter", "");
+        assert_eq!(tags_of(&tokenizer, "the building", 4), vec!["NN"]);
+        assert_eq!(tags_of(&tokenizer, "the evening", 4), vec!["NN", "VBG"]);
+
+        let tokenizer = build("partial_pos_tag_filter_negated", " negate_postag:yes");
+        assert_eq!(tags_of(&tokenizer, "the building", 4), vec!["NN", "VBG"]);
+        assert_eq!(tags_of(&tokenizer, "the evening", 4), vec!["NN"]);
+    }
+
+    #[test]
+    fn invalid_postag_regexp() {
+        let path = temp_dir("invalid_postag_regexp").join("disambiguation.xml");
+        fs::write(
+            &path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="de">
+    <rule id="BAD_TOKEN_POSTAG" name="a token with an invalid postag regex">
+        <pattern>
+            <token postag="SUB:(NOM" postag_regexp="yes"/>
+        </pattern>
+        <disambig action="filter" postag="SUB"/>
+    </rule>
+    <rule id="BAD_FILTER_POSTAG" name="a filter with an invalid postag regex">
+        <pattern>
+            <token>Katze</token>
+        </pattern>
+        <disambig action="filter" postag="SUB:(NOM"/>
+    </rule>
+</rules>"#,
+        )
+        .unwrap();
+        let mut build_info = german_build_info("invalid_postag_regexp");
+
+        match Tokenizer::from_xml(&path, &mut build_info, None, TokenizerOptions::default()) {
+            Err(Error::Disambiguator(error)) => {
+                assert_eq!(error.id.as_deref(), Some("BAD_TOKEN_POSTAG"));
+                assert!(error.message.contains("SUB:(NOM"), "{}", error);
+            }
+            x => panic!("unexpected result: {:?}", x.map(|_| ())),
+        }
+
+        let tokenizer =
+            Tokenizer::from_xml_lenient(&path, &mut build_info, None, TokenizerOptions::default())
+                .unwrap();
+        let ids: Vec<_> = tokenizer
+            .load_report()
+            .errors()
+            .into_iter()
+            .map(|x| x.id)
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                Some("BAD_TOKEN_POSTAG".to_string()),
+                Some("BAD_FILTER_POSTAG".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn srx_segmentation() {
+        let path = temp_dir("srx").join("segment.srx");
+        fs::write(
+            &path,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<srx xmlns="http://www.lisa.org/srx20" version="2.0">
+    <header segmentsubflows="yes" cascade="yes"/>
+    <body>
+        <languagerules>
+            <languagerule languagerulename="German">
+                <rule break="no">
+                    <beforebreak>\b(?:z\.\s?B|Dr|usw|bzw)\.</beforebreak>
+                    <afterbreak>\s</afterbreak>
+                </rule>
+                <rule break="no">
+                    <beforebreak>\bz\.</beforebreak>
+                    <afterbreak>\s?B\.</afterbreak>
+                </rule>
+                <rule break="no">
+                    <beforebreak>\p{javaLowerCase}\.</beforebreak>
+                    <afterbreak>\s</afterbreak>
+                </rule>
+            </languagerule>
+            <languagerule languagerulename="Default">
+                <rule break="yes">
+                    <beforebreak>[.?!…]+[»“”"]?</beforebreak>
+                    <afterbreak>\s+\P{Ll}</afterbreak>
+                </rule>
+            </languagerule>
+        </languagerules>
+        <maprules>
+            <languagemap languagepattern="de.*" languagerulename="German"/>
+            <languagemap languagepattern=".*" languagerulename="Default"/>
+        </maprules>
+    </body>
+</srx>"#,
+        )
+        .unwrap();
+
+        let text = "Er sagte: „Komm her!“ Dann ging er... und kam nicht wieder. Das ist z. B. bei Dr. Meier so. Ende.";
+        let sentences = |segmenter: &SrxSegmenter| -> Vec<String> {
+            segmenter
+                .segment(text)
+                .iter()
+                .map(|x| x.trim().to_string())
+                .collect()
+        };
+
+        let german = SrxSegmenter::from_xml(&path, "de-AT").unwrap();
+        // the rule with the java-specific character class is skipped
+        assert_eq!(german.rules().len(), 3);
+        assert_eq!(
+            sentences(&german),
+            vec![
+                "Er sagte: „Komm her!“",
+                "Dann ging er... und kam nicht wieder.",
+                "Das ist z. B. bei Dr. Meier so.",
+                "Ende."
+            ]
+        );
+
+        let english = SrxSegmenter::from_xml(&path, "en").unwrap();
+        assert_eq!(
+            sentences(&english),
+            vec![
+                "Er sagte: „Komm her!“",
+                "Dann ging er... und kam nicht wieder.",
+                "Das ist z.",
+                "B. bei Dr.",
+                "Meier so.",
+                "Ende."
+            ]
+        );
+    }
+
+    #[test]
+    fn abbreviations_file() {
+        let abbreviations = read_abbreviations("# Abkürzungen\nz. B.\n  z.B.\n\nDr.\n");
+
+        let mut abbreviations: Vec<_> = abbreviations.iter().map(|x| x.as_str()).collect();
+        abbreviations.sort_unstable();
+        assert_eq!(abbreviations, vec!["Dr.", "z. B.", "z.B."]);
+    }
+
+    #[test]
+    fn multiwords_file() {
+        let multiwords =
+            read_multiwords("# comment\nNew York\tNNP\n\nNew York City\tNNP \nno tag\n");
+
+        assert_eq!(
+            multiwords,
+            vec![
+                ("New York".to_string(), "NNP".to_string()),
+                ("New York City".to_string(), "NNP".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn case_attribute_matches_token_case() {
+        let tokenizer = tokenizer_from_xml(
+            "case",
+            "us\tus\tPRP\nus\tUS\tNNP\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="UPPER_US" name="uppercase us is the country">
+        <pattern>
+            <token case="upper">us</token>
+        </pattern>
+        <disambig action="filter" postag="NNP"/>
+    </rule>
+    <rule id="CAMEL_US" name="unknown case value">
+        <pattern>
+            <token case="camel">us</token>
+        </pattern>
+        <disambig action="filter" postag="PRP"/>
+    </rule>
+</rules>"#,
+        );
+
+        let text = "Tell us about the US.";
+        assert_eq!(tags_of(&tokenizer, text, 5), vec!["NNP", "PRP"]);
+        assert_eq!(tags_of(&tokenizer, text, 18), vec!["NNP"]);
+        assert_eq!(
+            tokenizer.load_report().rules[1].status,
+            RuleStatus::Skipped("case value \"camel\"".into())
+        );
+    }
+
+    #[test]
+    fn spacebefore() {
+        let build = |name: &str, pattern: &str| {
+            tokenizer_from_xml(
+                name,
+                "foo\tfoo\tNN\nfoo\tfoo\tVB\n",
+                &format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="FOO" name="foo with or without space">
+        <pattern>
+            {}
+        </pattern>
+        <disambig action="filter" postag="NN"/>
+    </rule>
+</rules>"#,
+                    pattern
+                ),
+            )
+        };
+
+        let cases = [
+            (
+                "spacebefore_no",
+                r#"<marker><token>foo</token></marker><token spacebefore="no">,</token>"#,
+                vec![("foo, bar", true), ("foo , bar", false)],
+            ),
+            (
+                "spacebefore_yes",
+                r#"<marker><token>foo</token></marker><token spacebefore="yes">,</token>"#,
+                vec![("foo, bar", false), ("foo , bar", true)],
+            ),
+            (
+                // negate only applies to the text, the space is still required to be missing
+                "spacebefore_negated",
+                r#"<marker><token>foo</token></marker><token spacebefore="no" negate="yes">,</token>"#,
+                vec![
+                    ("foo; bar", true),
+                    ("foo, bar", false),
+                    ("foo ; bar", false),
+                ],
+            ),
+            (
+                // the first token has space before if the text starts with whitespace
+                "spacebefore_first_token",
+                r#"<token spacebefore="no">foo</token>"#,
+                vec![("foo bar", true), (" foo bar", false)],
+            ),
+        ];
+
+        for (name, pattern, texts) in cases.iter() {
+            let tokenizer = build(name, pattern);
+
+            for (text, fires) in texts {
+                let expected = if *fires { vec!["NN"] } else { vec!["NN", "VB"] };
+                let start = text.find("foo").unwrap();
+                assert_eq!(
+                    tags_of(&tokenizer, text, start),
+                    expected,
+                    "{}: {:?}",
+                    name,
+                    text
+                );
+            }
+        }
+    }
 }
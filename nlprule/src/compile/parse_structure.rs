@@ -75,6 +75,19 @@ impl BuildInfo {
     }
 }
 
+/// Parses a `case_sensitive` attribute, a missing attribute is `default`.
+fn parse_case_sensitive(value: Option<&str>, default: bool) -> Result<bool, Error> {
+    match value {
+        Some("yes") => Ok(true),
+        Some("no") => Ok(false),
+        None => Ok(default),
+        Some(x) => Err(Error::Unimplemented(format!(
+            "case_sensitive value {:?}",
+            x
+        ))),
+    }
+}
+
 fn parse_match_attribs(
     attribs: impl structure::MatchAttributes,
     text: Option<&str>,
@@ -84,24 +97,12 @@ fn parse_match_attribs(
 ) -> Result<Atom, Error> {
     let mut atoms: Vec<Atom> = Vec::new();
 
-    let case_sensitive = if let Some(case_sensitive) = attribs.case_sensitive() {
-        match case_sensitive.as_str() {
-            "yes" => true,
-            "no" => false,
-            x => panic!("unknown case_sensitive value {}", x),
-        }
-    } else {
-        case_sensitive
-    };
+    let case_sensitive = parse_case_sensitive(attribs.case_sensitive().as_deref(), case_sensitive)?;
 
-    let inflected = if let Some(inflected) = attribs.inflected() {
-        match inflected.as_str() {
-            "yes" => true,
-            "no" => false,
-            x => panic!("unknown inflected value {}", x),
-        }
-    } else {
-        false
+    let inflected = match attribs.inflected().as_deref() {
+        Some("yes") => true,
+        Some("no") | None => false,
+        Some(x) => return Err(Error::Unimplemented(format!("inflected value {:?}", x))),
     };
 
     let is_regex = if let Some(regexp) = attribs.regexp() {
@@ -116,8 +117,8 @@ fn parse_match_attribs(
     // TODO: also reformat is_regex etc., maybe macro?
     let is_postag_regexp = match attribs.postag_regexp().as_deref() {
         Some("yes") => true,
-        None => false,
-        x => panic!("unknown is_postag_regexp value {:?}", x),
+        Some("no") | None => false,
+        Some(x) => return Err(Error::Unimplemented(format!("postag_regexp value {:?}", x))),
     };
 
     let negate = match attribs.negate().as_deref() {
@@ -128,8 +129,8 @@ fn parse_match_attribs(
 
     let negate_pos = match attribs.negate_pos().as_deref() {
         Some("yes") => true,
-        None => false,
-        x => panic!("unknown negate_pos value {:?}", x),
+        Some("no") | None => false,
+        Some(x) => return Err(Error::Unimplemented(format!("negate_pos value {:?}", x))),
     };
 
     let mut inflect_matcher = None;
@@ -165,7 +166,7 @@ fn parse_match_attribs(
 
     if let Some(postag) = attribs.postag() {
         let raw_matcher = if is_postag_regexp {
-            let regex = SerializeRegex::new(&postag.trim(), true, true);
+            let regex = SerializeRegex::new(postag.trim(), true, true);
             Matcher::new_regex(regex?, negate_pos, true)
         } else {
             Matcher::new_string(
@@ -212,25 +213,48 @@ fn parse_match_attribs(
             atoms.push(chunk_atom.into());
         }
         (None, None) => {}
-        _ => panic!("unexpected combination of chunk / chunk_re values."),
+        (Some(_), Some(_)) => {
+            return Err(Error::Unimplemented(
+                "`chunk` and `chunk_re` in the same token".into(),
+            ))
+        }
     }
 
-    if let Some(chunk) = attribs.chunk() {
-        let chunk_atom = ChunkAtom {
-            matcher: Matcher::new_string(either::Left(chunk.trim().to_string()), false, true, true),
+    if let Some(space_before) = attribs.spacebefore() {
+        // the kinds of whitespace are not part of the LT format, they allow rules for e. g. French punctuation spacing
+        let kind = match space_before.as_str() {
+            "yes" | "no" => None,
+            "space" => Some(SpaceKind::Space),
+            "nobreak" => Some(SpaceKind::NoBreak),
+            "narrownobreak" => Some(SpaceKind::NarrowNoBreak),
+            "thin" => Some(SpaceKind::Thin),
+            "linebreak" => Some(SpaceKind::LineBreak),
+            x => return Err(Error::Unimplemented(format!("spacebefore value {:?}", x))),
         };
 
-        atoms.push(chunk_atom.into());
+        match kind {
+            Some(value) => atoms.push((SpaceKindAtom { value }).into()),
+            None => atoms.push(
+                (SpaceBeforeAtom {
+                    value: space_before == "yes",
+                })
+                .into(),
+            ),
+        }
     }
 
-    if let Some(space_before) = attribs.spacebefore() {
-        let value = match space_before.as_str() {
-            "yes" => true,
-            "no" => false,
-            _ => panic!("unknown spacebefore value {}", space_before),
+    // not part of the LT format, allows rules to test the case of the original token text
+    if let Some(case) = attribs.case() {
+        let value = match case.as_str() {
+            "lower" => Case::Lower,
+            "title" => Case::Title,
+            "upper" => Case::Upper,
+            "mixed" => Case::Mixed,
+            "nonalpha" => Case::NonAlpha,
+            x => return Err(Error::Unimplemented(format!("case value {:?}", x))),
         };
 
-        atoms.push((SpaceBeforeAtom { value }).into());
+        atoms.push((CaseAtom { value }).into());
     }
 
     Ok(AndAtom::and(atoms))
@@ -249,43 +273,53 @@ fn get_exceptions(
                 structure::TokenPart::Exception(x) => Some(x),
                 _ => None,
             })
-            .filter_map(|x| {
-                let exception_text = if let Some(exception_text) = &x.text {
-                    Some(exception_text.as_str())
-                } else {
-                    None
-                };
-                let mut atom =
-                    parse_match_attribs(x, exception_text, case_sensitive, None, info).unwrap();
-
-                let offset = if let Some(scope) = &x.scope {
-                    match scope.as_str() {
-                        "next" => 1,
-                        "current" => 0,
-                        "previous" => -1,
-                        _ => panic!("unknown scope value {}", scope),
+            .map(|x| {
+                let exception_text = x
+                    .text
+                    .as_ref()
+                    .map(|exception_text| exception_text.as_str());
+                let mut atom = parse_match_attribs(x, exception_text, case_sensitive, None, info)?;
+
+                let offset = match x.scope.as_deref() {
+                    Some("next") => 1,
+                    Some("current") | None => 0,
+                    Some("previous") => -1,
+                    Some(x) => {
+                        return Err(Error::Unimplemented(format!("exception scope {:?}", x)))
                     }
-                } else {
-                    0
                 };
 
                 if offset != 0 {
                     atom = OffsetAtom::new(atom, offset).into();
                 }
 
-                if !only_shifted || (offset != 0) {
+                Ok(if !only_shifted || (offset != 0) {
                     Some(atom)
                 } else {
                     None
-                }
+                })
             })
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
+            .flatten()
+            .collect();
         Ok(NotAtom::not(OrAtom::or(exceptions)))
     } else {
         Ok((TrueAtom {}).into())
     }
 }
 
+/// Parses the value of a `min`, `max` or `skip` attribute where -1 means unlimited.
+fn parse_count(value: &str, attribute: &str) -> Result<usize, Error> {
+    if value == "-1" {
+        Ok(max_matches())
+    } else {
+        value
+            .parse()
+            .map_err(|_| Error::Unexpected(format!("invalid {} value {:?}", attribute, value)))
+    }
+}
+
 fn parse_token(
     token: &structure::Token,
     case_sensitive: bool,
@@ -312,31 +346,23 @@ fn parse_token(
 
     let min = token
         .min
-        .clone()
-        .map(|x| {
-            if x == "-1" {
-                max_matches()
-            } else {
-                x.parse().expect("can't parse min as usize")
-            }
-        })
+        .as_deref()
+        .map(|x| parse_count(x, "min"))
+        .transpose()?
         .unwrap_or(1usize);
     let mut max = token
         .max
-        .clone()
-        .map(|x| {
-            if x == "-1" {
-                max_matches()
-            } else {
-                x.parse().expect("can't parse max as usize")
-            }
-        })
+        .as_deref()
+        .map(|x| parse_count(x, "max"))
+        .transpose()?
         .unwrap_or(1usize);
     if min > 1 && max == 1 {
         max = max_matches();
     }
 
     let quantifier = Quantifier::new(min, max);
+    // exceptions inherit the case sensitivity of the token
+    let case_sensitive = parse_case_sensitive(token.case_sensitive.as_deref(), case_sensitive)?;
     let mut atom = parse_match_attribs(token, text, case_sensitive, text_match_idx, info)?;
     atom = AndAtom::and(vec![
         atom,
@@ -350,12 +376,8 @@ fn parse_token(
         greedy: true,
     });
 
-    if let Some(to_skip) = token.skip.clone() {
-        let to_skip = if to_skip == "-1" {
-            max_matches()
-        } else {
-            to_skip.parse().expect("can't parse skip as usize or -1")
-        };
+    if let Some(to_skip) = token.skip.as_deref() {
+        let to_skip = parse_count(to_skip, "skip")?;
 
         parts.push(Part {
             atom: get_exceptions(token, case_sensitive, true, info)?,
@@ -401,12 +423,6 @@ fn parse_match(
         }
     }
 
-    let case_conversion = if let Some(conversion) = &m.case_conversion {
-        Some(conversion.as_str())
-    } else {
-        None
-    };
-
     let pos_replacer = if let Some(postag) = m.postag {
         if postag.contains("+DT") || postag.contains("+INDT") {
             return Err(Error::Unimplemented(
@@ -439,24 +455,28 @@ fn parse_match(
 
     Ok(Match {
         id,
-        conversion: match case_conversion {
-            Some("alllower") => Conversion::AllLower,
-            Some("startlower") => Conversion::StartLower,
-            Some("startupper") => Conversion::StartUpper,
-            Some("allupper") => Conversion::AllUpper,
-            Some(x) => {
-                return Err(Error::Unimplemented(format!(
-                    "case conversion {} not supported.",
-                    x
-                )))
-            }
-            None => Conversion::Nop,
-        },
+        conversion: parse_conversion(m.case_conversion.as_deref())?,
         pos_replacer,
         regex_replacer,
     })
 }
 
+fn parse_conversion(case_conversion: Option<&str>) -> Result<Conversion, Error> {
+    Ok(match case_conversion {
+        Some("alllower") => Conversion::AllLower,
+        Some("startlower") => Conversion::StartLower,
+        Some("startupper") => Conversion::StartUpper,
+        Some("allupper") => Conversion::AllUpper,
+        Some(x) => {
+            return Err(Error::Unimplemented(format!(
+                "case conversion {} not supported.",
+                x
+            )))
+        }
+        None => Conversion::Nop,
+    })
+}
+
 fn parse_synthesizer_text(text: &str) -> Vec<SynthesizerPart> {
     lazy_static! {
         static ref MATCH_REGEX: Regex = Regex::new(r"\\(\d)").unwrap();
@@ -465,11 +485,11 @@ fn parse_synthesizer_text(text: &str) -> Vec<SynthesizerPart> {
     let mut parts = Vec::new();
     let mut end_index = 0;
 
-    for capture in MATCH_REGEX.captures_iter(&text) {
+    for capture in MATCH_REGEX.captures_iter(text) {
         let (start, end) = capture.pos(0).unwrap();
 
         if end_index != start {
-            parts.push(SynthesizerPart::Text((&text[end_index..start]).to_string()))
+            parts.push(SynthesizerPart::Text(text[end_index..start].to_string()))
         }
 
         let index = capture
@@ -488,7 +508,7 @@ fn parse_synthesizer_text(text: &str) -> Vec<SynthesizerPart> {
     }
 
     if end_index < text.len() {
-        parts.push(SynthesizerPart::Text((&text[end_index..]).to_string()))
+        parts.push(SynthesizerPart::Text(text[end_index..].to_string()))
     }
     parts
 }
@@ -629,10 +649,7 @@ fn parse_pattern(
     let mut end = None;
 
     let mut composition_parts = Vec::new();
-    let case_sensitive = match &pattern.case_sensitive {
-        Some(string) => string == "yes",
-        None => false,
-    };
+    let case_sensitive = parse_case_sensitive(pattern.case_sensitive.as_deref(), false)?;
 
     for part in &pattern.parts {
         match part {
@@ -716,11 +733,7 @@ impl Rule {
                 ))
             }
             (None, Some(regex)) => {
-                let case_sensitive = match regex.case_sensitive.as_deref() {
-                    Some("yes") => true,
-                    None => false,
-                    x => panic!("unknown case_sensitive value {:?}", x),
-                };
+                let case_sensitive = parse_case_sensitive(regex.case_sensitive.as_deref(), false)?;
                 let mark = regex.mark.map_or(0, |x| x.parse().unwrap());
                 let regex = SerializeRegex::new(&regex.text, false, case_sensitive)?;
                 let id_to_idx: DefaultHashMap<usize, usize> =
@@ -896,39 +909,119 @@ fn parse_tag_form(form: &str, info: &mut BuildInfo) -> owned::Word {
     }
 }
 
-impl owned::WordData {
-    fn from_structure(data: structure::WordData, info: &mut BuildInfo) -> Self {
-        owned::WordData::new(
+/// Parses a reference like `\1` to the token with the id, which must be in the pattern with `last_id` tokens.
+fn parse_reference(value: &str, last_id: usize) -> Result<Option<usize>, Error> {
+    let id = match value.strip_prefix('\\') {
+        Some(id) if !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) => id,
+        _ => return Ok(None),
+    };
+
+    match id.parse::<usize>() {
+        Ok(id) if (1..=last_id).contains(&id) => Ok(Some(id)),
+        _ => Err(Error::Unexpected(format!(
+            "reference {:?} to a token outside the pattern",
+            value
+        ))),
+    }
+}
+
+impl ReadingTemplate {
+    /// A `lemma` or `pos` like `\1` refers to a matched token. A missing `pos` becomes [PosId::EMPTY] and a missing
+    /// lemma the empty lemma, which `remove` treats as wildcards.
+    fn from_structure(
+        data: structure::WordData,
+        last_id: usize,
+        info: &mut BuildInfo,
+    ) -> Result<Self, Error> {
+        let lemma = data.lemma.unwrap_or_default();
+        let pos = data.pos.as_deref().map_or("", str::trim);
+
+        let lemma_ref = match (
+            parse_reference(&lemma, last_id)?,
+            parse_conversion(data.case_conversion.as_deref())?,
+        ) {
+            (Some(id), conversion) => Some((id, conversion)),
+            (None, Conversion::Nop) => None,
+            (None, _) => {
+                return Err(Error::Unexpected(
+                    "`case_conversion` in `wd` without a reference in `lemma`".into(),
+                ))
+            }
+        };
+        let pos_ref = parse_reference(pos, last_id)?;
+
+        let lemma = if lemma_ref.is_some() {
+            ""
+        } else {
+            lemma.as_str()
+        };
+        let pos = if pos_ref.is_some() {
+            PosId::EMPTY.to_owned_id()
+        } else {
             info.tagger
-                .id_word(data.lemma.unwrap_or_else(String::new).into())
-                .to_owned_id(),
-            info.tagger.id_tag(data.pos.as_str().trim()).to_owned_id(),
-        )
+                .tag_store()
+                .id(pos)
+                .ok_or_else(|| Error::UnknownTag(pos.to_string()))?
+                .to_owned_id()
+        };
+
+        Ok(ReadingTemplate {
+            data: owned::WordData::new(info.tagger.id_word(lemma.into()).to_owned_id(), pos),
+            lemma_ref,
+            pos_ref,
+        })
+    }
+
+    /// The reading of a `wd` in an action which does not resolve references.
+    fn into_word_data(self, action: &str) -> Result<owned::WordData, Error> {
+        if self.lemma_ref.is_some() || self.pos_ref.is_some() {
+            Err(Error::Unimplemented(format!(
+                "references in `wd` of `{}`",
+                action
+            )))
+        } else {
+            Ok(self.data)
+        }
+    }
+
+    /// Only `remove` can match readings with any part-of-speech tag, the other actions need a `pos`.
+    fn require_pos(self, action: &str) -> Result<Self, Error> {
+        if self.pos_ref.is_none() && self.data.pos.as_ref_id() == PosId::EMPTY {
+            Err(Error::Unexpected(format!(
+                "`wd` without `pos` in `{}`",
+                action
+            )))
+        } else {
+            Ok(self)
+        }
     }
 }
 
-fn parse_pos_filter(postag: &str, postag_regexp: Option<&str>, info: &mut BuildInfo) -> POSFilter {
-    match postag_regexp.as_deref() {
+fn parse_pos_filter(
+    postag: &str,
+    postag_regexp: Option<&str>,
+    info: &mut BuildInfo,
+) -> Result<POSFilter, Error> {
+    Ok(match postag_regexp {
         Some("yes") => POSFilter::new(PosMatcher::new(
-            Matcher::new_regex(
-                SerializeRegex::new(&postag, true, true).unwrap(),
-                false,
-                true,
-            ),
+            Matcher::new_regex(SerializeRegex::new(postag, true, true)?, false, true),
             info,
         )),
         Some(_) | None => POSFilter::new(PosMatcher::new(
             Matcher::new_string(either::Left(postag.into()), false, false, true),
             info,
         )),
-    }
+    })
 }
 
+/// The filters of the unified features, the filters to disambiguate with and the mask of the unified tokens.
+type UnifyParts = (Vec<Vec<POSFilter>>, Vec<Option<POSFilter>>, Vec<bool>);
+
 fn parse_unify(
     unify: &structure::Unify,
     unifications: &Option<Vec<structure::Unification>>,
     info: &mut BuildInfo,
-) -> (Vec<Vec<POSFilter>>, Vec<Option<POSFilter>>, Vec<bool>) {
+) -> Result<UnifyParts, Error> {
     let mut filters = Vec::new();
     let mut disambig = Vec::new();
     let mut mask = Vec::new();
@@ -955,16 +1048,20 @@ fn parse_unify(
                                 info,
                             )
                         })
-                        .collect(),
+                        .collect::<Result<_, _>>()?,
                 );
             }
             structure::UnifyTokenCombination::And(tokens)
             | structure::UnifyTokenCombination::Or(tokens) => {
                 mask.push(true);
                 disambig.push(
-                    tokens.tokens[0].postag.as_ref().map(|x| {
-                        parse_pos_filter(x, tokens.tokens[0].postag_regexp.as_deref(), info)
-                    }),
+                    tokens.tokens[0]
+                        .postag
+                        .as_ref()
+                        .map(|x| {
+                            parse_pos_filter(x, tokens.tokens[0].postag_regexp.as_deref(), info)
+                        })
+                        .transpose()?,
                 )
             }
             structure::UnifyTokenCombination::Token(token) => {
@@ -973,7 +1070,8 @@ fn parse_unify(
                     token
                         .postag
                         .as_ref()
-                        .map(|x| parse_pos_filter(x, token.postag_regexp.as_deref(), info)),
+                        .map(|x| parse_pos_filter(x, token.postag_regexp.as_deref(), info))
+                        .transpose()?,
                 )
             }
             structure::UnifyTokenCombination::Ignore(tokens) => {
@@ -982,20 +1080,34 @@ fn parse_unify(
                         structure::TokenCombination::And(tokens)
                         | structure::TokenCombination::Or(tokens) => {
                             mask.push(false);
-                            disambig.push(tokens.tokens[0].postag.as_ref().map(|x| {
-                                parse_pos_filter(x, tokens.tokens[0].postag_regexp.as_deref(), info)
-                            }))
+                            disambig.push(
+                                tokens.tokens[0]
+                                    .postag
+                                    .as_ref()
+                                    .map(|x| {
+                                        parse_pos_filter(
+                                            x,
+                                            tokens.tokens[0].postag_regexp.as_deref(),
+                                            info,
+                                        )
+                                    })
+                                    .transpose()?,
+                            )
                         }
                         structure::TokenCombination::Token(token) => {
                             mask.push(false);
                             disambig.push(
-                                token.postag.as_ref().map(|x| {
-                                    parse_pos_filter(x, token.postag_regexp.as_deref(), info)
-                                }),
+                                token
+                                    .postag
+                                    .as_ref()
+                                    .map(|x| {
+                                        parse_pos_filter(x, token.postag_regexp.as_deref(), info)
+                                    })
+                                    .transpose()?,
                             )
                         }
                         structure::TokenCombination::Unify(_) => {
-                            panic!("nested unify not supported")
+                            return Err(Error::Unimplemented("nested `unify`".into()))
                         }
                     }
                 }
@@ -1003,7 +1115,7 @@ fn parse_unify(
         }
     }
 
-    (filters, disambig, mask)
+    Ok((filters, disambig, mask))
 }
 
 impl DisambiguationRule {
@@ -1013,6 +1125,7 @@ impl DisambiguationRule {
     ) -> Result<DisambiguationRule, Error> {
         // might need the pattern later so clone it here
         let (composition, start, end) = parse_pattern(data.pattern.clone(), info)?;
+        let last_id = get_last_id(&composition.parts) as usize - 1;
 
         let antipatterns = if let Some(antipatterns) = data.antipatterns {
             antipatterns
@@ -1027,15 +1140,14 @@ impl DisambiguationRule {
             wds.into_iter()
                 .map(|part| match part {
                     structure::DisambiguationPart::WordData(x) => {
-                        either::Left(owned::WordData::from_structure(x, info))
+                        ReadingTemplate::from_structure(x, last_id, info).map(either::Left)
+                    }
+                    structure::DisambiguationPart::Match(x) => {
+                        parse_pos_filter(&x.postag.unwrap(), x.postag_regexp.as_deref(), info)
+                            .map(either::Right)
                     }
-                    structure::DisambiguationPart::Match(x) => either::Right(parse_pos_filter(
-                        &x.postag.unwrap(),
-                        x.postag_regexp.as_deref(),
-                        info,
-                    )),
                 })
-                .collect()
+                .collect::<Result<_, Error>>()?
         } else {
             Vec::new()
         };
@@ -1044,27 +1156,39 @@ impl DisambiguationRule {
             Some("remove") => {
                 if let Some(postag) = data.disambig.postag.as_ref() {
                     Ok(Disambiguation::Remove(vec![either::Right(
-                        parse_pos_filter(postag, Some("yes"), info),
+                        parse_pos_filter(postag, Some("yes"), info)?,
                     )]))
                 } else {
-                    Ok(Disambiguation::Remove(word_datas.into_iter().collect()))
+                    Ok(Disambiguation::Remove(
+                        word_datas
+                            .into_iter()
+                            .map(|x| match x {
+                                either::Left(x) => x.into_word_data("remove").map(either::Left),
+                                either::Right(x) => Ok(either::Right(x)),
+                            })
+                            .collect::<Result<_, _>>()?,
+                    ))
                 }
             }
             Some("add") => {
                 if data.disambig.postag.is_some() {
-                    panic!("postag not supported for `add`.")
+                    return Err(Error::Unimplemented("postag in `add`".into()));
                 }
 
                 Ok(Disambiguation::Add(
                     word_datas
                         .into_iter()
-                        .map(|x| x.left().expect("match not supported for `add`"))
-                        .collect(),
+                        .map(|x| {
+                            x.left()
+                                .ok_or_else(|| Error::Unimplemented("match in `add`".into()))
+                                .and_then(|x| x.require_pos("add"))
+                        })
+                        .collect::<Result<_, _>>()?,
                 ))
             }
             Some("replace") => {
                 if data.disambig.postag.is_some() {
-                    panic!("postag not supported for `replace`.")
+                    return Err(Error::Unimplemented("postag in `replace`".into()));
                 }
 
                 Ok(Disambiguation::Replace(
@@ -1072,9 +1196,10 @@ impl DisambiguationRule {
                         .into_iter()
                         .map(|x| {
                             x.left()
-                                .expect("match not supported for `replace` disambiguation")
+                                .ok_or_else(|| Error::Unimplemented("match in `replace`".into()))
+                                .and_then(|x| x.require_pos("replace"))
                         })
-                        .collect(),
+                        .collect::<Result<_, _>>()?,
                 ))
             }
             Some("ignore_spelling") => Ok(Disambiguation::Nop), // ignore_spelling can be ignored since we dont check spelling
@@ -1094,40 +1219,55 @@ impl DisambiguationRule {
                                     structure::TokenCombination::And(tokens)
                                     | structure::TokenCombination::Or(tokens) => &tokens.tokens[0],
                                     structure::TokenCombination::Unify(_) => {
-                                        panic!("`unify` not supported in `filterall`")
+                                        return Err(Error::Unimplemented(
+                                            "`unify` in `filterall`".into(),
+                                        ))
                                     }
                                 };
 
-                                marker_disambig.push(token.postag.as_ref().map(|x| {
-                                    either::Right(parse_pos_filter(
-                                        x,
-                                        token.postag_regexp.as_deref(),
-                                        info,
-                                    ))
-                                }));
+                                marker_disambig.push(
+                                    token
+                                        .postag
+                                        .as_ref()
+                                        .map(|x| {
+                                            parse_pos_filter(
+                                                x,
+                                                token.postag_regexp.as_deref(),
+                                                info,
+                                            )
+                                            .map(either::Right)
+                                        })
+                                        .transpose()?,
+                                );
                             }
                         }
-                        structure::PatternPart::Token(token) => {
-                            disambig.push(token.postag.as_ref().map(|x| {
-                                either::Right(parse_pos_filter(
-                                    x,
-                                    token.postag_regexp.as_deref(),
-                                    info,
-                                ))
-                            }))
-                        }
+                        structure::PatternPart::Token(token) => disambig.push(
+                            token
+                                .postag
+                                .as_ref()
+                                .map(|x| {
+                                    parse_pos_filter(x, token.postag_regexp.as_deref(), info)
+                                        .map(either::Right)
+                                })
+                                .transpose()?,
+                        ),
                         structure::PatternPart::And(tokens)
-                        | structure::PatternPart::Or(tokens) => {
-                            disambig.push(tokens.tokens[0].postag.as_ref().map(|x| {
-                                either::Right(parse_pos_filter(
-                                    x,
-                                    tokens.tokens[0].postag_regexp.as_deref(),
-                                    info,
-                                ))
-                            }))
-                        }
+                        | structure::PatternPart::Or(tokens) => disambig.push(
+                            tokens.tokens[0]
+                                .postag
+                                .as_ref()
+                                .map(|x| {
+                                    parse_pos_filter(
+                                        x,
+                                        tokens.tokens[0].postag_regexp.as_deref(),
+                                        info,
+                                    )
+                                    .map(either::Right)
+                                })
+                                .transpose()?,
+                        ),
                         structure::PatternPart::Unify(_) => {
-                            panic!("`unify` not supported in `filterall`")
+                            return Err(Error::Unimplemented("`unify` in `filterall`".into()))
                         }
                     }
                 }
@@ -1145,11 +1285,20 @@ impl DisambiguationRule {
             Some("filter") => {
                 if let Some(postag) = data.disambig.postag.as_ref() {
                     Ok(Disambiguation::Filter(vec![Some(either::Right(
-                        parse_pos_filter(postag, Some("yes"), info),
+                        parse_pos_filter(postag, Some("yes"), info)?,
                     ))]))
                 } else {
                     Ok(Disambiguation::Filter(
-                        word_datas.into_iter().map(Some).collect(),
+                        word_datas
+                            .into_iter()
+                            .map(|x| match x {
+                                either::Left(x) => x
+                                    .require_pos("filter")
+                                    .and_then(|x| x.into_word_data("filter"))
+                                    .map(|x| Some(either::Left(x))),
+                                either::Right(x) => Ok(Some(either::Right(x))),
+                            })
+                            .collect::<Result<_, _>>()?,
                     ))
                 }
             }
@@ -1161,20 +1310,28 @@ impl DisambiguationRule {
                 match &data.pattern.parts[..] {
                     [.., structure::PatternPart::Marker(marker)] => match &marker.tokens[..] {
                         [structure::TokenCombination::Unify(unify)] => {
-                            let (f, d, m) = parse_unify(&unify, &data.unifications, info);
+                            let (f, d, m) = parse_unify(unify, &data.unifications, info)?;
                             filters.extend(f);
                             disambig.extend(d);
                             mask.extend(m);
                         }
-                        _ => panic!("only `unify` as only element in `marker` is implemented"),
+                        _ => {
+                            return Err(Error::Unimplemented(
+                                "`unify` with other elements in `marker`".into(),
+                            ))
+                        }
                     },
                     [structure::PatternPart::Unify(unify)] => {
-                        let (f, d, m) = parse_unify(&unify, &data.unifications, info);
+                        let (f, d, m) = parse_unify(unify, &data.unifications, info)?;
                         filters.extend(f);
                         disambig.extend(d);
                         mask.extend(m);
                     }
-                    _ => panic!("only `unify` as only element in `pattern` is implemented"),
+                    _ => {
+                        return Err(Error::Unimplemented(
+                            "`unify` with other elements in `pattern`".into(),
+                        ))
+                    }
                 }
 
                 Ok(Disambiguation::Unify(filters, disambig, mask))
@@ -1184,12 +1341,25 @@ impl DisambiguationRule {
                     Ok(Disambiguation::Filter(vec![Some(either::Left(
                         owned::WordData::new(
                             info.tagger.id_word("".into()).to_owned_id(),
-                            info.tagger.id_tag(postag).to_owned_id(),
+                            info.tagger
+                                .tag_store()
+                                .id(postag)
+                                .ok_or_else(|| Error::UnknownTag(postag.clone()))?
+                                .to_owned_id(),
                         ),
                     ))]))
                 } else {
                     Ok(Disambiguation::Filter(
-                        word_datas.into_iter().map(Some).collect(),
+                        word_datas
+                            .into_iter()
+                            .map(|x| match x {
+                                either::Left(x) => x
+                                    .require_pos("filter")
+                                    .and_then(|x| x.into_word_data("filter"))
+                                    .map(|x| Some(either::Left(x))),
+                                either::Right(x) => Ok(Some(either::Right(x))),
+                            })
+                            .collect::<Result<_, _>>()?,
                     ))
                 }
             }
@@ -1199,15 +1369,17 @@ impl DisambiguationRule {
         let filter = if let Some(filter_data) = data.filter {
             let args = filter_data
                 .args
-                .split(' ')
+                .split_whitespace()
                 .map(|x| {
-                    let idx = x.find(':').unwrap();
-                    (
+                    let idx = x.find(':').ok_or_else(|| {
+                        Error::Unexpected(format!("invalid filter argument {:?}", x))
+                    })?;
+                    Ok((
                         x[..idx].to_string(),
                         x[(idx + ':'.len_utf8())..].to_string(),
-                    )
+                    ))
                 })
-                .collect();
+                .collect::<Result<_, Error>>()?;
 
             Some(get_filter(
                 filter_data.class.split('.').next_back().unwrap(),
@@ -1262,7 +1434,7 @@ impl DisambiguationRule {
                             info,
                         ),
                         after: parse_tag_form(
-                            &example
+                            example
                                 .outputform
                                 .as_ref()
                                 .expect("must have inputform when ambiguous example"),
@@ -1,31 +1,70 @@
+use super::Error;
 use serde::Deserialize;
 use std::fs::File;
 use std::io::BufReader;
 use xml::reader::EventReader;
 
 mod preprocess {
+    use xml::common::{Position, TextPosition};
     use xml::reader::EventReader;
     use xml::writer::EmitterConfig;
 
-    use super::Category;
+    use super::{Category, Location};
 
-    pub fn sanitize(input: impl std::io::Read, whitespace_sensitive_tags: &[&str]) -> String {
+    /// An error with the 1-based line and column in the input, if known.
+    pub type ParseError = (String, Option<(usize, usize)>);
+
+    fn line_column(position: TextPosition) -> (usize, usize) {
+        (position.row as usize + 1, position.column as usize + 1)
+    }
+
+    /// Whether an element is a rule container i. e. a unification, a rule group or a rule which is not in a group.
+    fn is_container(name: &str, parent: Option<&str>) -> bool {
+        name == "unification"
+            || name == "rulegroup"
+            || (name == "rule" && parent != Some("rulegroup"))
+    }
+
+    /// Normalizes the XML for deserialization. Also returns the line and column of each rule container
+    /// in the input since the sanitized XML is indented differently.
+    pub fn sanitize(
+        input: impl std::io::Read,
+        whitespace_sensitive_tags: &[&str],
+    ) -> Result<(String, Vec<(usize, usize)>), ParseError> {
         let mut sanitized = Vec::new();
 
         let mut writer = EmitterConfig::new()
             .perform_indent(true)
             .create_writer(&mut sanitized);
 
-        let parser = EventReader::new(input);
+        let mut parser = EventReader::new(input);
+        let mut events = Vec::new();
+        let mut positions = Vec::new();
+        let mut open: Vec<String> = Vec::new();
 
-        let events = parser
-            .into_iter()
-            .map(|x| x.expect("error reading XML"))
-            .filter(|x| {
+        loop {
+            let event = parser
+                .next()
+                .map_err(|x| (x.msg().to_string(), Some(line_column(x.position()))))?;
+
+            match &event {
+                xml::reader::XmlEvent::EndDocument => break,
                 // processing instructions break the writer and are useless to us
-                !matches!(x, xml::reader::XmlEvent::ProcessingInstruction { .. })
-            })
-            .collect::<Vec<_>>();
+                xml::reader::XmlEvent::ProcessingInstruction { .. } => continue,
+                xml::reader::XmlEvent::StartElement { name, .. } => {
+                    if is_container(&name.local_name, open.last().map(|x| x.as_str())) {
+                        positions.push(line_column(parser.position()));
+                    }
+                    open.push(name.local_name.clone());
+                }
+                xml::reader::XmlEvent::EndElement { .. } => {
+                    open.pop();
+                }
+                _ => {}
+            }
+
+            events.push(event);
+        }
 
         let mut out_events: Vec<xml::writer::XmlEvent> = Vec::new();
         let mut parents = Vec::new();
@@ -47,19 +86,18 @@ mod preprocess {
                     out_events.push(xml::writer::XmlEvent::end_element().into());
                     continue;
                 }
-                xml::reader::XmlEvent::Whitespace(whitespace) => {
+                xml::reader::XmlEvent::Whitespace(whitespace)
                     if parents
                         .iter()
-                        .any(|x| whitespace_sensitive_tags.contains(x))
-                    {
-                        out_events.push(
-                            xml::writer::XmlEvent::start_element("text")
-                                .attr("text", whitespace)
-                                .into(),
-                        );
-                        out_events.push(xml::writer::XmlEvent::end_element().into());
-                        continue;
-                    }
+                        .any(|x| whitespace_sensitive_tags.contains(x)) =>
+                {
+                    out_events.push(
+                        xml::writer::XmlEvent::start_element("text")
+                            .attr("text", whitespace)
+                            .into(),
+                    );
+                    out_events.push(xml::writer::XmlEvent::end_element().into());
+                    continue;
                 }
                 _ => {}
             }
@@ -70,37 +108,31 @@ mod preprocess {
         }
 
         for event in out_events {
-            writer.write(event).expect("error writing to output XML");
+            writer.write(event).map_err(|x| (x.to_string(), None))?;
         }
 
-        std::str::from_utf8(&sanitized)
-            .expect("invalid UTF-8")
-            .to_string()
+        let sanitized = String::from_utf8(sanitized).map_err(|x| (x.to_string(), None))?;
+        Ok((sanitized, positions))
     }
 
-    pub fn extract_rules(mut xml: impl std::io::Read) -> Vec<(String, Option<Category>)> {
-        let mut string = String::new();
-        xml.read_to_string(&mut string)
-            .expect("error writing to string.");
-
-        let document = roxmltree::Document::parse(&string).expect("error parsing XML");
+    /// Extracts the rule containers from the sanitized XML. `positions` are the positions returned by [sanitize].
+    pub fn extract_rules(
+        xml: &str,
+        positions: &[(usize, usize)],
+    ) -> Result<Vec<(String, Option<Category>, Location)>, ParseError> {
+        let document = roxmltree::Document::parse(xml).map_err(|x| (x.to_string(), None))?;
 
-        document
+        Ok(document
             .descendants()
             .filter(|x| {
-                let name = x.tag_name().name();
-
-                name == "unification"
-                    || name == "rulegroup"
-                    || (name == "rule"
-                        && x.parent_element()
-                            .expect("must have parent")
-                            .tag_name()
-                            .name()
-                            != "rulegroup")
+                is_container(
+                    x.tag_name().name(),
+                    x.parent_element().map(|x| x.tag_name().name()),
+                )
             })
-            .map(|x| {
-                let xml = string[x.range()].to_string();
+            .enumerate()
+            .map(|(i, x)| {
+                let xml = xml[x.range()].to_string();
                 let parent = x.parent_element().expect("must have parent");
 
                 let category = if parent.tag_name().name() == "category" {
@@ -114,12 +146,29 @@ mod preprocess {
                     None
                 };
 
-                (xml, category)
+                let location = Location {
+                    id: x.attribute("id").map(|x| x.to_owned()),
+                    line: positions.get(i).map(|x| x.0),
+                    column: positions.get(i).map(|x| x.1),
+                };
+
+                (xml, category, location)
             })
-            .collect()
+            .collect())
     }
 }
 
+/// Where a rule container (a rule, rule group or unification) starts in an XML file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    /// The ID of the rule or rule group.
+    pub id: Option<String>,
+    /// The 1-based line of the start tag.
+    pub line: Option<usize>,
+    /// The 1-based column of the start tag.
+    pub column: Option<usize>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Group {
     pub id: String,
@@ -149,9 +198,9 @@ impl std::ops::Deref for XMLString {
     }
 }
 
-impl std::convert::Into<String> for XMLString {
-    fn into(self) -> String {
-        self.text
+impl std::convert::From<XMLString> for String {
+    fn from(val: XMLString) -> Self {
+        val.text
     }
 }
 
@@ -168,9 +217,9 @@ impl std::ops::Deref for XMLText {
     }
 }
 
-impl std::convert::Into<String> for XMLText {
-    fn into(self) -> String {
-        self.text.into()
+impl std::convert::From<XMLText> for String {
+    fn from(val: XMLText) -> Self {
+        val.text.into()
     }
 }
 
@@ -252,6 +301,7 @@ pub struct Exception {
     pub chunk_re: Option<String>,
     pub regexp: Option<String>,
     pub spacebefore: Option<String>,
+    pub case: Option<String>,
     pub negate: Option<String>,
     pub negate_pos: Option<String>,
     pub scope: Option<String>,
@@ -289,6 +339,7 @@ pub struct Token {
     pub chunk_re: Option<String>,
     pub regexp: Option<String>,
     pub spacebefore: Option<String>,
+    pub case: Option<String>,
     pub negate: Option<String>,
     pub negate_pos: Option<String>,
     #[serde(rename = "$value")]
@@ -305,6 +356,7 @@ pub trait MatchAttributes {
     fn chunk_re(&self) -> &Option<String>;
     fn regexp(&self) -> &Option<String>;
     fn spacebefore(&self) -> &Option<String>;
+    fn case(&self) -> &Option<String>;
     fn negate(&self) -> &Option<String>;
     fn negate_pos(&self) -> &Option<String>;
 }
@@ -344,6 +396,10 @@ macro_rules! impl_match_attributes {
                 &self.spacebefore
             }
 
+            fn case(&self) -> &Option<String> {
+                &self.case
+            }
+
             fn negate(&self) -> &Option<String> {
                 &self.negate
             }
@@ -382,7 +438,7 @@ pub struct Ignore {
 #[serde(rename_all = "lowercase")]
 #[serde(deny_unknown_fields)]
 pub enum UnifyTokenCombination {
-    Token(Token),
+    Token(Box<Token>),
     Or(TokenVector),
     And(TokenVector),
     Feature(Feature),
@@ -401,7 +457,7 @@ pub struct Unify {
 #[serde(rename_all = "lowercase")]
 #[serde(deny_unknown_fields)]
 pub enum TokenCombination {
-    Token(Token),
+    Token(Box<Token>),
     Or(TokenVector),
     And(TokenVector),
     Unify(Unify),
@@ -418,7 +474,7 @@ pub struct PatternMarker {
 #[serde(rename_all = "lowercase")]
 #[serde(deny_unknown_fields)]
 pub enum PatternPart {
-    Token(Token),
+    Token(Box<Token>),
     Marker(PatternMarker),
     Or(TokenVector),
     And(TokenVector),
@@ -470,7 +526,9 @@ pub struct RuleGroup {
     pub antipatterns: Option<Vec<Pattern>>,
     pub default: Option<String>,
     pub name: String,
+    #[allow(dead_code)]
     pub short: Option<XMLText>,
+    #[allow(dead_code)]
     pub url: Option<XMLText>,
     #[serde(rename = "rule")]
     pub rules: Vec<Rule>,
@@ -480,7 +538,7 @@ pub struct RuleGroup {
 #[serde(rename_all = "lowercase")]
 #[serde(deny_unknown_fields)]
 pub enum RuleContainer {
-    Rule(Rule),
+    Rule(Box<Rule>),
     RuleGroup(RuleGroup),
 }
 
@@ -498,9 +556,10 @@ pub struct DisambiguationExample {
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct WordData {
-    pub pos: String,
+    pub pos: Option<String>,
     pub text: Option<String>,
     pub lemma: Option<String>,
+    pub case_conversion: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -594,7 +653,7 @@ pub struct Unification {
 #[serde(rename_all = "lowercase")]
 #[serde(deny_unknown_fields)]
 pub enum DisambiguationRuleContainer {
-    Rule(DisambiguationRule),
+    Rule(Box<DisambiguationRule>),
     RuleGroup(DisambiguationRuleGroup),
     Unification(Unification),
 }
@@ -633,21 +692,44 @@ macro_rules! flatten_group {
     }};
 }
 
+fn read_xml(
+    path: &std::path::Path,
+    whitespace_sensitive_tags: &[&str],
+) -> Result<Vec<(String, Option<Category>, Location)>, Error> {
+    let file = File::open(path).map_err(|source| Error::XmlNotFound {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let file = BufReader::new(file);
+
+    let parse_error = |(message, position): preprocess::ParseError| Error::XmlParse {
+        path: path.to_path_buf(),
+        message,
+        line: position.map(|x| x.0),
+        column: position.map(|x| x.1),
+    };
+
+    let (sanitized, positions) =
+        preprocess::sanitize(file, whitespace_sensitive_tags).map_err(parse_error)?;
+    preprocess::extract_rules(&sanitized, &positions).map_err(parse_error)
+}
+
 type GrammarRuleReading = (Rule, Option<Group>, Option<Category>);
 type DisambiguationRuleReading = (DisambiguationRule, Option<Group>, Option<Category>);
+/// A disambiguation rule with the location of its container. Rules in a group share the location of the group.
+type LocatedDisambiguationRule = (
+    Result<DisambiguationRuleReading, serde_xml_rs::Error>,
+    Location,
+);
 
 pub fn read_rules<P: AsRef<std::path::Path>>(
     path: P,
-) -> Vec<Result<GrammarRuleReading, serde_xml_rs::Error>> {
-    let file = File::open(path).unwrap();
-    let file = BufReader::new(file);
-
-    let sanitized = preprocess::sanitize(file, &["suggestion"]);
-    let rules = preprocess::extract_rules(sanitized.as_bytes());
+) -> Result<Vec<Result<GrammarRuleReading, serde_xml_rs::Error>>, Error> {
+    let rules = read_xml(path.as_ref(), &["suggestion"])?;
 
-    rules
+    Ok(rules
         .into_iter()
-        .map(|(xml, category)| {
+        .flat_map(|(xml, category, _)| {
             let mut out = Vec::new();
 
             let deseralized = RuleContainer::deserialize(&mut serde_xml_rs::Deserializer::new(
@@ -657,7 +739,7 @@ pub fn read_rules<P: AsRef<std::path::Path>>(
             out.extend(match deseralized {
                 Ok(rule_container) => match rule_container {
                     RuleContainer::Rule(rule) => {
-                        vec![Ok((rule, None, category))]
+                        vec![Ok((*rule, None, category))]
                     }
                     RuleContainer::RuleGroup(rule_group) => flatten_group!(rule_group, category)
                         .into_iter()
@@ -668,24 +750,19 @@ pub fn read_rules<P: AsRef<std::path::Path>>(
             });
             out
         })
-        .flatten()
-        .collect()
+        .collect())
 }
 
 pub fn read_disambiguation_rules<P: AsRef<std::path::Path>>(
     path: P,
-) -> Vec<Result<DisambiguationRuleReading, serde_xml_rs::Error>> {
-    let file = File::open(path).unwrap();
-    let file = BufReader::new(file);
-
-    let sanitized = preprocess::sanitize(file, &[]);
-    let rules = preprocess::extract_rules(sanitized.as_bytes());
+) -> Result<Vec<LocatedDisambiguationRule>, Error> {
+    let rules = read_xml(path.as_ref(), &[])?;
 
     let mut unifications = Vec::new();
 
     let rules: Vec<_> = rules
         .into_iter()
-        .map(|(xml, _)| {
+        .flat_map(|(xml, _, location)| {
             let mut out = Vec::new();
 
             let deseralized = DisambiguationRuleContainer::deserialize(
@@ -697,7 +774,7 @@ pub fn read_disambiguation_rules<P: AsRef<std::path::Path>>(
             out.extend(match deseralized {
                 Ok(rule_container) => match rule_container {
                     DisambiguationRuleContainer::Rule(rule) => {
-                        vec![Ok((rule, None, category))]
+                        vec![Ok((*rule, None, category))]
                     }
                     DisambiguationRuleContainer::RuleGroup(rule_group) => {
                         flatten_group!(rule_group, category)
@@ -713,20 +790,21 @@ pub fn read_disambiguation_rules<P: AsRef<std::path::Path>>(
                 },
                 Err(err) => vec![Err(err)],
             });
-            out
+            out.into_iter()
+                .map(|x| (x, location.clone()))
+                .collect::<Vec<_>>()
         })
-        .flatten()
         .collect();
 
-    rules
+    Ok(rules
         .into_iter()
-        .map(|result| match result {
+        .map(|(result, location)| match result {
             Ok(mut x) => {
                 x.0.unifications = Some(unifications.clone());
 
-                Ok(x)
+                (Ok(x), location)
             }
-            Err(x) => Err(x),
+            Err(x) => (Err(x), location),
         })
-        .collect()
+        .collect())
 }
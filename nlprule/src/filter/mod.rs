@@ -20,25 +20,34 @@ trait FromArgs: Sized {
     fn from_args(args: HashMap<String, String>) -> Result<Self, Error>;
 }
 
+/// Gets the value of an argument which every filter of a kind needs.
+fn required_arg<'a>(args: &'a HashMap<String, String>, name: &str) -> Result<&'a str, Error> {
+    args.get(name)
+        .map(|x| x.as_str())
+        .ok_or_else(|| Error::Unexpected(format!("missing filter argument {:?}", name)))
+}
+
+/// Keeps a match if the first capture group of `regexp` on the text of the token `no` has a reading
+/// matching `postag_regexp` (or no such reading if `negate_postag` is `yes`), e. g. to check that
+/// "building" is derived from a verb.
 #[derive(Serialize, Deserialize)]
 pub struct NoDisambiguationEnglishPartialPosTagFilter {
     index: usize,
     regexp: SerializeRegex,
     postag_regexp: SerializeRegex,
-    #[allow(dead_code)]
     negate_postag: bool,
 }
 
 impl FromArgs for NoDisambiguationEnglishPartialPosTagFilter {
     fn from_args(args: HashMap<String, String>) -> Result<Self, Error> {
-        if args.contains_key("negate_postag") {
-            panic!("negate_postag not supported in NoDisambiguationEnglishPartialPosTagFilter");
-        }
+        let index = required_arg(&args, "no")?;
 
         Ok(NoDisambiguationEnglishPartialPosTagFilter {
-            index: args.get("no").unwrap().parse::<usize>().unwrap(),
-            regexp: SerializeRegex::new(&args.get("regexp").unwrap(), true, true)?,
-            postag_regexp: SerializeRegex::new(&args.get("postag_regexp").unwrap(), true, true)?,
+            index: index.parse::<usize>().map_err(|_| {
+                Error::Unexpected(format!("invalid filter argument \"no\": {:?}", index))
+            })?,
+            regexp: SerializeRegex::new(required_arg(&args, "regexp")?, true, true)?,
+            postag_regexp: SerializeRegex::new(required_arg(&args, "postag_regexp")?, true, true)?,
             negate_postag: args.get("negate_postag").map_or(false, |x| x == "yes"),
         })
     }
@@ -50,16 +59,18 @@ impl Filterable for NoDisambiguationEnglishPartialPosTagFilter {
             let tokens = &group.tokens(graph.tokens());
 
             tokens.iter().all(|token| {
-                if let Some(captures) = self.regexp.captures(&token.word.text.as_ref()) {
+                if let Some(captures) = self.regexp.captures(token.word.text.as_ref()) {
                     // get group 2 because `must_fully_match` adds one group
                     let tags = tokenizer.tagger().get_tags(
-                        &captures.at(2).unwrap(),
+                        captures.at(2).unwrap(),
                         tokenizer.options().always_add_lower_tags,
                         tokenizer.options().use_compound_split_heuristic,
                     );
 
-                    tags.iter()
-                        .any(|x| self.postag_regexp.is_match(x.pos.as_ref()))
+                    let matches = tags
+                        .iter()
+                        .any(|x| self.postag_regexp.is_match(x.pos.as_ref()));
+                    matches != self.negate_postag
                 } else {
                     false
                 }
@@ -76,6 +87,6 @@ pub fn get_filter(name: &str, args: HashMap<String, String>) -> Result<Filter, E
         "NoDisambiguationEnglishPartialPosTagFilter" => {
             Ok(NoDisambiguationEnglishPartialPosTagFilter::from_args(args)?.into())
         }
-        _ => Err(Error::Unexpected(format!("unsupported filter {}", name))),
+        _ => Err(Error::Unimplemented(format!("filter {}", name))),
     }
 }
@@ -17,7 +17,7 @@
 //!     rules.correct("She was not been here since Monday.", &tokenizer),
 //!     String::from("She was not here since Monday.")
 //! );
-//! # Ok::<(), bincode::Error>(())
+//! # Ok::<(), nlprule::Error>(())
 //! ```
 //!
 //! # Example: get suggestions and correct a text
@@ -45,7 +45,7 @@
 //! let corrected = apply_suggestions(text, &suggestions);
 //!
 //! assert_eq!(corrected, "She was not here since Monday.");
-//! # Ok::<(), bincode::Error>(())
+//! # Ok::<(), nlprule::Error>(())
 //! ```
 //!
 //! Binaries are distributed with [Github releases](https://github.com/bminixhofer/nlprule/releases).
@@ -69,6 +69,29 @@ pub use tokenizer::Tokenizer;
 
 #[derive(Error, Debug)]
 pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Deserialization(#[from] bincode::Error),
+    #[error("invalid regex: {0}")]
+    Regex(#[from] onig::Error),
+    #[error("dump file {path:?} could not be read: {source}")]
+    DumpUnreadable {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("line {line} of dump file {path:?} is malformed: {content:?}")]
+    MalformedDumpLine {
+        path: String,
+        line: usize,
+        content: String,
+    },
+    #[error("unknown part-of-speech tag {0:?}")]
+    UnknownTag(String),
+    #[error("binary has format version {found}, expected version {expected}")]
+    VersionMismatch { found: u32, expected: u32 },
+    #[error("binary is corrupted: {0}")]
+    CorruptedBinary(String),
     #[error("unexpected condition: {0}")]
     Unexpected(String),
     #[error("feature not implemented: {0}")]
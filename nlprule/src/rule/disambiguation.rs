@@ -3,6 +3,7 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use super::engine::composition::PosMatcher;
+use super::grammar::Conversion;
 
 #[derive(Serialize, Deserialize)]
 pub struct POSFilter {
@@ -37,26 +38,142 @@ impl POSFilter {
     }
 }
 
+/// A reading added by `add` or `replace`. The lemma can be the text of a matched token and the part-of-speech
+/// tags can be copied from the readings of a matched token, both written as a reference like `\1` in the XML.
+#[derive(Serialize, Deserialize)]
+pub struct ReadingTemplate {
+    pub(crate) data: owned::WordData,
+    /// The id of the group whose text, after the conversion, is the lemma.
+    pub(crate) lemma_ref: Option<(usize, Conversion)>,
+    /// The id of the group whose tags are copied, with one reading for each tag.
+    pub(crate) pos_ref: Option<usize>,
+}
+
+impl ReadingTemplate {
+    fn references(&self) -> impl Iterator<Item = usize> + '_ {
+        self.lemma_ref.iter().map(|x| x.0).chain(self.pos_ref)
+    }
+
+    fn readings<'t>(
+        &'t self,
+        token: &IncompleteToken<'t>,
+        context: &MatchContext<'t>,
+    ) -> Vec<WordData<'t>> {
+        let lemma = match &self.lemma_ref {
+            Some((id, conversion)) => token
+                .tagger
+                .id_word(conversion.convert(context.text(*id)).into()),
+            None => self.data.lemma.as_ref_id(),
+        };
+        let lemma = if lemma.as_ref().is_empty() {
+            token.word.text.clone()
+        } else {
+            lemma
+        };
+
+        let tags = match self.pos_ref {
+            Some(id) => context.tags(id),
+            None => vec![self.data.pos.as_ref_id()],
+        };
+
+        tags.into_iter()
+            .map(|pos| WordData::new(lemma.clone(), pos))
+            .collect()
+    }
+}
+
+/// The text and tags of the groups of a match which the action refers to, by group id.
+pub(crate) struct MatchContext<'t> {
+    groups: DefaultHashMap<usize, (&'t str, Vec<PosId<'t>>)>,
+}
+
+impl<'t> MatchContext<'t> {
+    /// Creates the context from the byte spans of the tokens of each referenced group. The tags are the tags of
+    /// the first token of a group.
+    pub fn new(
+        tokens: &[IncompleteToken<'t>],
+        references: &[(usize, Vec<(usize, usize)>)],
+    ) -> Self {
+        let mut groups = DefaultHashMap::default();
+
+        for (id, spans) in references {
+            let matched: Vec<_> = spans
+                .iter()
+                .filter_map(|span| tokens.iter().find(|x| x.byte_span == *span))
+                .collect();
+
+            let (text, tags) = match (matched.first(), matched.last()) {
+                (Some(first), Some(last)) => {
+                    let text: &'t str = first.text;
+                    let mut tags = Vec::new();
+                    for data in &first.word.tags {
+                        if !tags.contains(&data.pos) {
+                            tags.push(data.pos.clone());
+                        }
+                    }
+
+                    (&text[first.byte_span.0..last.byte_span.1], tags)
+                }
+                _ => ("", Vec::new()),
+            };
+
+            groups.insert(*id, (text, tags));
+        }
+
+        MatchContext { groups }
+    }
+
+    fn text(&self, id: usize) -> &'t str {
+        self.groups.get(&id).map_or("", |x| x.0)
+    }
+
+    fn tags(&self, id: usize) -> Vec<PosId<'t>> {
+        self.groups.get(&id).map_or_else(Vec::new, |x| x.1.clone())
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub enum Disambiguation {
     Remove(Vec<either::Either<owned::WordData, POSFilter>>),
-    Add(Vec<owned::WordData>),
-    Replace(Vec<owned::WordData>),
+    Add(Vec<ReadingTemplate>),
+    Replace(Vec<ReadingTemplate>),
     Filter(Vec<Option<either::Either<owned::WordData, POSFilter>>>),
     Unify(Vec<Vec<POSFilter>>, Vec<Option<POSFilter>>, Vec<bool>),
     Nop,
 }
 
 impl Disambiguation {
-    pub fn apply<'t>(&'t self, groups: Vec<Vec<&mut IncompleteToken<'t>>>, retain_last: bool) {
+    /// The ids of the groups the action refers to.
+    pub fn references(&self) -> Vec<usize> {
+        let mut ids = Vec::new();
+
+        if let Disambiguation::Add(templates) | Disambiguation::Replace(templates) = self {
+            for id in templates.iter().flat_map(|x| x.references()) {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+
+        ids
+    }
+
+    pub fn apply<'t>(
+        &'t self,
+        groups: Vec<Vec<&mut IncompleteToken<'t>>>,
+        context: &MatchContext<'t>,
+        retain_last: bool,
+    ) {
         match self {
             Disambiguation::Remove(data_or_filters) => {
                 for (group, data_or_filter) in groups.into_iter().zip(data_or_filters) {
                     for token in group.into_iter() {
                         match data_or_filter {
                             either::Left(data) => {
+                                // an empty pos or lemma matches any
                                 token.word.tags.retain(|x| {
-                                    !(x.pos == data.pos.as_ref_id()
+                                    !((data.pos.as_ref_id() == PosId::EMPTY
+                                        || x.pos == data.pos.as_ref_id())
                                         && (data.lemma.as_ref().is_empty()
                                             || x.lemma == data.lemma.as_ref_id()))
                                 });
@@ -74,7 +191,7 @@ impl Disambiguation {
                         match data_or_filter {
                             either::Left(limit) => {
                                 for token in group.into_iter() {
-                                    let last = token.word.tags.get(0).map_or_else(
+                                    let last = token.word.tags.first().map_or_else(
                                         || token.word.text.clone(),
                                         |x| x.lemma.clone(),
                                     );
@@ -102,37 +219,23 @@ impl Disambiguation {
                     }
                 }
             }
-            Disambiguation::Add(datas) => {
-                for (group, data) in groups.into_iter().zip(datas) {
+            Disambiguation::Add(templates) => {
+                for (group, template) in groups.into_iter().zip(templates) {
                     for token in group.into_iter() {
-                        let data = WordData::new(
-                            if data.lemma.as_ref().is_empty() {
-                                token.word.text.clone()
-                            } else {
-                                data.lemma.as_ref_id()
-                            },
-                            data.pos.as_ref_id(),
-                        );
+                        let readings = template.readings(token, context);
 
-                        token.word.tags.push(data);
-                        token.word.tags.retain(|x| !x.pos.as_ref().is_empty());
+                        token.word.tags.extend(readings);
+                        token.word.tags.retain(|x| x.pos != PosId::EMPTY);
                     }
                 }
             }
-            Disambiguation::Replace(datas) => {
-                for (group, data) in groups.into_iter().zip(datas) {
+            Disambiguation::Replace(templates) => {
+                for (group, template) in groups.into_iter().zip(templates) {
                     for token in group.into_iter() {
-                        let data = WordData::new(
-                            if data.lemma.as_ref().is_empty() {
-                                token.word.text.clone()
-                            } else {
-                                data.lemma.as_ref_id()
-                            },
-                            data.pos.as_ref_id(),
-                        );
+                        let readings = template.readings(token, context);
 
                         token.word.tags.clear();
-                        token.word.tags.push(data);
+                        token.word.tags.extend(readings);
                     }
                 }
             }
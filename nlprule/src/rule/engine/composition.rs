@@ -44,7 +44,7 @@ impl Matcher {
                     }
                 }
                 either::Right(idx) => graph.by_id(*idx).map_or(false, |x| {
-                    x.tokens(&graph.tokens).get(0).map_or(false, |token| {
+                    x.tokens(graph.tokens).first().map_or(false, |token| {
                         if case_sensitive {
                             token.word.text.as_ref() == input
                         } else {
@@ -127,10 +127,10 @@ impl WordDataMatcher {
                 return false;
             }
 
-            let inflect_matches = self
-                .inflect_matcher
-                .as_ref()
-                .map_or(true, |m| m.is_match(&x.lemma, graph, case_sensitive));
+            // the special tags have the token text as lemma, it is not a lemma from the dictionary
+            let inflect_matches = self.inflect_matcher.as_ref().map_or(true, |m| {
+                !x.pos.is_special() && m.is_match(&x.lemma, graph, case_sensitive)
+            });
 
             inflect_matches
         })
@@ -151,20 +151,23 @@ pub trait Atomable: Send + Sync {
 #[enum_dispatch(Atomable)]
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Atom {
-    ChunkAtom(concrete::ChunkAtom),
-    SpaceBeforeAtom(concrete::SpaceBeforeAtom),
-    TextAtom(concrete::TextAtom),
-    WordDataAtom(concrete::WordDataAtom),
-    TrueAtom,
-    FalseAtom,
-    AndAtom,
-    OrAtom,
-    NotAtom,
-    OffsetAtom,
+    Case(concrete::CaseAtom),
+    Chunk(concrete::ChunkAtom),
+    SpaceBefore(concrete::SpaceBeforeAtom),
+    SpaceKind(concrete::SpaceKindAtom),
+    Text(concrete::TextAtom),
+    WordData(concrete::WordDataAtom),
+    True(TrueAtom),
+    False(FalseAtom),
+    And(AndAtom),
+    Or(OrAtom),
+    Not(NotAtom),
+    Offset(OffsetAtom),
 }
 
 pub mod concrete {
     use super::{Atomable, MatchGraph, Matcher, TextMatcher, Token, WordDataMatcher};
+    use crate::types::{Case, SpaceKind};
     use serde::{Deserialize, Serialize};
 
     #[derive(Debug, Serialize, Deserialize)]
@@ -202,6 +205,28 @@ pub mod concrete {
         }
     }
 
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct SpaceKindAtom {
+        pub(crate) value: SpaceKind,
+    }
+
+    impl Atomable for SpaceKindAtom {
+        fn is_match(&self, input: &[&Token], _graph: &MatchGraph, position: usize) -> bool {
+            input[position].space_kind() == self.value
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct CaseAtom {
+        pub(crate) value: Case,
+    }
+
+    impl Atomable for CaseAtom {
+        fn is_match(&self, input: &[&Token], _graph: &MatchGraph, position: usize) -> bool {
+            input[position].case == self.value
+        }
+    }
+
     #[derive(Debug, Serialize, Deserialize)]
     pub struct WordDataAtom {
         pub(crate) matcher: WordDataMatcher,
@@ -213,7 +238,7 @@ pub mod concrete {
             let tags = &input[position].word.tags;
 
             self.matcher
-                .is_match(&tags, graph, Some(self.case_sensitive))
+                .is_match(tags, graph, Some(self.case_sensitive))
         }
     }
 }
@@ -344,7 +369,7 @@ impl<'t> Default for MatchGraph<'t> {
     fn default() -> Self {
         MatchGraph {
             groups: Vec::new(),
-            id_to_idx: &(*EMPTY_MAP),
+            id_to_idx: &EMPTY_MAP,
             tokens: &[],
         }
     }
@@ -380,7 +405,7 @@ impl<'t> MatchGraph<'t> {
     }
 
     pub fn tokens(&self) -> &[&'t Token<'t>] {
-        &self.tokens[..]
+        self.tokens
     }
 
     pub fn fill_empty(&mut self) {
@@ -388,7 +413,7 @@ impl<'t> MatchGraph<'t> {
             .groups
             .iter()
             .find_map(|x| {
-                let tokens = x.tokens(&self.tokens);
+                let tokens = x.tokens(self.tokens);
                 if tokens.is_empty() {
                     None
                 } else {
@@ -402,7 +427,7 @@ impl<'t> MatchGraph<'t> {
             .iter()
             .rev()
             .find_map(|x| {
-                let tokens = x.tokens(&self.tokens);
+                let tokens = x.tokens(self.tokens);
                 if tokens.is_empty() {
                     None
                 } else {
@@ -414,7 +439,7 @@ impl<'t> MatchGraph<'t> {
         let group_tokens: Vec<_> = self
             .groups
             .iter()
-            .map(|x| x.tokens(&self.tokens))
+            .map(|x| x.tokens(self.tokens))
             .collect::<Vec<_>>();
         for (group, tokens) in self.groups.iter_mut().zip(group_tokens.iter()) {
             if !tokens.is_empty() {
@@ -517,7 +542,7 @@ impl Composition {
             }
 
             if part.atom.is_match(tokens, &graph, position) {
-                let mut group = &mut graph.groups[cur_atom_idx + 1];
+                let group = &mut graph.groups[cur_atom_idx + 1];
 
                 // set the group beginning if the char end was zero (i. e. the group was empty)
                 if group.char_span.1 == 0 {
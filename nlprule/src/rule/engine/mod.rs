@@ -66,9 +66,8 @@ impl Engine {
         match &self {
             Engine::Token(engine) => {
                 let mut graph_info: Vec<_> = (0..tokens.len())
-                    .into_iter()
                     .filter_map(|i| {
-                        if let Some(graph) = engine.get_match(&tokens, i) {
+                        if let Some(graph) = engine.get_match(tokens, i) {
                             let start_group = graph
                                 .by_id(start)
                                 .unwrap_or_else(|| panic!("group must exist in graph: {}", start));
@@ -85,8 +84,10 @@ impl Engine {
                     })
                     .collect();
 
-                graph_info.sort_by(|(_, start, _), (_, end, _)| start.cmp(end));
-                let mut mask = vec![false; tokens[0].text.chars().count()];
+                graph_info.sort_by_key(|(_, start, _)| *start);
+                // only chars up to the end of the last match can be masked
+                let mask_len = graph_info.iter().map(|(_, _, end)| *end).max().unwrap_or(0);
+                let mut mask = vec![false; mask_len];
 
                 for (graph, start, end) in graph_info {
                     if mask[start..end].iter().all(|x| !x) {
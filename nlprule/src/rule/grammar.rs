@@ -27,7 +27,7 @@ pub enum Conversion {
 }
 
 impl Conversion {
-    fn convert(&self, input: &str) -> String {
+    pub(crate) fn convert(&self, input: &str) -> String {
         match &self {
             Conversion::Nop => input.to_string(),
             Conversion::AllLower => input.to_lowercase(),
@@ -75,9 +75,7 @@ impl PosReplacer {
             )
             .iter()
             .map(|x| {
-                let group_words = tokenizer
-                    .tagger()
-                    .get_group_members(&x.lemma.as_ref().to_string());
+                let group_words = tokenizer.tagger().get_group_members(x.lemma.as_ref());
                 let mut data = Vec::new();
                 for word in group_words {
                     if let Some(i) = tokenizer
@@ -98,7 +96,7 @@ impl PosReplacer {
             .rev()
             .flatten()
             .collect();
-        candidates.sort_by(|(_, a), (_, b)| a.cmp(b));
+        candidates.sort_by_key(|(_, a)| *a);
         if candidates.is_empty() {
             None
         } else {
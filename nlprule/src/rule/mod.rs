@@ -48,8 +48,16 @@ pub struct DisambiguationRule {
     pub(crate) examples: Vec<disambiguation::DisambiguationExample>,
 }
 
+/// The tokens of one match of a [DisambiguationRule] by their byte spans.
+struct MatchSpans {
+    /// The tokens of each group from `start` to `end`.
+    groups: Vec<HashSet<(usize, usize)>>,
+    /// The tokens of each group the action refers to, by group id.
+    references: Vec<(usize, Vec<(usize, usize)>)>,
+}
+
 #[derive(Default)]
-pub(crate) struct Changes(Vec<Vec<HashSet<(usize, usize)>>>);
+pub(crate) struct Changes(Vec<MatchSpans>);
 
 impl Changes {
     pub fn is_empty(&self) -> bool {
@@ -69,6 +77,7 @@ impl DisambiguationRule {
         }
 
         let refs: Vec<&Token> = tokens.iter().collect();
+        let reference_ids = self.disambiguations.references();
 
         let mut all_byte_spans = Vec::new();
 
@@ -95,7 +104,24 @@ impl DisambiguationRule {
                 byte_spans.push(group_byte_spans);
             }
 
-            all_byte_spans.push(byte_spans);
+            let references = reference_ids
+                .iter()
+                .map(|id| {
+                    let spans = graph.by_id(*id).map_or_else(Vec::new, |group| {
+                        group
+                            .tokens(graph.tokens())
+                            .iter()
+                            .map(|x| x.byte_span)
+                            .collect()
+                    });
+                    (*id, spans)
+                })
+                .collect();
+
+            all_byte_spans.push(MatchSpans {
+                groups: byte_spans,
+                references,
+            });
         }
 
         Changes(all_byte_spans)
@@ -109,11 +135,12 @@ impl DisambiguationRule {
     ) {
         log::info!("applying {}", self.id);
 
-        for byte_spans in changes.0 {
+        for spans in changes.0 {
+            let context = disambiguation::MatchContext::new(tokens, &spans.references);
             let mut groups = Vec::new();
             let mut refs = tokens.iter_mut().collect::<Vec<_>>();
 
-            for group_byte_spans in byte_spans {
+            for group_byte_spans in spans.groups {
                 let mut group = Vec::new();
 
                 while let Some(i) = refs
@@ -127,7 +154,7 @@ impl DisambiguationRule {
             }
 
             self.disambiguations
-                .apply(groups, tokenizer.options().retain_last);
+                .apply(groups, &context, tokenizer.options().retain_last);
         }
     }
 
@@ -370,7 +397,7 @@ impl Rule {
         let mut passes = Vec::new();
 
         for test in self.examples.iter() {
-            let tokens = finalize(tokenizer.disambiguate(tokenizer.tokenize(&test.text())));
+            let tokens = finalize(tokenizer.disambiguate(tokenizer.tokenize(test.text())));
             info!("Tokens: {:#?}", tokens);
             let suggestions = self.apply(&tokens, tokenizer);
 
@@ -2,8 +2,9 @@
 
 use crate::tokenizer::Tokenizer;
 use crate::types::*;
+#[cfg(feature = "rayon")]
 use crate::utils::parallelism::MaybeParallelRefIterator;
-use crate::{rule::Rule, tokenizer::finalize};
+use crate::{rule::Rule, tokenizer::finalize, Error};
 use serde::{Deserialize, Serialize};
 use std::{
     fs::File,
@@ -41,15 +42,15 @@ pub struct Rules {
 }
 
 impl Rules {
-    /// Creates a new rules set from a file.
-    pub fn new<P: AsRef<Path>>(p: P) -> bincode::Result<Self> {
-        let reader = BufReader::new(File::open(p).unwrap());
-        bincode::deserialize_from(reader)
+    /// Creates a new rules set from a file. Like for [Tokenizer::new], the file must have been written by the same
+    /// version of nlprule.
+    pub fn new<P: AsRef<Path>>(p: P) -> Result<Self, Error> {
+        Self::new_from(BufReader::new(File::open(p)?))
     }
 
     /// Creates a new rules set from a reader.
-    pub fn new_from<R: Read>(reader: R) -> bincode::Result<Self> {
-        bincode::deserialize_from(reader)
+    pub fn new_from<R: Read>(reader: R) -> Result<Self, Error> {
+        Ok(bincode::deserialize_from(reader)?)
     }
 
     /// All rules ordered by priority.
@@ -68,12 +69,15 @@ impl Rules {
             return Vec::new();
         }
 
-        let mut output: Vec<(usize, Suggestion)> = self
-            .rules
-            .maybe_par_iter()
+        #[cfg(feature = "rayon")]
+        let rules = self.rules.maybe_par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let rules = self.rules.iter();
+
+        let mut output: Vec<(usize, Suggestion)> = rules
             .enumerate()
             .filter(|(_, x)| x.on())
-            .map(|(i, rule)| {
+            .flat_map(|(i, rule)| {
                 let mut output = Vec::new();
 
                 for suggestion in rule.apply(tokens, tokenizer) {
@@ -82,7 +86,6 @@ impl Rules {
 
                 output
             })
-            .flatten()
             .collect();
 
         output.sort_by(|(ia, a), (ib, b)| a.start.cmp(&b.start).then_with(|| ib.cmp(ia)));
@@ -4,81 +4,216 @@
 //! Tokens are *disambiguated* (i. e. information from the initial assignment is changed) in a rule-based way by
 //! [DisambiguationRule][crate::rule::DisambiguationRule]s.
 
-use crate::{types::*, utils::parallelism::MaybeParallelRefIterator};
+#[cfg(feature = "rayon")]
+use crate::utils::parallelism::MaybeParallelRefIterator;
+use crate::{types::*, Error};
 use lazy_static::lazy_static;
 use onig::Regex;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashSet,
+    borrow::Cow,
+    collections::{HashMap, HashSet, VecDeque},
     fs::File,
-    io::{BufReader, Read},
-    path::Path,
-    sync::Arc,
+    io::{BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
 };
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
 use unicode_segmentation::UnicodeSegmentation;
 
 pub mod chunk;
+pub mod chunker;
+pub mod conllu;
+pub mod doc;
+pub mod markup;
+pub mod multiword;
+mod split;
+pub mod srx;
 pub mod tag;
 
 use chunk::Chunker;
-use tag::Tagger;
+use multiword::MultiwordTagger;
+use srx::SrxSegmenter;
+use tag::{CompoundSplitting, Lookup, Tagger};
 
 use crate::rule::DisambiguationRule;
 
-// see https://stackoverflow.com/a/40296745
-fn split<F>(text: &str, split_func: F) -> Vec<&str>
-where
-    F: Fn(char) -> bool,
-{
-    let mut result = Vec::new();
-    let mut last = 0;
-    for (index, matched) in text.match_indices(split_func) {
-        if last != index {
-            result.push(&text[last..index]);
+/// *Finalizes* the tokens by e. g. adding a specific UNKNOWN part-of-speech tag.
+/// A special SENT_START token is inserted before the first token and before every token starting a sentence.
+/// SENT_START tokens have an empty span at the start of the token they precede.
+/// The token and sentence indices are assigned relative to the first token.
+/// After finalization grammatical error correction rules can be used on the tokens.
+pub fn finalize(tokens: Vec<IncompleteToken>) -> Vec<Token> {
+    let mut finalized = Vec::with_capacity(tokens.len() + 1);
+    let mut token_index = 0;
+    let mut sentence_index = 0;
+
+    for (i, token) in tokens.into_iter().enumerate() {
+        if i == 0 || token.is_sentence_start {
+            if i > 0 {
+                sentence_index += 1;
+            }
+            token_index = 0;
+
+            let mut sent_start = Token::sent_start(token.text, token.tagger);
+            sent_start.char_span = (token.char_span.0, token.char_span.0);
+            sent_start.grapheme_span = (token.grapheme_span.0, token.grapheme_span.0);
+            sent_start.utf16_span = (token.utf16_span.0, token.utf16_span.0);
+            sent_start.byte_span = (token.byte_span.0, token.byte_span.0);
+            sent_start.sentence_index = sentence_index;
+
+            finalized.push(sent_start);
+        }
+
+        if token.kind != TokenKind::Whitespace {
+            token_index += 1;
         }
-        result.push(matched);
-        last = index + matched.len();
+
+        let mut token: Token = token.into();
+        token.token_index = token_index;
+        token.sentence_index = sentence_index;
+        finalized.push(token);
     }
-    if last < text.len() {
-        result.push(&text[last..]);
+
+    finalized
+}
+
+/// Finalizes the tokens of a single sentence, `sentence_index` is the index of the sentence in the text.
+fn finalize_sentence(tokens: Vec<IncompleteToken>, sentence_index: usize) -> Vec<Token> {
+    let mut finalized = finalize(tokens);
+    for token in finalized.iter_mut() {
+        token.sentence_index = sentence_index;
     }
 
-    result
+    finalized
+}
+
+/// Reconstructs the text from the tokens using the whitespace stored on each token.
+/// Reproduces the original text including leading and trailing whitespace if the tokens are all tokens of the text
+/// (text consisting only of whitespace has no tokens, so it can not be reproduced).
+/// Special tokens like SENT_START are ignored.
+pub fn detokenize(tokens: &[Token]) -> String {
+    detokenize_with(tokens, &vec![None; tokens.len()])
 }
 
-fn get_token_strs(text: &str) -> Vec<&str> {
-    let mut tokens = Vec::new();
+/// Reconstructs the text from the tokens like [detokenize], replacing the text of the token at each index with the
+/// replacement at the same index if there is one. Replacements can contain multiple words. The whitespace before a
+/// token is kept if it is replaced. If it is removed (i. e. replaced with an empty string) the whitespace before it
+/// is used for the next token instead unless it is empty, so removing the second "the" in "the the cat" results
+/// in "the cat" and removing the comma in "sat, said" results in "sat said".
+///
+/// # Panics
+/// If `replacements` does not have the same length as `tokens`.
+pub fn detokenize_with(tokens: &[Token], replacements: &[Option<&str>]) -> String {
+    assert_eq!(
+        tokens.len(),
+        replacements.len(),
+        "there must be one replacement for each token"
+    );
 
-    lazy_static! {
-        // see https://stackoverflow.com/a/17773849
-        static ref URL_REGEX: Regex = Regex::new(r"(https?:\/\/(?:www\.|(?!www))[a-zA-Z0-9][a-zA-Z0-9-]+[a-zA-Z0-9]\.[^\s]{2,}|www\.[a-zA-Z0-9][a-zA-Z0-9-]+[a-zA-Z0-9]\.[^\s]{2,}|https?:\/\/(?:www\.|(?!www))[a-zA-Z0-9]+\.[^\s]{2,}|www\.[a-zA-Z0-9]+\.[^\s]{2,})").unwrap();
-    }
+    let mut output = String::new();
+    let mut pending_space: Option<&str> = None;
+    let mut last = None;
+
+    for (token, replacement) in tokens.iter().zip(replacements) {
+        // special tokens have an empty span
+        if token.byte_span.0 == token.byte_span.1 {
+            continue;
+        }
+
+        let space_before = match pending_space.take() {
+            Some(space) if !space.is_empty() || output.is_empty() => space,
+            _ => token.space_before,
+        };
+        let text = replacement.unwrap_or_else(|| token.word.text.as_ref());
 
-    let mut prev = 0;
-    let split_func = |c: char| c.is_whitespace() || crate::utils::splitting_chars().contains(c);
+        if text.is_empty() {
+            pending_space = Some(space_before);
+        } else {
+            output.push_str(space_before);
+            output.push_str(text);
+        }
+        last = Some(token);
+    }
 
-    for (start, end) in URL_REGEX.find_iter(text) {
-        tokens.extend(split(&text[prev..start], split_func));
-        tokens.push(&text[start..end]);
-        prev = end;
+    if let Some(token) = last {
+        let rest = &token.text[token.byte_span.1..];
+        if rest.trim().is_empty() {
+            output.push_str(rest);
+        }
     }
 
-    tokens.extend(split(&text[prev..text.len()], split_func));
+    output
+}
 
-    tokens
+/// Whether the text contains a carriage return which is not followed by a line feed.
+fn has_bare_carriage_return(text: &str) -> bool {
+    text.match_indices('\r')
+        .any(|(i, _)| !text[i + 1..].starts_with('\n'))
 }
 
-/// *Finalizes* the tokens by e. g. adding a specific UNKNOWN part-of-speech tag.
-/// After finalization grammatical error correction rules can be used on the tokens.
-pub fn finalize(tokens: Vec<IncompleteToken>) -> Vec<Token> {
-    if tokens.is_empty() {
-        return Vec::new();
+/// Replaces every carriage return which is not followed by a line feed with a space.
+fn replace_bare_carriage_returns(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\r' && chars.peek() != Some(&'\n') {
+            output.push(' ');
+        } else {
+            output.push(c);
+        }
+    }
+
+    output
+}
+
+/// The value of a Roman numeral like "XIV" or "MCMXCIV". Only numerals in the standard subtractive form are accepted,
+/// so "IIII" and "IC" are not numerals. Lowercase numerals like "iii" may only consist of "i", "v" and "x" because
+/// e. g. "mix" and "di" are words.
+fn roman_numeral_value(text: &str) -> Option<usize> {
+    lazy_static! {
+        static ref ROMAN_NUMERAL_REGEX: Regex =
+            Regex::new(r"\AM{0,3}(?:CM|CD|D?C{0,3})(?:XC|XL|L?X{0,3})(?:IX|IV|V?I{0,3})\z")
+                .unwrap();
     }
 
-    let mut finalized = vec![Token::sent_start(tokens[0].text, tokens[0].tagger)];
-    finalized.extend(tokens.into_iter().map(|x| x.into()));
+    let numeral = if !text.is_empty() && text.chars().all(|c| matches!(c, 'i' | 'v' | 'x')) {
+        text.to_uppercase()
+    } else if !text.is_empty() && text.chars().all(|c| "IVXLCDM".contains(c)) {
+        text.to_string()
+    } else {
+        return None;
+    };
 
-    finalized
+    if !ROMAN_NUMERAL_REGEX.is_match(&numeral) {
+        return None;
+    }
+
+    let values: Vec<isize> = numeral
+        .chars()
+        .map(|c| match c {
+            'I' => 1,
+            'V' => 5,
+            'X' => 10,
+            'L' => 50,
+            'C' => 100,
+            'D' => 500,
+            _ => 1000,
+        })
+        .collect();
+
+    // a numeral followed by a greater one is subtracted e. g. the "I" in "IV"
+    let value: isize = values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| match values.get(i + 1) {
+            Some(next) if next > value => -value,
+            _ => *value,
+        })
+        .sum();
+
+    Some(value as usize)
 }
 
 /// Options for a tokenizer.
@@ -86,13 +221,32 @@ pub fn finalize(tokens: Vec<IncompleteToken>) -> Vec<Token> {
 pub struct TokenizerOptions {
     /// Whether to allow errors while constructing the tokenizer.
     pub allow_errors: bool,
+    /// Whether disambiguation rules which use unsupported features are errors while constructing the tokenizer.
+    /// They are skipped (and logged at debug level) otherwise.
+    #[serde(default)]
+    pub strict: bool,
     /// Whether to retain the last tag if disambiguation leads to an empty tag.
     /// Language-specific in LT so it has to be an option.
     pub retain_last: bool,
     /// Whether to use a heuristic to split potential compound words.
     pub use_compound_split_heuristic: bool,
+    /// How unknown capitalized words are split into known parts to tag them like their head, for languages
+    /// with productive compounds like German. Compounds are not split if not set.
+    #[serde(default)]
+    pub compound_splitting: Option<CompoundSplitting>,
+    /// Whether unknown words are looked up ignoring diacritics (e. g. "cafe" as "café") when building the tagger,
+    /// with the [Folding::for_language][tag::Folding::for_language] of the `lang_code`. See [Tagger::with_folding].
+    #[serde(default)]
+    pub fold_diacritics: bool,
     /// Whether to always add tags for a lowercase version of the word when assigning part-of-speech tags.
     pub always_add_lower_tags: bool,
+    /// Whether to look up the tags of each distinct word form only once per call of [tokenize][Tokenizer::tokenize]
+    /// (or [tag_spans][Tokenizer::tag_spans]) and copy them to the other occurrences, which saves the lookups of
+    /// frequent words like "the" or ",". Each token still gets its own tags, so disambiguation of one occurrence
+    /// does not affect the others. Every hit clones the cached readings, so this only pays off if lookups are
+    /// more expensive than copying the tags, which is why it is off by default.
+    #[serde(default)]
+    pub cache_tags: bool,
     /// Disambiguation Rule IDs to use in this tokenizer.
     #[serde(default)]
     pub ids: Vec<String>,
@@ -105,42 +259,363 @@ pub struct TokenizerOptions {
     /// Used part-of-speech tags which are not in the tagger dictionary.
     #[serde(default)]
     pub extra_tags: Vec<String>,
+    /// How words containing apostrophes are split into tokens.
+    /// Derived from the `lang_code` with [ApostropheMode::for_language] if not set.
+    #[serde(default)]
+    pub apostrophes: Option<ApostropheMode>,
+    /// Words containing apostrophes which are never split, in addition to the built-in exceptions of the [ApostropheMode].
+    /// Matched case-insensitively, typographic apostrophes are treated like straight ones.
+    #[serde(default)]
+    pub apostrophe_exceptions: Vec<String>,
+    /// How words containing hyphens like "e-mail" or "state-of-the-art" are tokenized.
+    #[serde(default)]
+    pub hyphens: HyphenMode,
+    /// Whether to keep numbers with an optional sign and decimal or thousands separators as single tokens
+    /// e. g. "3.14", "-3,5" or "1.000.000". Separators must be followed by a digit to be part of a number.
+    #[serde(default)]
+    pub join_numbers: bool,
+    /// Whether to keep dotted numeric identifiers like versions ("0.3.1", "v2.4") and IPv4 addresses as single tokens.
+    /// These are usually not in the tagger dictionary and consequently tagged as UNKNOWN.
+    #[serde(default)]
+    pub join_versions: bool,
+    /// Whether to split units from numbers they are attached to e. g. "10km" -> "10", "km" so that rules can detect the
+    /// missing space. Only known units (built-in ones like "km", "kg", "h", "€" and the `units`) are split off.
+    #[serde(default)]
+    pub split_units: bool,
+    /// Units which are split off numbers if `split_units` is set, in addition to the built-in units. Case-sensitive.
+    #[serde(default)]
+    pub units: Vec<String>,
+    /// Whether to keep dates like "3.10.2021", "3.10." or "2021-10-03" as single tokens.
+    #[serde(default)]
+    pub join_dates: bool,
+    /// Whether to keep ordinals like "3." in "am 3. Oktober" as single tokens. A number followed by a period
+    /// is only considered an ordinal if the next word is lowercase or a month name, otherwise the period
+    /// is assumed to end the sentence.
+    #[serde(default)]
+    pub join_ordinals: bool,
+    /// The part-of-speech tag to assign to numbers, dates and ordinals which are not in the tagger dictionary.
+    /// Must be in the tagger dictionary or in the `extra_tags`. The lemma of numbers is the normalized number
+    /// e. g. "3.14" for "3,14" in German, using the decimal separator of the `lang_code`.
+    #[serde(default)]
+    pub number_tag: Option<String>,
+    /// Whether to add a reading with the `number_tag` and the decimal value as lemma to Roman numerals like "XIV"
+    /// or "iii". Single letters like "I" and numerals which are also words like "MIX" only get the additional
+    /// reading if they follow one of the `roman_numeral_contexts` e. g. "Chapter I". Their other readings are kept.
+    #[serde(default)]
+    pub roman_numerals: bool,
+    /// Words after which ambiguous Roman numerals are tagged as numerals, in addition to built-in ones like
+    /// "Chapter", "Part" or "Kapitel". Case-insensitive.
+    #[serde(default)]
+    pub roman_numeral_contexts: Vec<String>,
+    /// The part-of-speech tag to assign to punctuation and symbols which are not in the tagger dictionary.
+    /// Must be in the tagger dictionary or in the `extra_tags`.
+    #[serde(default)]
+    pub punctuation_tag: Option<String>,
+    /// Whether to keep runs of combined punctuation like "...", "?!" or "!!!" as single tokens.
+    #[serde(default)]
+    pub join_punctuation: bool,
+    /// Whether to keep URLs as single tokens.
+    #[serde(default = "default_true")]
+    pub detect_urls: bool,
+    /// Whether to also keep domains without scheme or `www.` prefix like "example.com" as single URL tokens.
+    /// Only domains ending in a known top-level domain are detected, but some of these are common words
+    /// (e. g. "home.at night") so this is off by default. Has no effect if `detect_urls` is disabled.
+    #[serde(default)]
+    pub detect_bare_domains: bool,
+    /// Whether to keep email addresses as single tokens.
+    #[serde(default = "default_true")]
+    pub detect_emails: bool,
+    /// Whether to keep file paths like "/usr/bin/env", "./data/en" or "C:\\Users" as single tokens.
+    #[serde(default)]
+    pub detect_paths: bool,
+    /// Whether to keep hashtags ("#nlproc") and mentions ("@username") as single tokens including the sigil.
+    #[serde(default)]
+    pub detect_social: bool,
+    /// The language code used to select the SRX rules for sentence segmentation e. g. "en" or "de".
+    #[serde(default)]
+    pub lang_code: String,
+    /// Abbreviations like "z. B." or "Dr." after which no sentence boundary is assumed, in addition to the
+    /// abbreviations loaded when building the tokenizer. Case-sensitive, must include the trailing period.
+    #[serde(default)]
+    pub extra_abbreviations: Vec<String>,
+    /// Additional patterns of which each match is kept as a single token. Take priority over all other detection.
+    #[serde(default)]
+    pub extra_patterns: Vec<Pattern>,
+    /// Whether to normalize the text of tokens to NFC before looking up their tags, so that e. g. "cafe\u{301}"
+    /// with a combining accent is tagged like "café". The spans of the tokens still refer to the original text.
+    #[serde(default)]
+    pub normalize_nfc: bool,
+    /// How invisible chars like soft hyphens and zero-width spaces within words are handled.
+    #[serde(default)]
+    pub invisible_chars: InvisibleCharMode,
+    /// Whether to normalize typographic quotes and apostrophes like "„", "“", "’" or "ʼ" to straight ones before
+    /// looking up tags and matching rules, so dictionary entries and rules written with straight quotes apply.
+    /// Apostrophe-like chars are also treated like apostrophes when splitting contractions. The original chars
+    /// are still available with [Token::original_text].
+    #[serde(default)]
+    pub normalize_quotes: bool,
+    /// How text which is not matched by any of the special token detections (URLs, numbers etc.) is split into words.
+    #[serde(default)]
+    pub word_segmentation: WordSegmentation,
+    /// Chars at which words are split in addition to whitespace and the [default split chars][crate::utils::splitting_chars]
+    /// e. g. "&" or the Catalan middle dot "·". Only applies to [WordSegmentation::Split].
+    #[serde(default)]
+    pub extra_split_chars: String,
+    /// Chars from the [default split chars][crate::utils::splitting_chars] at which words are not split. These chars
+    /// stay inside word tokens and are part of the text used to look up tags. Whitespace can not be removed.
+    #[serde(default)]
+    pub remove_split_chars: String,
+    /// Whether to assign chunks to the tokens after disambiguation with the rule-based English [chunker],
+    /// replacing the chunks of the OpenNLP [chunker][chunk::Chunker] if there is one. This makes chunk
+    /// conditions in grammar rules usable without a chunker model, but the chunks are not available to
+    /// disambiguation rules. Only useful for English, since the rules use the Penn Treebank tags.
+    #[serde(default)]
+    pub rule_chunker: bool,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl Default for TokenizerOptions {
     fn default() -> Self {
         TokenizerOptions {
             allow_errors: false,
+            strict: false,
             retain_last: false,
             use_compound_split_heuristic: false,
+            compound_splitting: None,
+            fold_diacritics: false,
             always_add_lower_tags: false,
+            cache_tags: false,
             ids: Vec::new(),
             ignore_ids: Vec::new(),
             known_failures: Vec::new(),
             extra_tags: Vec::new(),
+            apostrophes: None,
+            apostrophe_exceptions: Vec::new(),
+            hyphens: HyphenMode::default(),
+            join_numbers: false,
+            join_versions: false,
+            split_units: false,
+            units: Vec::new(),
+            join_dates: false,
+            join_ordinals: false,
+            number_tag: None,
+            roman_numerals: false,
+            roman_numeral_contexts: Vec::new(),
+            punctuation_tag: None,
+            join_punctuation: false,
+            detect_urls: true,
+            detect_bare_domains: false,
+            detect_emails: true,
+            detect_paths: false,
+            detect_social: false,
+            lang_code: String::new(),
+            extra_abbreviations: Vec::new(),
+            extra_patterns: Vec::new(),
+            normalize_nfc: false,
+            invisible_chars: InvisibleCharMode::default(),
+            normalize_quotes: false,
+            word_segmentation: WordSegmentation::default(),
+            extra_split_chars: String::new(),
+            remove_split_chars: String::new(),
+            rule_chunker: false,
+        }
+    }
+}
+
+/// A regular expression in the [Oniguruma syntax](https://github.com/kkos/oniguruma/blob/master/doc/RE).
+/// (De)serialized as the pattern string.
+#[derive(Debug)]
+pub struct Pattern {
+    pattern: String,
+    regex: Regex,
+}
+
+impl Pattern {
+    /// Compiles a new pattern.
+    pub fn new<S: Into<String>>(pattern: S) -> Result<Self, Error> {
+        let pattern = pattern.into();
+
+        Ok(Pattern {
+            regex: Regex::new(&pattern)?,
+            pattern,
+        })
+    }
+
+    /// Gets the pattern string.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub(crate) fn regex(&self) -> &Regex {
+        &self.regex
+    }
+}
+
+impl Clone for Pattern {
+    fn clone(&self) -> Self {
+        Pattern::new(self.pattern.clone()).expect("pattern has been compiled before")
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.pattern.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Pattern::new(pattern).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A position in a text in chars, extended grapheme clusters and UTF-16 code units.
+#[derive(Debug, Clone, Copy, Default)]
+struct Position {
+    chars: usize,
+    graphemes: usize,
+    utf16: usize,
+}
+
+impl Position {
+    /// Moves the position to the end of `text`, which must start at this position.
+    fn advance(&mut self, text: &str) {
+        for c in text.chars() {
+            self.chars += 1;
+            self.utf16 += c.len_utf16();
+        }
+        self.graphemes += text.graphemes(true).count();
+    }
+}
+
+/// How words containing apostrophes are split into tokens.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum ApostropheMode {
+    /// Always split at apostrophes e. g. "don't" -> "don", "'", "t". This is what LanguageTool does.
+    #[default]
+    Split,
+    /// Split English contractions into their parts e. g. "don't" -> "do", "n't" and "we're" -> "we", "'re".
+    /// Possessive apostrophes are split off ("James'" -> "James", "'"), exceptions like "o'clock" are kept.
+    English,
+    /// Split French elisions after the apostrophe e. g. "l'homme" -> "l'", "homme" and "jusqu'à" -> "jusqu'", "à".
+    /// Exceptions like "aujourd'hui" and "presqu'île" are kept.
+    French,
+}
+
+impl ApostropheMode {
+    /// Gets the apostrophe mode for a language code (e. g. "en" or "de").
+    pub fn for_language(lang_code: &str) -> Self {
+        match lang_code {
+            "en" => ApostropheMode::English,
+            "fr" => ApostropheMode::French,
+            _ => ApostropheMode::Split,
         }
     }
 }
 
+/// How words containing hyphens are tokenized.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum HyphenMode {
+    /// Keep hyphenated words as single tokens. This is what LanguageTool does.
+    #[default]
+    Keep,
+    /// Split hyphenated words into their parts and the hyphens e. g. "e-mail" -> "e", "-", "mail".
+    Split,
+    /// Keep hyphenated words as single tokens, but additionally assign the tags of the parts to the token.
+    /// Useful if the tagger dictionary contains the parts but not the compound.
+    Both,
+}
+
+/// How invisible chars within words are handled. These are soft hyphens (U+00AD), zero-width spaces (U+200B),
+/// zero-width non-joiners and joiners (U+200C, U+200D) and byte order marks (U+FEFF).
+/// The spans of tokens always refer to the original text, including the invisible chars.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum InvisibleCharMode {
+    /// Keep invisible chars in the token text, so e. g. "Mög\u{ad}lichkeit" is usually tagged as UNKNOWN.
+    #[default]
+    Keep,
+    /// Split tokens at zero-width spaces and remove the other invisible chars from the token text,
+    /// so "Mög\u{ad}lichkeit" is tagged like "Möglichkeit".
+    Strip,
+    /// Split tokens at all invisible chars.
+    Split,
+}
+
+/// How text is split into words after special tokens like URLs, emails and numbers have been detected.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum WordSegmentation {
+    /// Split at whitespace and splitting characters like punctuation. This is what LanguageTool does.
+    #[default]
+    Split,
+    /// Split at Unicode word boundaries as defined in [UAX #29](https://unicode.org/reports/tr29/#Word_Boundaries).
+    /// Meant for scripts which do not separate words with spaces like Japanese and Chinese: every ideograph
+    /// and Hiragana character becomes a token of its own, runs of Katakana and Latin letters are kept together.
+    /// This is not a morphological segmentation, but is enough for rules about punctuation and spacing.
+    UnicodeWords,
+}
+
+/// The environment variable with the directory [Tokenizer::for_language] loads tokenizers from.
+pub const STORAGE_ENV_VARIABLE: &str = "NLPRULE_STORAGE";
+
+lazy_static! {
+    static ref LANGUAGE_TOKENIZERS: Mutex<HashMap<PathBuf, Arc<Tokenizer>>> = Mutex::default();
+}
+
+/// The tags and whether lowercase readings were added by word, kind and sentence start, see
+/// [TokenizerOptions::cache_tags].
+type TagCache<'t> = DefaultHashMap<(Cow<'t, str>, TokenKind, bool), (Vec<WordData<'t>>, bool)>;
+
 /// The complete Tokenizer doing tagging, chunking and disambiguation.
+/// Tokenizers are independent of each other and only read from during tokenization, so tokenizers for multiple
+/// languages can be used in the same process and shared between threads with an [Arc].
 #[derive(Serialize, Deserialize, Default)]
 pub struct Tokenizer {
     pub(crate) rules: Vec<DisambiguationRule>,
     pub(crate) chunker: Option<Chunker>,
     pub(crate) tagger: Arc<Tagger>,
+    pub(crate) abbreviations: HashSet<String>,
+    pub(crate) segmenter: Option<SrxSegmenter>,
     pub(crate) options: TokenizerOptions,
+    pub(crate) multiword_tagger: Option<MultiwordTagger>,
+    #[cfg(feature = "compile")]
+    #[serde(skip)]
+    pub(crate) load_report: crate::compile::LoadReport,
 }
 
 impl Tokenizer {
-    /// Creates a new tokenizer from a file.
-    pub fn new<P: AsRef<Path>>(p: P) -> bincode::Result<Self> {
-        let reader = BufReader::new(File::open(p).unwrap());
-        bincode::deserialize_from(reader)
+    /// Creates a new tokenizer from a file. The file must have been written by the same version of nlprule: the
+    /// binary layout changes between versions (e. g. with new [options][TokenizerOptions]) and is not checked.
+    pub fn new<P: AsRef<Path>>(p: P) -> Result<Self, Error> {
+        Self::new_from(BufReader::new(File::open(p)?))
     }
 
     /// Creates a new tokenizer from a reader.
-    pub fn new_from<R: Read>(reader: R) -> bincode::Result<Self> {
-        bincode::deserialize_from(reader)
+    pub fn new_from<R: Read>(reader: R) -> Result<Self, Error> {
+        Ok(bincode::deserialize_from(reader)?)
+    }
+
+    /// Gets the tokenizer for a language code (e. g. "en" or "de") from the `<lang_code>_tokenizer.bin` file in the
+    /// directory set by the `NLPRULE_STORAGE` environment variable (`storage` by default).
+    /// Tokenizers are loaded once per file and cached, subsequent calls with the same language code and storage
+    /// directory return the same tokenizer.
+    pub fn for_language(lang_code: &str) -> Result<Arc<Self>, Error> {
+        let dir = std::env::var(STORAGE_ENV_VARIABLE).unwrap_or_else(|_| "storage".into());
+        let path = Path::new(&dir).join(format!("{}_tokenizer.bin", lang_code));
+
+        if let Some(tokenizer) = LANGUAGE_TOKENIZERS.lock().unwrap().get(&path) {
+            return Ok(Arc::clone(tokenizer));
+        }
+
+        // the lock is not held while loading so other languages can be loaded and used in the meantime.
+        // if the same file is loaded concurrently, the tokenizer which is inserted first is kept
+        let tokenizer = Arc::new(Tokenizer::new(&path)?);
+        let mut tokenizers = LANGUAGE_TOKENIZERS.lock().unwrap();
+
+        Ok(Arc::clone(tokenizers.entry(path).or_insert(tokenizer)))
     }
 
     pub fn rules(&self) -> &Vec<DisambiguationRule> {
@@ -155,10 +630,233 @@ impl Tokenizer {
         &self.chunker
     }
 
+    /// Abbreviations after which no sentence boundary is assumed. Does not include the `extra_abbreviations` of the options.
+    pub fn abbreviations(&self) -> &HashSet<String> {
+        &self.abbreviations
+    }
+
+    /// The SRX segmenter used to split text into sentences. Unicode sentence segmentation is used if there is none.
+    pub fn segmenter(&self) -> &Option<SrxSegmenter> {
+        &self.segmenter
+    }
+
     pub fn options(&self) -> &TokenizerOptions {
         &self.options
     }
 
+    /// The status of each disambiguation rule when the tokenizer was created with
+    /// [from_xml][Tokenizer::from_xml]. Empty for tokenizers loaded from a binary.
+    #[cfg(feature = "compile")]
+    pub fn load_report(&self) -> &crate::compile::LoadReport {
+        &self.load_report
+    }
+
+    /// The tagger for multiword expressions, which runs after the tagger and before disambiguation.
+    pub fn multiword_tagger(&self) -> &Option<MultiwordTagger> {
+        &self.multiword_tagger
+    }
+
+    /// Mutable access to the options e. g. to change how the text is split into tokens.
+    /// Options which are only used when building the tokenizer (like the rule IDs) have no effect.
+    pub fn options_mut(&mut self) -> &mut TokenizerOptions {
+        &mut self.options
+    }
+
+    /// The text of a token as it is looked up in the tagger. Invisible chars are removed unless they are kept
+    /// according to the [InvisibleCharMode], quotes are straightened if `normalize_quotes` is set and the text
+    /// is normalized to NFC if `normalize_nfc` is set. Text which does not change is not copied.
+    fn normalize<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        let mut text = Cow::Borrowed(text);
+
+        if self.options.invisible_chars != InvisibleCharMode::Keep
+            && text.contains(split::is_invisible)
+        {
+            text = Cow::Owned(text.replace(split::is_invisible, ""));
+        }
+        if self.options.normalize_quotes && text.contains(|c| split::normalize_quote(c).is_some()) {
+            text = Cow::Owned(
+                text.chars()
+                    .map(|c| split::normalize_quote(c).unwrap_or(c))
+                    .collect(),
+            );
+        }
+        if self.options.normalize_nfc && is_nfc_quick(text.chars()) != IsNormalized::Yes {
+            text = Cow::Owned(text.nfc().collect());
+        }
+
+        text
+    }
+
+    /// Looks up the tags of a word. Sentence-initial words also get the readings of their lowercase variant
+    /// (or of their titlecased variant if they are lowercase), see [Tagger::get_tags_with_lookup].
+    /// Returns the tags and whether readings of the lowercase variant were added because the word starts a sentence.
+    fn get_tags<'t>(
+        &'t self,
+        word: &str,
+        kind: TokenKind,
+        is_sentence_start: bool,
+    ) -> (Vec<WordData<'t>>, bool) {
+        let mut has_lowercase_readings = false;
+        let mut lookup = |word: &str| {
+            self.tagger
+                .get_tags_with_lookup(
+                    word,
+                    is_sentence_start || self.options.always_add_lower_tags,
+                    is_sentence_start,
+                    self.options.use_compound_split_heuristic,
+                )
+                .into_iter()
+                .map(|(data, lookup)| {
+                    has_lowercase_readings |= is_sentence_start && lookup == Lookup::Lowercase;
+                    data
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut tags = lookup(word);
+
+        if tags.is_empty() {
+            if let Some(splitting) = &self.options.compound_splitting {
+                tags = self.tagger.get_compound_tags(word, splitting);
+            }
+        }
+
+        if self.options.hyphens == HyphenMode::Both && word.contains('-') {
+            for part in word.split('-').filter(|x| !x.is_empty()) {
+                for data in lookup(part) {
+                    if !tags.contains(&data) {
+                        tags.push(data);
+                    }
+                }
+            }
+        }
+
+        let kind_tag = match kind {
+            TokenKind::Number => self.options.number_tag.as_ref(),
+            TokenKind::Punctuation => self.options.punctuation_tag.as_ref(),
+            _ => None,
+        };
+
+        if tags.is_empty() {
+            if let Some(tag) = kind_tag {
+                if let Some(pos) = self.tagger.tag_store().id(tag) {
+                    let lemma = match kind {
+                        TokenKind::Number => split::normalize_number(word, &self.options),
+                        _ => None,
+                    }
+                    .unwrap_or_else(|| word.to_string());
+
+                    tags.push(WordData::new(self.tagger.id_word(lemma.into()), pos));
+                }
+            }
+        }
+
+        (tags, has_lowercase_readings)
+    }
+
+    /// Looks up the tags of a word like [get_tags][Tokenizer::get_tags], but reuses the result for the same word,
+    /// kind and sentence start from the `cache`, if any (see [TokenizerOptions::cache_tags]).
+    fn get_tags_cached<'t>(
+        &'t self,
+        word: Cow<'t, str>,
+        kind: TokenKind,
+        is_sentence_start: bool,
+        cache: &mut Option<TagCache<'t>>,
+    ) -> (Vec<WordData<'t>>, bool) {
+        let cache = match cache {
+            Some(cache) => cache,
+            None => return self.get_tags(&word, kind, is_sentence_start),
+        };
+
+        let key = (word, kind, is_sentence_start);
+        if let Some(cached) = cache.get(&key) {
+            return cached.clone();
+        }
+
+        let output = self.get_tags(&key.0, kind, is_sentence_start);
+        cache.insert(key, output.clone());
+        output
+    }
+
+    /// A new cache for [get_tags_cached][Tokenizer::get_tags_cached] if tags should be cached.
+    fn tag_cache<'t>(&self) -> Option<TagCache<'t>> {
+        if self.options.cache_tags {
+            Some(DefaultHashMap::default())
+        } else {
+            None
+        }
+    }
+
+    /// Adds a numeral reading to the tokens which are Roman numerals, see [TokenizerOptions::roman_numerals].
+    fn tag_roman_numerals<'t>(&'t self, tokens: &mut [IncompleteToken<'t>]) {
+        const CONTEXTS: &[&str] = &[
+            "chapter",
+            "part",
+            "book",
+            "volume",
+            "article",
+            "section",
+            "appendix",
+            "act",
+            "scene",
+            "phase",
+            "stage",
+            "type",
+            "class",
+            "war",
+            "kapitel",
+            "teil",
+            "band",
+            "artikel",
+            "abschnitt",
+            "anhang",
+            "akt",
+            "szene",
+            "stufe",
+            "typ",
+            "klasse",
+            "krieg",
+        ];
+
+        let tag = match self
+            .options
+            .number_tag
+            .as_ref()
+            .and_then(|tag| self.tagger.tag_store().id(tag))
+        {
+            Some(tag) => tag,
+            None => return,
+        };
+
+        for i in 0..tokens.len() {
+            let text = tokens[i].word.text.as_ref();
+            let value = match roman_numeral_value(text) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            // the previous token is only considered within the sentence
+            let ambiguous = text.chars().count() == 1 || !tokens[i].word.tags.is_empty();
+            if ambiguous
+                && (i == 0 || tokens[i].is_sentence_start || {
+                    let prev = tokens[i - 1].word.text.as_ref().to_lowercase();
+                    !CONTEXTS.contains(&prev.as_str())
+                        && !self
+                            .options
+                            .roman_numeral_contexts
+                            .iter()
+                            .any(|x| x.to_lowercase() == prev)
+                })
+            {
+                continue;
+            }
+
+            let data = WordData::new(self.tagger.id_word(value.to_string().into()), tag.clone());
+            if !tokens[i].word.tags.contains(&data) {
+                tokens[i].word.tags.push(data);
+            }
+        }
+    }
+
     pub(crate) fn disambiguate_up_to_id<'t>(
         &'t self,
         mut tokens: Vec<IncompleteToken<'t>>,
@@ -171,21 +869,26 @@ impl Tokenizer {
 
         while i < n {
             let finalized = finalize(tokens.clone());
+            let apply = |(j, rule): (usize, &DisambiguationRule)| {
+                let changes = rule.apply(&finalized, self);
+                if changes.is_empty() {
+                    None
+                } else {
+                    Some((j + i, changes))
+                }
+            };
+
+            #[cfg(feature = "rayon")]
             let result = self.rules[i..n]
                 .maybe_par_iter()
                 .enumerate()
-                .filter_map(|(j, rule)| {
-                    let changes = rule.apply(&finalized, &self);
-                    if changes.is_empty() {
-                        None
-                    } else {
-                        Some((j + i, changes))
-                    }
-                })
+                .filter_map(apply)
                 .find_first(|_| true);
+            #[cfg(not(feature = "rayon"))]
+            let result = self.rules[i..n].iter().enumerate().find_map(apply);
 
             if let Some((index, changes)) = result {
-                self.rules[index].change(&mut tokens, &self, changes);
+                self.rules[index].change(&mut tokens, self, changes);
                 i = index + 1;
             } else {
                 i = n;
@@ -201,51 +904,203 @@ impl Tokenizer {
         &'t self,
         tokens: Vec<IncompleteToken<'t>>,
     ) -> Vec<IncompleteToken<'t>> {
-        self.disambiguate_up_to_id(tokens, None)
+        let mut tokens = self.disambiguate_up_to_id(tokens, None);
+
+        if self.options.rule_chunker {
+            chunker::apply(&mut tokens);
+        }
+
+        tokens
+    }
+
+    fn is_abbreviation(&self, text: &str) -> bool {
+        self.abbreviations.contains(text)
+            || self.options.extra_abbreviations.iter().any(|x| x == text)
+    }
+
+    /// Whether the sentence boundary between `prev_end` and `next_start` is preceded by an abbreviation
+    /// or lies within an abbreviation consisting of multiple words like "z. B.".
+    fn is_abbreviation_boundary(&self, text: &str, prev_end: usize, next_start: usize) -> bool {
+        // abbreviations rarely consist of more than three words
+        const MAX_WORDS: usize = 3;
+
+        let before = &text[..prev_end];
+        let mut starts: Vec<usize> = before
+            .rmatch_indices(char::is_whitespace)
+            .take(MAX_WORDS)
+            .map(|(i, x)| i + x.len())
+            .collect();
+        if starts.len() < MAX_WORDS {
+            starts.push(0);
+        }
+
+        let after = &text[next_start..];
+        let mut ends = vec![prev_end];
+        ends.extend(
+            after
+                .split_whitespace()
+                .take(MAX_WORDS - 1)
+                .map(|x| x.as_ptr() as usize - text.as_ptr() as usize + x.len()),
+        );
+
+        starts.iter().any(|start| {
+            let word_start = start + text[*start..prev_end].len()
+                - text[*start..prev_end]
+                    .trim_start_matches(|c: char| !c.is_alphanumeric())
+                    .len();
+
+            ends.iter().any(|end| {
+                let candidate = &text[word_start..*end];
+                candidate.ends_with('.') && self.is_abbreviation(candidate)
+            })
+        })
+    }
+
+    /// Byte spans of the sentences in the text without leading and trailing whitespace.
+    fn sentence_spans<'a>(&'a self, text: &'a str) -> impl Iterator<Item = (usize, usize)> + 'a {
+        // byte spans of the sentences in `base`, a text with the same byte offsets as `text`
+        fn offsets<'a>(
+            base: &'a str,
+            sentences: impl Iterator<Item = &'a str> + 'a,
+        ) -> impl Iterator<Item = (usize, usize)> + 'a {
+            sentences.map(move |sentence| {
+                let start = sentence.as_ptr() as usize - base.as_ptr() as usize;
+                (start, start + sentence.len())
+            })
+        }
+
+        let sentences: Box<dyn Iterator<Item = (usize, usize)> + 'a> = match &self.segmenter {
+            Some(segmenter) => Box::new(offsets(text, segmenter.segment(text).into_iter())),
+            None if has_bare_carriage_return(text) => {
+                // Unicode segmentation breaks after every carriage return. A carriage return which is not part
+                // of a CRLF line ending is treated like a space instead, the space has the same length in bytes.
+                let replaced = replace_bare_carriage_returns(text);
+                let spans: Vec<_> = offsets(&replaced, replaced.unicode_sentences()).collect();
+                Box::new(spans.into_iter())
+            }
+            None => Box::new(offsets(text, text.unicode_sentences())),
+        };
+
+        let mut spans = sentences
+            .filter_map(move |(ptr, end)| {
+                let sentence = &text[ptr..end];
+                // sentences can start and end with whitespace which is never part of a token
+                let start = ptr + sentence.len() - sentence.trim_start().len();
+                let end = ptr + sentence.trim_end().len();
+
+                if start == end {
+                    None
+                } else {
+                    Some((start, end))
+                }
+            })
+            .peekable();
+
+        std::iter::from_fn(move || {
+            let mut span = spans.next()?;
+
+            while let Some(&(start, end)) = spans.peek() {
+                if !self.is_abbreviation_boundary(text, span.1, start) {
+                    break;
+                }
+                span.1 = end;
+                spans.next();
+            }
+
+            Some(span)
+        })
     }
 
     /// Tokenize the given text. This applies chunking and tagging, but does not do disambiguation.
+    /// The tokens can be disambiguated with [disambiguate][Tokenizer::disambiguate].
     pub fn tokenize<'t>(&'t self, text: &'t str) -> Vec<IncompleteToken<'t>> {
-        let sentence_indices = text
-            .unicode_sentences()
-            .map(|sentence| {
-                let ptr = sentence.as_ptr() as usize;
-                (ptr, ptr + sentence.len())
+        let sentences: Vec<_> = self.sentence_spans(text).collect();
+        self.tokenize_part(text, (0, text.len()), Position::default(), &sentences)
+    }
+
+    /// Tokenize and finalize the given text without disambiguation, so the tokens have all tags and lemmas from
+    /// the tagger. Considerably faster than disambiguating the tokens e. g. to count words, and useful to inspect
+    /// which tags the disambiguation rules remove.
+    pub fn tokenize_raw<'t>(&'t self, text: &'t str) -> Vec<Token<'t>> {
+        finalize(self.tokenize(text))
+    }
+
+    /// Tokenizes the text in the byte span. `position` is the position of the start of the span in the text,
+    /// `sentences` the byte spans of the sentences in the text.
+    fn tokenize_part<'t>(
+        &'t self,
+        text: &'t str,
+        span: (usize, usize),
+        position: Position,
+        sentences: &[(usize, usize)],
+    ) -> Vec<IncompleteToken<'t>> {
+        let sentence_indices =
+            sentences
+                .iter()
+                .fold((HashSet::new(), HashSet::new()), |mut a, (start, end)| {
+                    a.0.insert(*start);
+                    a.1.insert(*end);
+                    a
+                });
+
+        let part = &text[span.0..span.1];
+        let grapheme_starts: Vec<usize> = part.grapheme_indices(true).map(|(i, _)| i).collect();
+        // number of graphemes starting before the byte index
+        let grapheme_index = |byte_index: usize| {
+            position.graphemes + grapheme_starts.partition_point(|x| *x + span.0 < byte_index)
+        };
+
+        // number of chars and UTF-16 code units in the string
+        fn lengths(string: &str) -> (usize, usize) {
+            string.chars().fold((0, 0), |(chars, utf16), c| {
+                (chars + 1, utf16 + c.len_utf16())
             })
-            .fold((HashSet::new(), HashSet::new()), |mut a, x| {
-                a.0.insert(x.0);
-                a.1.insert(x.1);
-                a
-            });
-
-        let mut current_char = 0;
-        let token_strs = get_token_strs(text);
+        }
+
+        // chars and UTF-16 code units before the current token string
+        let mut current = (position.chars, position.utf16);
+        let mut cache = self.tag_cache();
+        let token_strs = split::get_token_strs(part, &self.options);
         let mut tokens: Vec<_> = token_strs
             .into_iter()
-            .map(|x| {
-                let char_start = current_char;
-                let ptr = x.as_ptr() as usize;
-                current_char += x.chars().count();
-
-                let byte_start = ptr - text.as_ptr() as usize;
+            .map(|(x, kind)| {
+                // token strings can contain whitespace, the spans refer to the trimmed text
                 let trimmed = x.trim();
+                let leading = &x[..x.len() - x.trim_start().len()];
+                let trailing = &x[leading.len() + trimmed.len()..];
+
+                let byte_start = trimmed.as_ptr() as usize - text.as_ptr() as usize;
+                let byte_end = byte_start + trimmed.len();
+
+                let lead = lengths(leading);
+                let start = (current.0 + lead.0, current.1 + lead.1);
+                let len = lengths(trimmed);
+                let end = (start.0 + len.0, start.1 + len.1);
+                let trail = lengths(trailing);
+                current = (end.0 + trail.0, end.1 + trail.1);
 
-                let is_sentence_start = sentence_indices.0.contains(&ptr);
-                let is_sentence_end = sentence_indices.1.contains(&(ptr + x.len()));
+                let before = &text[..byte_start];
+                let space_before = &before[before.trim_end().len()..];
+
+                let is_sentence_start = sentence_indices.0.contains(&byte_start);
+                let is_sentence_end = sentence_indices.1.contains(&byte_end);
+                let word = self.normalize(trimmed);
+                let (tags, has_lowercase_readings) =
+                    self.get_tags_cached(word.clone(), kind, is_sentence_start, &mut cache);
 
                 IncompleteToken {
-                    word: Word::new_with_tags(
-                        self.tagger.id_word(trimmed.into()),
-                        self.tagger.get_tags(
-                            trimmed,
-                            is_sentence_start || self.options.always_add_lower_tags,
-                            self.options.use_compound_split_heuristic,
-                        ),
-                    ),
-                    char_span: (char_start, current_char),
-                    byte_span: (byte_start, byte_start + x.len()),
+                    word: Word::new_with_tags(self.tagger.id_word(word.clone()), tags),
+                    char_span: (start.0, end.0),
+                    grapheme_span: (grapheme_index(byte_start), grapheme_index(byte_end)),
+                    utf16_span: (start.1, end.1),
+                    byte_span: (byte_start, byte_end),
+                    kind,
+                    case: Case::of(trimmed),
+                    is_sentence_start,
                     is_sentence_end,
-                    has_space_before: text[..byte_start].ends_with(char::is_whitespace),
+                    has_lowercase_readings,
+                    has_space_before: !space_before.is_empty(),
+                    space_before,
                     chunks: Vec::new(),
                     text,
                     tagger: self.tagger.as_ref(),
@@ -254,9 +1109,21 @@ impl Tokenizer {
             .filter(|token| !token.word.text.as_ref().is_empty())
             .collect();
 
+        if self.options.roman_numerals {
+            self.tag_roman_numerals(&mut tokens);
+        }
+
+        if let Some(multiword_tagger) = &self.multiword_tagger {
+            multiword_tagger.apply(&mut tokens);
+        }
+
         if !tokens.is_empty() {
-            let last_idx = tokens.len() - 1;
-            tokens[last_idx].is_sentence_end = true;
+            // the last token of the text ends a sentence even if it is not part of one, e. g. a lone "."
+            // which the segmenter skips. Tokens of a part which ends earlier end a sentence only if one ends there
+            if span.1 == text.len() {
+                let last_idx = tokens.len() - 1;
+                tokens[last_idx].is_sentence_end = true;
+            }
 
             if let Some(chunker) = &self.chunker {
                 chunker.apply(&mut tokens);
@@ -265,26 +1132,2282 @@ impl Tokenizer {
 
         tokens
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::Tokenizer;
-    use lazy_static::lazy_static;
-    use quickcheck_macros::quickcheck;
-    use std::fs::File;
-    use std::io::BufReader;
+    /// Tag, chunk and disambiguate tokens which have already been split by another tokenizer. `spans` are the byte
+    /// spans of the tokens in the text, `sentence_starts` the indices of the tokens starting a sentence (the first
+    /// token always starts a sentence). The tokens are not split or merged and have the [Word][TokenKind::Word] kind.
+    /// Like for [tokenize][Tokenizer::tokenize] a SENT_START token is inserted before every sentence.
+    ///
+    /// # Panics
+    /// If the spans are not sorted, overlap or do not lie on char boundaries of the text.
+    pub fn tag_spans<'t>(
+        &'t self,
+        text: &'t str,
+        spans: &[(usize, usize)],
+        sentence_starts: &[usize],
+    ) -> Vec<Token<'t>> {
+        let grapheme_starts: Vec<usize> = text.grapheme_indices(true).map(|(i, _)| i).collect();
+        let grapheme_index =
+            |byte_index: usize| grapheme_starts.partition_point(|x| *x < byte_index);
+        let sentence_starts: HashSet<usize> = sentence_starts.iter().copied().collect();
 
-    #[quickcheck]
-    fn can_tokenize_anything(text: String) -> bool {
-        lazy_static! {
-            static ref TOKENIZER: Tokenizer = {
-                let reader = BufReader::new(File::open("../storage/en_tokenizer.bin").unwrap());
-                bincode::deserialize_from(reader).unwrap()
-            };
-        }
+        let mut position = Position::default();
+        let mut prev_end = 0;
+        let mut cache = self.tag_cache();
 
-        TOKENIZER.tokenize(&text);
-        true
+        let mut tokens: Vec<_> = spans
+            .iter()
+            .enumerate()
+            .map(|(i, &(byte_start, byte_end))| {
+                assert!(
+                    prev_end <= byte_start && byte_start <= byte_end,
+                    "spans must be sorted and must not overlap"
+                );
+
+                position.advance(&text[prev_end..byte_start]);
+                let start = position;
+                position.advance(&text[byte_start..byte_end]);
+                prev_end = byte_end;
+
+                let raw = &text[byte_start..byte_end];
+                let word = self.normalize(raw);
+                let before = &text[..byte_start];
+                let space_before = &before[before.trim_end().len()..];
+
+                let is_sentence_start = i == 0 || sentence_starts.contains(&i);
+                let is_sentence_end = i + 1 == spans.len() || sentence_starts.contains(&(i + 1));
+                let (tags, has_lowercase_readings) = self.get_tags_cached(
+                    word.clone(),
+                    TokenKind::Word,
+                    is_sentence_start,
+                    &mut cache,
+                );
+
+                IncompleteToken {
+                    word: Word::new_with_tags(self.tagger.id_word(word.clone()), tags),
+                    char_span: (start.chars, position.chars),
+                    grapheme_span: (grapheme_index(byte_start), grapheme_index(byte_end)),
+                    utf16_span: (start.utf16, position.utf16),
+                    byte_span: (byte_start, byte_end),
+                    kind: TokenKind::Word,
+                    case: Case::of(raw),
+                    is_sentence_start,
+                    is_sentence_end,
+                    has_lowercase_readings,
+                    has_space_before: !space_before.is_empty(),
+                    space_before,
+                    chunks: Vec::new(),
+                    text,
+                    tagger: self.tagger.as_ref(),
+                }
+            })
+            .collect();
+
+        if self.options.roman_numerals {
+            self.tag_roman_numerals(&mut tokens);
+        }
+
+        if let Some(multiword_tagger) = &self.multiword_tagger {
+            multiword_tagger.apply(&mut tokens);
+        }
+
+        if let Some(chunker) = &self.chunker {
+            if !tokens.is_empty() {
+                chunker.apply(&mut tokens);
+            }
+        }
+
+        finalize(self.disambiguate(tokens))
+    }
+
+    /// Tag, chunk and disambiguate the words of a pretokenized text like [tag_spans][Tokenizer::tag_spans].
+    /// The spans of the returned tokens are the spans the words would have if they were joined with a single space.
+    pub fn tag_tokens(&self, words: &[&str], sentence_starts: &[usize]) -> Vec<owned::Token> {
+        let text = words.join(" ");
+        let mut spans = Vec::with_capacity(words.len());
+        let mut start = 0;
+
+        for word in words {
+            spans.push((start, start + word.len()));
+            start += word.len() + 1;
+        }
+
+        self.tag_spans(&text, &spans, sentence_starts)
+            .iter()
+            .map(|x| x.to_owned_token())
+            .collect()
+    }
+
+    /// Tokenize and disambiguate the given text, keeping the whitespace between tokens as tokens of the
+    /// [Whitespace][TokenKind::Whitespace] kind. The text of the returned tokens concatenated is the input text.
+    /// Whitespace tokens are not tagged and are inserted after disambiguation, so they do not influence the
+    /// disambiguation rules. Note that grammar rules do not expect whitespace tokens.
+    pub fn tokenize_lossless<'t>(&'t self, text: &'t str) -> Vec<IncompleteToken<'t>> {
+        let tokens = self.disambiguate(self.tokenize(text));
+        let mut lossless = Vec::with_capacity(tokens.len() * 2 + 1);
+
+        // start of the (byte, char, grapheme, UTF-16) spans
+        type Offsets = (usize, usize, usize, usize);
+
+        let whitespace = |start: Offsets, end: Offsets| IncompleteToken {
+            word: Word::new_with_tags(self.tagger.id_word(text[start.0..end.0].into()), Vec::new()),
+            byte_span: (start.0, end.0),
+            char_span: (start.1, end.1),
+            grapheme_span: (start.2, end.2),
+            utf16_span: (start.3, end.3),
+            kind: TokenKind::Whitespace,
+            case: Case::NonAlpha,
+            is_sentence_start: false,
+            is_sentence_end: false,
+            has_lowercase_readings: false,
+            has_space_before: false,
+            space_before: "",
+            chunks: Vec::new(),
+            text,
+            tagger: self.tagger.as_ref(),
+        };
+
+        let mut prev: Offsets = (0, 0, 0, 0);
+        for token in tokens {
+            if token.byte_span.0 > prev.0 {
+                let start = (
+                    token.byte_span.0,
+                    token.char_span.0,
+                    token.grapheme_span.0,
+                    token.utf16_span.0,
+                );
+                lossless.push(whitespace(prev, start));
+            }
+
+            prev = (
+                token.byte_span.1,
+                token.char_span.1,
+                token.grapheme_span.1,
+                token.utf16_span.1,
+            );
+            lossless.push(token);
+        }
+
+        if text.len() > prev.0 {
+            let rest = &text[prev.0..];
+            let end = (
+                text.len(),
+                prev.1 + rest.chars().count(),
+                prev.2 + rest.graphemes(true).count(),
+                prev.3 + rest.encode_utf16().count(),
+            );
+            lossless.push(whitespace(prev, end));
+        }
+
+        lossless
+    }
+
+    /// Tokenize the given text and group the tokens by sentence.
+    /// Every sentence is disambiguated and finalized on its own, so each one starts with a SENT_START token
+    /// and disambiguation rules can not match across sentence boundaries.
+    pub fn tokenize_sentences<'t>(&'t self, text: &'t str) -> Vec<Vec<Token<'t>>> {
+        let mut sentences: Vec<Vec<IncompleteToken>> = Vec::new();
+
+        for token in self.tokenize(text) {
+            match sentences.last_mut() {
+                Some(sentence) if !token.is_sentence_start => sentence.push(token),
+                _ => sentences.push(vec![token]),
+            }
+        }
+
+        sentences
+            .into_iter()
+            .enumerate()
+            .map(|(i, sentence)| finalize_sentence(self.disambiguate(sentence), i))
+            .collect()
+    }
+
+    /// Tokenize, disambiguate and finalize the text like [tokenize_sentences][Tokenizer::tokenize_sentences] and
+    /// return a [Doc][doc::Doc] which owns the text and the tokens and can look up the token at an offset.
+    pub fn analyze<S: Into<String>>(&self, text: S) -> doc::Doc {
+        doc::Doc::new(text.into(), self)
+    }
+
+    /// Tokenize text with Markdown or HTML markup and group the tokens by sentence. Markup and code are
+    /// not tokenized, the spans of the tokens refer to the marked-up source. See [markup] for details.
+    pub fn tokenize_markup(&self, source: &str, markup: markup::Markup) -> Vec<Vec<owned::Token>> {
+        markup::PlainText::new(source, markup).tokenize(self)
+    }
+
+    /// Tokenize the text segments of an annotated text and group the tokens by sentence.
+    /// The spans of the tokens refer to the whole document including the markup.
+    pub fn tokenize_annotated(&self, text: &markup::AnnotatedText) -> Vec<Vec<owned::Token>> {
+        text.plain_text().tokenize(self)
+    }
+
+    /// Tokenize the given text one sentence at a time. Each sentence is tokenized, disambiguated and finalized
+    /// only when it is requested from the iterator, so only the tokens of one sentence are kept in memory.
+    /// Yields the same sentences as [tokenize_sentences][Tokenizer::tokenize_sentences] unless a token would span
+    /// multiple sentences.
+    pub fn tokenize_iter<'t>(&'t self, text: &'t str) -> impl Iterator<Item = Vec<Token<'t>>> + 't {
+        let mut position = Position::default();
+        let mut prev_end = 0;
+
+        self.sentence_spans(text).enumerate().map(move |(i, span)| {
+            position.advance(&text[prev_end..span.0]);
+            let tokens = self.tokenize_part(text, span, position, &[span]);
+
+            position.advance(&text[span.0..span.1]);
+            prev_end = span.1;
+
+            finalize_sentence(self.disambiguate(tokens), i)
+        })
+    }
+
+    /// Tokenize the given text and group the tokens by sentence like [tokenize_iter][Tokenizer::tokenize_iter],
+    /// but tokenizes, disambiguates and finalizes the sentences in parallel. The sentences are in the order of
+    /// the text. Runs serially if parallelism is disabled with the `NLPRULE_PARALLELISM` environment variable.
+    /// Requires the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn tokenize_parallel<'t>(&'t self, text: &'t str) -> Vec<Vec<Token<'t>>> {
+        let mut position = Position::default();
+        let mut prev_end = 0;
+
+        let sentences: Vec<_> = self
+            .sentence_spans(text)
+            .enumerate()
+            .map(|(i, span)| {
+                position.advance(&text[prev_end..span.0]);
+                let start = position;
+
+                position.advance(&text[span.0..span.1]);
+                prev_end = span.1;
+
+                (i, span, start)
+            })
+            .collect();
+
+        sentences
+            .maybe_par_iter()
+            .map(|(i, span, position)| {
+                finalize_sentence(
+                    self.disambiguate(self.tokenize_part(text, *span, *position, &[*span])),
+                    *i,
+                )
+            })
+            .collect()
+    }
+
+    /// Tokenize the text read from the reader one sentence at a time like [tokenize_iter][Tokenizer::tokenize_iter].
+    /// The text is read line by line and buffered up to the next blank line, then the buffered paragraph is split
+    /// into sentences, so sentences do not continue across blank lines. The spans of the tokens refer to the complete
+    /// text read from the reader.
+    pub fn tokenize_reader<'a, R: BufRead + 'a>(
+        &'a self,
+        mut reader: R,
+    ) -> impl Iterator<Item = Result<Vec<owned::Token>, Error>> + 'a {
+        let mut buffer = String::new();
+        let mut ready = VecDeque::new();
+        // the number of bytes and the position of the text before the buffer
+        let mut offset = (0, Position::default());
+        let mut sentence_index = 0;
+        let mut done = false;
+
+        std::iter::from_fn(move || loop {
+            if let Some(sentence) = ready.pop_front() {
+                return Some(Ok(sentence));
+            }
+            if done {
+                return None;
+            }
+
+            let line_start = buffer.len();
+            let is_eof = match reader.read_line(&mut buffer) {
+                Ok(n) => n == 0,
+                Err(error) => {
+                    done = true;
+                    return Some(Err(error.into()));
+                }
+            };
+
+            // the buffer is only segmented at the end of a paragraph, segmenting it after every line would take
+            // quadratic time for paragraphs with many lines
+            if is_eof {
+                done = true;
+            } else if !buffer[line_start..].trim().is_empty() {
+                continue;
+            }
+
+            let spans: Vec<_> = self.sentence_spans(&buffer).collect();
+
+            // the tokens are created from the buffer and their spans are shifted by the text before it
+            let mut position = Position::default();
+            let mut prev_end = 0;
+            for span in spans {
+                position.advance(&buffer[prev_end..span.0]);
+                let tokens = finalize_sentence(
+                    self.disambiguate(self.tokenize_part(&buffer, span, position, &[span])),
+                    sentence_index,
+                );
+                sentence_index += 1;
+
+                position.advance(&buffer[span.0..span.1]);
+                prev_end = span.1;
+
+                let (bytes, chars) = offset;
+                ready.push_back(
+                    tokens
+                        .iter()
+                        .map(|token| {
+                            let mut token = token.to_owned_token();
+                            token.byte_span =
+                                (token.byte_span.0 + bytes, token.byte_span.1 + bytes);
+                            token.char_span = (
+                                token.char_span.0 + chars.chars,
+                                token.char_span.1 + chars.chars,
+                            );
+                            token.grapheme_span = (
+                                token.grapheme_span.0 + chars.graphemes,
+                                token.grapheme_span.1 + chars.graphemes,
+                            );
+                            token.utf16_span = (
+                                token.utf16_span.0 + chars.utf16,
+                                token.utf16_span.1 + chars.utf16,
+                            );
+                            token
+                        })
+                        .collect(),
+                );
+            }
+
+            // whitespace after the last complete sentence is kept to compute `space_before` of the next token
+            offset.0 += prev_end;
+            offset.1.advance(&buffer[..prev_end]);
+            buffer.drain(..prev_end);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        finalize,
+        multiword::MultiwordTagger,
+        roman_numeral_value,
+        srx::{SrxRule, SrxSegmenter},
+        tag::{Casing, Tagger},
+        ApostropheMode, HyphenMode, Pattern, Tokenizer, TokenizerOptions, WordSegmentation,
+    };
+    #[cfg(feature = "compile")]
+    use crate::compile::tests::tokenizer_from_xml;
+    use crate::types::{Case, SpaceKind, TokenKind};
+    #[cfg(feature = "compile")]
+    use crate::types::{Token, WordData};
+    use lazy_static::lazy_static;
+    use quickcheck_macros::quickcheck;
+    use std::collections::HashSet;
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::sync::Arc;
+
+    #[quickcheck]
+    fn can_tokenize_anything(text: String) -> bool {
+        lazy_static! {
+            static ref TOKENIZER: Tokenizer = {
+                let reader = BufReader::new(File::open("../storage/en_tokenizer.bin").unwrap());
+                bincode::deserialize_from(reader).unwrap()
+            };
+        }
+
+        TOKENIZER.tokenize(&text);
+        true
+    }
+
+    /// A tokenizer with an empty dictionary, but with the special tags needed to finalize tokens.
+    fn finalizing_tokenizer() -> Tokenizer {
+        let tagger = Tagger::from_dumps(
+            &[] as &[&str],
+            &[] as &[&str],
+            &[] as &[&str],
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        Tokenizer {
+            tagger: Arc::new(tagger),
+            ..Tokenizer::default()
+        }
+    }
+
+    /// A tokenizer with a dictionary of one `word\tlemma\ttag` entry per line and without disambiguation rules.
+    fn tokenizer_from_dump(dump: &str) -> Tokenizer {
+        let tagger = Tagger::from_readers(
+            vec![dump.as_bytes()],
+            Vec::<&[u8]>::new(),
+            &[] as &[&str],
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        Tokenizer {
+            tagger: Arc::new(tagger),
+            ..Tokenizer::default()
+        }
+    }
+
+    /// The sorted tags of the disambiguated token starting at the char index.
+    fn tags_of(tokenizer: &Tokenizer, text: &str, char_start: usize) -> Vec<String> {
+        let tokens = tokenizer.disambiguate(tokenizer.tokenize(text));
+        let token = tokens.iter().find(|x| x.char_span.0 == char_start).unwrap();
+
+        let mut tags: Vec<_> = token
+            .word
+            .tags
+            .iter()
+            .map(|x| x.pos.0.to_string())
+            .collect();
+        tags.sort();
+        tags
+    }
+
+    fn span_tokenizer() -> Tokenizer {
+        Tokenizer {
+            options: TokenizerOptions {
+                apostrophes: Some(ApostropheMode::English),
+                hyphens: HyphenMode::Split,
+                join_numbers: true,
+                join_punctuation: true,
+                detect_paths: true,
+                detect_social: true,
+                split_units: true,
+                // matches surrounding whitespace, so the token strings are not trimmed
+                extra_patterns: vec![Pattern::new(r"\s*\[\[[^\]]*\]\]\s*").unwrap()],
+                ..TokenizerOptions::default()
+            },
+            ..finalizing_tokenizer()
+        }
+    }
+
+    #[test]
+    fn tokens_have_frequencies() {
+        let mut tagger = Tagger::from_readers(
+            vec![&b"Haus\tHaus\tSUB\nlief\tlaufen\tVER\n"[..]],
+            Vec::<&[u8]>::new(),
+            &[] as &[&str],
+            &HashSet::new(),
+        )
+        .unwrap();
+        tagger
+            .load_frequencies(format!(
+                "{}/tests/fixtures/frequencies.txt",
+                env!("CARGO_MANIFEST_DIR")
+            ))
+            .unwrap();
+        let tokenizer = Tokenizer {
+            tagger: Arc::new(tagger),
+            ..Tokenizer::default()
+        };
+
+        let frequencies: Vec<_> = tokenizer
+            .tokenize_raw("Das Haus lief.")
+            .iter()
+            .filter(|x| !x.is_sentence_start())
+            .map(|x| (x.word.text.as_ref().to_string(), x.frequency()))
+            .collect();
+
+        assert_eq!(
+            frequencies,
+            vec![
+                ("Das".to_string(), None),
+                ("Haus".to_string(), Some(120)),
+                ("lief".to_string(), Some(40)),
+                (".".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_describe_tags() {
+        let mut tagger = Tagger::from_readers(
+            vec![&b"Haus\tHaus\tSUB:NOM:SIN:NEU\nHaus\tHaus\tSUB:AKK:SIN:NEU\n"[..]],
+            Vec::<&[u8]>::new(),
+            &[] as &[&str],
+            &HashSet::new(),
+        )
+        .unwrap();
+        tagger
+            .load_tagset(format!(
+                "{}/tests/fixtures/tagset.txt",
+                env!("CARGO_MANIFEST_DIR")
+            ))
+            .unwrap();
+        let tokenizer = Tokenizer {
+            tagger: Arc::new(tagger),
+            ..Tokenizer::default()
+        };
+
+        let tokens = tokenizer.tokenize_raw("Haus");
+        // the accusative is not in the tagset
+        assert_eq!(
+            tokens[1].describe_tags(),
+            "Substantiv, Nominativ Singular Neutrum; SUB:AKK:SIN:NEU"
+        );
+        assert_eq!(tokens[0].describe_tags(), "");
+    }
+
+    #[test]
+    fn sentence_start_adds_lowercase_readings() {
+        let tagger = Tagger::from_readers(
+            vec![&b"run\trun\tVB\nthe\tthe\tDT\nBerlin\tBerlin\tNNP\nberlin\tberlin\tNN\nLaufen\tLaufen\tSUB\nlaufen\tlaufen\tVER\nist\tsein\tVER\n"[..]],
+            Vec::<&[u8]>::new(),
+            &[] as &[&str],
+            &HashSet::new(),
+        )
+        .unwrap();
+        let tokenizer = Tokenizer {
+            tagger: Arc::new(tagger),
+            ..Tokenizer::default()
+        };
+
+        let tags = |text: &str, index: usize| {
+            let tokens = tokenizer.tokenize(text);
+            let token = &tokens[index];
+            let mut tags: Vec<_> = token
+                .word
+                .tags
+                .iter()
+                .map(|x| x.pos.as_ref().to_string())
+                .collect();
+            tags.sort();
+            (tags, token.has_lowercase_readings)
+        };
+        let expected =
+            |tags: &[&str], flag: bool| (tags.iter().map(|x| x.to_string()).collect(), flag);
+
+        // a capitalized word without own readings only has the lowercase ones at the start of a sentence
+        assert_eq!(tags("Run the tests.", 0), expected(&["VB"], true));
+        // within a sentence, the lowercase readings are only a fallback for unknown words and not flagged
+        assert_eq!(tags("Run the Run.", 2), expected(&["VB"], false));
+        // proper nouns keep their reading
+        assert_eq!(tags("Berlin is nice.", 0), expected(&["NN", "NNP"], true));
+        assert_eq!(tags("the Berlin", 1), expected(&["NNP"], false));
+        assert_eq!(
+            tags("Laufen ist gesund.", 0),
+            expected(&["SUB", "VER"], true)
+        );
+        assert_eq!(tags("Das Laufen ist gesund.", 1), expected(&["SUB"], false));
+        // lowercase words are not flagged
+        assert_eq!(
+            tags("laufen ist gesund.", 0),
+            expected(&["SUB", "VER"], false)
+        );
+    }
+
+    fn spans_match_text(tokenizer: &Tokenizer, text: &str) -> bool {
+        tokenizer.tokenize(text).iter().all(|token| {
+            let chars: String = text
+                .chars()
+                .skip(token.char_span.0)
+                .take(token.char_span.1 - token.char_span.0)
+                .collect();
+
+            &text[token.byte_span.0..token.byte_span.1] == token.word.text.as_ref()
+                && chars == token.word.text.as_ref()
+        })
+    }
+
+    #[test]
+    fn spans_match_trimmed_text() {
+        let tokenizer = span_tokenizer();
+
+        for text in &[
+            "See [[ a link ]]  here.",
+            "  Leading and trailing whitespace \t\n",
+            "Tabs\tand\nnewlines\r\nand\u{a0}nbsp\u{2003}em space.",
+            "Don't go to /usr/local/bin with 10km and 3.14...",
+            "  [[x]]\n\n[[ y ]] 👩\u{200d}👦 cafe\u{301}",
+            "",
+            "   ",
+        ] {
+            assert!(spans_match_text(&tokenizer, text), "{:?}", text);
+        }
+    }
+
+    #[quickcheck]
+    fn spans_match_text_with_random_whitespace(words: Vec<(String, u8)>) -> bool {
+        const WHITESPACE: &[&str] = &["", " ", "  ", "\t", "\n", "\r\n", "\u{a0}", "\u{2003}"];
+
+        let text: String = words
+            .iter()
+            .map(|(word, i)| format!("{}{}", word, WHITESPACE[*i as usize % WHITESPACE.len()]))
+            .collect();
+
+        spans_match_text(&span_tokenizer(), &text)
+    }
+
+    #[test]
+    fn split_units_have_no_space_before() {
+        let mut tokenizer = Tokenizer::default();
+        tokenizer.options_mut().split_units = true;
+
+        let tokens = tokenizer.tokenize("Run 10km");
+        let spans: Vec<_> = tokens
+            .iter()
+            .map(|x| (x.word.text.as_ref(), x.byte_span, x.has_space_before))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                ("Run", (0, 3), false),
+                ("10", (4, 6), true),
+                ("km", (6, 8), false)
+            ]
+        );
+    }
+
+    #[test]
+    fn grapheme_spans() {
+        let tokenizer = Tokenizer::default();
+
+        let spans = |text: &str| -> Vec<((usize, usize), (usize, usize))> {
+            tokenizer
+                .tokenize(text)
+                .iter()
+                .map(|x| (x.char_span, x.grapheme_span))
+                .collect()
+        };
+
+        assert_eq!(
+            spans("We 👩\u{200d}👩\u{200d}👦 ok"),
+            vec![((0, 2), (0, 2)), ((3, 8), (3, 4)), ((9, 11), (5, 7))]
+        );
+        assert_eq!(
+            spans("A cafe\u{301}, ok"),
+            vec![
+                ((0, 1), (0, 1)),
+                ((2, 7), (2, 6)),
+                ((7, 8), (6, 7)),
+                ((9, 11), (8, 10))
+            ]
+        );
+        assert_eq!(
+            spans("🇦🇹 \u{1f44d}\u{1f3fd} ok"),
+            vec![((0, 2), (0, 1)), ((3, 5), (2, 3)), ((6, 8), (4, 6))]
+        );
+    }
+
+    #[test]
+    fn utf16_spans() {
+        let tokenizer = Tokenizer::default();
+        let tokens = tokenizer.tokenize("𝔘nicode 😀 test, Ωμέγα");
+        let spans: Vec<_> = tokens
+            .iter()
+            .map(|x| (x.word.text.as_ref(), x.utf16_span))
+            .collect();
+
+        assert_eq!(
+            spans,
+            vec![
+                ("𝔘nicode", (0, 8)),
+                ("😀", (9, 11)),
+                ("test", (12, 16)),
+                (",", (16, 17)),
+                ("Ωμέγα", (18, 23))
+            ]
+        );
+    }
+
+    #[test]
+    fn whitespace_before_tokens() {
+        let tokenizer = Tokenizer::default();
+        let tokens = tokenizer.tokenize("  Foo,bar\n\nBaz\tqux");
+        let spaces: Vec<_> = tokens
+            .iter()
+            .map(|x| (x.word.text.as_ref(), x.has_space_before, x.space_before))
+            .collect();
+
+        assert_eq!(
+            spaces,
+            vec![
+                ("Foo", true, "  "),
+                (",", false, ""),
+                ("bar", false, ""),
+                ("Baz", true, "\n\n"),
+                ("qux", true, "\t")
+            ]
+        );
+    }
+
+    #[test]
+    fn lossless_tokens_reproduce_text() {
+        let tokenizer = span_tokenizer();
+
+        for text in &[
+            "  Leading, 10km  and\ttabs\n\nand trailing whitespace \n",
+            "See [[ a link ]]  here.",
+            "No whitespace",
+            "   ",
+            "",
+        ] {
+            let tokens = tokenizer.tokenize_lossless(text);
+            let concatenated: String = tokens.iter().map(|x| x.word.text.as_ref()).collect();
+            assert_eq!(&concatenated, text);
+
+            for token in &tokens {
+                assert_eq!(
+                    &text[token.byte_span.0..token.byte_span.1],
+                    token.word.text.as_ref()
+                );
+                if token.kind == TokenKind::Whitespace {
+                    assert!(token.word.text.as_ref().trim().is_empty());
+                    assert!(token.word.tags.is_empty());
+                }
+            }
+
+            let without_whitespace: Vec<_> = tokens
+                .into_iter()
+                .filter(|x| x.kind != TokenKind::Whitespace)
+                .collect();
+            assert_eq!(
+                without_whitespace,
+                tokenizer.disambiguate(tokenizer.tokenize(text))
+            );
+        }
+
+        let tokenizer = Tokenizer::default();
+        let tokens = tokenizer.tokenize_lossless("A  b");
+        let kinds: Vec<_> = tokens
+            .iter()
+            .map(|x| (x.word.text.as_ref(), x.kind, x.char_span))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("A", TokenKind::Word, (0, 1)),
+                ("  ", TokenKind::Whitespace, (1, 3)),
+                ("b", TokenKind::Word, (3, 4))
+            ]
+        );
+    }
+
+    #[test]
+    fn token_kinds() {
+        let tokenizer = span_tokenizer();
+        let text =
+            "See https://example.com, /usr/bin or foo@example.com for #nlproc by @user: 42 €!";
+        let tokens = super::finalize(tokenizer.tokenize_lossless(text));
+        let kinds: Vec<_> = tokens
+            .iter()
+            .filter(|x| x.kind != TokenKind::Whitespace)
+            .map(|x| (x.word.text.as_ref(), x.kind))
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                ("", TokenKind::SentStart),
+                ("See", TokenKind::Word),
+                ("https://example.com", TokenKind::Url),
+                (",", TokenKind::Punctuation),
+                ("/usr/bin", TokenKind::Path),
+                ("or", TokenKind::Word),
+                ("foo@example.com", TokenKind::Email),
+                ("for", TokenKind::Word),
+                ("#nlproc", TokenKind::Hashtag),
+                ("by", TokenKind::Word),
+                ("@user", TokenKind::Mention),
+                (":", TokenKind::Punctuation),
+                ("42", TokenKind::Number),
+                ("€", TokenKind::Punctuation),
+                ("!", TokenKind::Punctuation)
+            ]
+        );
+        assert_eq!(
+            tokens
+                .iter()
+                .filter(|x| x.kind == TokenKind::Whitespace)
+                .count(),
+            10
+        );
+    }
+
+    #[test]
+    fn token_case() {
+        let tokenizer = finalizing_tokenizer();
+        let tokens = super::finalize(tokenizer.tokenize("Berlin NASA iPhone 123 house, A"));
+        let cases: Vec<_> = tokens
+            .iter()
+            .map(|x| (x.word.text.as_ref(), x.case))
+            .collect();
+
+        assert_eq!(
+            cases,
+            vec![
+                ("", Case::NonAlpha),
+                ("Berlin", Case::Title),
+                ("NASA", Case::Upper),
+                ("iPhone", Case::Mixed),
+                ("123", Case::NonAlpha),
+                ("house", Case::Lower),
+                (",", Case::NonAlpha),
+                ("A", Case::Title)
+            ]
+        );
+        assert!(tokens[0].is_sentence_start());
+        assert!(!tokens[1].is_sentence_start());
+    }
+
+    #[test]
+    fn unicode_word_segmentation() {
+        let mut tokenizer = finalizing_tokenizer();
+        let text = "私はコーヒーを飲みます。";
+        assert_eq!(tokenizer.tokenize(text).len(), 1);
+
+        tokenizer.options_mut().word_segmentation = WordSegmentation::UnicodeWords;
+        let spans: Vec<_> = tokenizer
+            .tokenize(text)
+            .into_iter()
+            .map(|x| (x.word.text.as_ref().to_string(), x.byte_span, x.char_span))
+            .collect();
+
+        assert_eq!(
+            spans,
+            vec![
+                ("私".to_string(), (0, 3), (0, 1)),
+                ("は".to_string(), (3, 6), (1, 2)),
+                ("コーヒー".to_string(), (6, 18), (2, 6)),
+                ("を".to_string(), (18, 21), (6, 7)),
+                ("飲".to_string(), (21, 24), (7, 8)),
+                ("み".to_string(), (24, 27), (8, 9)),
+                ("ま".to_string(), (27, 30), (9, 10)),
+                ("す".to_string(), (30, 33), (10, 11)),
+                ("。".to_string(), (33, 36), (11, 12)),
+            ]
+        );
+    }
+
+    #[test]
+    fn unicode_space_kinds() {
+        let tokenizer = finalizing_tokenizer();
+        let text =
+            "Quoi\u{202F}? Il dit\u{A0}: «\u{A0}1\u{A0}000\u{2009}€\u{A0}» et\u{2003}fin.\n\nOui";
+        let tokens = super::finalize(tokenizer.tokenize(text));
+        let words: Vec<_> = tokens.iter().filter(|x| !x.is_sentence_start()).collect();
+
+        let spaces: Vec<_> = words
+            .iter()
+            .map(|x| {
+                assert!(!x.word.text.as_ref().contains(char::is_whitespace));
+                (x.word.text.as_ref(), x.space_before, x.space_kind())
+            })
+            .collect();
+
+        assert_eq!(
+            spaces,
+            vec![
+                ("Quoi", "", SpaceKind::None),
+                ("?", "\u{202F}", SpaceKind::NarrowNoBreak),
+                ("Il", " ", SpaceKind::Space),
+                ("dit", " ", SpaceKind::Space),
+                (":", "\u{A0}", SpaceKind::NoBreak),
+                ("«", " ", SpaceKind::Space),
+                ("1", "\u{A0}", SpaceKind::NoBreak),
+                ("000", "\u{A0}", SpaceKind::NoBreak),
+                ("€", "\u{2009}", SpaceKind::Thin),
+                ("»", "\u{A0}", SpaceKind::NoBreak),
+                ("et", " ", SpaceKind::Space),
+                ("fin", "\u{2003}", SpaceKind::Other),
+                (".", "", SpaceKind::None),
+                ("Oui", "\n\n", SpaceKind::LineBreak),
+            ]
+        );
+        assert!(words[6].has_space_before);
+        assert_eq!(words[6].kind, TokenKind::Number);
+        assert_eq!(words[7].kind, TokenKind::Number);
+        assert_eq!(super::detokenize(&tokens), text);
+    }
+
+    #[test]
+    fn token_and_sentence_indices() {
+        let tokenizer = finalizing_tokenizer();
+        let text = "Foo bar.  Baz, qux!\nEnd";
+
+        let indices = |tokens: &[super::Token]| -> Vec<(String, usize, usize)> {
+            tokens
+                .iter()
+                .filter(|x| x.kind != TokenKind::Whitespace)
+                .map(|x| {
+                    (
+                        x.word.text.as_ref().to_string(),
+                        x.sentence_index,
+                        x.token_index,
+                    )
+                })
+                .collect()
+        };
+
+        let tokens = super::finalize(tokenizer.disambiguate(tokenizer.tokenize(text)));
+        let expected = indices(&tokens);
+        assert_eq!(
+            expected,
+            vec![
+                ("".to_string(), 0, 0),
+                ("Foo".to_string(), 0, 1),
+                ("bar".to_string(), 0, 2),
+                (".".to_string(), 0, 3),
+                ("".to_string(), 1, 0),
+                ("Baz".to_string(), 1, 1),
+                (",".to_string(), 1, 2),
+                ("qux".to_string(), 1, 3),
+                ("!".to_string(), 1, 4),
+                ("".to_string(), 2, 0),
+                ("End".to_string(), 2, 1)
+            ]
+        );
+
+        let lossless = super::finalize(tokenizer.tokenize_lossless(text));
+        assert_eq!(indices(&lossless), expected);
+
+        let sentences: Vec<_> = tokenizer
+            .tokenize_sentences(text)
+            .iter()
+            .flat_map(|x| indices(x))
+            .collect();
+        assert_eq!(sentences, expected);
+
+        let sentences: Vec<_> = tokenizer
+            .tokenize_iter(text)
+            .flat_map(|x| indices(&x))
+            .collect();
+        assert_eq!(sentences, expected);
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let tokenizer = finalizing_tokenizer();
+        let lf = "First line here.\nSecond sentence, same paragraph.\n\nNew paragraph starts. It ends!\n";
+        let crlf = lf.replace('\n', "\r\n");
+
+        let lf_sentences = tokenizer.tokenize_sentences(lf);
+        let crlf_sentences = tokenizer.tokenize_sentences(&crlf);
+        assert_eq!(lf_sentences.len(), 4);
+        assert_eq!(crlf_sentences.len(), lf_sentences.len());
+
+        for (lf_sentence, crlf_sentence) in lf_sentences.iter().zip(crlf_sentences.iter()) {
+            assert_eq!(lf_sentence.len(), crlf_sentence.len());
+
+            for (lf_token, crlf_token) in lf_sentence.iter().zip(crlf_sentence.iter()) {
+                // one carriage return before every line feed preceding the token
+                let shift = lf[..lf_token.byte_span.0].matches('\n').count();
+
+                assert_eq!(lf_token.word.text, crlf_token.word.text);
+                assert_eq!(lf_token.has_space_before, crlf_token.has_space_before);
+                assert_eq!(
+                    (lf_token.byte_span.0 + shift, lf_token.byte_span.1 + shift),
+                    crlf_token.byte_span
+                );
+                assert_eq!(
+                    (lf_token.char_span.0 + shift, lf_token.char_span.1 + shift),
+                    crlf_token.char_span
+                );
+            }
+        }
+
+        let second = &crlf_sentences[1][1];
+        assert_eq!(second.word.text.as_ref(), "Second");
+        assert_eq!(second.space_before, "\r\n");
+
+        // a bare carriage return is whitespace, but no sentence boundary
+        let sentences = tokenizer.tokenize_sentences("Foo\rbar baz.");
+        assert_eq!(sentences.len(), 1);
+        let texts: Vec<_> = sentences[0]
+            .iter()
+            .map(|x| (x.word.text.as_ref(), x.byte_span, x.has_space_before))
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("", (0, 0), false),
+                ("Foo", (0, 3), false),
+                ("bar", (4, 7), true),
+                ("baz", (8, 11), true),
+                (".", (11, 12), false)
+            ]
+        );
+    }
+
+    #[quickcheck]
+    fn lossless_tokens_reproduce_random_text(text: String) -> bool {
+        let tokenizer = span_tokenizer();
+        let tokens = tokenizer.tokenize_lossless(&text);
+        let concatenated: String = tokens.iter().map(|x| x.word.text.as_ref()).collect();
+        concatenated == text
+    }
+
+    #[test]
+    fn new_fails_on_missing_file() {
+        assert!(matches!(
+            Tokenizer::new("does/not/exist.bin"),
+            Err(crate::Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn roman_numeral_values() {
+        for (numeral, value) in &[
+            ("I", 1),
+            ("iii", 3),
+            ("IV", 4),
+            ("ix", 9),
+            ("XIV", 14),
+            ("XL", 40),
+            ("XC", 90),
+            ("CD", 400),
+            ("MCMXCIV", 1994),
+            ("MMMCMXCIX", 3999),
+        ] {
+            assert_eq!(roman_numeral_value(numeral), Some(*value), "{}", numeral);
+        }
+
+        for text in &[
+            "", "IIII", "IC", "VX", "XM", "MMMM", "Xiv", "mix", "di", "Chapter",
+        ] {
+            assert_eq!(roman_numeral_value(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "compile")]
+    fn raw_tokens_are_not_disambiguated() {
+        let tokenizer = tokenizer_from_xml(
+            "raw_tokens",
+            "Die\tdie\tART:DEF\nDie\tdie\tPRO:DEM\nKatze\tKatze\tSUB\nschläft\tschlafen\tVER\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="de">
+    <rule id="ARTICLE_BEFORE_NOUN" name="die before a noun is an article">
+        <pattern>
+            <marker>
+                <token>Die</token>
+            </marker>
+            <token postag="SUB"/>
+        </pattern>
+        <disambig action="filter" postag="ART:DEF"/>
+    </rule>
+</rules>"#,
+        );
+
+        fn tags(tokens: &[Token]) -> Vec<(String, Vec<String>)> {
+            tokens
+                .iter()
+                .map(|x| {
+                    let mut tags: Vec<_> = x
+                        .word
+                        .tags
+                        .iter()
+                        .map(|x| x.pos.0.to_string())
+                        .filter(|x| !x.is_empty())
+                        .collect();
+                    tags.sort();
+                    (x.word.text.as_ref().to_string(), tags)
+                })
+                .collect()
+        }
+        let token = |text: &str, tags: &[&str]| {
+            (
+                text.to_string(),
+                tags.iter().map(|x| x.to_string()).collect::<Vec<_>>(),
+            )
+        };
+
+        let text = "Die Katze schläft.";
+        let raw = tokenizer.tokenize_raw(text);
+        let disambiguated = finalize(tokenizer.disambiguate(tokenizer.tokenize(text)));
+
+        assert_eq!(
+            tags(&raw),
+            vec![
+                token("", &["SENT_START"]),
+                token("Die", &["ART:DEF", "PRO:DEM"]),
+                token("Katze", &["SUB"]),
+                token("schläft", &["VER"]),
+                token(".", &["SENT_END", "UNKNOWN"]),
+            ]
+        );
+        assert_eq!(tags(&disambiguated)[1], token("Die", &["ART:DEF"]));
+        assert_eq!(tags(&raw)[2..], tags(&disambiguated)[2..]);
+        assert_eq!(
+            raw.iter().map(|x| x.byte_span).collect::<Vec<_>>(),
+            disambiguated
+                .iter()
+                .map(|x| x.byte_span)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "compile")]
+    fn cached_tags_are_disambiguated_separately() {
+        let mut tokenizer = tokenizer_from_xml(
+            "cached_tags",
+            "Die\tdie\tART:DEF\nDie\tdie\tPRO:DEM\nKatze\tKatze\tSUB\nschläft\tschlafen\tVER\nUnd\tund\tKON\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="de">
+    <rule id="ARTICLE_BEFORE_NOUN" name="die before a noun is an article">
+        <pattern>
+            <marker>
+                <token>Die</token>
+            </marker>
+            <token postag="SUB"/>
+        </pattern>
+        <disambig action="filter" postag="ART:DEF"/>
+    </rule>
+</rules>"#,
+        );
+        assert!(!tokenizer.options().cache_tags);
+        tokenizer.options_mut().cache_tags = true;
+
+        let tags = |tokenizer: &Tokenizer| -> Vec<Vec<String>> {
+            let text = "Und Die Katze Die schläft.";
+            finalize(tokenizer.disambiguate(tokenizer.tokenize(text)))
+                .iter()
+                .filter(|x| x.word.text.as_ref() == "Die")
+                .map(|x| {
+                    let mut tags: Vec<_> = x
+                        .word
+                        .tags
+                        .iter()
+                        .map(|x| x.pos.0.to_string())
+                        .filter(|x| !x.is_empty())
+                        .collect();
+                    tags.sort();
+                    tags
+                })
+                .collect()
+        };
+
+        // both occurrences share the cached lookup, but only the first is filtered
+        let cached = tags(&tokenizer);
+        assert_eq!(cached, vec![vec!["ART:DEF"], vec!["ART:DEF", "PRO:DEM"]]);
+
+        tokenizer.options_mut().cache_tags = false;
+        assert_eq!(tags(&tokenizer), cached);
+    }
+
+    #[test]
+    fn token_lemmas() {
+        let tokenizer =
+            tokenizer_from_dump("saw\tsee\tVBD\nsaw\tsaw\tNN\nsaw\tsee\tVBN\nI\tI\tPRP\n");
+
+        let tokens = tokenizer.tokenize_raw("I saw Kim.");
+        let lemmas: Vec<_> = tokens.iter().map(|x| x.lemmas()).collect();
+
+        assert_eq!(
+            lemmas,
+            vec![
+                vec![""],
+                vec!["I"],
+                vec!["see", "saw"],
+                // no readings, so the text is the only lemma
+                vec!["Kim"],
+                vec!["."],
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "compile")]
+    fn custom_words_are_disambiguated() {
+        let tokenizer = tokenizer_from_xml(
+            "custom_words",
+            "Die\tdie\tART:DEF\nDie\tdie\tPRO:DEM\nKatze\tKatze\tSUB\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="de">
+    <rule id="ARTICLE_BEFORE_NOUN" name="die before a noun is an article">
+        <pattern>
+            <marker>
+                <token>Die</token>
+            </marker>
+            <token postag="SUB"/>
+        </pattern>
+        <disambig action="filter" postag="ART:DEF"/>
+    </rule>
+</rules>"#,
+        );
+
+        let tags = |text: &str, index: usize| {
+            let tokens = finalize(tokenizer.disambiguate(tokenizer.tokenize(text)));
+            let mut tags: Vec<_> = tokens[index]
+                .word
+                .tags
+                .iter()
+                .map(|x| x.pos.0.to_string())
+                .filter(|x| !x.is_empty() && x != "SENT_END")
+                .collect();
+            tags.sort();
+            tags
+        };
+
+        assert_eq!(tags("Die Drohne", 2), vec!["UNKNOWN"]);
+        assert_eq!(tags("Die Drohne", 1), vec!["ART:DEF", "PRO:DEM"]);
+
+        tokenizer
+            .tagger()
+            .add_word("Drohne", "Drohne", "SUB")
+            .unwrap();
+        assert_eq!(tags("Die Drohne", 2), vec!["SUB"]);
+        assert_eq!(tags("Die Drohne", 1), vec!["ART:DEF"]);
+
+        tokenizer.tagger().remove_word("Drohne");
+        assert_eq!(tags("Die Drohne", 2), vec!["UNKNOWN"]);
+        assert_eq!(tags("Die Drohne", 1), vec!["ART:DEF", "PRO:DEM"]);
+    }
+
+    #[test]
+    #[cfg(feature = "compile")]
+    fn overridden_words_are_disambiguated() {
+        let tokenizer = tokenizer_from_xml(
+            "overridden_words",
+            "Die\tdie\tART:DEF\nDie\tdie\tPRO:DEM\nDrohne\tdrohen\tVER\nKatze\tKatze\tSUB\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="de">
+    <rule id="ARTICLE_BEFORE_NOUN" name="die before a noun is an article">
+        <pattern>
+            <marker>
+                <token>Die</token>
+            </marker>
+            <token postag="SUB"/>
+        </pattern>
+        <disambig action="filter" postag="ART:DEF"/>
+    </rule>
+</rules>"#,
+        );
+
+        assert_eq!(tags_of(&tokenizer, "Die Drohne", 4), vec!["VER"]);
+        assert_eq!(
+            tags_of(&tokenizer, "Die Drohne", 0),
+            vec!["ART:DEF", "PRO:DEM"]
+        );
+
+        let tagger = tokenizer.tagger();
+        tagger
+            .override_word(
+                "Drohne",
+                vec![WordData::new(
+                    tagger.id_word("Drohne".into()),
+                    tagger.id_tag("SUB"),
+                )],
+            )
+            .unwrap();
+
+        // the rules only see the overridden reading
+        assert_eq!(tags_of(&tokenizer, "Die Drohne", 4), vec!["SUB"]);
+        assert_eq!(tags_of(&tokenizer, "Die Drohne", 0), vec!["ART:DEF"]);
+    }
+
+    #[test]
+    #[cfg(feature = "compile")]
+    fn sent_start_rule_fires_on_every_sentence() {
+        let tokenizer = tokenizer_from_xml(
+            "sent_start",
+            "foo\tfoo\tNN\nfoo\tfoo\tVB\nbar\tbar\tNN\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="SENT_START_FOO" name="sentence initial foo is a verb">
+        <pattern>
+            <token postag="SENT_START"/>
+            <marker>
+                <token>foo</token>
+            </marker>
+        </pattern>
+        <disambig action="filter" postag="VB"/>
+    </rule>
+</rules>"#,
+        );
+
+        let text = "Bar foo. Foo bar. Foo bar.";
+
+        assert_eq!(tags_of(&tokenizer, text, 4), vec!["NN", "VB"]);
+        assert_eq!(tags_of(&tokenizer, text, 9), vec!["VB"]);
+        assert_eq!(tags_of(&tokenizer, text, 18), vec!["VB"]);
+
+        let tokens = finalize(tokenizer.tokenize(text));
+        let sent_starts: Vec<_> = tokens
+            .iter()
+            .filter(|x| x.word.tags.iter().any(|x| x.pos.0 == "SENT_START"))
+            .map(|x| x.byte_span)
+            .collect();
+        assert_eq!(sent_starts, vec![(0, 0), (9, 9), (18, 18)]);
+    }
+
+    #[test]
+    #[cfg(feature = "compile")]
+    fn sentences_are_disambiguated_separately() {
+        let tokenizer = tokenizer_from_xml(
+            "sentences",
+            "foo\tfoo\tNN\nfoo\tfoo\tVB\nbar\tbar\tNN\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="BAR_FOO" name="foo after bar is a verb">
+        <pattern>
+            <token skip="-1">bar</token>
+            <marker>
+                <token>foo</token>
+            </marker>
+        </pattern>
+        <disambig action="filter" postag="VB"/>
+    </rule>
+</rules>"#,
+        );
+
+        let text = "Bar.\n\nFoo bar\n\nFoo";
+        let sentences = tokenizer.tokenize_sentences(text);
+
+        let texts: Vec<Vec<_>> = sentences
+            .iter()
+            .map(|sentence| {
+                sentence
+                    .iter()
+                    .map(|x| x.word.text.as_ref().to_string())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            texts,
+            vec![
+                vec!["", "Bar", "."],
+                vec!["", "Foo", "bar"],
+                vec!["", "Foo"]
+            ]
+        );
+
+        for sentence in &sentences {
+            assert!(sentence[0]
+                .word
+                .tags
+                .iter()
+                .any(|x| x.pos.0 == "SENT_START"));
+            assert_eq!(sentence[0].byte_span.0, sentence[1].byte_span.0);
+        }
+
+        // the rule matches across sentence boundaries because of the skip if the text is disambiguated as a whole
+        assert_eq!(tags_of(&tokenizer, text, 6), vec!["VB"]);
+
+        let foo_tags: Vec<Vec<_>> = sentences[1..]
+            .iter()
+            .map(|sentence| {
+                let mut tags: Vec<_> = sentence[1]
+                    .word
+                    .tags
+                    .iter()
+                    .map(|x| x.pos.0)
+                    .filter(|x| !x.is_empty())
+                    .collect();
+                tags.sort_unstable();
+                tags
+            })
+            .collect();
+        assert_eq!(foo_tags[0], vec!["NN", "VB"]);
+        assert_eq!(foo_tags[1], vec!["NN", "SENT_END", "VB"]);
+    }
+
+    #[test]
+    #[cfg(feature = "compile")]
+    fn sent_end_rule_fires_on_every_sentence() {
+        let tokenizer = tokenizer_from_xml(
+            "sent_end",
+            "foo\tfoo\tNN\nfoo\tfoo\tVB\nbar\tbar\tNN\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="SENT_END_FOO" name="sentence final foo is a verb">
+        <pattern>
+            <marker>
+                <token>foo</token>
+            </marker>
+            <token postag="SENT_END"/>
+        </pattern>
+        <disambig action="filter" postag="VB"/>
+    </rule>
+</rules>"#,
+        );
+
+        let text = "Bar foo.  Foo bar.\nBar foo!";
+
+        assert_eq!(tags_of(&tokenizer, text, 4), vec!["VB"]);
+        assert_eq!(tags_of(&tokenizer, text, 10), vec!["NN", "VB"]);
+        assert_eq!(tags_of(&tokenizer, text, 23), vec!["VB"]);
+
+        let tokens = finalize(tokenizer.tokenize(text));
+        let sent_ends: Vec<_> = tokens
+            .iter()
+            .filter(|x| x.word.tags.iter().any(|x| x.pos.0 == "SENT_END"))
+            .map(|x| x.byte_span)
+            .collect();
+        assert_eq!(sent_ends, vec![(7, 8), (17, 18), (26, 27)]);
+    }
+
+    #[test]
+    fn owned_tokens_outlive_text() {
+        let tokenizer = tokenizer_from_dump("foo\tfoo\tNN\nbar\tbar\tNN\n");
+
+        let text = String::from("Foo bar. Bar foo.");
+        let owned: Vec<_> = finalize(tokenizer.tokenize(&text))
+            .iter()
+            .map(|x| x.to_owned_token())
+            .collect();
+
+        let expected = text.clone();
+        drop(text);
+
+        let owned = std::thread::spawn(move || owned).join().unwrap();
+        let tokens = finalize(tokenizer.tokenize(&expected));
+
+        assert_eq!(owned.len(), tokens.len());
+        for (owned, token) in owned.iter().zip(tokens.iter()) {
+            let borrowed = owned.as_ref_token(&expected, tokenizer.tagger());
+
+            assert_eq!(borrowed.word, token.word);
+            assert_eq!(borrowed.char_span, token.char_span);
+            assert_eq!(borrowed.byte_span, token.byte_span);
+            assert_eq!(borrowed.to_owned_token(), *owned);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "compile")]
+    fn owned_tokens_roundtrip() {
+        let tokenizer = tokenizer_from_dump("Haus\tHaus\tSUB:NOM:SIN:NEU\nHaus\tHaus\tSUB:AKK:SIN:NEU\ndas\tdas\tART:DEF:NOM:SIN:NEU\nist\tsein\tVER:3:SIN:PRÄ\ngroß\tgroß\tADJ:PRD:GRU\n");
+
+        let text = "Das Haus ist groß. Ist das Haus groß?\nJa, sehr groß!";
+        let tokens: Vec<Vec<_>> = tokenizer
+            .tokenize_sentences(text)
+            .iter()
+            .map(|sentence| sentence.iter().map(|x| x.to_owned_token()).collect())
+            .collect();
+        assert_eq!(tokens.len(), 3);
+
+        let json = serde_json::to_string(&tokens).unwrap();
+        let from_json: Vec<Vec<crate::types::owned::Token>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(from_json, tokens);
+
+        let bytes = bincode::serialize(&tokens).unwrap();
+        let from_bytes: Vec<Vec<crate::types::owned::Token>> =
+            bincode::deserialize(&bytes).unwrap();
+        assert_eq!(from_bytes, tokens);
+    }
+
+    #[test]
+    fn hyphen_modes_assign_tags() {
+        let mut tokenizer = tokenizer_from_dump("An\tan\tDT\nmail\tmail\tNN\ne\te\tLS\nstate\tstate\tNN\nof\tof\tIN\nthe\tthe\tDT\nart\tart\tNN\nwell-known\twell-known\tJJ\nknown\tknow\tVBN\n");
+        let text = "An e-mail about state-of-the-art well-known.";
+
+        assert!(tags_of(&tokenizer, text, 3).is_empty());
+        assert!(tags_of(&tokenizer, text, 16).is_empty());
+        assert_eq!(tags_of(&tokenizer, text, 33), vec!["JJ"]);
+
+        tokenizer.options_mut().hyphens = HyphenMode::Both;
+        assert_eq!(tags_of(&tokenizer, text, 3), vec!["LS", "NN"]);
+        assert_eq!(tags_of(&tokenizer, text, 16), vec!["DT", "IN", "NN", "NN"]);
+        assert_eq!(tags_of(&tokenizer, text, 33), vec!["JJ", "VBN"]);
+
+        tokenizer.options_mut().hyphens = HyphenMode::Split;
+        assert_eq!(tags_of(&tokenizer, text, 3), vec!["LS"]);
+        assert_eq!(tags_of(&tokenizer, text, 5), vec!["NN"]);
+        assert_eq!(tags_of(&tokenizer, text, 22), vec!["IN"]);
+    }
+
+    #[test]
+    fn numbers_get_number_tag() {
+        let mut tokenizer =
+            tokenizer_from_dump("Am\tam\tPRP\nOktober\tOktober\tSUB\nzwei\tzwei\tZAL\n");
+        let options = tokenizer.options_mut();
+        options.join_dates = true;
+        options.join_ordinals = true;
+        options.join_numbers = true;
+
+        let text = "Am 3. Oktober 2021 oder am 3.10.2021";
+        assert!(tags_of(&tokenizer, text, 3).is_empty());
+
+        tokenizer.options_mut().number_tag = Some("ZAL".into());
+        assert_eq!(tags_of(&tokenizer, text, 3), vec!["ZAL"]);
+        assert_eq!(tags_of(&tokenizer, text, 14), vec!["ZAL"]);
+        assert_eq!(tags_of(&tokenizer, text, 27), vec!["ZAL"]);
+        assert_eq!(tags_of(&tokenizer, text, 6), vec!["SUB"]);
+    }
+
+    #[test]
+    fn numbers_get_normalized_lemma() {
+        let mut tokenizer =
+            tokenizer_from_dump("Wir\twir\tPRO\nhaben\thaben\tVER\nzwei\tzwei\tZAL\n");
+        let options = tokenizer.options_mut();
+        options.join_numbers = true;
+        options.number_tag = Some("ZAL".into());
+        options.lang_code = "de".into();
+
+        fn data(tokenizer: &Tokenizer, text: &str) -> Vec<(String, Vec<(String, String)>)> {
+            tokenizer
+                .tokenize(text)
+                .iter()
+                .map(|x| {
+                    let tags = x
+                        .word
+                        .tags
+                        .iter()
+                        .map(|x| (x.lemma.as_ref().to_string(), x.pos.0.to_string()))
+                        .collect();
+                    (x.word.text.as_ref().to_string(), tags)
+                })
+                .collect()
+        }
+
+        let number = |text: &str, lemma: &str| {
+            (
+                text.to_string(),
+                vec![(lemma.to_string(), "ZAL".to_string())],
+            )
+        };
+
+        assert_eq!(
+            data(&tokenizer, "Wir haben 42, 3,14 und -1.000 oder 4x4"),
+            vec![
+                (
+                    "Wir".to_string(),
+                    vec![("wir".to_string(), "PRO".to_string())]
+                ),
+                (
+                    "haben".to_string(),
+                    vec![("haben".to_string(), "VER".to_string())]
+                ),
+                number("42", "42"),
+                (",".to_string(), vec![]),
+                number("3,14", "3.14"),
+                ("und".to_string(), vec![]),
+                number("-1.000", "-1000"),
+                ("oder".to_string(), vec![]),
+                ("4x4".to_string(), vec![]),
+            ]
+        );
+
+        tokenizer.options_mut().lang_code = "en".into();
+        assert_eq!(
+            data(&tokenizer, "+1,000,000 3.14 1.000.000"),
+            vec![
+                number("+1,000,000", "1000000"),
+                number("3.14", "3.14"),
+                number("1.000.000", "1000000"),
+            ]
+        );
+    }
+
+    #[test]
+    fn roman_numerals() {
+        let mut tokenizer = tokenizer_from_dump(
+            "I\tI\tPRP\nChapter\tchapter\tNN\nM\tM\tNNP\nD\tD\tNNP\nread\tread\tVB\none\tone\tCD\n",
+        );
+        tokenizer.options_mut().number_tag = Some("CD".into());
+
+        fn lemmas(tokenizer: &Tokenizer, text: &str, char_start: usize) -> Vec<(String, String)> {
+            let tokens = tokenizer.tokenize(text);
+            let token = tokens.iter().find(|x| x.char_span.0 == char_start).unwrap();
+
+            token
+                .word
+                .tags
+                .iter()
+                .map(|x| (x.lemma.as_ref().to_string(), x.pos.0.to_string()))
+                .collect()
+        }
+        let reading = |lemma: &str, tag: &str| (lemma.to_string(), tag.to_string());
+
+        let text =
+            "I read Chapter I and XIV, iii. and MCMXCIV of Chapter M but not Chapter IC or D.";
+        assert!(lemmas(&tokenizer, text, 21).is_empty());
+
+        tokenizer.options_mut().roman_numerals = true;
+        // the pronoun is not a numeral, but keeps its reading after "Chapter"
+        assert_eq!(lemmas(&tokenizer, text, 0), vec![reading("I", "PRP")]);
+        assert_eq!(
+            lemmas(&tokenizer, text, 15),
+            vec![reading("I", "PRP"), reading("1", "CD")]
+        );
+        assert_eq!(lemmas(&tokenizer, text, 21), vec![reading("14", "CD")]);
+        assert_eq!(lemmas(&tokenizer, text, 26), vec![reading("3", "CD")]);
+        assert_eq!(lemmas(&tokenizer, text, 35), vec![reading("1994", "CD")]);
+        assert_eq!(
+            lemmas(&tokenizer, text, 54),
+            vec![reading("M", "NNP"), reading("1000", "CD")]
+        );
+        assert!(lemmas(&tokenizer, text, 72).is_empty());
+        assert_eq!(lemmas(&tokenizer, text, 78), vec![reading("D", "NNP")]);
+
+        tokenizer.options_mut().roman_numeral_contexts = vec!["OR".into()];
+        assert_eq!(
+            lemmas(&tokenizer, text, 78),
+            vec![reading("D", "NNP"), reading("500", "CD")]
+        );
+    }
+
+    #[test]
+    fn rule_chunker_matches_fixtures() {
+        let fixtures = std::fs::read_to_string(format!(
+            "{}/tests/fixtures/chunks_en.txt",
+            env!("CARGO_MANIFEST_DIR")
+        ))
+        .unwrap();
+        let lines: Vec<Vec<&str>> = fixtures
+            .lines()
+            .filter(|x| !x.starts_with('#'))
+            .map(|x| x.split('\t').collect())
+            .collect();
+
+        for sentence in lines.split(|x| x == &[""]) {
+            let dump: String = sentence
+                .iter()
+                .flat_map(|x| {
+                    x[1].split('|')
+                        .map(move |tag| format!("{}\t{}\t{}\n", x[0], x[0].to_lowercase(), tag))
+                })
+                .collect();
+            let text = sentence.iter().map(|x| x[0]).collect::<Vec<_>>().join(" ");
+
+            let mut tokenizer = tokenizer_from_dump(&dump);
+            let chunks = |tokenizer: &Tokenizer| -> Vec<String> {
+                tokenizer
+                    .disambiguate(tokenizer.tokenize(&text))
+                    .iter()
+                    .map(|x| x.chunks.join("|"))
+                    .collect()
+            };
+
+            assert!(chunks(&tokenizer).iter().all(|x| x.is_empty()), "{}", text);
+
+            tokenizer.options_mut().rule_chunker = true;
+            assert_eq!(
+                chunks(&tokenizer),
+                sentence.iter().map(|x| x[2]).collect::<Vec<_>>(),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn abbreviations_prevent_sentence_boundaries() {
+        let mut tokenizer = finalizing_tokenizer();
+        tokenizer.abbreviations = ["z. B.", "z.B.", "Dr."]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+
+        fn sentence_texts(tokenizer: &Tokenizer, text: &str) -> Vec<String> {
+            tokenizer
+                .tokenize_sentences(text)
+                .iter()
+                .map(|sentence| {
+                    let (start, end) = (
+                        sentence[0].byte_span.0,
+                        sentence.last().unwrap().byte_span.1,
+                    );
+                    text[start..end].to_string()
+                })
+                .collect()
+        }
+
+        assert_eq!(
+            sentence_texts(&tokenizer, "Das ist z.B. gut. Wirklich."),
+            vec!["Das ist z.B. gut.", "Wirklich."]
+        );
+        assert_eq!(
+            sentence_texts(&tokenizer, "Das ist z. B. gut. Wirklich."),
+            vec!["Das ist z. B. gut.", "Wirklich."]
+        );
+        assert_eq!(
+            sentence_texts(&tokenizer, "Wir treffen Dr. Müller. Er ist nett."),
+            vec!["Wir treffen Dr. Müller.", "Er ist nett."]
+        );
+        assert_eq!(
+            sentence_texts(&tokenizer, "Äpfel, Birnen etc. Alles ist gut."),
+            vec!["Äpfel, Birnen etc.", "Alles ist gut."]
+        );
+
+        tokenizer
+            .options_mut()
+            .extra_abbreviations
+            .push("etc.".into());
+        assert_eq!(
+            sentence_texts(&tokenizer, "Äpfel, Birnen etc. Alles ist gut."),
+            vec!["Äpfel, Birnen etc. Alles ist gut."]
+        );
+    }
+
+    #[test]
+    fn srx_segmentation() {
+        let mut tokenizer = finalizing_tokenizer();
+        tokenizer.segmenter = Some(SrxSegmenter::new(vec![
+            SrxRule::new(false, r"\b(?:z\.\s?B|Dr)\.", r"\s").unwrap(),
+            SrxRule::new(false, r"\bz\.", r"\s?B\.").unwrap(),
+            SrxRule::new(true, r"[.?!…]+[»“”\x22]?", r"\s+\P{Ll}").unwrap(),
+        ]));
+
+        let text = "Er sagte: „Komm her!“ Dann ging er. Das ist z. B. bei Dr. Meier so. Ende.";
+        let sentences: Vec<_> = tokenizer
+            .tokenize_sentences(text)
+            .iter()
+            .map(|sentence| {
+                let (start, end) = (
+                    sentence[0].byte_span.0,
+                    sentence.last().unwrap().byte_span.1,
+                );
+                &text[start..end]
+            })
+            .collect();
+
+        assert_eq!(
+            sentences,
+            vec![
+                "Er sagte: „Komm her!“",
+                "Dann ging er.",
+                "Das ist z. B. bei Dr. Meier so.",
+                "Ende."
+            ]
+        );
+    }
+
+    #[test]
+    fn detokenize_roundtrip() {
+        use super::{detokenize, detokenize_with};
+
+        let tokenizer = tokenizer_from_dump("the\tthe\tDT\ncat\tcat\tNN\n");
+
+        for text in &[
+            "See https://example.com/a_(b)?c=d, then \"quote\" it.",
+            "  Leading\tand  mixed\u{a0}whitespace.\n\nNext paragraph: «guillemets» and ‚quotes‘ \n",
+            "Mail foo@example.com (or don't)!",
+            "",
+        ] {
+            let tokens = finalize(tokenizer.tokenize(text));
+            assert_eq!(&detokenize(&tokens), text);
+
+            let sentences = tokenizer.tokenize_sentences(text);
+            let joined: String = sentences.iter().map(|x| detokenize(x)).collect();
+            assert_eq!(&joined, text);
+        }
+
+        let text = "The the cat sat, said Tom.";
+        let tokens = finalize(tokenizer.tokenize(text));
+        let texts: Vec<_> = tokens.iter().map(|x| x.word.text.as_ref()).collect();
+        assert_eq!(
+            texts,
+            vec!["", "The", "the", "cat", "sat", ",", "said", "Tom", "."]
+        );
+
+        let mut replacements = vec![None; tokens.len()];
+        replacements[2] = Some("");
+        replacements[4] = Some("sat down");
+        assert_eq!(
+            detokenize_with(&tokens, &replacements),
+            "The cat sat down, said Tom."
+        );
+
+        let mut replacements = vec![None; tokens.len()];
+        replacements[1] = Some("");
+        replacements[2] = Some("The");
+        replacements[5] = Some("");
+        assert_eq!(
+            detokenize_with(&tokens, &replacements),
+            "The cat sat said Tom."
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "compile")]
+    fn streaming_tokenization() {
+        use crate::types::owned;
+
+        let tokenizer = tokenizer_from_xml(
+            "streaming",
+            "the\tthe\tDT\ncat\tcat\tNN\nsat\tsit\tVBD\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="THE_CAT" name="cat after the is a noun">
+        <pattern>
+            <token>the</token>
+            <marker>
+                <token>cat</token>
+            </marker>
+        </pattern>
+        <disambig action="filter" postag="NN"/>
+    </rule>
+</rules>"#,
+        );
+
+        let paragraph = "The cat sat on the mat. Did it? Yes, it did!\nVisit https://example.com/cat.html\n\n  \
+            It costs 10€ (or $11.50) – cheap… “Quoted,” she said. Ünïcödé 👩\u{200d}👦 text.\t";
+        let mut text = String::new();
+        // 1 MB instead of a larger input to keep the test fast in debug builds, the runtime is linear in the length
+        while text.len() < 1_000_000 {
+            text.push_str(paragraph);
+        }
+
+        let to_owned = |sentence: &Vec<crate::types::Token>| -> Vec<owned::Token> {
+            sentence.iter().map(|x| x.to_owned_token()).collect()
+        };
+        let expected: Vec<_> = tokenizer
+            .tokenize_sentences(&text)
+            .iter()
+            .map(to_owned)
+            .collect();
+
+        let mut iter_count = 0;
+        for (sentence, expected) in tokenizer.tokenize_iter(&text).zip(expected.iter()) {
+            assert_eq!(&to_owned(&sentence), expected);
+            iter_count += 1;
+        }
+        assert_eq!(iter_count, expected.len());
+
+        let mut reader_count = 0;
+        for (sentence, expected) in tokenizer
+            .tokenize_reader(text.as_bytes())
+            .zip(expected.iter())
+        {
+            assert_eq!(&sentence.unwrap(), expected);
+            reader_count += 1;
+        }
+        assert_eq!(reader_count, expected.len());
+    }
+
+    #[test]
+    #[cfg(all(feature = "compile", feature = "rayon"))]
+    fn parallel_tokenization() {
+        let tokenizer = tokenizer_from_xml(
+            "parallel",
+            "the\tthe\tDT\ncat\tcat\tNN\nsat\tsit\tVBD\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="THE_CAT" name="cat after the is a noun">
+        <pattern>
+            <token>the</token>
+            <marker>
+                <token>cat</token>
+            </marker>
+        </pattern>
+        <disambig action="filter" postag="NN"/>
+    </rule>
+</rules>"#,
+        );
+
+        let text = "The cat sat on the mat. Did it? Yes, it did!\n\n  Ünïcödé 👩\u{200d}👦 text… “Quoted,” she said.\t"
+            .repeat(500);
+
+        let to_owned = |sentences: Vec<Vec<crate::types::Token>>| -> Vec<Vec<_>> {
+            sentences
+                .iter()
+                .map(|sentence| sentence.iter().map(|x| x.to_owned_token()).collect())
+                .collect()
+        };
+
+        assert_eq!(
+            to_owned(tokenizer.tokenize_parallel(&text)),
+            to_owned(tokenizer.tokenize_sentences(&text))
+        );
+    }
+
+    #[test]
+    fn tokenizers_for_multiple_languages() {
+        let dir = std::env::temp_dir().join(format!("nlprule_for_language_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let languages = [
+            ("en", "the\tthe\tDT\ncat\tcat\tNN\n", "The cat", "NN"),
+            (
+                "de",
+                "die\tder\tART:DEF:NOM:SIN:FEM\nKatze\tKatze\tSUB:NOM:SIN:FEM\n",
+                "Die Katze",
+                "SUB:NOM:SIN:FEM",
+            ),
+        ];
+
+        for (lang_code, dump, _, _) in languages.iter() {
+            let tokenizer = tokenizer_from_dump(dump);
+            let file = File::create(dir.join(format!("{}_tokenizer.bin", lang_code))).unwrap();
+            bincode::serialize_into(file, &tokenizer).unwrap();
+        }
+
+        std::env::set_var(super::STORAGE_ENV_VARIABLE, &dir);
+
+        let handles: Vec<_> = languages
+            .iter()
+            .map(|&(lang_code, _, text, pos)| {
+                let tokenizer = Tokenizer::for_language(lang_code).unwrap();
+                assert!(Arc::ptr_eq(
+                    &tokenizer,
+                    &Tokenizer::for_language(lang_code).unwrap()
+                ));
+
+                std::thread::spawn(move || {
+                    let noun_start = text.find(' ').unwrap() + 1;
+                    assert_eq!(tags_of(&tokenizer, text, noun_start), vec![pos.to_string()]);
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert!(Tokenizer::for_language("xx").is_err());
+
+        // tokenizers are cached by file, so changing the storage directory loads the tokenizer again
+        let en = Tokenizer::for_language("en").unwrap();
+        let other_dir = dir.join("other");
+        std::fs::create_dir_all(&other_dir).unwrap();
+        let file = File::create(other_dir.join("en_tokenizer.bin")).unwrap();
+        bincode::serialize_into(file, &tokenizer_from_dump("cat\tcat\tNNP\n")).unwrap();
+
+        std::env::set_var(super::STORAGE_ENV_VARIABLE, &other_dir);
+        let other_en = Tokenizer::for_language("en").unwrap();
+        assert!(!Arc::ptr_eq(&en, &other_en));
+        assert_eq!(tags_of(&other_en, "The cat", 4), vec!["NNP".to_string()]);
+    }
+
+    #[test]
+    #[cfg(feature = "compile")]
+    fn tag_pretokenized_words() {
+        let tokenizer = tokenizer_from_xml(
+            "pretokenized",
+            "foo\tfoo\tNN\nfoo\tfoo\tVB\nbar\tbar\tNN\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="SENT_START_FOO" name="sentence initial foo is a verb">
+        <pattern>
+            <token postag="SENT_START"/>
+            <marker>
+                <token>foo</token>
+            </marker>
+        </pattern>
+        <disambig action="filter" postag="VB"/>
+    </rule>
+</rules>"#,
+        );
+
+        let tokens = tokenizer.tag_tokens(&["Bar", "foo", "Foo", "bar"], &[2]);
+        let summary: Vec<_> = tokens
+            .iter()
+            .map(|x| {
+                let mut tags: Vec<_> = x
+                    .word
+                    .tags
+                    .iter()
+                    .map(|x| x.pos.0.as_str())
+                    .filter(|x| !x.is_empty())
+                    .collect();
+                tags.sort_unstable();
+                (x.word.text.0.as_str(), x.byte_span, tags.join(","))
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("", (0, 0), "SENT_START".to_string()),
+                ("Bar", (0, 3), "NN".to_string()),
+                ("foo", (4, 7), "NN,SENT_END,VB".to_string()),
+                ("", (8, 8), "SENT_START".to_string()),
+                ("Foo", (8, 11), "VB".to_string()),
+                ("bar", (12, 15), "NN,SENT_END".to_string()),
+            ]
+        );
+
+        let text = "Foo  bar";
+        let tokens = tokenizer.tag_spans(text, &[(0, 3), (5, 8)], &[]);
+        let spans: Vec<_> = tokens
+            .iter()
+            .map(|x| (x.word.text.as_ref(), x.char_span, x.space_before))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![("", (0, 0), ""), ("Foo", (0, 3), ""), ("bar", (5, 8), "  ")]
+        );
+        assert!(tokens[1].word.tags.iter().any(|x| x.pos.0 == "VB"));
+        assert!(!tokens[1].word.tags.iter().any(|x| x.pos.0 == "NN"));
+    }
+
+    #[test]
+    #[cfg(feature = "compile")]
+    fn spacebefore_kind_matches_unicode_spaces() {
+        let tokenizer = tokenizer_from_xml(
+            "spacebefore_kind",
+            ":\t:\tPONCT\n:\t:\tPONCT_ESP\n!\t!\tPONCT\n!\t!\tPONCT_ESP\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="fr">
+    <rule id="COLON_NBSP" name="colon after a non-breaking space">
+        <pattern>
+            <token spacebefore="nobreak">:</token>
+        </pattern>
+        <disambig action="filter" postag="PONCT_ESP"/>
+    </rule>
+    <rule id="EXCLAMATION_NNBSP" name="exclamation mark after a narrow non-breaking space">
+        <pattern>
+            <token spacebefore="narrownobreak">!</token>
+        </pattern>
+        <disambig action="filter" postag="PONCT_ESP"/>
+    </rule>
+</rules>"#,
+        );
+
+        let text = "Attention\u{202F}! Il dit\u{A0}: oui. Il dit : non !";
+        assert_eq!(tags_of(&tokenizer, text, 10), vec!["PONCT_ESP"]);
+        assert_eq!(tags_of(&tokenizer, text, 19), vec!["PONCT_ESP"]);
+        assert_eq!(tags_of(&tokenizer, text, 33), vec!["PONCT", "PONCT_ESP"]);
+        assert_eq!(tags_of(&tokenizer, text, 39), vec!["PONCT", "PONCT_ESP"]);
+    }
+
+    #[test]
+    fn language_specific_casing() {
+        let tagger = |lang_code: &str| {
+            Tagger::from_readers(
+                vec!["istanbul\tistanbul\tNP\nısparta\tısparta\tNP\nοδος\tοδος\tNOUN\n".as_bytes()],
+                Vec::<&[u8]>::new(),
+                &[] as &[&str],
+                &HashSet::new(),
+            )
+            .unwrap()
+            .with_casing(Casing::for_language(lang_code))
+        };
+        let lemmas = |tagger: &Tagger, word: &str| -> Vec<String> {
+            tagger
+                .get_tags(word, true, false)
+                .iter()
+                .map(|x| x.lemma.as_ref().to_string())
+                .collect()
+        };
+
+        let default = tagger("en");
+        assert!(lemmas(&default, "İstanbul").is_empty());
+        assert!(lemmas(&default, "ISPARTA").is_empty());
+
+        let turkish = tagger("tr");
+        assert_eq!(lemmas(&turkish, "İstanbul"), vec!["istanbul"]);
+        assert_eq!(lemmas(&turkish, "ISPARTA"), vec!["ısparta"]);
+
+        let greek = tagger("el");
+        assert_eq!(lemmas(&greek, "ΟΔΟΣ"), vec!["οδος"]);
+        assert_eq!(Casing::Greek.lowercase("ΣΟΦΟΣ"), "σοφος");
+        assert_eq!(Casing::Greek.lowercase("οδοσ, σοφοσ"), "οδος, σοφος");
+
+        let tokenizer = Tokenizer {
+            tagger: Arc::new(turkish),
+            ..Tokenizer::default()
+        };
+        let text = "İstanbul ve ISPARTA";
+        let lower: Vec<_> = tokenizer
+            .tokenize_sentences(text)
+            .into_iter()
+            .flatten()
+            .filter(|x| !x.is_sentence_start())
+            .map(|x| x.lower())
+            .collect();
+        assert_eq!(lower, vec!["istanbul", "ve", "ısparta"]);
+    }
+
+    #[test]
+    #[cfg(feature = "compile")]
+    fn quote_normalization() {
+        let mut tokenizer = tokenizer_from_xml(
+            "quotes",
+            "do\tdo\tVB\nn't\tnot\tRB\n\"\t\"\tQ_OPEN\n\"\t\"\tQ_CLOSE\nHallo\tHallo\tITJ\n",
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<rules lang="en">
+    <rule id="OPENING_QUOTE" name="quote before a word opens">
+        <pattern>
+            <marker>
+                <token>"</token>
+            </marker>
+            <token>Hallo</token>
+        </pattern>
+        <disambig action="filter" postag="Q_OPEN"/>
+    </rule>
+</rules>"#,
+        );
+        tokenizer.options_mut().apostrophes = Some(ApostropheMode::English);
+
+        let tags = |tokenizer: &Tokenizer, text: &str| -> Vec<(String, Vec<String>)> {
+            tokenizer
+                .tokenize(text)
+                .iter()
+                .map(|x| {
+                    (
+                        x.word.text.as_ref().to_string(),
+                        x.word.tags.iter().map(|x| x.pos.0.to_string()).collect(),
+                    )
+                })
+                .collect()
+        };
+
+        let text = "Er sagte „Hallo“.";
+        assert!(tags_of(&tokenizer, text, 9).is_empty());
+        assert_ne!(tags(&tokenizer, "don‘t"), tags(&tokenizer, "don't"));
+
+        tokenizer.options_mut().normalize_quotes = true;
+        assert_eq!(tags_of(&tokenizer, text, 9), vec!["Q_OPEN"]);
+        assert_eq!(tags_of(&tokenizer, text, 15), vec!["Q_CLOSE", "Q_OPEN"]);
+
+        let expected = vec![
+            ("do".to_string(), vec!["VB".to_string()]),
+            ("n't".to_string(), vec!["RB".to_string()]),
+        ];
+        assert_eq!(tags(&tokenizer, "don't"), expected);
+        assert_eq!(tags(&tokenizer, "don’t"), expected);
+        assert_eq!(tags(&tokenizer, "don‘t"), expected);
+
+        let sentences = tokenizer.tokenize_sentences(text);
+        let quote = sentences[0].iter().find(|x| x.char_span.0 == 9).unwrap();
+        assert_eq!(quote.word.text.as_ref(), "\"");
+        assert_eq!(quote.original_text(), "„");
+    }
+
+    #[test]
+    fn nfc_normalization() {
+        let mut tokenizer = tokenizer_from_dump(
+            "Mädchen\tMädchen\tSUB\nSchlüssel\tSchlüssel\tSUB\ncafé\tcafé\tN\nà\tà\tP\n",
+        );
+
+        let text = "Das Ma\u{308}dchen und der Schlu\u{308}ssel, cafe\u{301} a\u{300} Paris.";
+        assert!(tags_of(&tokenizer, text, 4).is_empty());
+
+        tokenizer.options_mut().normalize_nfc = true;
+        assert_eq!(tags_of(&tokenizer, text, 4), vec!["SUB"]);
+        assert_eq!(tags_of(&tokenizer, text, 21), vec!["SUB"]);
+        assert_eq!(tags_of(&tokenizer, text, 33), vec!["N"]);
+        assert_eq!(tags_of(&tokenizer, text, 39), vec!["P"]);
+
+        let tokens = tokenizer.tokenize(text);
+        let spans: Vec<_> = tokens
+            .iter()
+            .map(|x| {
+                (
+                    x.word.text.as_ref(),
+                    &text[x.byte_span.0..x.byte_span.1],
+                    x.char_span,
+                )
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                ("Das", "Das", (0, 3)),
+                ("Mädchen", "Ma\u{308}dchen", (4, 12)),
+                ("und", "und", (13, 16)),
+                ("der", "der", (17, 20)),
+                ("Schlüssel", "Schlu\u{308}ssel", (21, 31)),
+                (",", ",", (31, 32)),
+                ("café", "cafe\u{301}", (33, 38)),
+                ("à", "a\u{300}", (39, 41)),
+                ("Paris", "Paris", (42, 47)),
+                (".", ".", (47, 48)),
+            ]
+        );
+
+        // text which is already normalized is not copied
+        assert!(tokens
+            .iter()
+            .filter(|x| x.word.text.as_ref().is_ascii())
+            .all(|x| matches!(x.word.text.0, std::borrow::Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn invisible_chars() {
+        use super::InvisibleCharMode;
+
+        let mut tokenizer = tokenizer_from_dump("Möglichkeit\tMöglichkeit\tSUB\nMög\tMög\tVER\nlichkeit\tlichkeit\tSUB\neine\tein\tART\ngute\tgut\tADJ\n");
+
+        let text = "Eine Mög\u{ad}lichkeit gute\u{200b}Möglichkeit";
+        let tokens = |tokenizer: &Tokenizer| -> Vec<(String, (usize, usize))> {
+            tokenizer
+                .tokenize(text)
+                .iter()
+                .map(|x| (x.word.text.as_ref().to_string(), x.byte_span))
+                .collect()
+        };
+
+        assert_eq!(
+            tokens(&tokenizer),
+            vec![
+                ("Eine".to_string(), (0, 4)),
+                ("Mög\u{ad}lichkeit".to_string(), (5, 19)),
+                ("gute\u{200b}Möglichkeit".to_string(), (20, 39)),
+            ]
+        );
+        assert!(tags_of(&tokenizer, text, 5).is_empty());
+
+        tokenizer.options_mut().invisible_chars = InvisibleCharMode::Strip;
+        assert_eq!(
+            tokens(&tokenizer),
+            vec![
+                ("Eine".to_string(), (0, 4)),
+                ("Möglichkeit".to_string(), (5, 19)),
+                ("gute".to_string(), (20, 24)),
+                ("Möglichkeit".to_string(), (27, 39)),
+            ]
+        );
+        assert_eq!(tags_of(&tokenizer, text, 5), vec!["SUB"]);
+        assert_eq!(tags_of(&tokenizer, text, 18), vec!["ADJ"]);
+        assert_eq!(tags_of(&tokenizer, text, 23), vec!["SUB"]);
+
+        tokenizer.options_mut().invisible_chars = InvisibleCharMode::Split;
+        assert_eq!(
+            tokens(&tokenizer),
+            vec![
+                ("Eine".to_string(), (0, 4)),
+                ("Mög".to_string(), (5, 9)),
+                ("lichkeit".to_string(), (11, 19)),
+                ("gute".to_string(), (20, 24)),
+                ("Möglichkeit".to_string(), (27, 39)),
+            ]
+        );
+        assert_eq!(tags_of(&tokenizer, text, 5), vec!["VER"]);
+    }
+
+    #[test]
+    fn multiwords() {
+        let multiwords: Vec<_> = [
+            ("New York", "NNP"),
+            ("New York City", "NNP"),
+            ("ad hoc", "JJ"),
+            ("hoc est", "XX"),
+        ]
+        .iter()
+        .map(|(text, tag)| (text.to_string(), tag.to_string()))
+        .collect();
+        let tagger = Tagger::from_readers(
+            vec![&b"New\tnew\tJJ\nYork\tYork\tNNP\n"[..]],
+            Vec::<&[u8]>::new(),
+            &MultiwordTagger::required_tags(&multiwords),
+            &HashSet::new(),
+        )
+        .unwrap();
+        let mut tokenizer = Tokenizer {
+            tagger: Arc::new(tagger),
+            ..Tokenizer::default()
+        };
+        tokenizer.multiword_tagger =
+            Some(MultiwordTagger::new(&multiwords, &tokenizer.tagger).unwrap());
+
+        // two tokens
+        let text = "I like New York.";
+        assert_eq!(tags_of(&tokenizer, text, 7), vec!["<NNP>", "JJ"]);
+        assert_eq!(tags_of(&tokenizer, text, 11), vec!["</NNP>", "NNP"]);
+
+        // the longest expression wins
+        let text = "I like New York City.";
+        assert_eq!(tags_of(&tokenizer, text, 7), vec!["<NNP>", "JJ"]);
+        assert_eq!(tags_of(&tokenizer, text, 11), vec!["NNP"]);
+        assert_eq!(tags_of(&tokenizer, text, 16), vec!["</NNP>"]);
+
+        // expressions do not overlap
+        let text = "It is ad hoc est.";
+        assert_eq!(tags_of(&tokenizer, text, 6), vec!["<JJ>"]);
+        assert_eq!(tags_of(&tokenizer, text, 9), vec!["</JJ>"]);
+        assert!(tags_of(&tokenizer, text, 13).is_empty());
+
+        let tokens = tokenizer.tokenize_raw("New York City");
+        assert!(tokens[1]
+            .word
+            .tags
+            .iter()
+            .any(|x| x.pos.0 == "<NNP>" && x.lemma.0 == "New York City"));
     }
 }
@@ -8,7 +8,7 @@ use crate::types::{DefaultHashMap, DefaultHasher};
 
 use super::IncompleteToken;
 
-fn softmax(vec: &mut Vec<f32>) {
+fn softmax(vec: &mut [f32]) {
     for x in vec.iter_mut() {
         *x = x.exp();
     }
@@ -42,13 +42,13 @@ impl<'a> PartialEq for Sequence<'a> {
 
 impl<'a> Ord for Sequence<'a> {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(&other).unwrap()
+        other.log_prob.partial_cmp(&self.log_prob).unwrap()
     }
 }
 
 impl<'a> PartialOrd for Sequence<'a> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        other.log_prob.partial_cmp(&self.log_prob)
+        Some(self.cmp(other))
     }
 }
 
@@ -131,7 +131,7 @@ impl Model {
         let mut prior =
             vec![(1. / (self.outcome_labels.len() as f32)).ln(); self.outcome_labels.len()];
 
-        for context in context.iter().filter_map(|x| self.pmap.get(&x)) {
+        for context in context.iter().filter_map(|x| self.pmap.get(x)) {
             for (idx, param) in context.outcomes.iter().zip(context.parameters.iter()) {
                 prior[*idx] += param;
             }
@@ -168,7 +168,7 @@ impl Model {
         hash_fn: H,
         valid_fn: V,
         size: usize,
-    ) -> Vec<Sequence> {
+    ) -> Vec<Sequence<'_>> {
         let mut prev: BinaryHeap<Sequence> = BinaryHeap::new();
         let mut next: BinaryHeap<Sequence> = BinaryHeap::new();
         prev.push(Sequence::default());
@@ -182,15 +182,14 @@ impl Model {
 
             for seq in prev.iter() {
                 let hash = hash_fn(seq.outcomes(), i);
-                if cache.get(&hash).is_none() {
+                let scores = cache.entry(hash).or_insert_with(|| {
                     let context = context_fn(tokens, seq.outcomes(), i);
-                    cache.insert(hash, self.eval(&context));
-                }
-                let scores = cache.get(&hash).unwrap();
-                let top_n = self.get_top_n(&scores, size, 1e-1);
+                    self.eval(&context)
+                });
+                let top_n = self.get_top_n(scores, size, 1e-1);
 
                 for (_, p, pred) in top_n {
-                    if valid_fn(tokens, &seq.outcomes(), i, pred) {
+                    if valid_fn(tokens, seq.outcomes(), i, pred) {
                         let next_outcomes: Vec<_> = [seq.outcomes(), &[pred]].concat();
                         let next_probs: Vec<_> = [seq.probs(), &[p]].concat();
 
@@ -202,7 +201,7 @@ impl Model {
                 if next.is_empty() {
                     for (j, p) in scores.iter().enumerate() {
                         let pred = self.outcome_labels[j].as_str();
-                        if valid_fn(tokens, &seq.outcomes(), i, pred) {
+                        if valid_fn(tokens, seq.outcomes(), i, pred) {
                             let mut next_outcomes: Vec<_> = seq.outcomes().to_vec();
                             next_outcomes.push(pred);
 
@@ -238,14 +237,14 @@ impl MaxentTokenizer {
             };
         }
 
-        context.push(hash::hash_slice(&[&key, "=", &String::from(c)]));
+        context.push(hash::hash_slice(&[key, "=", &String::from(c)]));
 
         if c.is_alphabetic() {
             add!("_alpha");
             if c.is_uppercase() {
                 add!("_caps");
             }
-        } else if c.is_digit(10) {
+        } else if c.is_ascii_digit() {
             add!("_num");
         } else if c.is_whitespace() {
             add!("_ws");
@@ -427,7 +426,7 @@ impl MaxentPosTagger {
         context.push(hash::hash_str("default"));
         context.push(hash::hash_slice(&["w=", lex]));
 
-        context.extend(Self::get_suffixes_prefixes(&lex));
+        context.extend(Self::get_suffixes_prefixes(lex));
 
         if lex.contains('-') {
             context.push(hash::hash_str("h"));
@@ -468,7 +467,7 @@ impl MaxentPosTagger {
         }
     }
 
-    fn tag(&self, tokens: &[&str]) -> Sequence {
+    fn tag(&self, tokens: &[&str]) -> Sequence<'_> {
         self.model
             .beam_search(
                 tokens,
@@ -490,7 +489,7 @@ impl MaxentChunker {
     #[allow(clippy::manual_strip)]
     fn valid(&self, _input: &[(&str, &str)], outcomes: &[&str], _i: usize, outcome: &str) -> bool {
         if outcome.starts_with("I-") {
-            if let Some(prev_outcome) = outcomes.iter().rev().next() {
+            if let Some(prev_outcome) = outcomes.iter().next_back() {
                 // can byte index here because "I, B, E, -" are all 1 byte
                 *prev_outcome == "O" || prev_outcome[2..] == outcome[2..]
             } else {
@@ -557,7 +556,7 @@ impl MaxentChunker {
             )
         };
 
-        return vec![
+        vec![
             // add word features
             hash::hash_str(&w_2),
             hash::hash_str(&w_1),
@@ -604,10 +603,10 @@ impl MaxentChunker {
             hash::hash_slice(&[&p_1, &w2]),
             hash::hash_slice(&[&p_1, &w_1, &w0]),
             hash::hash_slice(&[&p_1, &w0, &w1]),
-        ];
+        ]
     }
 
-    fn chunk(&self, input: &[(&str, &str)]) -> Sequence {
+    fn chunk(&self, input: &[(&str, &str)]) -> Sequence<'_> {
         self.model
             .beam_search(
                 input,
@@ -632,8 +631,13 @@ pub struct Chunker {
 impl Chunker {
     /// Populates the `.chunks` field of the passed tokens by predicting with the maximum entropy model.
     pub fn apply(&self, tokens: &mut Vec<IncompleteToken>) {
+        // only the text covered by the tokens is chunked, so sentences can be chunked separately
+        let text_start = tokens[0].byte_span.0;
+        let text_end = tokens[tokens.len() - 1].byte_span.1;
+        let char_offset = tokens[0].char_span.0;
+
         // replacements must not change char indices
-        let text = tokens[0].text.replace('’', "\'");
+        let text = tokens[0].text[text_start..text_end].replace('’', "\'");
 
         let mut byte_to_char_idx: DefaultHashMap<usize, usize> = text
             .char_indices()
@@ -661,8 +665,9 @@ impl Chunker {
             .zip(internal_tokens)
             .map(|(chunk, token)| {
                 let byte_start = token.as_ptr() as usize - text.as_ptr() as usize;
-                let char_start = *byte_to_char_idx.get(&byte_start).unwrap();
-                let char_end = *byte_to_char_idx.get(&(byte_start + token.len())).unwrap();
+                let char_start = *byte_to_char_idx.get(&byte_start).unwrap() + char_offset;
+                let char_end =
+                    *byte_to_char_idx.get(&(byte_start + token.len())).unwrap() + char_offset;
 
                 (*chunk, (char_start, char_end))
             })
@@ -0,0 +1,227 @@
+//! A rule-based chunker for English which needs no model, unlike the [OpenNLP chunker][super::chunk::Chunker].
+//! It runs after disambiguation and assigns chunks from the Penn Treebank tags of the tokens with a small cascade of
+//! rules. The chunks have the format of the chunks LanguageTool's English chunker assigns:
+//! - noun phrases get `B-NP-<number>` on the first token, `I-NP-<number>` inside and `E-NP-<number>` on the last token
+//!   (a single token gets both `B-NP-<number>` and `E-NP-<number>`), the number is `plural` if the phrase contains
+//!   a plural noun and `singular` otherwise.
+//! - verb groups get `B-VP` and `I-VP`, including adverbs between verbs and "to" before an infinitive.
+//! - prepositions get `B-PP`, adverbs outside of verb groups `B-ADVP` and `I-ADVP` and adjectives outside of noun
+//!   phrases `B-ADJP` and `I-ADJP`.
+//! - all other tokens get `O`.
+
+use crate::types::*;
+
+/// The word class of a token, derived from its tags.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Class {
+    /// Determiners, possessive pronouns and the possessive ending, which start a noun phrase.
+    Determiner,
+    Adjective,
+    Number,
+    Noun,
+    Pronoun,
+    /// Verbs including modals.
+    Verb,
+    Adverb,
+    Preposition,
+    To,
+}
+
+impl Class {
+    fn of_tag(tag: &str) -> Option<Class> {
+        Some(match tag {
+            "DT" | "PDT" | "PRP$" | "WDT" | "WP$" | "POS" => Class::Determiner,
+            "JJ" | "JJR" | "JJS" => Class::Adjective,
+            "CD" => Class::Number,
+            "NN" | "NNS" | "NNP" | "NNPS" | "FW" => Class::Noun,
+            "PRP" | "WP" | "EX" => Class::Pronoun,
+            "MD" | "VB" | "VBD" | "VBG" | "VBN" | "VBP" | "VBZ" => Class::Verb,
+            "RB" | "RBR" | "RBS" | "WRB" => Class::Adverb,
+            "IN" => Class::Preposition,
+            "TO" => Class::To,
+            _ => return None,
+        })
+    }
+
+    /// The classes to prefer for a token with readings of multiple classes, in order.
+    fn preference(previous: Option<Class>) -> &'static [Class] {
+        use Class::*;
+
+        match previous {
+            // a noun phrase is open, e. g. "an email"
+            Some(Determiner) | Some(Adjective) | Some(Number) => &[Noun, Adjective, Number],
+            // a subject or verb group is followed by a verb, e. g. "I can", "the fox jumps"
+            Some(Noun) | Some(Pronoun) | Some(Verb) | Some(To) => &[Verb],
+            _ => &[],
+        }
+    }
+}
+
+/// The class of each token. Tokens with readings of multiple classes are resolved with the class of the previous
+/// token, then by a fixed priority.
+fn classes(tokens: &[IncompleteToken]) -> Vec<Option<Class>> {
+    const PRIORITY: &[Class] = &[
+        Class::Preposition,
+        Class::Determiner,
+        Class::Pronoun,
+        Class::Noun,
+        Class::Verb,
+        Class::Adjective,
+        Class::Number,
+        Class::Adverb,
+        Class::To,
+    ];
+    let mut output: Vec<Option<Class>> = Vec::with_capacity(tokens.len());
+
+    for token in tokens {
+        let mut candidates: Vec<Class> = Vec::new();
+        for class in token
+            .word
+            .tags
+            .iter()
+            .filter_map(|x| Class::of_tag(x.pos.as_ref()))
+        {
+            if !candidates.contains(&class) {
+                candidates.push(class);
+            }
+        }
+
+        let previous = output.last().copied().flatten();
+        let class = Class::preference(previous)
+            .iter()
+            .chain(PRIORITY)
+            .find(|x| candidates.contains(x))
+            .copied();
+        output.push(class);
+    }
+
+    output
+}
+
+// like the OpenNLP chunker, only common plural nouns determine the number
+fn is_plural(token: &IncompleteToken) -> bool {
+    token.word.tags.iter().any(|x| x.pos.as_ref() == "NNS")
+}
+
+/// Assigns the chunks of a noun phrase.
+fn noun_phrase(tokens: &mut [IncompleteToken]) {
+    let number = if tokens.iter().any(is_plural) {
+        "plural"
+    } else {
+        "singular"
+    };
+    let last = tokens.len() - 1;
+
+    for (i, token) in tokens.iter_mut().enumerate() {
+        token.chunks = match (i == 0, i == last) {
+            (true, true) => vec![format!("B-NP-{}", number), format!("E-NP-{}", number)],
+            (true, false) => vec![format!("B-NP-{}", number)],
+            (false, true) => vec![format!("E-NP-{}", number)],
+            (false, false) => vec![format!("I-NP-{}", number)],
+        };
+    }
+}
+
+/// Assigns the chunks of a phrase of another type, e. g. "VP".
+fn phrase(tokens: &mut [IncompleteToken], kind: &str) {
+    for (i, token) in tokens.iter_mut().enumerate() {
+        let position = if i == 0 { "B" } else { "I" };
+        token.chunks = vec![format!("{}-{}", position, kind)];
+    }
+}
+
+/// Chunks one sentence.
+fn chunk_sentence(tokens: &mut [IncompleteToken]) {
+    use Class::*;
+
+    let classes = classes(tokens);
+    let class = |i: usize| classes.get(i).copied().flatten();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        // adverbs modifying a following adjective belong to its phrase, e. g. "very big"
+        let mut start = i;
+        while class(start) == Some(Adverb) {
+            start += 1;
+        }
+        if class(start) != Some(Adjective) {
+            start = i;
+        }
+
+        let end = match class(start) {
+            Some(Determiner) | Some(Adjective) | Some(Number) | Some(Noun) => {
+                // determiners only start a noun phrase, e. g. "her" and "an email" in "send her an email"
+                let mut end = start + 1;
+                while let Some(Adjective) | Some(Number) | Some(Noun) | Some(Adverb) = class(end) {
+                    end += 1;
+                }
+                // trailing adverbs and adjectives after the head are not part of the phrase
+                while end > start + 1 && !matches!(class(end - 1), Some(Noun) | Some(Number)) {
+                    end -= 1;
+                }
+
+                let has_head = (start..end).any(|x| matches!(class(x), Some(Noun) | Some(Number)));
+                if has_head || class(start) == Some(Determiner) {
+                    noun_phrase(&mut tokens[i..end]);
+                } else {
+                    phrase(&mut tokens[i..end], "ADJP");
+                }
+                end
+            }
+            Some(Pronoun) => {
+                noun_phrase(&mut tokens[i..i + 1]);
+                i + 1
+            }
+            Some(Verb) | Some(To) if class(i) == Some(Verb) || class(i + 1) == Some(Verb) => {
+                let mut end = i + 1;
+                loop {
+                    // adverbs are only part of the group if a verb follows, e. g. "was not been"
+                    let mut next = end;
+                    while class(next) == Some(Adverb) {
+                        next += 1;
+                    }
+
+                    match class(next) {
+                        Some(Verb) => end = next + 1,
+                        Some(To) if class(next + 1) == Some(Verb) => end = next + 2,
+                        _ => break,
+                    }
+                }
+
+                phrase(&mut tokens[i..end], "VP");
+                end
+            }
+            Some(Preposition) | Some(To) => {
+                phrase(&mut tokens[i..i + 1], "PP");
+                i + 1
+            }
+            Some(Adverb) => {
+                let mut end = i + 1;
+                while class(end) == Some(Adverb) {
+                    end += 1;
+                }
+
+                phrase(&mut tokens[i..end], "ADVP");
+                end
+            }
+            _ => {
+                tokens[i].chunks = vec!["O".to_string()];
+                i + 1
+            }
+        };
+
+        i = end;
+    }
+}
+
+/// Populates the `chunks` of the tokens, replacing existing chunks. Each sentence is chunked separately.
+pub fn apply(tokens: &mut [IncompleteToken]) {
+    let mut start = 0;
+
+    for i in 0..tokens.len() {
+        if tokens[i].is_sentence_end || i + 1 == tokens.len() {
+            chunk_sentence(&mut tokens[start..=i]);
+            start = i + 1;
+        }
+    }
+}
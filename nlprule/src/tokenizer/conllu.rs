@@ -0,0 +1,158 @@
+//! Export of tokens in the [CoNLL-U](https://universaldependencies.org/format.html) format.
+//! Part-of-speech tags are not from the universal tagset, so they are written to the XPOS column and UPOS is left empty.
+
+use crate::types::*;
+use std::fmt::Write;
+
+fn is_special(token: &Token) -> bool {
+    token.word.text.as_ref().is_empty()
+}
+
+fn join_unique<'a>(values: impl Iterator<Item = &'a str>) -> String {
+    let mut unique: Vec<&str> = Vec::new();
+
+    for value in values {
+        if !unique.contains(&value) {
+            unique.push(value);
+        }
+    }
+
+    if unique.is_empty() {
+        "_".into()
+    } else {
+        unique.join("|")
+    }
+}
+
+/// Converts tokens grouped by sentence (as returned by [tokenize_sentences][super::Tokenizer::tokenize_sentences])
+/// to CoNLL-U. Special tokens like SENT_START are skipped, alternative lemmas and tags of a token are joined with `|`.
+pub fn to_conllu(sentences: &[Vec<Token>]) -> String {
+    let sentences: Vec<Vec<&Token>> = sentences
+        .iter()
+        .map(|sentence| sentence.iter().filter(|x| !is_special(x)).collect())
+        .filter(|sentence: &Vec<_>| !sentence.is_empty())
+        .collect();
+
+    let mut output = String::new();
+
+    for (i, sentence) in sentences.iter().enumerate() {
+        let first = sentence[0];
+        let last = sentence[sentence.len() - 1];
+        let next_sentence = sentences.get(i + 1).map(|x| x[0]);
+
+        writeln!(
+            output,
+            "# text = {}",
+            &first.text[first.byte_span.0..last.byte_span.1]
+        )
+        .unwrap();
+
+        for (j, token) in sentence.iter().enumerate() {
+            let tags: Vec<_> = token
+                .word
+                .tags
+                .iter()
+                .filter(|x| !x.pos.as_ref().is_empty())
+                .collect();
+
+            let lemma = join_unique(tags.iter().map(|x| x.lemma.as_ref()));
+            let pos = join_unique(tags.iter().map(|x| x.pos.as_ref()));
+
+            let next = sentence.get(j + 1).copied().or(next_sentence);
+            let misc = match next {
+                Some(next) if !next.has_space_before => "SpaceAfter=No",
+                _ => "_",
+            };
+
+            writeln!(
+                output,
+                "{}\t{}\t{}\t_\t{}\t_\t_\t_\t_\t{}",
+                j + 1,
+                token.word.text.as_ref(),
+                lemma,
+                pos,
+                misc
+            )
+            .unwrap();
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::tag::Tagger;
+
+    fn token<'t>(
+        text: &'t str,
+        tagger: &'t Tagger,
+        byte_span: (usize, usize),
+        tags: &[(&'t str, &'t str)],
+    ) -> Token<'t> {
+        let word_text = &text[byte_span.0..byte_span.1];
+
+        Token {
+            word: Word::new_with_tags(
+                WordId(word_text.into(), None),
+                tags.iter()
+                    .map(|(lemma, pos)| WordData::new(WordId((*lemma).into(), None), PosId(pos, 0)))
+                    .collect(),
+            ),
+            char_span: byte_span,
+            grapheme_span: byte_span,
+            utf16_span: byte_span,
+            byte_span,
+            kind: TokenKind::Word,
+            case: Case::of(word_text),
+            token_index: 0,
+            sentence_index: 0,
+            has_lowercase_readings: false,
+            has_space_before: text[..byte_span.0].ends_with(char::is_whitespace),
+            space_before: "",
+            chunks: Vec::new(),
+            text,
+            tagger,
+        }
+    }
+
+    #[test]
+    fn golden() {
+        let tagger = Tagger::default();
+        let text = "She runs. Dogs bark";
+
+        let sentences = vec![
+            vec![
+                token(text, &tagger, (0, 0), &[("", "SENT_START")]),
+                token(text, &tagger, (0, 3), &[("she", "PRP"), ("She", "")]),
+                token(
+                    text,
+                    &tagger,
+                    (4, 8),
+                    &[("run", "VBZ"), ("run", "NNS"), ("runs", "")],
+                ),
+                token(text, &tagger, (8, 9), &[(".", ""), (".", "SENT_END")]),
+            ],
+            vec![
+                token(text, &tagger, (10, 10), &[("", "SENT_START")]),
+                token(text, &tagger, (10, 14), &[("dog", "NNS"), ("dogs", "DOGS")]),
+                token(text, &tagger, (15, 19), &[("bark", "")]),
+            ],
+        ];
+
+        let expected = "# text = She runs.
+1\tShe\tshe\t_\tPRP\t_\t_\t_\t_\t_
+2\truns\trun\t_\tVBZ|NNS\t_\t_\t_\t_\tSpaceAfter=No
+3\t.\t.\t_\tSENT_END\t_\t_\t_\t_\t_
+
+# text = Dogs bark
+1\tDogs\tdog|dogs\t_\tNNS|DOGS\t_\t_\t_\t_\t_
+2\tbark\t_\t_\t_\t_\t_\t_\t_\t_
+
+";
+
+        assert_eq!(to_conllu(&sentences), expected);
+    }
+}
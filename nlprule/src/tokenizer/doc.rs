@@ -0,0 +1,590 @@
+//! An analyzed text which owns its text and tokens, see [Tokenizer::analyze].
+//! Provides lookups of the token at an offset and conversions between byte and char offsets in O(log n)
+//! using the sorted spans of the tokens. The tokens of a sentence can be navigated with a [Sentence]
+//! without having to account for the SENT_START token.
+//! After an edit only the sentences around the edit are tokenized again, see [Doc::edit].
+
+use super::{finalize_sentence, Position, Tokenizer};
+use crate::types::{owned, IncompleteToken};
+use std::ops::Range;
+
+/// Byte and char offsets at the start and end of every token, starting at (0, 0).
+fn offsets(tokens: &[owned::Token]) -> Vec<(usize, usize)> {
+    let mut offsets = vec![(0, 0)];
+    for token in tokens.iter() {
+        for offset in [
+            (token.byte_span.0, token.char_span.0),
+            (token.byte_span.1, token.char_span.1),
+        ] {
+            if offset.0 > offsets.last().unwrap().0 {
+                offsets.push(offset);
+            }
+        }
+    }
+
+    offsets
+}
+
+fn shift(span: (usize, usize), delta: isize) -> (usize, usize) {
+    (
+        (span.0 as isize + delta) as usize,
+        (span.1 as isize + delta) as usize,
+    )
+}
+
+/// A tokenized, disambiguated and finalized text.
+/// Tokens are sorted by their spans and do not overlap. Whitespace between tokens is not part of any token.
+/// Each sentence starts with a SENT_START token with an empty span at the start of the first token of the sentence.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Doc {
+    text: String,
+    tokens: Vec<owned::Token>,
+    // index ranges of the sentences in `tokens`
+    sentences: Vec<Range<usize>>,
+    // byte and char offsets at the start and end of every token, starting at (0, 0)
+    offsets: Vec<(usize, usize)>,
+}
+
+impl Doc {
+    pub(crate) fn new(text: String, tokenizer: &Tokenizer) -> Self {
+        let mut tokens = Vec::new();
+        let mut sentences = Vec::new();
+
+        for sentence in tokenizer.tokenize_sentences(&text) {
+            let start = tokens.len();
+            tokens.extend(sentence.iter().map(|x| x.to_owned_token()));
+            sentences.push(start..tokens.len());
+        }
+
+        Doc {
+            text,
+            offsets: offsets(&tokens),
+            tokens,
+            sentences,
+        }
+    }
+
+    /// Replaces the byte range of the text with the replacement and updates the tokens. Only the sentences
+    /// touching the range and one sentence before and after them are tokenized and disambiguated again,
+    /// the spans of the tokens after them are shifted. The result is the same as analyzing the edited text.
+    /// `tokenizer` must be the tokenizer which created this doc.
+    ///
+    /// # Panics
+    /// If the range does not lie on char boundaries of the text, like [String::replace_range].
+    pub fn edit(&mut self, range: Range<usize>, replacement: &str, tokenizer: &Tokenizer) {
+        let old_len = self.text.len();
+        self.text.replace_range(range.clone(), replacement);
+        let delta = self.text.len() as isize - old_len as isize;
+
+        if self.sentences.is_empty() {
+            *self = Doc::new(std::mem::take(&mut self.text), tokenizer);
+            return;
+        }
+
+        // the spans are not affected by the edit up to the start of the range
+        let spans: Vec<_> = self.sentences().map(|x| x.byte_span()).collect();
+        let n = spans.len();
+        // the sentences touching the range and one sentence before and after them, the boundaries of which
+        // are not influenced by the edit
+        let mut lo = spans
+            .partition_point(|(_, end)| *end < range.start)
+            .saturating_sub(1);
+        let mut hi = spans
+            .partition_point(|(start, _)| *start <= range.end)
+            .min(n - 1);
+        // the tokens at a boundary without whitespace depend on the text on both sides of it (e. g. ".example.org"
+        // is not a domain), so sentences which are not separated by whitespace are tokenized again together
+        // the segmentation also looks ahead past characters which are not letters (e. g. ". 3 b" is not split because
+        // of the lowercase "b"), so a sentence without letters before the range can be merged into the previous one
+        while lo > 0
+            && (spans[lo - 1].1 == spans[lo].0
+                || !self.text[spans[lo].0..range.start].contains(char::is_alphabetic))
+        {
+            lo -= 1;
+        }
+        while hi < n - 1 && spans[hi].1 == spans[hi + 1].0 {
+            hi += 1;
+        }
+
+        let start = if lo == 0 { 0 } else { spans[lo].0 };
+        let end = if hi == n - 1 {
+            self.text.len()
+        } else {
+            (spans[hi].1 as isize + delta) as usize
+        };
+
+        let mut position = Position::default();
+        if lo > 0 {
+            let prev = &self.tokens[self.sentences[lo].start - 1];
+            position = Position {
+                chars: prev.char_span.1,
+                graphemes: prev.grapheme_span.1,
+                utf16: prev.utf16_span.1,
+            };
+            position.advance(&self.text[prev.byte_span.1..start]);
+        }
+
+        let sentence_spans: Vec<_> = tokenizer
+            .sentence_spans(&self.text[start..end])
+            .map(|(x, y)| (x + start, y + start))
+            .collect();
+
+        let mut incomplete: Vec<Vec<IncompleteToken>> = Vec::new();
+        for token in tokenizer.tokenize_part(&self.text, (start, end), position, &sentence_spans) {
+            match incomplete.last_mut() {
+                Some(sentence) if !token.is_sentence_start => sentence.push(token),
+                _ => incomplete.push(vec![token]),
+            }
+        }
+
+        let new_sentences: Vec<Vec<owned::Token>> = incomplete
+            .into_iter()
+            .enumerate()
+            .map(|(i, sentence)| {
+                finalize_sentence(tokenizer.disambiguate(sentence), lo + i)
+                    .iter()
+                    .map(|x| x.to_owned_token())
+                    .collect()
+            })
+            .collect();
+
+        let region = self.sentences[lo].start..self.sentences[hi].end;
+        let mut tail = self.tokens.split_off(region.end);
+
+        if !tail.is_empty() {
+            let old_end = &self.tokens[region.end - 1];
+            let mut new_end = position;
+            new_end.advance(&self.text[start..end]);
+
+            let char_delta = new_end.chars as isize - old_end.char_span.1 as isize;
+            let grapheme_delta = new_end.graphemes as isize - old_end.grapheme_span.1 as isize;
+            let utf16_delta = new_end.utf16 as isize - old_end.utf16_span.1 as isize;
+            let sentence_delta = new_sentences.len() as isize - (hi - lo + 1) as isize;
+
+            for token in tail.iter_mut() {
+                token.byte_span = shift(token.byte_span, delta);
+                token.char_span = shift(token.char_span, char_delta);
+                token.grapheme_span = shift(token.grapheme_span, grapheme_delta);
+                token.utf16_span = shift(token.utf16_span, utf16_delta);
+                token.sentence_index = (token.sentence_index as isize + sentence_delta) as usize;
+            }
+        }
+
+        self.tokens.truncate(region.start);
+        let tail_sentences = self.sentences.split_off(hi + 1);
+        self.sentences.truncate(lo);
+
+        for sentence in new_sentences {
+            let start = self.tokens.len();
+            self.tokens.extend(sentence);
+            self.sentences.push(start..self.tokens.len());
+        }
+
+        let token_delta = self.tokens.len() as isize - region.end as isize;
+        self.sentences
+            .extend(tail_sentences.into_iter().map(|range| {
+                let range = shift((range.start, range.end), token_delta);
+                range.0..range.1
+            }));
+        self.tokens.extend(tail);
+        self.offsets = offsets(&self.tokens);
+    }
+
+    /// The analyzed text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// All tokens of the text including the SENT_START tokens.
+    pub fn tokens(&self) -> &[owned::Token] {
+        &self.tokens
+    }
+
+    /// The sentences of the text.
+    pub fn sentences(&self) -> impl Iterator<Item = Sentence<'_>> {
+        self.sentences
+            .iter()
+            .map(move |range| Sentence::new(&self.text, &self.tokens[range.clone()]))
+    }
+
+    /// The sentence with the given index.
+    pub fn sentence(&self, index: usize) -> Option<Sentence<'_>> {
+        self.sentences
+            .get(index)
+            .map(|range| Sentence::new(&self.text, &self.tokens[range.clone()]))
+    }
+
+    /// The index of the first token which ends after the offset. `end` returns the end of the span of a token.
+    fn first_ending_after(&self, offset: usize, end: fn(&owned::Token) -> usize) -> usize {
+        self.tokens.partition_point(|token| end(token) <= offset)
+    }
+
+    /// The token which covers the byte offset. `None` if the offset is in whitespace between tokens or outside of
+    /// the text. Never returns tokens with an empty span like SENT_START.
+    pub fn token_at_byte(&self, offset: usize) -> Option<&owned::Token> {
+        let index = self.first_ending_after(offset, |x| x.byte_span.1);
+        // if the token at the index has an empty span, it starts after the offset
+        self.tokens
+            .get(index)
+            .filter(|token| token.byte_span.0 <= offset)
+    }
+
+    /// The token which covers the char offset, like [token_at_byte][Doc::token_at_byte].
+    pub fn token_at_char(&self, offset: usize) -> Option<&owned::Token> {
+        let index = self.first_ending_after(offset, |x| x.char_span.1);
+        self.tokens
+            .get(index)
+            .filter(|token| token.char_span.0 <= offset)
+    }
+
+    /// The tokens which overlap the byte range. Tokens with an empty span like SENT_START are included if they are
+    /// at the start of the range or within it, but not if they are at the end. Empty if the range is empty.
+    pub fn tokens_in_range(&self, range: Range<usize>) -> &[owned::Token] {
+        if range.start >= range.end {
+            return &[];
+        }
+
+        // tokens ending at the start of the range are excluded unless they are empty and consequently start there
+        let start = self.tokens.partition_point(|token| {
+            token.byte_span.1 < range.start
+                || (token.byte_span.1 == range.start && token.byte_span.0 < range.start)
+        });
+        let end = self
+            .tokens
+            .partition_point(|token| token.byte_span.0 < range.end);
+
+        &self.tokens[start..end.max(start)]
+    }
+
+    /// Converts a byte offset to a char offset. `None` if the offset is not at a char boundary.
+    /// The length of the text in bytes is converted to the number of chars.
+    pub fn byte_to_char(&self, offset: usize) -> Option<usize> {
+        if !self.text.is_char_boundary(offset) {
+            return None;
+        }
+
+        let index = self.offsets.partition_point(|(byte, _)| *byte <= offset) - 1;
+        let (byte, chars) = self.offsets[index];
+
+        Some(chars + self.text[byte..offset].chars().count())
+    }
+
+    /// Converts a char offset to a byte offset. `None` if the offset is greater than the number of chars.
+    /// The number of chars is converted to the length of the text in bytes.
+    pub fn char_to_byte(&self, offset: usize) -> Option<usize> {
+        let index = self.offsets.partition_point(|(_, chars)| *chars <= offset) - 1;
+        let (byte, chars) = self.offsets[index];
+
+        self.text[byte..]
+            .char_indices()
+            .map(|(i, _)| byte + i)
+            .chain(std::iter::once(self.text.len()))
+            .nth(offset - chars)
+    }
+}
+
+/// A view of the tokens of one sentence of a [Doc]. Indices refer to the tokens without the SENT_START token,
+/// so index 0 is the first token of the sentence.
+#[derive(Debug, Clone, Copy)]
+pub struct Sentence<'a> {
+    text: &'a str,
+    sent_start: &'a owned::Token,
+    tokens: &'a [owned::Token],
+}
+
+impl<'a> Sentence<'a> {
+    /// `tokens` must start with the SENT_START token.
+    fn new(text: &'a str, tokens: &'a [owned::Token]) -> Self {
+        let (sent_start, tokens) = tokens
+            .split_first()
+            .expect("sentences start with a SENT_START token");
+
+        Sentence {
+            text,
+            sent_start,
+            tokens,
+        }
+    }
+
+    /// The SENT_START token of this sentence. Has an empty span at the start of the first token.
+    pub fn sent_start(&self) -> &'a owned::Token {
+        self.sent_start
+    }
+
+    /// The tokens of this sentence without the SENT_START token.
+    pub fn tokens(&self) -> &'a [owned::Token] {
+        self.tokens
+    }
+
+    /// The number of tokens without the SENT_START token.
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Whether this sentence has no tokens apart from the SENT_START token.
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// The token at the index.
+    pub fn get(&self, index: usize) -> Option<&'a owned::Token> {
+        self.tokens.get(index)
+    }
+
+    /// The token before the token at the index. `None` for the first token.
+    pub fn prev(&self, index: usize) -> Option<&'a owned::Token> {
+        index.checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    /// The token after the token at the index. `None` for the last token.
+    pub fn next(&self, index: usize) -> Option<&'a owned::Token> {
+        self.get(index + 1)
+    }
+
+    /// The byte span from the start of the first token to the end of the last token.
+    pub fn byte_span(&self) -> (usize, usize) {
+        let end = self.tokens.last().unwrap_or(self.sent_start).byte_span.1;
+        (self.sent_start.byte_span.0, end)
+    }
+
+    /// The char span from the start of the first token to the end of the last token.
+    pub fn char_span(&self) -> (usize, usize) {
+        let end = self.tokens.last().unwrap_or(self.sent_start).char_span.1;
+        (self.sent_start.char_span.0, end)
+    }
+
+    /// The text covered by this sentence, without leading and trailing whitespace.
+    pub fn text(&self) -> &'a str {
+        let (start, end) = self.byte_span();
+        &self.text[start..end]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tokenizer::{tag::Tagger, TokenizerOptions},
+        types::TokenKind,
+    };
+    use quickcheck_macros::quickcheck;
+    use std::{collections::HashSet, sync::Arc};
+
+    fn tokenizer() -> Tokenizer {
+        let tagger = Tagger::from_dumps(
+            &[] as &[&str],
+            &[] as &[&str],
+            &[] as &[&str],
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        Tokenizer {
+            tagger: Arc::new(tagger),
+            options: TokenizerOptions {
+                detect_bare_domains: true,
+                ..TokenizerOptions::default()
+            },
+            ..Tokenizer::default()
+        }
+    }
+
+    fn doc(text: &str) -> Doc {
+        tokenizer().analyze(text)
+    }
+
+    fn texts(tokens: &[owned::Token]) -> Vec<&str> {
+        tokens.iter().map(|x| x.word.text.as_ref()).collect()
+    }
+
+    #[test]
+    fn token_lookup() {
+        let text = "Grüße, Welt!  Wie geht’s?";
+        let doc = doc(text);
+
+        assert_eq!(doc.text(), text);
+        assert_eq!(
+            doc.sentences()
+                .map(|x| texts(x.tokens()))
+                .collect::<Vec<_>>(),
+            vec![
+                vec!["Grüße", ",", "Welt", "!"],
+                vec!["Wie", "geht", "’", "s", "?"]
+            ]
+        );
+        assert!(doc.sentence(2).is_none());
+
+        // "ü" is two bytes long
+        assert_eq!(doc.token_at_byte(0).unwrap().word.text.as_ref(), "Grüße");
+        assert_eq!(doc.token_at_byte(3).unwrap().word.text.as_ref(), "Grüße");
+        assert_eq!(doc.token_at_byte(7).unwrap().word.text.as_ref(), ",");
+        assert!(doc.token_at_byte(8).is_none());
+        assert!(doc.token_at_byte(15).is_none());
+        assert_eq!(doc.token_at_byte(16).unwrap().word.text.as_ref(), "Wie");
+        assert!(doc.token_at_byte(text.len()).is_none());
+
+        assert_eq!(doc.token_at_char(4).unwrap().word.text.as_ref(), "Grüße");
+        assert_eq!(doc.token_at_char(5).unwrap().word.text.as_ref(), ",");
+        assert!(doc.token_at_char(12).is_none());
+        assert_eq!(doc.token_at_char(14).unwrap().word.text.as_ref(), "Wie");
+        assert_eq!(doc.token_at_char(22).unwrap().word.text.as_ref(), "’");
+    }
+
+    #[test]
+    fn tokens_in_range() {
+        let doc = doc("Grüße, Welt!  Wie geht’s?");
+
+        assert_eq!(texts(doc.tokens_in_range(0..7)), vec!["", "Grüße"]);
+        assert_eq!(
+            texts(doc.tokens_in_range(3..10)),
+            vec!["Grüße", ",", "Welt"]
+        );
+        assert_eq!(texts(doc.tokens_in_range(8..9)), Vec::<&str>::new());
+        assert_eq!(texts(doc.tokens_in_range(13..19)), vec!["!", "", "Wie"]);
+        assert_eq!(texts(doc.tokens_in_range(14..19)), vec!["", "Wie"]);
+        assert_eq!(texts(doc.tokens_in_range(15..19)), vec!["", "Wie"]);
+        assert_eq!(texts(doc.tokens_in_range(16..16)), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn offset_conversion() {
+        let text = "Grüße, Welt!  Wie geht’s?";
+        let doc = doc(text);
+
+        for (chars, (byte, _)) in text.char_indices().enumerate() {
+            assert_eq!(doc.byte_to_char(byte), Some(chars));
+            assert_eq!(doc.char_to_byte(chars), Some(byte));
+        }
+
+        let n_chars = text.chars().count();
+        assert_eq!(doc.byte_to_char(text.len()), Some(n_chars));
+        assert_eq!(doc.char_to_byte(n_chars), Some(text.len()));
+        assert_eq!(doc.byte_to_char(3), None);
+        assert_eq!(doc.byte_to_char(text.len() + 1), None);
+        assert_eq!(doc.char_to_byte(n_chars + 1), None);
+    }
+    #[test]
+    fn sentence_navigation() {
+        let text = "Hallo. Siehe https://example.com/a_b für Details!";
+        let doc = doc(text);
+        let sentence = doc.sentence(1).unwrap();
+
+        assert_eq!(sentence.sent_start().byte_span, (7, 7));
+        assert_eq!(
+            sentence.text(),
+            "Siehe https://example.com/a_b für Details!"
+        );
+        assert_eq!(sentence.byte_span(), (7, text.len()));
+        assert_eq!(sentence.char_span(), (7, text.chars().count()));
+        assert_eq!(sentence.len(), 5);
+
+        assert!(sentence.prev(0).is_none());
+        assert_eq!(sentence.get(0).unwrap().word.text.as_ref(), "Siehe");
+        assert_eq!(sentence.next(0).unwrap().kind, TokenKind::Url);
+        assert_eq!(
+            sentence.next(0).unwrap().word.text.as_ref(),
+            "https://example.com/a_b"
+        );
+        assert_eq!(sentence.prev(2).unwrap().kind, TokenKind::Url);
+        assert_eq!(sentence.next(1).unwrap().word.text.as_ref(), "für");
+        assert_eq!(sentence.prev(4).unwrap().word.text.as_ref(), "Details");
+        assert!(sentence.next(4).is_none());
+        assert!(sentence.get(5).is_none());
+
+        let first = doc.sentence(0).unwrap();
+        assert_eq!(first.text(), "Hallo.");
+        assert!(first.prev(0).is_none());
+        assert_eq!(first.next(0).unwrap().word.text.as_ref(), ".");
+    }
+    const SAMPLE: &str = "Das ist ein Test. Wir schreiben heute einen Text mit vielen Sätzen! Ist das nicht schön? \
+        Ja, natürlich.  Siehe https://example.com für mehr.\n\nNeuer Absatz mit Grüßen: 3 Äpfel und 2 Birnen. Ende.";
+
+    #[test]
+    fn edit_sentences() {
+        let tokenizer = tokenizer();
+        let mut doc = tokenizer.analyze(SAMPLE);
+
+        // merges the first two sentences
+        doc.edit(16..19, " und w", &tokenizer);
+        assert_eq!(doc, tokenizer.analyze(doc.text()));
+        assert_eq!(
+            doc.sentence(0).unwrap().text(),
+            "Das ist ein Test und wir schreiben heute einen Text mit vielen Sätzen!"
+        );
+
+        // splits the last sentence
+        let end = doc.text().len();
+        doc.edit(end - 5..end - 4, "Das. E", &tokenizer);
+        assert_eq!(doc, tokenizer.analyze(doc.text()));
+
+        doc.edit(0..doc.text().len(), "", &tokenizer);
+        assert_eq!(doc, tokenizer.analyze(""));
+        doc.edit(0..0, "Wieder da.", &tokenizer);
+        assert_eq!(doc, tokenizer.analyze("Wieder da."));
+    }
+
+    #[test]
+    fn edit_after_sentence_without_letters() {
+        let tokenizer = tokenizer();
+        let mut doc = tokenizer.analyze("Ende. 3 ");
+        assert_eq!(doc.sentences().count(), 2);
+
+        // the lowercase word after "3" means the period does not end a sentence anymore
+        let end = doc.text().len();
+        doc.edit(end..end, "https://example.org ", &tokenizer);
+        assert_eq!(doc, tokenizer.analyze(doc.text()));
+        assert_eq!(doc.sentences().count(), 1);
+    }
+
+    #[quickcheck]
+    fn edits_match_full_analysis(edits: Vec<(u16, u8, u8)>) -> bool {
+        const REPLACEMENTS: &[&str] = &[
+            "",
+            "a",
+            "Wort",
+            " ",
+            "  ",
+            ".",
+            ". ",
+            "! Das",
+            "? ",
+            "\n",
+            "\n\n",
+            "ß",
+            "ä",
+            ",",
+            "’s",
+            "3",
+            "Neu",
+            "https://example.org ",
+            "example.org",
+            "z. B. ",
+            "Text. Noch ein Satz",
+        ];
+
+        let tokenizer = tokenizer();
+        let mut doc = tokenizer.analyze(SAMPLE);
+
+        for (start, len, replacement) in edits {
+            let text = doc.text();
+            let floor = |mut index: usize| {
+                index = index.min(text.len());
+                while !text.is_char_boundary(index) {
+                    index -= 1;
+                }
+                index
+            };
+
+            let start = floor(start as usize % (text.len() + 1));
+            let end = floor(start + len as usize % 16);
+            let replacement = REPLACEMENTS[replacement as usize % REPLACEMENTS.len()];
+
+            doc.edit(start..end, replacement, &tokenizer);
+            if doc != tokenizer.analyze(doc.text()) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
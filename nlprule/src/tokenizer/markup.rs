@@ -0,0 +1,565 @@
+//! Tokenization of text with Markdown or HTML markup.
+//! The markup is replaced with whitespace (one char per char) so that only the plain text is tokenized.
+//! Code (fenced code blocks, inline code and `<code>`, `<pre>`, `<script>` and `<style>` elements) is excluded entirely.
+//! The spans of the tokens refer to the marked-up source, so suggestions can be applied in place.
+//! Documents where the caller already knows which parts are markup can be built as an [AnnotatedText].
+
+use super::{Position, Tokenizer};
+use crate::types::owned;
+use lazy_static::lazy_static;
+use onig::Regex;
+
+/// The markup language of a text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Markup {
+    /// Markdown e. g. a README. Supports the common subset of CommonMark: headings, block quotes, lists,
+    /// emphasis, links, images, inline code, fenced code blocks and inline HTML.
+    Markdown,
+    /// A simple subset of HTML: tags, comments and entities. Block-level tags like `<p>` separate sentences.
+    Html,
+}
+
+/// A text with markup replaced by whitespace.
+/// For [Markup] the plain text has the same number of chars as the source, but can have fewer bytes if the markup
+/// contains chars which are longer than a space. For an [AnnotatedText] markup can also be removed entirely.
+#[derive(Debug, Clone)]
+pub struct PlainText<'a> {
+    source: &'a str,
+    text: String,
+    // plain byte index after every replaced piece of markup and the total difference to the source byte index
+    shifts: Vec<(usize, usize)>,
+}
+
+impl<'a> PlainText<'a> {
+    /// Replaces the markup in the source with whitespace.
+    pub fn new(source: &'a str, markup: Markup) -> Self {
+        let mut mask = vec![Blank::Keep; source.len()];
+        match markup {
+            Markup::Markdown => markdown_ranges(source, &mut mask),
+            Markup::Html => html_ranges(source, &mut mask),
+        }
+
+        let mut plain = PlainText::empty(source);
+        let mut buffer = [0; 4];
+
+        for (i, c) in source.char_indices() {
+            // line breaks are kept so that markup does not join lines into one sentence
+            let replacement = match mask[i] {
+                _ if c == '\n' || c == '\r' => c,
+                Blank::Keep => c,
+                Blank::Space => ' ',
+                Blank::LineBreak => '\n',
+            };
+            plain.push(c.len_utf8(), replacement.encode_utf8(&mut buffer));
+        }
+
+        plain
+    }
+
+    fn empty(source: &'a str) -> Self {
+        PlainText {
+            source,
+            text: String::with_capacity(source.len()),
+            shifts: Vec::new(),
+        }
+    }
+
+    /// Appends `replacement` to the plain text for the next `source_len` bytes of the source.
+    fn push(&mut self, source_len: usize, replacement: &str) {
+        self.text.push_str(replacement);
+
+        if source_len != replacement.len() {
+            let shift = self.shifts.last().map_or(0, |(_, shift)| *shift);
+            self.shifts
+                .push((self.text.len(), shift + source_len - replacement.len()));
+        }
+    }
+
+    /// The marked-up source.
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+
+    /// The plain text with the markup replaced by whitespace.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Converts a byte span in the plain text to the byte span in the source.
+    /// Markup which was removed at the start or end of the span is not part of the source span.
+    pub fn source_span(&self, span: (usize, usize)) -> (usize, usize) {
+        let shift = |i: usize| i.checked_sub(1).map_or(0, |i| self.shifts[i].1);
+
+        let start = span.0 + shift(self.shifts.partition_point(|(index, _)| *index <= span.0));
+        let end = span.1 + shift(self.shifts.partition_point(|(index, _)| *index < span.1));
+        (start, end.max(start))
+    }
+
+    /// Tokenizes the plain text and groups the tokens by sentence like
+    /// [tokenize_sentences][Tokenizer::tokenize_sentences]. The spans of the tokens refer to the source,
+    /// `space_before` contains the whitespace the markup was replaced with.
+    pub fn tokenize(&self, tokenizer: &Tokenizer) -> Vec<Vec<owned::Token>> {
+        let mut position = Position::default();
+        let mut prev_end = 0;
+
+        tokenizer
+            .tokenize_sentences(&self.text)
+            .iter()
+            .map(|sentence| {
+                sentence
+                    .iter()
+                    .map(|token| {
+                        let mut token = token.to_owned_token();
+                        let (start, end) = self.source_span(token.byte_span);
+
+                        position.advance(&self.source[prev_end..start]);
+                        let start_position = position;
+                        position.advance(&self.source[start..end]);
+                        prev_end = end;
+
+                        token.byte_span = (start, end);
+                        token.char_span = (start_position.chars, position.chars);
+                        token.grapheme_span = (start_position.graphemes, position.graphemes);
+                        token.utf16_span = (start_position.utf16, position.utf16);
+                        token
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// How the markup of an [AnnotatedText] is treated during tokenization.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MarkupMode {
+    /// Markup is removed, the text before and after it is tokenized as if it were adjacent. Markup does not
+    /// influence sentence segmentation and a token can span markup e. g. in "Ex<i>am</i>ple".
+    #[default]
+    Gap,
+    /// Markup is replaced with a single space, so it always separates tokens.
+    Whitespace,
+}
+
+/// A document consisting of text and markup segments like LanguageTool's `AnnotatedText`.
+/// Only the text is tokenized, the spans of the tokens refer to the whole document.
+///
+/// ```
+/// use nlprule::tokenizer::markup::AnnotatedText;
+///
+/// let mut text = AnnotatedText::new();
+/// text.push_text("A ").push_markup("<b>").push_text("bold").push_markup("</b>").push_text(" word.");
+///
+/// assert_eq!(text.source(), "A <b>bold</b> word.");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AnnotatedText {
+    source: String,
+    // byte spans of the markup segments in the source
+    markup: Vec<(usize, usize)>,
+    mode: MarkupMode,
+}
+
+impl AnnotatedText {
+    /// Creates an empty annotated text. Markup is treated as a [gap][MarkupMode::Gap].
+    pub fn new() -> Self {
+        AnnotatedText::default()
+    }
+
+    /// Sets how markup is treated during tokenization.
+    pub fn markup_mode(&mut self, mode: MarkupMode) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Appends text which is tokenized.
+    pub fn push_text(&mut self, text: &str) -> &mut Self {
+        self.source.push_str(text);
+        self
+    }
+
+    /// Appends markup which is not tokenized.
+    pub fn push_markup(&mut self, markup: &str) -> &mut Self {
+        let start = self.source.len();
+        self.source.push_str(markup);
+
+        match self.markup.last_mut() {
+            // consecutive markup segments are merged so they are replaced with one space at most
+            Some(last) if last.1 == start => last.1 = self.source.len(),
+            _ => self.markup.push((start, self.source.len())),
+        }
+        self
+    }
+
+    /// The whole document including the markup.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The document with markup removed or replaced according to the [MarkupMode].
+    pub fn plain_text(&self) -> PlainText<'_> {
+        let mut plain = PlainText::empty(&self.source);
+        let replacement = match self.mode {
+            MarkupMode::Gap => "",
+            MarkupMode::Whitespace => " ",
+        };
+
+        let mut prev_end = 0;
+        for (start, end) in &self.markup {
+            let text = &self.source[prev_end..*start];
+            plain.push(text.len(), text);
+            plain.push(end - start, if start == end { "" } else { replacement });
+            prev_end = *end;
+        }
+        let text = &self.source[prev_end..];
+        plain.push(text.len(), text);
+
+        plain
+    }
+}
+
+/// How a byte of the source is replaced in the plain text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Blank {
+    Keep,
+    Space,
+    LineBreak,
+}
+
+fn blank(mask: &mut [Blank], start: usize, end: usize) {
+    for value in &mut mask[start..end] {
+        *value = Blank::Space;
+    }
+}
+
+fn overlaps(ranges: &[(usize, usize)], start: usize, end: usize) -> bool {
+    ranges.iter().any(|(s, e)| start < *e && *s < end)
+}
+
+fn markdown_ranges(source: &str, mask: &mut [Blank]) {
+    lazy_static! {
+        // headings, block quotes and list markers, possibly nested e. g. "> - item"
+        static ref BLOCK_REGEX: Regex =
+            Regex::new(r"^(?:\s*(?:#{1,6}(?=\s|$)|>|[-*+](?=\s)|\d+[.)](?=\s)))+").unwrap();
+        // horizontal rules and link reference definitions are removed entirely
+        static ref LINE_REGEX: Regex =
+            Regex::new(r"^\s*(?:(?:[-*_]\s*){3,}|\[[^\]]+\]:\s*\S+.*)$").unwrap();
+        static ref CODE_REGEX: Regex = Regex::new(r"(`+).*?\1|!\[[^\]]*\]\([^)]*\)").unwrap();
+        static ref LINK_REGEX: Regex = Regex::new(r"(\[)[^\]]*(\]\([^)]*\))").unwrap();
+        static ref INLINE_REGEX: Regex = Regex::new(
+            r"<[^>\n]+>|\*+|~~|(?<![\p{L}\p{N}])_+|_+(?![\p{L}\p{N}])|\\(?=[\p{P}\p{S}])"
+        )
+        .unwrap();
+    }
+
+    // the fence which started the current code block
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+
+    for line in source.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+
+        let content = line.trim_end_matches(['\r', '\n']);
+        let trimmed = content.trim_start();
+        let end = start + content.len();
+
+        if let Some(open) = fence {
+            blank(mask, start, end);
+            if trimmed.starts_with(open) {
+                fence = None;
+            }
+            continue;
+        }
+
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            fence = Some(&trimmed[..3]);
+            blank(mask, start, end);
+            continue;
+        }
+
+        if LINE_REGEX.find(content).is_some() {
+            blank(mask, start, end);
+            continue;
+        }
+
+        if let Some((_, block_end)) = BLOCK_REGEX.find(content) {
+            blank(mask, start, start + block_end);
+        }
+
+        // inline code and images are removed entirely, other markup must not be looked for inside them
+        let code: Vec<_> = CODE_REGEX
+            .find_iter(content)
+            .map(|(s, e)| (start + s, start + e))
+            .collect();
+        for (s, e) in &code {
+            blank(mask, *s, *e);
+        }
+
+        for captures in LINK_REGEX.captures_iter(content) {
+            let (s, e) = captures.pos(0).unwrap();
+            if overlaps(&code, start + s, start + e) {
+                continue;
+            }
+
+            for group in 1..=2 {
+                let (s, e) = captures.pos(group).unwrap();
+                blank(mask, start + s, start + e);
+            }
+        }
+
+        for (s, e) in INLINE_REGEX.find_iter(content) {
+            if !overlaps(&code, start + s, start + e) {
+                blank(mask, start + s, start + e);
+            }
+        }
+    }
+}
+
+fn html_ranges(source: &str, mask: &mut [Blank]) {
+    lazy_static! {
+        static ref CODE_REGEX: Regex =
+            Regex::new(r"(?i)<!--[\s\S]*?-->|<(code|pre|script|style)\b[^>]*>[\s\S]*?</\1\s*>")
+                .unwrap();
+        static ref TAG_REGEX: Regex = Regex::new(r"</?([a-zA-Z][a-zA-Z0-9]*)\b[^>]*>").unwrap();
+        static ref ENTITY_REGEX: Regex =
+            Regex::new(r"&(?:[a-zA-Z]+|#\d+|#x[0-9a-fA-F]+);").unwrap();
+    }
+
+    const BLOCK_TAGS: &[&str] = &[
+        "address",
+        "article",
+        "aside",
+        "blockquote",
+        "br",
+        "dd",
+        "div",
+        "dl",
+        "dt",
+        "figcaption",
+        "figure",
+        "footer",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "header",
+        "hr",
+        "li",
+        "main",
+        "nav",
+        "ol",
+        "p",
+        "section",
+        "table",
+        "td",
+        "th",
+        "tr",
+        "ul",
+    ];
+
+    let code: Vec<_> = CODE_REGEX.find_iter(source).collect();
+    for (start, end) in &code {
+        blank(mask, *start, *end);
+    }
+
+    for captures in TAG_REGEX.captures_iter(source) {
+        let (start, end) = captures.pos(0).unwrap();
+        if overlaps(&code, start, end) {
+            continue;
+        }
+
+        blank(mask, start, end);
+        let name = captures.at(1).unwrap().to_lowercase();
+        if BLOCK_TAGS.contains(&name.as_str()) {
+            mask[start] = Blank::LineBreak;
+        }
+    }
+
+    for (start, end) in ENTITY_REGEX.find_iter(source) {
+        if !overlaps(&code, start, end) {
+            blank(mask, start, end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::tag::Tagger;
+    use std::{collections::HashSet, sync::Arc};
+
+    fn tokenizer() -> Tokenizer {
+        let tagger = Tagger::from_dumps(
+            &[] as &[&str],
+            &[] as &[&str],
+            &[] as &[&str],
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        Tokenizer {
+            tagger: Arc::new(tagger),
+            ..Tokenizer::default()
+        }
+    }
+
+    fn texts(source: &str, markup: Markup) -> Vec<Vec<String>> {
+        PlainText::new(source, markup)
+            .tokenize(&tokenizer())
+            .iter()
+            .map(|sentence| {
+                sentence
+                    .iter()
+                    .filter(|x| x.byte_span.0 < x.byte_span.1)
+                    .map(|x| {
+                        assert_eq!(&source[x.byte_span.0..x.byte_span.1], x.word.text.0);
+                        x.word.text.0.clone()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn markdown_readme() {
+        let readme = "# nlprule\n\n\
+            A **fast** and _small_ [grammar checker](https://example.com/docs) written in Rust.\n\n\
+            ```rust\n\
+            let tokenizer = Tokenizer::new(\"en.bin\"); // not checked\n\
+            ```\n\n\
+            - Use `Tokenizer::new` to load a <em>binary</em>!\n\
+            > Ünïcödé ![logo](logo.png) works\n";
+
+        assert_eq!(
+            texts(readme, Markup::Markdown),
+            vec![
+                vec!["nlprule"],
+                vec![
+                    "A", "fast", "and", "small", "grammar", "checker", "written", "in", "Rust", "."
+                ],
+                vec!["Use", "to", "load", "a", "binary", "!"],
+                vec!["Ünïcödé", "works"],
+            ]
+        );
+    }
+
+    #[test]
+    fn html_fragment() {
+        let html =
+            "<p>Fish&nbsp;and <b>chips</b> are good.</p><p>Try <code>x &lt; y</code> now</p>";
+
+        assert_eq!(
+            texts(html, Markup::Html),
+            vec![
+                vec!["Fish", "and", "chips", "are", "good", "."],
+                vec!["Try", "now"]
+            ]
+        );
+    }
+
+    #[test]
+    fn spans_refer_to_source() {
+        let source = "[😀 link](https://ü.example) «**Ünïcödé**»";
+        let plain = PlainText::new(source, Markup::Markdown);
+        assert_eq!(plain.text().chars().count(), source.chars().count());
+
+        let tokens: Vec<_> = plain
+            .tokenize(&tokenizer())
+            .into_iter()
+            .flatten()
+            .filter(|x| x.byte_span.0 < x.byte_span.1)
+            .map(|x| (x.word.text.0, x.char_span, x.utf16_span))
+            .collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                ("😀".to_string(), (1, 2), (1, 3)),
+                ("link".to_string(), (3, 7), (4, 8)),
+                ("«".to_string(), (28, 29), (29, 30)),
+                ("Ünïcödé".to_string(), (31, 38), (32, 39)),
+                ("»".to_string(), (40, 41), (41, 42)),
+            ]
+        );
+    }
+
+    fn spans(text: &AnnotatedText) -> Vec<Vec<(String, (usize, usize))>> {
+        tokenizer()
+            .tokenize_annotated(text)
+            .into_iter()
+            .map(|sentence| {
+                sentence
+                    .into_iter()
+                    .filter(|x| x.byte_span.0 < x.byte_span.1)
+                    .map(|x| (x.word.text.0, x.byte_span))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn owned(tokens: &[(&str, (usize, usize))]) -> Vec<(String, (usize, usize))> {
+        tokens
+            .iter()
+            .map(|(x, span)| (x.to_string(), *span))
+            .collect()
+    }
+
+    #[test]
+    fn annotated_markup_mid_sentence() {
+        let mut text = AnnotatedText::new();
+        text.push_text("This is ")
+            .push_markup("<b>")
+            .push_text("bold")
+            .push_markup("</b>")
+            .push_text(" and ex")
+            .push_markup("<i>")
+            .push_text("am")
+            .push_markup("</i>")
+            .push_text("ple text.");
+        assert_eq!(
+            text.source(),
+            "This is <b>bold</b> and ex<i>am</i>ple text."
+        );
+
+        assert_eq!(
+            spans(&text),
+            vec![owned(&[
+                ("This", (0, 4)),
+                ("is", (5, 7)),
+                ("bold", (11, 15)),
+                ("and", (20, 23)),
+                ("example", (24, 38)),
+                ("text", (39, 43)),
+                (".", (43, 44)),
+            ])]
+        );
+
+        text.markup_mode(MarkupMode::Whitespace);
+        assert_eq!(
+            spans(&text)[0][4..7],
+            owned(&[("ex", (24, 26)), ("am", (29, 31)), ("ple", (35, 38))])[..]
+        );
+    }
+
+    #[test]
+    fn annotated_markup_between_sentences() {
+        let mut text = AnnotatedText::new();
+        text.push_text("Hello there.")
+            .push_markup("<br/>")
+            .push_text("How are you");
+
+        // without a space after the period the text is one sentence, like in plain text
+        assert_eq!(spans(&text).len(), 1);
+
+        text.markup_mode(MarkupMode::Whitespace);
+        assert_eq!(
+            spans(&text),
+            vec![
+                owned(&[("Hello", (0, 5)), ("there", (6, 11)), (".", (11, 12))]),
+                owned(&[("How", (17, 20)), ("are", (21, 24)), ("you", (25, 28))]),
+            ]
+        );
+    }
+}
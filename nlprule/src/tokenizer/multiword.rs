@@ -0,0 +1,129 @@
+//! Tagging of fixed multiword expressions like "ad hoc", "New York" or "im Allgemeinen" as units, like the
+//! `multiwords.txt` files of LanguageTool. The first token of a match gets the reading `<tag>` and the last token
+//! the reading `</tag>`, both with the expression as lemma.
+
+use super::tag::Tagger;
+use crate::types::*;
+use crate::Error;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Multiword {
+    tokens: Vec<String>,
+    text: String,
+    start_tag: owned::PosId,
+    end_tag: owned::PosId,
+}
+
+/// Finds multiword expressions in token sequences. At each position, the longest matching expression wins and
+/// matches do not overlap.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct MultiwordTagger {
+    /// Expressions by their first token, sorted by descending number of tokens.
+    expressions: DefaultHashMap<String, Vec<Multiword>>,
+}
+
+impl MultiwordTagger {
+    /// Creates a multiword tagger from pairs of expression and tag. The tokens of an expression are separated
+    /// by whitespace. The tags `<tag>` and `</tag>` must be known to the tagger, e. g. as `extra_tags`.
+    pub fn new<S1: AsRef<str>, S2: AsRef<str>>(
+        expressions: &[(S1, S2)],
+        tagger: &Tagger,
+    ) -> Result<Self, Error> {
+        let mut output: DefaultHashMap<String, Vec<Multiword>> = DefaultHashMap::default();
+
+        for (text, tag) in expressions {
+            let (text, tag) = (text.as_ref(), tag.as_ref());
+            let tokens: Vec<String> = text.split_whitespace().map(|x| x.to_string()).collect();
+
+            if tokens.is_empty() {
+                continue;
+            }
+
+            let id_tag = |tag: String| match tagger.tag_store().id(&tag) {
+                Some(pos) => Ok(pos.to_owned_id()),
+                None => Err(Error::UnknownTag(tag)),
+            };
+
+            output
+                .entry(tokens[0].clone())
+                .or_default()
+                .push(Multiword {
+                    text: tokens.join(" "),
+                    start_tag: id_tag(format!("<{}>", tag))?,
+                    end_tag: id_tag(format!("</{}>", tag))?,
+                    tokens,
+                });
+        }
+
+        for multiwords in output.values_mut() {
+            multiwords.sort_by_key(|x| std::cmp::Reverse(x.tokens.len()));
+        }
+
+        Ok(MultiwordTagger {
+            expressions: output,
+        })
+    }
+
+    /// The tags `<tag>` and `</tag>` of all tags in the `expressions`. They must be added to the tagger
+    /// before creating a [MultiwordTagger] for the expressions.
+    pub fn required_tags<S1: AsRef<str>, S2: AsRef<str>>(expressions: &[(S1, S2)]) -> Vec<String> {
+        let mut tags = Vec::new();
+
+        for (_, tag) in expressions {
+            for tag in &[
+                format!("<{}>", tag.as_ref()),
+                format!("</{}>", tag.as_ref()),
+            ] {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+        }
+
+        tags
+    }
+
+    /// Adds the readings of the expressions to the tokens. Expressions do not span multiple sentences.
+    pub fn apply<'t>(&'t self, tokens: &mut [IncompleteToken<'t>]) {
+        let mut i = 0;
+
+        while i < tokens.len() {
+            let candidates = match self.expressions.get(tokens[i].word.text.as_ref()) {
+                Some(candidates) => candidates,
+                None => {
+                    i += 1;
+                    continue;
+                }
+            };
+
+            let matched = candidates.iter().find(|multiword| {
+                let end = i + multiword.tokens.len();
+
+                end <= tokens.len()
+                    && tokens[i + 1..end].iter().all(|x| !x.is_sentence_start)
+                    && multiword
+                        .tokens
+                        .iter()
+                        .zip(&tokens[i..end])
+                        .all(|(expected, token)| token.word.text.as_ref() == expected)
+            });
+
+            match matched {
+                Some(multiword) => {
+                    let end = i + multiword.tokens.len() - 1;
+                    let tagger = tokens[i].tagger;
+                    let lemma = || tagger.id_word(multiword.text.as_str().into());
+
+                    let start_data = WordData::new(lemma(), multiword.start_tag.as_ref_id());
+                    let end_data = WordData::new(lemma(), multiword.end_tag.as_ref_id());
+                    tokens[i].word.tags.push(start_data);
+                    tokens[end].word.tags.push(end_data);
+
+                    i = end + 1;
+                }
+                None => i += 1,
+            }
+        }
+    }
+}
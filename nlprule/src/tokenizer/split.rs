@@ -0,0 +1,1284 @@
+//! Splitting of raw text into token strings.
+//! Text is split in *passes*. Each pass finds spans which become single tokens (e. g. URLs) and the text in between
+//! is handed to the next pass. The remaining text is split at whitespace and split chars (the
+//! [default set][crate::utils::splitting_chars] adjusted by the [TokenizerOptions]) or at Unicode word boundaries,
+//! depending on the [WordSegmentation].
+
+#[cfg(test)]
+use super::Pattern;
+use super::{ApostropheMode, HyphenMode, InvisibleCharMode, TokenizerOptions, WordSegmentation};
+use crate::types::TokenKind;
+use lazy_static::lazy_static;
+use onig::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A pass returns the byte spans of tokens in the given text. Spans must be sorted and must not overlap.
+type Pass = fn(&str, &TokenizerOptions) -> Vec<(usize, usize)>;
+
+// see https://stackoverflow.com/a/40296745
+fn split<F>(text: &str, split_func: F) -> Vec<&str>
+where
+    F: Fn(char) -> bool,
+{
+    let mut result = Vec::new();
+    let mut last = 0;
+    for (index, matched) in text.match_indices(split_func) {
+        if last != index {
+            result.push(&text[last..index]);
+        }
+        result.push(matched);
+        last = index + matched.len();
+    }
+    if last < text.len() {
+        result.push(&text[last..]);
+    }
+
+    result
+}
+
+/// Whether the text is split at the char. Whitespace is always split at.
+fn is_split_char(c: char, options: &TokenizerOptions) -> bool {
+    c.is_whitespace()
+        || ((crate::utils::splitting_chars().contains(c) || options.extra_split_chars.contains(c))
+            && !options.remove_split_chars.contains(c))
+}
+
+fn default_split<'t>(text: &'t str, options: &TokenizerOptions) -> Vec<&'t str> {
+    split(text, |c: char| is_split_char(c, options))
+}
+
+/// Byte spans of the tokens the default split would create.
+fn default_split_spans(
+    text: &str,
+    offset: usize,
+    options: &TokenizerOptions,
+) -> Vec<(usize, usize)> {
+    default_split(text, options)
+        .into_iter()
+        .map(|x| {
+            let start = x.as_ptr() as usize - text.as_ptr() as usize + offset;
+            (start, start + x.len())
+        })
+        .collect()
+}
+
+fn extra_patterns(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    let mut spans: Vec<_> = options
+        .extra_patterns
+        .iter()
+        .flat_map(|pattern| pattern.regex().find_iter(text))
+        .filter(|(start, end)| end > start)
+        .collect();
+    spans.sort();
+
+    // earlier and longer matches take priority over overlapping ones
+    let mut prev_end = 0;
+    spans.retain(|(start, end)| {
+        if *start >= prev_end {
+            prev_end = *end;
+            true
+        } else {
+            false
+        }
+    });
+
+    spans
+}
+
+fn emails(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        static ref EMAIL_REGEX: Regex = Regex::new(r"(?<![\p{L}\p{N}._%+\-/:])[\p{L}\p{N}_%+\-]+(?:\.[\p{L}\p{N}_%+\-]+)*@[\p{L}\p{N}](?:[\p{L}\p{N}\-]*[\p{L}\p{N}])?(?:\.[\p{L}\p{N}](?:[\p{L}\p{N}\-]*[\p{L}\p{N}])?)*\.\p{L}{2,}(?![\p{L}\p{N}])").unwrap();
+    }
+
+    if !options.detect_emails {
+        return Vec::new();
+    }
+
+    EMAIL_REGEX.find_iter(text).collect()
+}
+
+/// Removes trailing punctuation which is most likely not part of the URL or path e. g. the sentence end in "See example.com.".
+/// Closing brackets are kept if they are balanced within the URL, like in Wikipedia links.
+fn trim_trailing_punctuation(url: &str) -> &str {
+    let mut url = url;
+
+    while let Some(c) = url.chars().last() {
+        let strip = match c {
+            '.' | ',' | ':' | ';' | '!' | '?' | '\'' | '"' | '’' | '”' | '»' | '…' => true,
+            ')' => url.matches('(').count() < url.matches(')').count(),
+            ']' => url.matches('[').count() < url.matches(']').count(),
+            '}' => url.matches('{').count() < url.matches('}').count(),
+            _ => false,
+        };
+
+        if !strip {
+            break;
+        }
+        url = &url[..url.len() - c.len_utf8()];
+    }
+
+    url
+}
+
+fn trimmed_matches(text: &str, regex: &Regex) -> Vec<(usize, usize)> {
+    regex
+        .find_iter(text)
+        .map(|(start, end)| {
+            (
+                start,
+                start + trim_trailing_punctuation(&text[start..end]).len(),
+            )
+        })
+        .filter(|(start, end)| end > start)
+        .collect()
+}
+
+fn urls(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        // see https://stackoverflow.com/a/17773849
+        static ref URL_REGEX: Regex = Regex::new(r"(https?:\/\/(?:www\.|(?!www))[a-zA-Z0-9][a-zA-Z0-9-]+[a-zA-Z0-9]\.[^\s]{2,}|www\.[a-zA-Z0-9][a-zA-Z0-9-]+[a-zA-Z0-9]\.[^\s]{2,}|https?:\/\/(?:www\.|(?!www))[a-zA-Z0-9]+\.[^\s]{2,}|www\.[a-zA-Z0-9]+\.[^\s]{2,})").unwrap();
+    }
+
+    if !options.detect_urls {
+        return Vec::new();
+    }
+
+    trimmed_matches(text, &URL_REGEX)
+}
+
+/// URLs without scheme or `www.` prefix are only detected if they end in one of these top-level domains.
+/// Domains which are common file extensions (e. g. "md", "rs", "pl", "sh") are deliberately not included.
+const TOP_LEVEL_DOMAINS: &[&str] = &[
+    "com", "org", "net", "edu", "gov", "mil", "int", "info", "biz", "io", "app", "dev", "eu", "de",
+    "at", "ch", "fr", "it", "es", "nl", "be", "lu", "dk", "se", "no", "fi", "uk", "ie", "us", "ca",
+    "au", "nz", "ru", "jp", "cn", "br", "in",
+];
+
+fn domains(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        static ref DOMAIN_REGEX: Regex = Regex::new(&format!(
+            r"(?<![\p{{L}}\p{{N}}@._\-/])(?:[a-zA-Z0-9](?:[a-zA-Z0-9\-]*[a-zA-Z0-9])?\.)+(?:{})(?:/[^\s]*)?(?![\p{{L}}\p{{N}}\-])",
+            TOP_LEVEL_DOMAINS.join("|")
+        ))
+        .unwrap();
+    }
+
+    if !options.detect_urls || !options.detect_bare_domains {
+        return Vec::new();
+    }
+
+    trimmed_matches(text, &DOMAIN_REGEX)
+}
+
+fn paths(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        static ref PATH_REGEX: Regex = Regex::new(concat!(
+            // unix: relative to the home or current directory or absolute with at least two segments
+            r"(?<![\p{L}\p{N}/.~:\\])(?:(?:~|\.\.?)(?:/[\p{L}\p{N}._\-+~@%]+)+|(?:/[\p{L}\p{N}._\-+~@%]+){2,})/?",
+            // windows: drive letter followed by a backslash
+            r"|(?<![\p{L}\p{N}])[A-Za-z]:\\(?:[\p{L}\p{N}._\-+~@%$]+\\?)*"
+        ))
+        .unwrap();
+    }
+
+    if !options.detect_paths {
+        return Vec::new();
+    }
+
+    trimmed_matches(text, &PATH_REGEX)
+}
+
+fn hashtags(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        static ref HASHTAG_REGEX: Regex =
+            Regex::new(r"(?<![\p{L}\p{N}_&#@])#[\p{L}\p{N}_]*\p{L}[\p{L}\p{N}_]*").unwrap();
+    }
+
+    if !options.detect_social {
+        return Vec::new();
+    }
+
+    HASHTAG_REGEX.find_iter(text).collect()
+}
+
+fn mentions(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        static ref MENTION_REGEX: Regex =
+            Regex::new(r"(?<![\p{L}\p{N}_.#@])@[\p{L}\p{N}_]+").unwrap();
+    }
+
+    if !options.detect_social {
+        return Vec::new();
+    }
+
+    MENTION_REGEX.find_iter(text).collect()
+}
+
+const UNITS: &[&str] = &[
+    "mm", "cm", "m", "km", "mg", "g", "kg", "t", "ml", "l", "ms", "s", "min", "h", "kB", "MB",
+    "GB", "TB", "Hz", "kHz", "MHz", "GHz", "W", "kW", "kWh", "V", "A", "°C", "°F", "°", "%", "‰",
+    "€", "$", "£", "¥",
+];
+
+fn units(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        static ref UNIT_REGEX: Regex =
+            Regex::new(r"(?<![\p{L}\p{N}_.,])\d+(?:[.,]\d+)*[\p{L}\p{Sc}%‰°]+(?![\p{L}\p{N}])")
+                .unwrap();
+    }
+
+    if !options.split_units {
+        return Vec::new();
+    }
+
+    let mut spans = Vec::new();
+
+    for (start, end) in UNIT_REGEX.find_iter(text) {
+        let word = &text[start..end];
+        let split = start
+            + word
+                .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+                .unwrap();
+        let unit = &text[split..end];
+
+        if UNITS.contains(&unit) || options.units.iter().any(|x| x == unit) {
+            spans.push((start, split));
+            spans.push((split, end));
+        }
+    }
+
+    spans
+}
+
+fn dates(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        static ref DATE_REGEX: Regex = Regex::new(concat!(
+            r"(?<![\p{L}\p{N}.])(?:\d{1,2}\.\d{1,2}\.(?:\d{4}|\d{2})?",
+            r"|\d{4}-\d{2}-\d{2})(?![\p{L}\p{N}])"
+        ))
+        .unwrap();
+    }
+
+    if !options.join_dates {
+        return Vec::new();
+    }
+
+    DATE_REGEX.find_iter(text).collect()
+}
+
+fn ordinals(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        static ref ORDINAL_REGEX: Regex = Regex::new(concat!(
+            r"(?<![\p{L}\p{N}.])\d+\.(?=\s+(?:\p{Ll}|(?:Januar|Jänner|Februar|März|April|Mai|Juni|Juli|August",
+            r"|September|Oktober|November|Dezember)(?![\p{L}\p{N}])))"
+        ))
+        .unwrap();
+    }
+
+    if !options.join_ordinals {
+        return Vec::new();
+    }
+
+    ORDINAL_REGEX.find_iter(text).collect()
+}
+
+fn versions(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        static ref VERSION_REGEX: Regex =
+            Regex::new(r"(?<![\p{L}\p{N}.])[vV]?\d+(?:\.\d+)+(?![\p{L}\p{N}])").unwrap();
+    }
+
+    if !options.join_versions {
+        return Vec::new();
+    }
+
+    VERSION_REGEX.find_iter(text).collect()
+}
+
+fn numbers(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        // separators must be followed by a digit so sentence-final periods are not part of the number.
+        // hyphenated numbers like "10-20" are left to the default split.
+        static ref NUMBER_REGEX: Regex =
+            Regex::new(r"(?<![\p{L}\p{N}\-])[+\-]?\d+(?:[.,]\d+)*(?![\p{L}\p{N}\-])").unwrap();
+    }
+
+    if !options.join_numbers {
+        return Vec::new();
+    }
+
+    NUMBER_REGEX.find_iter(text).collect()
+}
+
+/// The decimal separator of numbers in the language of the options.
+fn decimal_separator(options: &TokenizerOptions) -> char {
+    match options.lang_code.as_str() {
+        "" | "en" | "ga" | "ja" | "zh" => '.',
+        _ => ',',
+    }
+}
+
+/// Normalizes a number with an optional sign and decimal or thousands separators like "-3,5" or "1.000" by removing
+/// the thousands separators and the plus sign and replacing the decimal separator with a period, so "1.000" and
+/// "3,14" become "1000" and "3.14" in German. A separator which occurs multiple times is a thousands separator.
+/// `None` if the text is not such a number, e. g. a date or an ordinal.
+pub(crate) fn normalize_number(text: &str, options: &TokenizerOptions) -> Option<String> {
+    lazy_static! {
+        static ref FULL_NUMBER_REGEX: Regex = Regex::new(r"\A[+\-]?\d+(?:[.,]\d+)*\z").unwrap();
+    }
+
+    if !FULL_NUMBER_REGEX.is_match(text) {
+        return None;
+    }
+
+    let decimal = decimal_separator(options);
+    let has_decimal = text.matches(decimal).count() == 1;
+
+    Some(
+        text.trim_start_matches('+')
+            .chars()
+            .filter_map(|c| match c {
+                '.' | ',' if c == decimal && has_decimal => Some('.'),
+                '.' | ',' => None,
+                c => Some(c),
+            })
+            .collect(),
+    )
+}
+
+fn punctuation(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        static ref PUNCTUATION_REGEX: Regex = Regex::new(r"\.{2,}|…{2,}|[?!]{2,}").unwrap();
+    }
+
+    if !options.join_punctuation {
+        return Vec::new();
+    }
+
+    PUNCTUATION_REGEX.find_iter(text).collect()
+}
+
+/// The straight quote or apostrophe for a typographic quote, prime or apostrophe-like char.
+pub(crate) fn normalize_quote(c: char) -> Option<char> {
+    match c {
+        '‘' | '’' | '‚' | '‛' | 'ʼ' | '′' | '`' | '´' => Some('\''),
+        '“' | '”' | '„' | '‟' | '″' => Some('"'),
+        _ => None,
+    }
+}
+
+/// Whether the char is an apostrophe. Typographic quotes which are used as apostrophes e. g. in "don‘t" are
+/// only apostrophes if quotes are normalized.
+fn is_apostrophe(c: char, options: &TokenizerOptions) -> bool {
+    c == '\'' || c == '’' || (options.normalize_quotes && normalize_quote(c) == Some('\''))
+}
+
+fn is_apostrophe_exception(word: &str, exceptions: &[&str], options: &TokenizerOptions) -> bool {
+    let normalize = |word: &str| -> String {
+        word.chars()
+            .map(|c| if is_apostrophe(c, options) { '\'' } else { c })
+            .collect::<String>()
+            .to_lowercase()
+    };
+    let normalized = normalize(word);
+
+    exceptions.contains(&normalized.as_str())
+        || options
+            .apostrophe_exceptions
+            .iter()
+            .any(|x| normalize(x) == normalized)
+}
+
+/// Splits an English word containing apostrophes into LT-style pieces.
+fn english_apostrophes(word: &str, options: &TokenizerOptions) -> Option<Vec<(usize, usize)>> {
+    const EXCEPTIONS: &[&str] = &["o'clock", "ma'am"];
+    const SUFFIXES: &[&str] = &["s", "re", "ve", "ll", "d", "m"];
+
+    if is_apostrophe_exception(word, EXCEPTIONS, options) {
+        return Some(vec![(0, word.len())]);
+    }
+
+    let (apostrophe, c) = word
+        .char_indices()
+        .find(|(_, c)| is_apostrophe(*c, options))?;
+    let after = apostrophe + c.len_utf8();
+    let suffix = &word[after..];
+
+    if suffix.is_empty() {
+        // trailing possessive e. g. "James'"
+        Some(vec![(0, apostrophe), (apostrophe, after)])
+    } else if suffix.eq_ignore_ascii_case("t")
+        && apostrophe > 1
+        && word[..apostrophe].to_lowercase().ends_with('n')
+    {
+        // negation e. g. "don't" -> "do" + "n't"
+        Some(vec![(0, apostrophe - 1), (apostrophe - 1, word.len())])
+    } else if SUFFIXES.iter().any(|x| suffix.eq_ignore_ascii_case(x)) && apostrophe > 0 {
+        Some(vec![(0, apostrophe), (apostrophe, word.len())])
+    } else {
+        None
+    }
+}
+
+/// Splits French elisions after the apostrophe e. g. "l'homme" -> "l'", "homme".
+fn french_apostrophes(word: &str, options: &TokenizerOptions) -> Option<Vec<(usize, usize)>> {
+    const EXCEPTIONS: &[&str] = &["aujourd'hui", "presqu'île", "prud'homme", "prud'hommes"];
+    const PREFIXES: &[&str] = &[
+        "l", "d", "j", "qu", "n", "s", "t", "m", "c", "jusqu", "lorsqu", "puisqu",
+    ];
+
+    if is_apostrophe_exception(word, EXCEPTIONS, options) {
+        return Some(vec![(0, word.len())]);
+    }
+
+    let (apostrophe, c) = word
+        .char_indices()
+        .find(|(_, c)| is_apostrophe(*c, options))?;
+    let after = apostrophe + c.len_utf8();
+    let prefix = word[..apostrophe].to_lowercase();
+
+    if after < word.len() && PREFIXES.contains(&prefix.as_str()) {
+        let mut pieces = vec![(0, after)];
+        // the rest can contain another apostrophe e. g. "qu'aujourd'hui"
+        match french_apostrophes(&word[after..], options) {
+            Some(rest) => pieces.extend(rest.into_iter().map(|(s, e)| (s + after, e + after))),
+            None if word[after..].contains(|c| is_apostrophe(c, options)) => {
+                pieces.extend(default_split_spans(&word[after..], after, options))
+            }
+            None => pieces.push((after, word.len())),
+        }
+
+        Some(pieces)
+    } else {
+        None
+    }
+}
+
+fn apostrophes(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        static ref APOSTROPHE_REGEX: Regex =
+            Regex::new(r"[\p{L}\p{N}]+(?:['’][\p{L}\p{N}]+)*['’]?").unwrap();
+        static ref NORMALIZED_APOSTROPHE_REGEX: Regex =
+            Regex::new(r"[\p{L}\p{N}]+(?:['’‘‚‛ʼ′`´][\p{L}\p{N}]+)*['’‘‚‛ʼ′`´]?").unwrap();
+    }
+
+    let mode = options
+        .apostrophes
+        .unwrap_or_else(|| ApostropheMode::for_language(&options.lang_code));
+
+    let split_word = match mode {
+        ApostropheMode::Split => return Vec::new(),
+        ApostropheMode::English => english_apostrophes,
+        ApostropheMode::French => french_apostrophes,
+    };
+
+    let mut spans = Vec::new();
+
+    let regex: &Regex = if options.normalize_quotes {
+        &NORMALIZED_APOSTROPHE_REGEX
+    } else {
+        &APOSTROPHE_REGEX
+    };
+
+    for (start, end) in regex.find_iter(text) {
+        let word = &text[start..end];
+        if !word.contains(|c| is_apostrophe(c, options)) {
+            continue;
+        }
+
+        match split_word(word, options) {
+            Some(pieces) => spans.extend(
+                pieces
+                    .into_iter()
+                    .filter(|(piece_start, piece_end)| piece_end > piece_start)
+                    .map(|(piece_start, piece_end)| (start + piece_start, start + piece_end)),
+            ),
+            None => spans.extend(default_split_spans(word, start, options)),
+        }
+    }
+
+    spans
+}
+
+fn hyphens(text: &str, options: &TokenizerOptions) -> Vec<(usize, usize)> {
+    lazy_static! {
+        static ref HYPHEN_REGEX: Regex = Regex::new(r"[\p{L}\p{N}]+(?:-[\p{L}\p{N}]+)+").unwrap();
+    }
+
+    if options.hyphens != HyphenMode::Split {
+        return Vec::new();
+    }
+
+    HYPHEN_REGEX
+        .find_iter(text)
+        .flat_map(|(start, end)| {
+            let word = &text[start..end];
+            split(word, |c| c == '-').into_iter().map(move |x| {
+                let piece_start = x.as_ptr() as usize - word.as_ptr() as usize + start;
+                (piece_start, piece_start + x.len())
+            })
+        })
+        .collect()
+}
+
+// emails come before URLs because the URL regex matches parts of the domain
+const PASSES: &[(Pass, TokenKind)] = &[
+    (extra_patterns, TokenKind::Word),
+    (emails, TokenKind::Email),
+    (urls, TokenKind::Url),
+    (domains, TokenKind::Url),
+    (paths, TokenKind::Path),
+    (hashtags, TokenKind::Hashtag),
+    (mentions, TokenKind::Mention),
+    (units, TokenKind::Word),
+    (dates, TokenKind::Number),
+    (ordinals, TokenKind::Number),
+    (versions, TokenKind::Word),
+    (numbers, TokenKind::Number),
+    (punctuation, TokenKind::Punctuation),
+    (apostrophes, TokenKind::Word),
+    (hyphens, TokenKind::Word),
+];
+
+/// The kind of a regular token string i. e. one which is not e. g. a URL.
+fn default_kind(text: &str) -> TokenKind {
+    if text.chars().all(|c| c.is_numeric()) {
+        TokenKind::Number
+    } else if text
+        .chars()
+        .all(|c| !c.is_alphanumeric() && !c.is_whitespace())
+    {
+        TokenKind::Punctuation
+    } else {
+        TokenKind::Word
+    }
+}
+
+fn apply_passes<'t>(
+    text: &'t str,
+    passes: &[(Pass, TokenKind)],
+    options: &TokenizerOptions,
+    tokens: &mut Vec<(&'t str, TokenKind)>,
+) {
+    if let Some(((pass, kind), rest)) = passes.split_first() {
+        let mut prev = 0;
+
+        for (start, end) in pass(text, options) {
+            apply_passes(&text[prev..start], rest, options, tokens);
+            let token = &text[start..end];
+            // passes for regular tokens can create numbers and punctuation e. g. when splitting units
+            let kind = if *kind == TokenKind::Word {
+                default_kind(token)
+            } else {
+                *kind
+            };
+
+            tokens.push((token, kind));
+            prev = end;
+        }
+
+        apply_passes(&text[prev..], rest, options, tokens);
+    } else {
+        let words = match options.word_segmentation {
+            WordSegmentation::Split => default_split(text, options),
+            // word bounds cover the entire text including whitespace, which is trimmed from the tokens later
+            WordSegmentation::UnicodeWords => text.split_word_bounds().collect(),
+        };
+        tokens.extend(words.into_iter().map(|x| (x, default_kind(x))));
+    }
+}
+
+/// Whether the char is invisible in rendered text and can occur within words, like soft hyphens and zero-width spaces.
+pub(crate) fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{ad}' | '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{feff}'
+    )
+}
+
+/// Splits the token strings at invisible chars according to the [InvisibleCharMode]. The invisible chars become
+/// token strings of their own.
+fn split_invisible<'t>(
+    tokens: Vec<(&'t str, TokenKind)>,
+    options: &TokenizerOptions,
+) -> Vec<(&'t str, TokenKind)> {
+    let is_separator: fn(char) -> bool = match options.invisible_chars {
+        InvisibleCharMode::Keep => return tokens,
+        // zero-width spaces separate words, the other invisible chars are removed when looking up tags
+        InvisibleCharMode::Strip => |c| c == '\u{200b}',
+        InvisibleCharMode::Split => is_invisible,
+    };
+
+    tokens
+        .into_iter()
+        .flat_map(|(x, kind)| {
+            split(x, is_separator)
+                .into_iter()
+                .map(move |piece| (piece, kind))
+        })
+        .collect()
+}
+
+/// Splits the text into token strings and their kind. The token strings cover the entire text, including whitespace.
+pub(crate) fn get_token_strs<'t>(
+    text: &'t str,
+    options: &TokenizerOptions,
+) -> Vec<(&'t str, TokenKind)> {
+    let mut tokens = Vec::new();
+    apply_passes(text, PASSES, options, &mut tokens);
+
+    split_invisible(tokens, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens<'t>(text: &'t str, options: &TokenizerOptions) -> Vec<&'t str> {
+        get_token_strs(text, options)
+            .into_iter()
+            .map(|(x, _)| x)
+            .filter(|x| !x.trim().is_empty())
+            .collect()
+    }
+
+    fn kinds<'t>(text: &'t str, options: &TokenizerOptions) -> Vec<(&'t str, TokenKind)> {
+        get_token_strs(text, options)
+            .into_iter()
+            .filter(|(x, _)| !x.trim().is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn splits_apostrophes_by_default() {
+        let options = TokenizerOptions::default();
+
+        assert_eq!(tokens("don't", &options), vec!["don", "'", "t"]);
+    }
+
+    #[test]
+    fn english_contractions() {
+        let options = TokenizerOptions {
+            apostrophes: Some(ApostropheMode::English),
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(
+            tokens("I don't know.", &options),
+            vec!["I", "do", "n't", "know", "."]
+        );
+        assert_eq!(tokens("It’s here", &options), vec!["It", "’s", "here"]);
+        assert_eq!(tokens("we're", &options), vec!["we", "'re"]);
+        assert_eq!(tokens("at 5 o'clock", &options), vec!["at", "5", "o'clock"]);
+        assert_eq!(tokens("O’clock", &options), vec!["O’clock"]);
+        assert_eq!(tokens("James' car", &options), vec!["James", "'", "car"]);
+        assert_eq!(
+            tokens("rock'n'roll", &options),
+            vec!["rock", "'", "n", "'", "roll"]
+        );
+        assert_eq!(tokens("'quoted'", &options), vec!["'", "quoted", "'"]);
+    }
+
+    #[test]
+    fn apostrophes_for_language() {
+        let options = TokenizerOptions {
+            lang_code: "en".into(),
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(tokens("don't", &options), vec!["do", "n't"]);
+
+        let options = TokenizerOptions {
+            lang_code: "en".into(),
+            apostrophes: Some(ApostropheMode::Split),
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(tokens("don't", &options), vec!["don", "'", "t"]);
+
+        let options = TokenizerOptions {
+            lang_code: "fr".into(),
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(tokens("l'homme", &options), vec!["l'", "homme"]);
+    }
+
+    #[test]
+    fn french_elision() {
+        let options = TokenizerOptions {
+            apostrophes: Some(ApostropheMode::French),
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(tokens("l'homme", &options), vec!["l'", "homme"]);
+        assert_eq!(tokens("L’homme", &options), vec!["L’", "homme"]);
+        assert_eq!(tokens("jusqu'à", &options), vec!["jusqu'", "à"]);
+        assert_eq!(
+            tokens("qu'aujourd'hui", &options),
+            vec!["qu'", "aujourd'hui"]
+        );
+        assert_eq!(tokens("Aujourd’hui", &options), vec!["Aujourd’hui"]);
+        assert_eq!(tokens("presqu'île", &options), vec!["presqu'île"]);
+        assert_eq!(tokens("entr'acte", &options), vec!["entr", "'", "acte"]);
+        assert_eq!(tokens("l'", &options), vec!["l", "'"]);
+    }
+
+    #[test]
+    fn joins_numbers() {
+        let options = TokenizerOptions {
+            join_numbers: true,
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(tokens("1 and 42", &options), vec!["1", "and", "42"]);
+        assert_eq!(tokens("pi is 3.14", &options), vec!["pi", "is", "3.14"]);
+        assert_eq!(
+            tokens("3,14 und 1.000.000", &options),
+            vec!["3,14", "und", "1.000.000"]
+        );
+        assert_eq!(
+            tokens("1,000,000 people", &options),
+            vec!["1,000,000", "people"]
+        );
+        assert_eq!(
+            tokens("It costs 3.50.", &options),
+            vec!["It", "costs", "3.50", "."]
+        );
+        assert_eq!(
+            tokens("1, 2, 3.", &options),
+            vec!["1", ",", "2", ",", "3", "."]
+        );
+        assert_eq!(tokens("-5 or +2.5", &options), vec!["-5", "or", "+2.5"]);
+        assert_eq!(tokens("3,5% mehr", &options), vec!["3,5", "%", "mehr"]);
+        assert_eq!(tokens("pages 10-20", &options), vec!["pages", "10-20"]);
+        assert_eq!(tokens("B2.5", &options), vec!["B2", ".", "5"]);
+
+        let options = TokenizerOptions::default();
+        assert_eq!(tokens("3.14", &options), vec!["3", ".", "14"]);
+    }
+
+    #[test]
+    fn joins_versions() {
+        let options = TokenizerOptions {
+            join_versions: true,
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(
+            tokens("Update nlprule 0.3.1 or v2.4 now.", &options),
+            vec!["Update", "nlprule", "0.3.1", "or", "v2.4", "now", "."]
+        );
+        assert_eq!(
+            tokens("Ping 10.0.0.1, then 192.168.1.254.", &options),
+            vec!["Ping", "10.0.0.1", ",", "then", "192.168.1.254", "."]
+        );
+        assert_eq!(
+            tokens("It uses version 2.4.", &options),
+            vec!["It", "uses", "version", "2.4", "."]
+        );
+        assert_eq!(
+            tokens("It costs 3 dollars.", &options),
+            vec!["It", "costs", "3", "dollars", "."]
+        );
+        assert_eq!(tokens("dev2.4", &options), vec!["dev2", ".", "4"]);
+    }
+
+    #[test]
+    fn joins_dates_and_ordinals() {
+        let mut options = TokenizerOptions::default();
+        assert_eq!(
+            tokens("Am 3.10.2021 um 3.", &options),
+            vec!["Am", "3", ".", "10", ".", "2021", "um", "3", "."]
+        );
+
+        options.join_dates = true;
+        options.join_ordinals = true;
+        assert_eq!(
+            kinds("Am 3. Oktober 2021 und am 3.10. bzw. 2021-10-03.", &options),
+            vec![
+                ("Am", TokenKind::Word),
+                ("3.", TokenKind::Number),
+                ("Oktober", TokenKind::Word),
+                ("2021", TokenKind::Number),
+                ("und", TokenKind::Word),
+                ("am", TokenKind::Word),
+                ("3.10.", TokenKind::Number),
+                ("bzw", TokenKind::Word),
+                (".", TokenKind::Punctuation),
+                ("2021-10-03", TokenKind::Number),
+                (".", TokenKind::Punctuation),
+            ]
+        );
+        assert_eq!(
+            tokens("Am 3.10.2021.", &options),
+            vec!["Am", "3.10.2021", "."]
+        );
+        assert_eq!(
+            tokens("Siehe Kapitel 3. Das ist gut.", &options),
+            vec!["Siehe", "Kapitel", "3", ".", "Das", "ist", "gut", "."]
+        );
+        assert_eq!(
+            tokens("Siehe Kapitel 3.", &options),
+            vec!["Siehe", "Kapitel", "3", "."]
+        );
+        assert_eq!(
+            tokens("der 2. dieser", &options),
+            vec!["der", "2.", "dieser"]
+        );
+        assert_eq!(
+            tokens("Version 1.2.3", &options),
+            vec!["Version", "1", ".", "2", ".", "3"]
+        );
+    }
+
+    #[test]
+    fn splits_units() {
+        let mut options = TokenizerOptions::default();
+        assert_eq!(tokens("10km", &options), vec!["10km"]);
+
+        options.split_units = true;
+        assert_eq!(
+            get_token_strs("Run 10km for 100€ in 24h.", &options)
+                .into_iter()
+                .map(|(x, _)| x)
+                .collect::<Vec<_>>(),
+            vec![
+                "Run", " ", "10", "km", " ", "for", " ", "100", "€", " ", "in", " ", "24", "h", "."
+            ]
+        );
+        assert_eq!(
+            tokens("It is 2,5kg", &options),
+            vec!["It", "is", "2,5", "kg"]
+        );
+        assert_eq!(
+            tokens("A4 3D mp3 10x", &options),
+            vec!["A4", "3D", "mp3", "10x"]
+        );
+
+        options.units.push("x".into());
+        assert_eq!(tokens("10x", &options), vec!["10", "x"]);
+    }
+
+    #[test]
+    fn joins_punctuation() {
+        let options = TokenizerOptions {
+            join_punctuation: true,
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(
+            tokens("Wait... what?!", &options),
+            vec!["Wait", "...", "what", "?!"]
+        );
+        assert_eq!(tokens("Really!!!", &options), vec!["Really", "!!!"]);
+        assert_eq!(tokens("And then…", &options), vec!["And", "then", "…"]);
+        assert_eq!(
+            tokens("Yes. No! Why?", &options),
+            vec!["Yes", ".", "No", "!", "Why", "?"]
+        );
+        assert_eq!(
+            tokens("See www.example.com... now", &options),
+            vec!["See", "www.example.com", "...", "now"]
+        );
+
+        let options = TokenizerOptions::default();
+        assert_eq!(tokens("Wait...", &options), vec!["Wait", ".", ".", "."]);
+    }
+
+    #[test]
+    fn detects_emails() {
+        let options = TokenizerOptions::default();
+
+        assert_eq!(
+            kinds("Mail foo.bar@example.co.uk.", &options),
+            vec![
+                ("Mail", TokenKind::Word),
+                ("foo.bar@example.co.uk", TokenKind::Email),
+                (".", TokenKind::Punctuation)
+            ]
+        );
+        assert_eq!(
+            kinds("foo+tag@bar.de, or not", &options),
+            vec![
+                ("foo+tag@bar.de", TokenKind::Email),
+                (",", TokenKind::Punctuation),
+                ("or", TokenKind::Word),
+                ("not", TokenKind::Word)
+            ]
+        );
+        assert_eq!(
+            kinds("https://example.com/a@b.com", &options),
+            vec![("https://example.com/a@b.com", TokenKind::Url)]
+        );
+        assert_eq!(
+            kinds("See www.example.com", &options),
+            vec![
+                ("See", TokenKind::Word),
+                ("www.example.com", TokenKind::Url)
+            ]
+        );
+        assert_eq!(tokens("an @ sign", &options), vec!["an", "@", "sign"]);
+    }
+
+    #[test]
+    fn detects_urls() {
+        let options = TokenizerOptions {
+            detect_bare_domains: true,
+            ..TokenizerOptions::default()
+        };
+
+        for url in &[
+            "example.com",
+            "github.com/bminixhofer/nlprule",
+            "www.example.de",
+            "https://foo.bar/baz?q=1",
+            "http://localhost.rs",
+        ] {
+            assert_eq!(kinds(url, &options), vec![(*url, TokenKind::Url)]);
+        }
+
+        assert_eq!(
+            tokens("Das ist z.B. so", &options),
+            vec!["Das", "ist", "z", ".", "B", ".", "so"]
+        );
+        assert_eq!(
+            tokens("e.g. this", &options),
+            vec!["e", ".", "g", ".", "this"]
+        );
+        assert_eq!(
+            tokens("i.e. that", &options),
+            vec!["i", ".", "e", ".", "that"]
+        );
+        assert_eq!(
+            tokens("see readme.md", &options),
+            vec!["see", "readme", ".", "md"]
+        );
+        assert_eq!(
+            tokens("in main.rs", &options),
+            vec!["in", "main", ".", "rs"]
+        );
+        assert_eq!(tokens("U.S.", &options), vec!["U", ".", "S", "."]);
+        assert_eq!(tokens("ends.Com", &options), vec!["ends", ".", "Com"]);
+    }
+
+    #[test]
+    fn bare_domains_are_opt_in() {
+        let options = TokenizerOptions::default();
+
+        assert_eq!(
+            tokens("I was home.at night", &options),
+            vec!["I", "was", "home", ".", "at", "night"]
+        );
+        assert_eq!(
+            tokens("see example.com", &options),
+            vec!["see", "example", ".", "com"]
+        );
+        assert_eq!(
+            kinds("www.example.com", &options),
+            vec![("www.example.com", TokenKind::Url)]
+        );
+        assert_eq!(
+            kinds("https://home.at", &options),
+            vec![("https://home.at", TokenKind::Url)]
+        );
+    }
+
+    #[test]
+    fn strips_punctuation_from_urls() {
+        let options = TokenizerOptions {
+            detect_bare_domains: true,
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(
+            kinds("visit http://a.com.", &options),
+            vec![
+                ("visit", TokenKind::Word),
+                ("http://a.com", TokenKind::Url),
+                (".", TokenKind::Punctuation)
+            ]
+        );
+        assert_eq!(
+            tokens("(docs at https://x.org/y),", &options),
+            vec!["(", "docs", "at", "https://x.org/y", ")", ","]
+        );
+        assert_eq!(
+            tokens("(see example.com)", &options),
+            vec!["(", "see", "example.com", ")"]
+        );
+        assert_eq!(
+            tokens(
+                "See https://en.wikipedia.org/wiki/Rust_(programming_language).",
+                &options
+            ),
+            vec![
+                "See",
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+                "."
+            ]
+        );
+        assert_eq!(
+            tokens("\"www.example.com/?q=1\"!", &options),
+            vec!["\"", "www.example.com/?q=1", "\"", "!"]
+        );
+    }
+
+    #[test]
+    fn configurable_detection() {
+        let mut options = TokenizerOptions {
+            detect_urls: false,
+            detect_bare_domains: true,
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(tokens("example.com", &options), vec!["example", ".", "com"]);
+        assert_eq!(
+            tokens("https://example.com", &options),
+            vec!["https", ":", "/", "/", "example", ".", "com"]
+        );
+        assert_eq!(
+            kinds("a@b.de", &options),
+            vec![("a@b.de", TokenKind::Email)]
+        );
+
+        options.detect_emails = false;
+        assert_eq!(tokens("a@b.de", &options), vec!["a@b", ".", "de"]);
+
+        options.extra_patterns = vec![
+            Pattern::new(r"[A-Z]+-\d+").unwrap(),
+            Pattern::new(r":\w+:").unwrap(),
+        ];
+        assert_eq!(
+            tokens("Fixed in JIRA-123 :tada:.", &options),
+            vec!["Fixed", "in", "JIRA-123", ":tada:", "."]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "compile")]
+    fn options_deserialize_with_defaults() {
+        let options: TokenizerOptions = serde_json::from_str(
+            r##"{
+                "allow_errors": false,
+                "retain_last": false,
+                "use_compound_split_heuristic": false,
+                "always_add_lower_tags": false,
+                "extra_patterns": ["#\\d+"]
+            }"##,
+        )
+        .unwrap();
+
+        assert!(options.detect_urls && options.detect_emails);
+        assert_eq!(options.extra_patterns[0].as_str(), r"#\d+");
+        assert_eq!(tokens("see #12", &options), vec!["see", "#12"]);
+    }
+
+    #[test]
+    fn detects_paths() {
+        let options = TokenizerOptions {
+            detect_paths: true,
+            ..TokenizerOptions::default()
+        };
+
+        for path in &[
+            "/usr/local/bin/nlprule",
+            "./data/dumps/en",
+            "../README.md",
+            "~/.bashrc",
+            "/etc/nginx/",
+            r"C:\Users\me\file.txt",
+            r"D:\",
+        ] {
+            assert_eq!(kinds(path, &options), vec![(*path, TokenKind::Path)]);
+        }
+
+        assert_eq!(
+            kinds("Edit /etc/nginx/nginx.conf.", &options),
+            vec![
+                ("Edit", TokenKind::Word),
+                ("/etc/nginx/nginx.conf", TokenKind::Path),
+                (".", TokenKind::Punctuation)
+            ]
+        );
+        assert_eq!(
+            tokens(r"Open C:\Users\me\file.txt, then close it.", &options),
+            vec![
+                "Open",
+                r"C:\Users\me\file.txt",
+                ",",
+                "then",
+                "close",
+                "it",
+                "."
+            ]
+        );
+        assert_eq!(tokens("and/or", &options), vec!["and", "/", "or"]);
+        assert_eq!(tokens("km/h", &options), vec!["km", "/", "h"]);
+        assert_eq!(tokens("a / b / c", &options), vec!["a", "/", "b", "/", "c"]);
+
+        let options = TokenizerOptions::default();
+        assert_eq!(tokens("/usr/bin", &options), vec!["/", "usr", "/", "bin"]);
+    }
+
+    #[test]
+    fn detects_hashtags_and_mentions() {
+        let mut options = TokenizerOptions {
+            detect_social: true,
+            ..TokenizerOptions::default()
+        };
+
+        assert_eq!(
+            kinds("Great #rustlang!", &options),
+            vec![
+                ("Great", TokenKind::Word),
+                ("#rustlang", TokenKind::Hashtag),
+                ("!", TokenKind::Punctuation)
+            ]
+        );
+        assert_eq!(
+            kinds("Thanks @user_1, see #größe.", &options),
+            vec![
+                ("Thanks", TokenKind::Word),
+                ("@user_1", TokenKind::Mention),
+                (",", TokenKind::Punctuation),
+                ("see", TokenKind::Word),
+                ("#größe", TokenKind::Hashtag),
+                (".", TokenKind::Punctuation)
+            ]
+        );
+        assert_eq!(
+            tokens("We're #1", &options),
+            vec!["We", "'", "re", "#", "1"]
+        );
+        assert_eq!(tokens("C#", &options), vec!["C", "#"]);
+        assert_eq!(
+            kinds("foo@example.com", &options),
+            vec![("foo@example.com", TokenKind::Email)]
+        );
+
+        options.detect_social = false;
+        assert_eq!(
+            tokens("Great #rustlang @user", &options),
+            vec!["Great", "#", "rustlang", "@user"]
+        );
+    }
+
+    #[test]
+    fn hyphen_modes() {
+        let mut options = TokenizerOptions::default();
+        assert_eq!(tokens("An e-mail.", &options), vec!["An", "e-mail", "."]);
+        assert_eq!(
+            tokens("state-of-the-art", &options),
+            vec!["state-of-the-art"]
+        );
+
+        options.hyphens = HyphenMode::Both;
+        assert_eq!(tokens("An e-mail.", &options), vec!["An", "e-mail", "."]);
+
+        options.hyphens = HyphenMode::Split;
+        assert_eq!(
+            tokens("An e-mail.", &options),
+            vec!["An", "e", "-", "mail", "."]
+        );
+        assert_eq!(
+            tokens("state-of-the-art", &options),
+            vec!["state", "-", "of", "-", "the", "-", "art"]
+        );
+        assert_eq!(
+            tokens("E-Mail-Adresse", &options),
+            vec!["E", "-", "Mail", "-", "Adresse"]
+        );
+        assert_eq!(tokens("a - b", &options), vec!["a", "-", "b"]);
+    }
+
+    fn tokens_concat(text: &str, options: &TokenizerOptions) -> String {
+        get_token_strs(text, options)
+            .into_iter()
+            .map(|(x, _)| x)
+            .collect()
+    }
+
+    #[test]
+    fn token_strs_cover_text() {
+        let mut options = TokenizerOptions {
+            apostrophes: Some(ApostropheMode::English),
+            ..TokenizerOptions::default()
+        };
+        let text = "They’ve said: \"don't\" – see https://example.com/a'b or mail me@x.org.";
+
+        assert_eq!(tokens_concat(text, &options), text);
+
+        options.apostrophes = Some(ApostropheMode::French);
+        let text = "Qu’il l'ait vu aujourd'hui, jusqu'à présent.";
+
+        assert_eq!(tokens_concat(text, &options), text);
+    }
+
+    #[test]
+    fn unicode_word_segmentation() {
+        let options = TokenizerOptions {
+            word_segmentation: WordSegmentation::UnicodeWords,
+            ..TokenizerOptions::default()
+        };
+        let text = "詳細は https://example.com を見て、コーヒーを2杯。";
+
+        assert_eq!(
+            kinds(text, &options),
+            vec![
+                ("詳", TokenKind::Word),
+                ("細", TokenKind::Word),
+                ("は", TokenKind::Word),
+                ("https://example.com", TokenKind::Url),
+                ("を", TokenKind::Word),
+                ("見", TokenKind::Word),
+                ("て", TokenKind::Word),
+                ("、", TokenKind::Punctuation),
+                ("コーヒー", TokenKind::Word),
+                ("を", TokenKind::Word),
+                ("2", TokenKind::Number),
+                ("杯", TokenKind::Word),
+                ("。", TokenKind::Punctuation),
+            ]
+        );
+        assert_eq!(tokens_concat(text, &options), text);
+    }
+
+    #[test]
+    fn configurable_split_chars() {
+        let text = "La col·lecció d'art & ciència, 50 km/h.";
+
+        assert_eq!(
+            tokens(text, &TokenizerOptions::default()),
+            vec![
+                "La",
+                "col·lecció",
+                "d",
+                "'",
+                "art",
+                "&",
+                "ciència",
+                ",",
+                "50",
+                "km",
+                "/",
+                "h",
+                "."
+            ]
+        );
+
+        let catalan = TokenizerOptions {
+            lang_code: "ca".into(),
+            extra_split_chars: "·".into(),
+            remove_split_chars: "/".into(),
+            ..TokenizerOptions::default()
+        };
+        assert_eq!(
+            tokens(text, &catalan),
+            vec![
+                "La", "col", "·", "lecció", "d", "'", "art", "&", "ciència", ",", "50", "km/h", "."
+            ]
+        );
+        assert_eq!(tokens_concat(text, &catalan), text);
+    }
+}
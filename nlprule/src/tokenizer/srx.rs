@@ -0,0 +1,130 @@
+//! Sentence segmentation with [SRX](https://www.unicode.org/uli/pas/srx/srx20.html) rules like the ones
+//! LanguageTool uses (`segment.srx`). Can be used by the tokenizer instead of Unicode sentence segmentation.
+
+use super::Pattern;
+use crate::Error;
+use onig::SearchOptions;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A rule deciding whether there is a sentence boundary at a position.
+/// Matches if the text before the position ends with a match of `before` and the text after the position
+/// starts with a match of `after`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SrxRule {
+    is_break: bool,
+    before: Pattern,
+    after: Pattern,
+}
+
+impl SrxRule {
+    pub fn new(is_break: bool, before: &str, after: &str) -> Result<Self, Error> {
+        Ok(SrxRule {
+            is_break,
+            before: Pattern::new(before)?,
+            after: Pattern::new(after)?,
+        })
+    }
+
+    /// Whether a match of this rule means that there is a sentence boundary (`break="yes"` in SRX).
+    pub fn is_break(&self) -> bool {
+        self.is_break
+    }
+
+    pub fn before(&self) -> &Pattern {
+        &self.before
+    }
+
+    pub fn after(&self) -> &Pattern {
+        &self.after
+    }
+}
+
+/// Splits text into sentences with SRX rules.
+/// The first matching rule at a position decides whether there is a boundary, there is no boundary if no rule matches.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SrxSegmenter {
+    rules: Vec<SrxRule>,
+}
+
+impl SrxSegmenter {
+    /// Creates a new segmenter from the rules, ordered by priority.
+    pub fn new(rules: Vec<SrxRule>) -> Self {
+        SrxSegmenter { rules }
+    }
+
+    pub fn rules(&self) -> &[SrxRule] {
+        &self.rules
+    }
+
+    /// Byte offsets of the sentence boundaries in the text, sorted and excluding the start and end of the text.
+    pub fn breaks(&self, text: &str) -> Vec<usize> {
+        let mut decisions: HashMap<usize, bool> = HashMap::new();
+
+        for rule in &self.rules {
+            for (_, end) in rule.before.regex().find_iter(text) {
+                if end == 0 || end >= text.len() || decisions.contains_key(&end) {
+                    continue;
+                }
+
+                if rule
+                    .after
+                    .regex()
+                    .match_with_options(text, end, SearchOptions::SEARCH_OPTION_NONE, None)
+                    .is_some()
+                {
+                    decisions.insert(end, rule.is_break);
+                }
+            }
+        }
+
+        let mut breaks: Vec<_> = decisions
+            .into_iter()
+            .filter_map(|(index, is_break)| if is_break { Some(index) } else { None })
+            .collect();
+        breaks.sort_unstable();
+        breaks
+    }
+
+    /// Splits the text into sentences. The sentences cover the entire text, including whitespace.
+    pub fn segment<'t>(&self, text: &'t str) -> Vec<&'t str> {
+        let mut prev = 0;
+        let mut sentences = Vec::new();
+
+        for index in self.breaks(text) {
+            sentences.push(&text[prev..index]);
+            prev = index;
+        }
+
+        if prev < text.len() {
+            sentences.push(&text[prev..]);
+        }
+
+        sentences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let segmenter = SrxSegmenter::new(vec![
+            SrxRule::new(false, r"\b(?:Dr|z\.\s?B)\.", r"\s").unwrap(),
+            SrxRule::new(false, r"\bz\.", r"\s?B\.").unwrap(),
+            SrxRule::new(false, r"\.\.\.", r"\s+\p{Ll}").unwrap(),
+            SrxRule::new(true, r"[.?!]+[»“\x22]?", r"\s").unwrap(),
+        ]);
+
+        assert_eq!(
+            segmenter.segment("Wir treffen Dr. Müller... oder so. Ist das z. B. gut? Ja."),
+            vec![
+                "Wir treffen Dr. Müller... oder so.",
+                " Ist das z. B. gut?",
+                " Ja."
+            ]
+        );
+        assert_eq!(segmenter.segment(""), Vec::<&str>::new());
+    }
+}
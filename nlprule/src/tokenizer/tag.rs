@@ -1,181 +1,1869 @@
 //! A dictionary-based tagger. The raw format is tuples of the form `(word, lemma, part-of-speech)`
 //! where each word typically has multiple entries with different part-of-speech tags.
 
-use crate::types::*;
+use crate::{types::*, Error};
+#[cfg(not(feature = "fst"))]
 use bimap::BiMap;
 use indexmap::IndexMap;
-use serde::{Deserialize, Serialize};
+use log::warn;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashSet;
-use std::io::BufRead;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+#[cfg(feature = "mmap")]
+use std::sync::Arc;
+use std::sync::RwLock;
 use std::{borrow::Cow, fs::File};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+mod binary;
+mod bk_tree;
+mod intern;
+mod morfologik;
+mod validate;
+
+pub use validate::{IssueKind, Severity, ValidationIssue};
+
+/// Identifies a binary written by [Tagger::to_writer].
+const BINARY_MAGIC: &[u8; 4] = b"NLPT";
+/// The version of the binary format of the tagger. Must be incremented whenever the serialized fields change.
+const BINARY_VERSION: u32 = 6;
+
+/// The magic bytes at the start of gzip-compressed dumps.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// The magic bytes at the start of zstd-compressed dumps.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// The 64-bit FNV-1a hash of the data, stable across platforms and Rust versions.
+fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Language-specific rules for converting text to lowercase.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum Casing {
+    /// The default Unicode case mapping of [str::to_lowercase].
+    #[default]
+    Default,
+    /// Turkish and Azerbaijani: "I" is the uppercase of dotless "ı" and "İ" the uppercase of "i", so "ISPARTA"
+    /// becomes "ısparta" and "İstanbul" becomes "istanbul" instead of "i\u{307}stanbul" with a combining dot.
+    Turkic,
+    /// Greek: a sigma which ends a word is lowercased to the final form "ς" and one within a word to "σ", so
+    /// uppercase words like "ΟΔΟΣ" match the dictionary entry "οδος". Unlike [str::to_lowercase] this also
+    /// corrects lowercase text with a non-final "σ" at the end of a word.
+    Greek,
+}
+
+impl Casing {
+    /// The casing rules for a language code like "en" or "tr".
+    pub fn for_language(lang_code: &str) -> Self {
+        match lang_code {
+            "tr" | "az" => Casing::Turkic,
+            "el" => Casing::Greek,
+            _ => Casing::Default,
+        }
+    }
+
+    /// Converts the text to lowercase.
+    pub fn lowercase(&self, text: &str) -> String {
+        match self {
+            Casing::Default => text.to_lowercase(),
+            Casing::Turkic => text.replace('I', "ı").replace('İ', "i").to_lowercase(),
+            Casing::Greek => {
+                let lower = text.to_lowercase();
+                let mut output = String::with_capacity(lower.len());
+                let mut chars = lower.chars().peekable();
+                let mut prev_alphabetic = false;
+
+                while let Some(c) = chars.next() {
+                    let next_alphabetic = chars.peek().map_or(false, |x| x.is_alphabetic());
+
+                    match c {
+                        'σ' | 'ς' if prev_alphabetic && !next_alphabetic => output.push('ς'),
+                        'ς' => output.push('σ'),
+                        _ => output.push(c),
+                    }
+                    prev_alphabetic = c.is_alphabetic();
+                }
+
+                output
+            }
+        }
+    }
+}
+
+/// How words are folded for the diacritics-insensitive fallback lookup, see [Tagger::with_folding].
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Folding {
+    /// Removes diacritics, so "resume" finds "résumé".
+    Diacritics,
+    /// Removes diacritics like [Folding::Diacritics] and also matches the two-letter spellings of umlauts and "ß"
+    /// which are used if they can not be typed, so both "uber" and "ueber" find "über" and "Strasse" finds "Straße".
+    German,
+}
+
+impl Folding {
+    /// The folding for a language code like "en" or "de".
+    pub fn for_language(lang_code: &str) -> Self {
+        match lang_code {
+            "de" => Folding::German,
+            _ => Folding::Diacritics,
+        }
+    }
+
+    /// Removes combining marks from the canonical decomposition of the text.
+    fn strip(text: &str) -> String {
+        text.nfd().filter(|c| !is_combining_mark(*c)).collect()
+    }
+
+    /// The keys under which a lowercase dictionary word can be found.
+    fn keys(self, lower: &str) -> Vec<String> {
+        let mut keys = vec![Folding::strip(lower)];
+
+        if self == Folding::German {
+            let expanded = Folding::strip(
+                &lower
+                    .replace('ä', "ae")
+                    .replace('ö', "oe")
+                    .replace('ü', "ue")
+                    .replace('ß', "ss"),
+            );
+
+            if !keys.contains(&expanded) {
+                keys.push(expanded);
+            }
+        }
+
+        keys
+    }
+}
+
+/// The words known to the tagger and their IDs. The IDs are the indices of the words in sorted order.
+/// Stored in a [BiMap] or, with the `fst` feature, in a finite-state transducer mapping words to IDs plus one string
+/// with all words, which needs considerably less memory for large dictionaries.
+/// Serialized as a map from words to IDs regardless of the backend, so binaries can be loaded with both.
+pub struct WordStore {
+    #[cfg(not(feature = "fst"))]
+    words: BiMap<String, u32>,
+    #[cfg(feature = "fst")]
+    ids: fst::Map<Vec<u8>>,
+    // all words concatenated in the order of their IDs
+    #[cfg(feature = "fst")]
+    text: String,
+    // the end of each word in `text`, indexed by ID
+    #[cfg(feature = "fst")]
+    ends: Vec<u32>,
+    // the words of a memory-mapped binary, used instead of the fields above if set
+    #[cfg(feature = "mmap")]
+    mapped: Option<Arc<binary::Mapped>>,
+    // built on the first search for similar words which can not use the transducer
+    similar_index: RwLock<Option<bk_tree::BkTree>>,
+}
+
+impl WordStore {
+    /// Creates a store from words and their IDs. The IDs must be `0..words.len()`.
+    fn new(mut words: Vec<(String, u32)>) -> Self {
+        words.sort_by_key(|(_, id)| *id);
+        debug_assert!(words
+            .iter()
+            .enumerate()
+            .all(|(i, (_, id))| i == *id as usize));
+
+        #[cfg(not(feature = "fst"))]
+        {
+            WordStore {
+                words: words.into_iter().collect(),
+                #[cfg(feature = "mmap")]
+                mapped: None,
+                similar_index: RwLock::default(),
+            }
+        }
+
+        #[cfg(feature = "fst")]
+        {
+            let mut text = String::new();
+            let mut ends = Vec::with_capacity(words.len());
+            for (word, _) in words.iter() {
+                text.push_str(word);
+                ends.push(text.len() as u32);
+            }
+
+            // the keys of the transducer must be inserted in lexicographic order
+            words.sort_unstable_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+            let ids = fst::Map::from_iter(words.iter().map(|(word, id)| (word, *id as u64)))
+                .expect("words are sorted and unique");
+
+            WordStore {
+                ids,
+                text,
+                ends,
+                #[cfg(feature = "mmap")]
+                mapped: None,
+                similar_index: RwLock::default(),
+            }
+        }
+    }
+
+    /// Creates a store which reads the words from a memory-mapped binary.
+    #[cfg(feature = "mmap")]
+    fn mapped(mapped: Arc<binary::Mapped>) -> Self {
+        let mut store = WordStore::new(Vec::new());
+        store.mapped = Some(mapped);
+        store
+    }
+
+    /// Whether the words are read from a memory-mapped binary.
+    fn is_mapped(&self) -> bool {
+        #[cfg(feature = "mmap")]
+        {
+            self.mapped.is_some()
+        }
+
+        #[cfg(not(feature = "mmap"))]
+        {
+            false
+        }
+    }
+
+    /// The ID of the word.
+    pub fn id(&self, word: &str) -> Option<u32> {
+        #[cfg(feature = "mmap")]
+        {
+            if let Some(mapped) = &self.mapped {
+                return mapped.sections().id(word);
+            }
+        }
+
+        #[cfg(not(feature = "fst"))]
+        {
+            self.words.get_by_left(word).copied()
+        }
+
+        #[cfg(feature = "fst")]
+        {
+            self.ids.get(word).map(|id| id as u32)
+        }
+    }
+
+    /// The word with the ID.
+    pub fn word(&self, id: u32) -> Option<&str> {
+        #[cfg(feature = "mmap")]
+        {
+            if let Some(mapped) = &self.mapped {
+                return mapped.sections().word(id);
+            }
+        }
+
+        #[cfg(not(feature = "fst"))]
+        {
+            self.words.get_by_right(&id).map(|x| x.as_str())
+        }
+
+        #[cfg(feature = "fst")]
+        {
+            let id = id as usize;
+            let end = *self.ends.get(id)? as usize;
+            let start = if id == 0 {
+                0
+            } else {
+                self.ends[id - 1] as usize
+            };
+
+            Some(&self.text[start..end])
+        }
+    }
+
+    /// The words and their IDs in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> {
+        #[cfg(not(any(feature = "fst", feature = "mmap")))]
+        {
+            self.words.iter().map(|(word, id)| (word.as_str(), *id))
+        }
+
+        #[cfg(any(feature = "fst", feature = "mmap"))]
+        {
+            (0..self.len() as u32).map(move |id| (self.word(id).unwrap(), id))
+        }
+    }
+
+    /// The number of words.
+    pub fn len(&self) -> usize {
+        #[cfg(feature = "mmap")]
+        {
+            if let Some(mapped) = &self.mapped {
+                return mapped.sections().len();
+            }
+        }
+
+        #[cfg(not(feature = "fst"))]
+        {
+            self.words.len()
+        }
+
+        #[cfg(feature = "fst")]
+        {
+            self.ends.len()
+        }
+    }
+
+    /// Whether the store contains no words.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The words within the Levenshtein distance `max_distance` of `word` and their distance, in arbitrary order.
+    /// With the `fst` feature the transducer is intersected with a Levenshtein automaton. Otherwise a BK-tree of all
+    /// words is searched, which is built on the first call and needs about 64 bytes per word.
+    pub fn similar(&self, word: &str, max_distance: usize) -> Vec<(&str, usize)> {
+        #[cfg(feature = "fst")]
+        {
+            use fst::{automaton::Levenshtein, IntoStreamer, Streamer};
+
+            // the automaton can get too large for long words and large distances, then the BK-tree is used
+            if let (false, Ok(automaton)) = (
+                self.is_mapped(),
+                Levenshtein::new(word, max_distance as u32),
+            ) {
+                let mut output = Vec::new();
+                let mut stream = self.ids.search(automaton).into_stream();
+
+                while let Some((_, id)) = stream.next() {
+                    let candidate = self.word(id as u32).unwrap();
+                    if let Some(distance) =
+                        crate::utils::edit_distance(word, candidate, max_distance)
+                    {
+                        output.push((candidate, distance));
+                    }
+                }
+
+                return output;
+            }
+        }
+
+        let lookup = |id| self.word(id).expect("IDs of the tree are in the store");
+
+        if self.similar_index.read().unwrap().is_none() {
+            let mut index = self.similar_index.write().unwrap();
+            // another thread can have built the tree in the meantime
+            if index.is_none() {
+                *index = Some(bk_tree::BkTree::new(0..self.len() as u32, lookup));
+            }
+        }
+
+        self.similar_index
+            .read()
+            .unwrap()
+            .as_ref()
+            .expect("tree is built above")
+            .find(word, max_distance, lookup)
+            .into_iter()
+            .map(|(id, distance)| (lookup(id), distance))
+            .collect()
+    }
+
+    /// An estimate of the heap memory used by the store in bytes.
+    fn heap_bytes(&self) -> usize {
+        let similar_index = self
+            .similar_index
+            .read()
+            .unwrap()
+            .as_ref()
+            .map_or(0, |x| x.heap_bytes());
+
+        // the pages of a memory-mapped binary are not on the heap
+        if self.is_mapped() {
+            return similar_index;
+        }
+
+        #[cfg(not(feature = "fst"))]
+        {
+            // both directions are hash tables of pointers to reference-counted words and IDs
+            let rc = 2 * std::mem::size_of::<usize>();
+            let words: usize = self
+                .words
+                .iter()
+                .map(|(word, _)| rc + std::mem::size_of::<String>() + word.capacity())
+                .sum();
+            let ids = self.len() * (rc + std::mem::size_of::<usize>());
+
+            words + ids + 2 * table_bytes::<usize, usize>(self.len()) + similar_index
+        }
+
+        #[cfg(feature = "fst")]
+        {
+            self.ids.as_fst().as_bytes().len()
+                + self.text.capacity()
+                + self.ends.capacity() * std::mem::size_of::<u32>()
+                + similar_index
+        }
+    }
+}
+
+/// An estimate of the heap memory of the table of a hash map with the given capacity in bytes, without the heap
+/// memory of the keys and values. The table has about 8/7 buckets per element and a control byte per bucket.
+fn table_bytes<K, V>(capacity: usize) -> usize {
+    if capacity == 0 {
+        0
+    } else {
+        let buckets = (capacity * 8 / 7).next_power_of_two();
+        buckets * (std::mem::size_of::<(K, V)>() + 1) + 16
+    }
+}
+
+/// Statistics of a tagger, see [Tagger::stats].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaggerStats {
+    /// The number of distinct words with at least one reading.
+    pub words: usize,
+    /// The number of readings, i. e. distinct (word, lemma, tag) entries.
+    pub readings: usize,
+    /// The number of distinct lemmas.
+    pub lemmas: usize,
+    /// The number of distinct tags, including the special tags like SENT_START.
+    pub tags: usize,
+    /// An estimate of the heap memory used by the tagger in bytes. Accounts for the strings, the vectors and the
+    /// tables of the hash maps, but not for allocator overhead.
+    pub heap_bytes: usize,
+}
+
+impl std::fmt::Display for TaggerStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "words:     {}", self.words)?;
+        writeln!(f, "readings:  {}", self.readings)?;
+        writeln!(f, "lemmas:    {}", self.lemmas)?;
+        writeln!(f, "tags:      {}", self.tags)?;
+        write!(
+            f,
+            "heap size: {:.2} MiB ({} bytes, estimated)",
+            self.heap_bytes as f64 / (1024. * 1024.),
+            self.heap_bytes
+        )
+    }
+}
+
+impl Default for WordStore {
+    fn default() -> Self {
+        WordStore::new(Vec::new())
+    }
+}
+
+impl Serialize for WordStore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for WordStore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let words: DefaultHashMap<String, u32> = Deserialize::deserialize(deserializer)?;
+        Ok(WordStore::new(words.into_iter().collect()))
+    }
+}
+
+/// The special tags in the order of their fixed IDs.
+const SPECIAL_TAGS: [PosId<'static>; 4] = [
+    PosId::EMPTY,
+    PosId::SENT_START,
+    PosId::SENT_END,
+    PosId::UNKNOWN,
+];
+
+/// Interns the distinct part-of-speech tags of a tagger as [PosId]s. The special tags have the fixed IDs of
+/// [PosId::EMPTY], [PosId::SENT_START], [PosId::SENT_END] and [PosId::UNKNOWN], the other tags are sorted so
+/// that the IDs are consistent across runs.
+#[derive(Debug, Clone)]
+pub struct TagStore {
+    tags: Vec<String>,
+    ids: DefaultHashMap<String, u16>,
+}
+
+impl TagStore {
+    fn new<'a>(tags: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut tags: Vec<_> = tags
+            .into_iter()
+            .filter(|x| !SPECIAL_TAGS.iter().any(|special| special.0 == *x))
+            .collect();
+        tags.sort_unstable();
+        tags.dedup();
+
+        TagStore::from_tags(
+            SPECIAL_TAGS
+                .iter()
+                .map(|x| x.0)
+                .chain(tags)
+                .map(|x| x.to_string())
+                .collect(),
+        )
+    }
+
+    fn from_tags(tags: Vec<String>) -> Self {
+        let ids = tags
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x.clone(), i as u16))
+            .collect();
+
+        TagStore { tags, ids }
+    }
+
+    /// The ID of the tag, if the tag is known.
+    pub fn id(&self, tag: &str) -> Option<PosId<'_>> {
+        self.ids
+            .get_key_value(tag)
+            .map(|(tag, id)| PosId(tag.as_str(), *id))
+    }
+
+    /// The tag with the ID, if the ID is known.
+    pub fn as_str(&self, id: u16) -> Option<&str> {
+        self.tags.get(id as usize).map(|x| x.as_str())
+    }
+
+    fn pos(&self, id: u16) -> PosId<'_> {
+        PosId(self.tags[id as usize].as_str(), id)
+    }
+
+    /// The tags ordered by their IDs.
+    pub fn iter(&self) -> impl Iterator<Item = PosId<'_>> {
+        self.tags
+            .iter()
+            .enumerate()
+            .map(|(i, x)| PosId(x.as_str(), i as u16))
+    }
+
+    /// The number of tags, including the special tags.
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Whether the store contains no tags. Never true since the special tags are always included.
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
+
+impl TagStore {
+    /// An estimate of the heap memory used by the store in bytes.
+    fn heap_bytes(&self) -> usize {
+        let strings: usize = self.tags.iter().map(|x| x.capacity()).sum();
+
+        // each tag is stored twice, in the vector and as key of the map
+        2 * strings
+            + self.tags.capacity() * std::mem::size_of::<String>()
+            + table_bytes::<String, u16>(self.ids.capacity())
+    }
+}
+
+impl Default for TagStore {
+    fn default() -> Self {
+        TagStore::new(std::iter::empty())
+    }
+}
+
+impl Serialize for TagStore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.tags.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TagStore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tags: Vec<String> = Deserialize::deserialize(deserializer)?;
+
+        if SPECIAL_TAGS
+            .iter()
+            .enumerate()
+            .any(|(i, special)| tags.get(i).map(|x| x.as_str()) != Some(special.0))
+        {
+            return Err(serde::de::Error::custom(
+                "the special tags must have their fixed IDs",
+            ));
+        }
+
+        Ok(TagStore::from_tags(tags))
+    }
+}
+
+/// Human-readable descriptions of part-of-speech tags like "Verb, 3. Person Singular, Präsens" for
+/// "VER:3:SIN:PRÄ", see [Tagger::load_tagset]. Tags without a description are not an error, so partial
+/// tagsets can be used.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TagSet {
+    descriptions: DefaultHashMap<String, String>,
+}
+
+impl TagSet {
+    /// Reads a tagset with one tag and its description separated by a tab per line. Lines starting with `#` are
+    /// comments. If a tag occurs multiple times, the last description wins. Lines without a tab are skipped
+    /// with a warning.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, Error> {
+        let mut descriptions = DefaultHashMap::default();
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '\t');
+            match (parts.next(), parts.next()) {
+                (Some(tag), Some(description)) if !tag.trim().is_empty() => {
+                    descriptions.insert(tag.trim().to_string(), description.trim().to_string());
+                }
+                _ => warn!("skipping malformed tagset line {}: {:?}", i + 1, line),
+            }
+        }
+
+        Ok(TagSet { descriptions })
+    }
+
+    /// The description of the tag, if the tagset has one.
+    pub fn describe(&self, tag: &str) -> Option<&str> {
+        self.descriptions.get(tag).map(|x| x.as_str())
+    }
+
+    /// The number of described tags.
+    pub fn len(&self) -> usize {
+        self.descriptions.len()
+    }
+
+    /// Whether the tagset describes no tags.
+    pub fn is_empty(&self) -> bool {
+        self.descriptions.is_empty()
+    }
+}
+
+/// How the readings of a word were found, see [Tagger::get_tags_with_lookup].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lookup {
+    /// The word was found with its exact case.
+    Exact,
+    /// The lowercase variant of a title- or uppercase word was found.
+    Lowercase,
+    /// The titlecased variant of a lowercase word was found.
+    Titlecase,
+    /// A suffix of the word was found by the compound splitting heuristic.
+    Compound,
+    /// A word which only differs in diacritics was found by the fallback of [Tagger::with_folding].
+    Approximate,
+}
+
+fn default_min_part_length() -> usize {
+    3
+}
+
+fn default_linking_elements() -> Vec<String> {
+    vec!["s".into(), "en".into()]
+}
+
+fn default_head_tag_prefix() -> String {
+    "SUB".into()
+}
+
+/// Configuration of the compound splitting fallback for languages with productive compounds like German,
+/// see [Tagger::get_compound_tags].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CompoundSplitting {
+    /// The minimum number of chars of each part of a compound.
+    #[serde(default = "default_min_part_length")]
+    pub min_part_length: usize,
+    /// Linking elements which may follow a part which is not the head e. g. the "s" in "Verbindungsfehler".
+    #[serde(default = "default_linking_elements")]
+    pub linking_elements: Vec<String>,
+    /// The prefix of the tags of nouns. Only nouns are used as the head of a compound.
+    #[serde(default = "default_head_tag_prefix")]
+    pub head_tag_prefix: String,
+}
+
+impl Default for CompoundSplitting {
+    fn default() -> Self {
+        CompoundSplitting {
+            min_part_length: default_min_part_length(),
+            linking_elements: default_linking_elements(),
+            head_tag_prefix: default_head_tag_prefix(),
+        }
+    }
+}
+
+/// Lists of items by word ID, stored contiguously in one vector. Most words have only a few readings, so this needs
+/// much less memory than a vector (or map) per word and keeps the items of a word next to each other.
+#[derive(Serialize, Deserialize, Default)]
+struct Arena<T> {
+    /// The end of the items of each word in `items`, indexed by ID. The items of a word start at the end of the
+    /// items of the previous ID. IDs after the last word with items are not stored.
+    ends: Vec<u32>,
+    items: Vec<T>,
+}
+
+impl<T> Arena<T> {
+    /// Creates an arena from the lists of items of each word.
+    fn new<I: IntoIterator<Item = T>>(lists: impl IntoIterator<Item = (u32, I)>) -> Self {
+        let mut lists: Vec<_> = lists.into_iter().collect();
+        lists.sort_by_key(|(id, _)| *id);
+
+        let mut ends = Vec::new();
+        let mut items = Vec::new();
+        for (id, list) in lists {
+            // words without a list in between have no items
+            ends.resize(id as usize, items.len() as u32);
+            items.extend(list);
+            ends.push(items.len() as u32);
+        }
+
+        // trailing words without items do not need to be stored
+        while ends.len() > 1 && ends[ends.len() - 2] == ends[ends.len() - 1] {
+            ends.pop();
+        }
+        if ends == [0] {
+            ends.clear();
+        }
+
+        ends.shrink_to_fit();
+        items.shrink_to_fit();
+
+        Arena { ends, items }
+    }
+
+    /// The items of the word with the ID, empty if the word has none.
+    fn get(&self, id: u32) -> &[T] {
+        let id = id as usize;
+        match self.ends.get(id) {
+            Some(end) => {
+                let start = if id == 0 { 0 } else { self.ends[id - 1] };
+                &self.items[start as usize..*end as usize]
+            }
+            None => &[],
+        }
+    }
+
+    /// The IDs of the words with items, in ascending order.
+    fn ids(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.ends.len() as u32).filter(move |id| !self.get(*id).is_empty())
+    }
+
+    /// An estimate of the heap memory used by the arena in bytes.
+    fn heap_bytes(&self) -> usize {
+        self.ends.capacity() * std::mem::size_of::<u32>()
+            + self.items.capacity() * std::mem::size_of::<T>()
+    }
+}
+
+/// Readings of a word changed at runtime, see [Tagger::add_word] and [Tagger::remove_word].
+#[derive(Debug, Clone, Default)]
+struct CustomWord {
+    /// Pairs of lemma and tag id.
+    readings: Vec<(String, u16)>,
+    /// Whether the readings from the dictionary are removed.
+    removed: bool,
+}
 
 /// The lexical tagger.
+/// A reader of dump lines, labeled with the path it was opened from if it has one.
+type DumpSource<R> = (Option<String>, R);
+
 #[derive(Serialize, Deserialize, Default)]
 pub struct Tagger {
-    tags: DefaultHashMap<u32, IndexMap<u32, Vec<u16>>>,
-    tag_store: BiMap<String, u16>,
-    word_store: BiMap<String, u32>,
-    groups: DefaultHashMap<u32, Vec<u32>>,
+    /// The readings of each word as pairs of lemma ID and tag ID, ordered by the first occurrence of the lemma
+    /// for the word, then by the first occurrence of the tag for the lemma.
+    tags: Arena<(u32, u16)>,
+    tag_store: TagStore,
+    word_store: WordStore,
+    /// The IDs of the words with each lemma.
+    groups: Arena<u32>,
+    #[serde(default)]
+    casing: Casing,
+    /// Occurrences of words in a corpus, see [Tagger::load_frequencies].
+    #[serde(default)]
+    frequencies: DefaultHashMap<String, u32>,
+    #[serde(default)]
+    frequency_total: u64,
+    /// The folding for the diacritics-insensitive fallback, see [Tagger::with_folding].
+    #[serde(default)]
+    folding: Option<Folding>,
+    /// The IDs of the words in the dictionary by their folded lowercase variants.
+    #[serde(default)]
+    folded: DefaultHashMap<String, Vec<u32>>,
+    /// Descriptions of the tags, see [Tagger::load_tagset].
+    #[serde(default)]
+    tagset: TagSet,
+    /// The dictionary of a tagger read with `Tagger::from_mmap`, used instead of `tags` and `groups` if set.
+    #[cfg(feature = "mmap")]
+    #[serde(skip_deserializing, serialize_with = "binary::serialize_mapped")]
+    mapped: Option<Arc<binary::Mapped>>,
+    /// Words changed at runtime. Consulted before the dictionary and not serialized.
+    #[serde(skip)]
+    custom_words: RwLock<DefaultHashMap<String, CustomWord>>,
 }
 
-impl Tagger {
-    fn get_lines<S1: AsRef<str>, S2: AsRef<str>>(
-        paths: &[S1],
-        remove_paths: &[S2],
-    ) -> std::io::Result<Vec<(String, String, String)>> {
-        let mut output = Vec::new();
-        let mut disallowed: Vec<String> = Vec::new();
+/// Entries to remove from the dumps, read from files like `removed.txt`.
+#[derive(Default)]
+struct Removals {
+    /// Pairs of lemma and tag by word. A `*` as lemma or tag matches all lemmas or tags, respectively.
+    entries: DefaultHashMap<String, Vec<(String, String)>>,
+}
+
+impl Removals {
+    fn read<R: BufRead>(sources: impl IntoIterator<Item = DumpSource<R>>) -> Result<Self, Error> {
+        let mut removals = Removals::default();
+
+        for (path, reader) in sources {
+            for line in reader.lines() {
+                let line = line.map_err(|source| match path.clone() {
+                    Some(path) => Error::DumpUnreadable { path, source },
+                    None => Error::Io(source),
+                })?;
+                if line.trim().is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                if let [word, lemma, tag, ..] = line.split('\t').collect::<Vec<_>>().as_slice() {
+                    removals
+                        .entries
+                        .entry(word.to_string())
+                        .or_default()
+                        .push((lemma.to_string(), tag.to_string()));
+                }
+            }
+        }
+
+        Ok(removals)
+    }
+
+    fn contains(&self, word: &str, lemma: &str, tag: &str) -> bool {
+        let matches = |x: &str, pattern: &str| pattern == "*" || x == pattern;
+
+        self.entries.get(word).map_or(false, |entries| {
+            entries
+                .iter()
+                .any(|(x, y)| matches(lemma, x) && matches(tag, y))
+        })
+    }
+}
+
+/// The files in a directory of dumps, see [Tagger::from_dump_dir].
+struct DumpDir {
+    dumps: Vec<String>,
+    removed: Vec<String>,
+    added: Vec<String>,
+    overrides: Vec<String>,
+}
 
-        for path in remove_paths {
-            let file = File::open(path.as_ref())?;
-            let reader = std::io::BufReader::new(file);
+impl DumpDir {
+    fn read(dir: &Path) -> Result<Self, Error> {
+        let unreadable = |source| Error::DumpUnreadable {
+            path: dir.to_string_lossy().into_owned(),
+            source,
+        };
 
-            for line in reader.lines() {
-                let line = line?;
-                if line.starts_with('#') {
-                    continue;
-                }
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(unreadable)? {
+            let path = entry.map_err(unreadable)?.path();
+            let name = path.file_name().and_then(|x| x.to_str()).unwrap_or("");
 
-                disallowed.push(line.to_string());
+            if path.is_file() && !name.starts_with('.') {
+                paths.push(path.to_string_lossy().into_owned());
             }
         }
 
-        for path in paths {
-            let file = File::open(path.as_ref())?;
-            let reader = std::io::BufReader::new(file);
+        let overlay = |name: &str| {
+            let path = dir.join(name).to_string_lossy().into_owned();
+            paths
+                .iter()
+                .filter(|x| **x == path)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let removed = overlay("removed.txt");
+        let added = overlay("added.txt");
+        let overrides = overlay("overrides.txt");
+        let dumps: Vec<_> = paths
+            .iter()
+            .filter(|x| !removed.contains(x) && !added.contains(x) && !overrides.contains(x))
+            .cloned()
+            .collect();
 
-            for line in reader.lines() {
-                let line = line?;
-                if line.starts_with('#') {
+        Ok(DumpDir {
+            dumps,
+            removed,
+            added,
+            overrides,
+        })
+    }
+}
+
+impl Tagger {
+    /// Reads the lines of the dumps into `lines`. Each source is labeled with its path, if it has one, to report
+    /// read errors. Blank lines and comments are skipped. Lines with less than three fields are skipped with a warning or,
+    /// if `strict` is true, reported as [Error::MalformedDumpLine].
+    /// A `*` as lemma or tag of a line to remove matches all lemmas or tags, respectively.
+    fn get_lines<R1: BufRead, R2: BufRead>(
+        sources: impl IntoIterator<Item = DumpSource<R1>>,
+        remove_sources: impl IntoIterator<Item = DumpSource<R2>>,
+        strict: bool,
+        lines: &mut intern::Lines,
+    ) -> Result<(), Error> {
+        let removals = Removals::read(remove_sources)?;
+
+        let unreadable = |path: &Option<String>| {
+            let path = path.clone();
+            move |source| match path {
+                Some(path) => Error::DumpUnreadable { path, source },
+                None => Error::Io(source),
+            }
+        };
+
+        for (i, (path, reader)) in sources.into_iter().enumerate() {
+            for (j, line) in reader.lines().enumerate() {
+                let line = line.map_err(unreadable(&path))?;
+                if line.trim().is_empty() || line.starts_with('#') {
                     continue;
                 }
 
                 let parts: Vec<_> = line.split('\t').collect();
 
-                if disallowed.contains(&line) {
+                if parts.len() < 3 {
+                    let path = path.clone().unwrap_or_else(|| format!("<reader {}>", i));
+
+                    if strict {
+                        return Err(Error::MalformedDumpLine {
+                            path,
+                            line: j + 1,
+                            content: line,
+                        });
+                    }
+
+                    warn!(
+                        "skipping malformed line {} of dump file {:?}: {:?}",
+                        j + 1,
+                        path,
+                        line
+                    );
                     continue;
                 }
 
-                let word = parts[0].to_string();
-                let inflection = parts[1].to_string();
-                let tag = parts[2].to_string();
+                if removals.contains(parts[0], parts[1], parts[2]) {
+                    continue;
+                }
 
-                output.push((word, inflection, tag))
+                lines.push(parts[0], parts[1], parts[2]);
             }
         }
 
-        Ok(output)
+        Ok(())
+    }
+
+    /// Opens a dump file. Gzip-compressed files (and zstd-compressed files with the `zstd` feature)
+    /// are detected by their magic bytes and decompressed while reading.
+    fn open_dump(path: &str) -> std::io::Result<Box<dyn BufRead>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let magic = reader.fill_buf()?;
+
+        Ok(if magic.starts_with(&GZIP_MAGIC) {
+            Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader)))
+        } else if magic.starts_with(&ZSTD_MAGIC) {
+            #[cfg(feature = "zstd")]
+            {
+                Box::new(BufReader::new(zstd::stream::read::Decoder::with_buffer(
+                    reader,
+                )?))
+            }
+            #[cfg(not(feature = "zstd"))]
+            {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "zstd-compressed dumps require the `zstd` feature",
+                ));
+            }
+        } else {
+            Box::new(reader)
+        })
+    }
+
+    /// Opens the files at the given paths, labeling each reader with its path.
+    /// The files are sorted by their file name (then by their full path) so the order of the tags does not
+    /// depend on the order in which the paths are given.
+    fn open_dumps<S: AsRef<str>>(paths: &[S]) -> Result<Vec<DumpSource<Box<dyn BufRead>>>, Error> {
+        let mut paths: Vec<&str> = paths.iter().map(|x| x.as_ref()).collect();
+        paths.sort_by_key(|x| (Path::new(*x).file_name(), *x));
+
+        paths
+            .into_iter()
+            .map(|path| {
+                let path = path.to_string();
+                match Tagger::open_dump(&path) {
+                    Ok(reader) => Ok((Some(path), reader)),
+                    Err(source) => Err(Error::DumpUnreadable { path, source }),
+                }
+            })
+            .collect()
     }
 
-    /// Creates a tagger from raw files.
+    /// Creates a tagger from raw files. See [from_readers][Tagger::from_readers] to read the data from other sources.
+    /// The files can be gzip-compressed (or zstd-compressed with the `zstd` feature) and are read in the order
+    /// of their file names.
     ///
     /// # Arguments
     /// * `paths`: Paths to files where each line contains the word, lemma and tag, respectively,
-    /// separated by tabs, to be added to the tagger.
+    ///   separated by tabs, to be added to the tagger.
     /// * `remove_paths`: Paths to files where each line contains the word, lemma and tag, respectively,
-    /// separated by tabs, to be removed from the tagger if present in the files from `paths`.
+    ///   separated by tabs, to be removed from the tagger if present in the files from `paths`.
+    ///
+    /// Lines with less than three fields are skipped with a warning, see [from_dumps_strict][Tagger::from_dumps_strict]
+    /// to fail on them instead.
     pub fn from_dumps<S1: AsRef<str>, S2: AsRef<str>, S3: AsRef<str>>(
         paths: &[S1],
         remove_paths: &[S2],
         extra_tags: &[S3],
         common_words: &HashSet<String>,
-    ) -> std::io::Result<Self> {
-        let mut tags = DefaultHashMap::default();
-        let mut groups = DefaultHashMap::default();
+    ) -> Result<Self, Error> {
+        let mut lines = intern::Lines::default();
+        Tagger::get_lines(
+            Tagger::open_dumps(paths)?,
+            Tagger::open_dumps(remove_paths)?,
+            false,
+            &mut lines,
+        )?;
+
+        Ok(Tagger::from_lines(lines, extra_tags, common_words))
+    }
+
+    /// Creates a tagger from raw files like [from_dumps][Tagger::from_dumps], but fails with
+    /// [Error::MalformedDumpLine] on the first line with less than three fields.
+    pub fn from_dumps_strict<S1: AsRef<str>, S2: AsRef<str>, S3: AsRef<str>>(
+        paths: &[S1],
+        remove_paths: &[S2],
+        extra_tags: &[S3],
+        common_words: &HashSet<String>,
+    ) -> Result<Self, Error> {
+        let mut lines = intern::Lines::default();
+        Tagger::get_lines(
+            Tagger::open_dumps(paths)?,
+            Tagger::open_dumps(remove_paths)?,
+            true,
+            &mut lines,
+        )?;
+
+        Ok(Tagger::from_lines(lines, extra_tags, common_words))
+    }
+
+    /// Creates a tagger from a directory of dumps in the layout used by LanguageTool. All files in the directory
+    /// are read as dumps, except for the optional overlays `removed.txt`, `added.txt` and `overrides.txt`:
+    /// entries in `removed.txt` are removed after all dumps are merged, then the entries in `added.txt` are added.
+    /// A `*` as lemma or tag in `removed.txt` removes all readings with any lemma or tag, respectively.
+    ///
+    /// `overrides.txt` is applied last. It has the format of the dumps, the readings of each word in it replace
+    /// all other readings of the word. A line with only a word clears the readings of the word given so far,
+    /// so a word-only line on its own removes the word.
+    pub fn from_dump_dir<P: AsRef<Path>, S: AsRef<str>>(
+        dir: P,
+        extra_tags: &[S],
+        common_words: &HashSet<String>,
+    ) -> Result<Self, Error> {
+        Tagger::from_dump_dirs(&[dir], extra_tags, common_words)
+    }
+
+    /// Creates a tagger from multiple directories of dumps like [from_dump_dir][Tagger::from_dump_dir], e. g. the
+    /// LanguageTool dumps and a directory with a custom dictionary. The directories are merged in order: the dumps
+    /// of each directory are added to the entries of the previous ones, then its `removed.txt` is applied to all
+    /// entries so far, so later directories can shadow entries of earlier ones, then its `added.txt` is added.
+    /// Entries which occur in multiple directories are only added once. The `overrides.txt` of all directories
+    /// are applied in order after all other entries are merged.
+    pub fn from_dump_dirs<P: AsRef<Path>, S: AsRef<str>>(
+        dirs: &[P],
+        extra_tags: &[S],
+        common_words: &HashSet<String>,
+    ) -> Result<Self, Error> {
+        let mut lines = intern::Lines::default();
+        let mut overrides: IndexMap<String, Vec<(String, String)>> = IndexMap::new();
+
+        for dir in dirs {
+            let dir = DumpDir::read(dir.as_ref())?;
+
+            Tagger::get_lines(
+                Tagger::open_dumps(&dir.dumps)?,
+                std::iter::empty::<DumpSource<&[u8]>>(),
+                false,
+                &mut lines,
+            )?;
+
+            let removals = Removals::read(Tagger::open_dumps(&dir.removed)?)?;
+            lines.retain(|word, lemma, tag| !removals.contains(word, lemma, tag));
+
+            Tagger::get_lines(
+                Tagger::open_dumps(&dir.added)?,
+                std::iter::empty::<DumpSource<&[u8]>>(),
+                false,
+                &mut lines,
+            )?;
+
+            for (path, reader) in Tagger::open_dumps(&dir.overrides)? {
+                Tagger::read_overrides(path, reader, &mut overrides)?;
+            }
+        }
+
+        lines.retain(|word, _, _| !overrides.contains_key(word));
+        for (word, readings) in overrides {
+            for (lemma, tag) in readings {
+                lines.push(&word, &lemma, &tag);
+            }
+        }
+
+        Ok(Tagger::from_lines(lines, extra_tags, common_words))
+    }
+
+    /// Reads the readings of an `overrides.txt` into `overrides`, see [from_dump_dir][Tagger::from_dump_dir].
+    fn read_overrides<R: BufRead>(
+        path: Option<String>,
+        reader: R,
+        overrides: &mut IndexMap<String, Vec<(String, String)>>,
+    ) -> Result<(), Error> {
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.map_err(|source| match path.clone() {
+                Some(path) => Error::DumpUnreadable { path, source },
+                None => Error::Io(source),
+            })?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match line.split('\t').collect::<Vec<_>>().as_slice() {
+                [word] => {
+                    overrides.insert(word.to_string(), Vec::new());
+                }
+                [word, lemma, tag, ..] => {
+                    let readings = overrides.entry(word.to_string()).or_default();
+                    let reading = (lemma.to_string(), tag.to_string());
+
+                    if !readings.contains(&reading) {
+                        readings.push(reading);
+                    }
+                }
+                _ => warn!(
+                    "skipping malformed line {} of overrides {:?}: {:?}",
+                    i + 1,
+                    path,
+                    line
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a tagger from data in the format of the dumps read from arbitrary sources, e. g. bytes embedded
+    /// with `include_bytes!` wrapped in a [Cursor][std::io::Cursor].
+    ///
+    /// # Arguments
+    /// * `readers`: Readers where each line contains the word, lemma and tag, respectively,
+    ///   separated by tabs, to be added to the tagger.
+    /// * `remove_readers`: Readers where each line contains the word, lemma and tag, respectively,
+    ///   separated by tabs, to be removed from the tagger if present in `readers`.
+    pub fn from_readers<R1: BufRead, R2: BufRead, S: AsRef<str>>(
+        readers: impl IntoIterator<Item = R1>,
+        remove_readers: impl IntoIterator<Item = R2>,
+        extra_tags: &[S],
+        common_words: &HashSet<String>,
+    ) -> Result<Self, Error> {
+        let mut lines = intern::Lines::default();
+        Tagger::get_lines(
+            readers.into_iter().map(|x| (None, x)),
+            remove_readers.into_iter().map(|x| (None, x)),
+            false,
+            &mut lines,
+        )?;
+
+        Ok(Tagger::from_lines(lines, extra_tags, common_words))
+    }
+
+    /// Creates a tagger from a binary [morfologik](https://github.com/morfologik/morfologik-stemming) dictionary
+    /// as shipped with LanguageTool, without exporting it to a dump first.
+    /// The `.info` file with the same name as `dict_path` must exist next to it.
+    /// See [from_morfologik_bytes][Tagger::from_morfologik_bytes] to read the dictionary from memory.
+    pub fn from_morfologik<P: AsRef<Path>, S: AsRef<str>>(
+        dict_path: P,
+        extra_tags: &[S],
+        common_words: &HashSet<String>,
+    ) -> Result<Self, Error> {
+        let dict_path = dict_path.as_ref();
+        let info_path = dict_path.with_extension("info");
+
+        let unreadable = |path: &Path| {
+            let path = path.to_string_lossy().into_owned();
+            move |source| Error::DumpUnreadable { path, source }
+        };
+
+        let dict = std::fs::read(dict_path).map_err(unreadable(dict_path))?;
+        let info = std::fs::read(&info_path).map_err(unreadable(&info_path))?;
+
+        Tagger::from_morfologik_bytes(
+            &dict,
+            &String::from_utf8_lossy(&info),
+            extra_tags,
+            common_words,
+        )
+    }
+
+    /// Creates a tagger from the content of the `.dict` and `.info` files of a binary morfologik dictionary.
+    pub fn from_morfologik_bytes<S: AsRef<str>>(
+        dict: &[u8],
+        info: &str,
+        extra_tags: &[S],
+        common_words: &HashSet<String>,
+    ) -> Result<Self, Error> {
+        let lines = morfologik::read_entries(dict, info)?;
+
+        Ok(Tagger::from_lines(lines, extra_tags, common_words))
+    }
+
+    fn from_lines<S: AsRef<str>>(
+        lines: intern::Lines,
+        extra_tags: &[S],
+        common_words: &HashSet<String>,
+    ) -> Self {
+        let mut tags: DefaultHashMap<u32, IndexMap<u32, Vec<u16>>> = DefaultHashMap::default();
+        let mut groups: DefaultHashMap<u32, Vec<u32>> = DefaultHashMap::default();
+
+        // the interned strings can include the ones of removed lines, only the strings of the remaining lines are stored
+        let mut used_words = vec![false; lines.word_count()];
+        let mut used_tags = vec![false; lines.tag_count()];
+        for (word, (lemma, tag)) in lines.entries() {
+            used_words[word.0 as usize] = true;
+            used_words[lemma.0 as usize] = true;
+            used_tags[tag.0 as usize] = true;
+        }
 
         let mut tag_store = HashSet::new();
         let mut word_store = HashSet::new();
 
-        // hardcoded special tags
-        tag_store.insert("");
-        tag_store.insert("SENT_START");
-        tag_store.insert("SENT_END");
-        tag_store.insert("UNKNOWN");
-
         // add language specific special tags
         tag_store.extend(extra_tags.iter().map(|x| x.as_ref()));
 
-        let lines = Tagger::get_lines(paths, remove_paths)?;
+        let punct = "!\"#$%&\\'()*+,-./:;<=>?@[\\]^_`{|}~";
+        for i in 0..punct.len() {
+            word_store.insert(&punct[i..(i + 1)]);
+        }
+
+        word_store.extend(common_words.iter().map(|x| x.as_str()));
+
+        for (i, _) in used_words.iter().enumerate().filter(|(_, used)| **used) {
+            word_store.insert(lines.word(intern::LemmaId(i as u32)));
+        }
+        for (i, _) in used_tags.iter().enumerate().filter(|(_, used)| **used) {
+            tag_store.insert(lines.tag(intern::PosId(i as u32)));
+        }
+
+        // word store ids should be consistent across runs
+        let mut word_store: Vec<_> = word_store.iter().collect();
+        word_store.sort();
+
+        let word_store = WordStore::new(
+            word_store
+                .iter()
+                .enumerate()
+                .map(|(i, x)| (x.to_string(), i as u32))
+                .collect(),
+        );
+        let tag_store = TagStore::new(tag_store);
+
+        // the IDs in the stores of the interned strings, looked up once per string instead of once per line.
+        // the strings of removed lines are not in the stores, but are also not used by any line
+        let word_ids: Vec<u32> = (0..lines.word_count() as u32)
+            .map(|i| {
+                word_store
+                    .id(lines.word(intern::LemmaId(i)))
+                    .unwrap_or_default()
+            })
+            .collect();
+        let tag_ids: Vec<u16> = (0..lines.tag_count() as u32)
+            .map(|i| tag_store.id(lines.tag(intern::PosId(i))).map_or(0, |x| x.1))
+            .collect();
+
+        for (word, (lemma, tag)) in lines.entries() {
+            let word_id = word_ids[word.0 as usize];
+            let lemma_id = word_ids[lemma.0 as usize];
+            let tag_id = tag_ids[tag.0 as usize];
+
+            let group = groups.entry(lemma_id).or_default();
+            if !group.contains(&word_id) {
+                group.push(word_id);
+            }
+
+            let word_tags = tags
+                .entry(word_id)
+                .or_default()
+                .entry(lemma_id)
+                .or_default();
+            // the same entry can occur in multiple dumps
+            if !word_tags.contains(&tag_id) {
+                word_tags.push(tag_id);
+            }
+        }
+        drop(lines);
+
+        let tags = tags.into_iter().map(|(word_id, lemmas)| {
+            let readings = lemmas.into_iter().flat_map(|(lemma_id, tag_ids)| {
+                tag_ids.into_iter().map(move |tag_id| (lemma_id, tag_id))
+            });
+            (word_id, readings)
+        });
+
+        Tagger {
+            tags: Arena::new(tags),
+            groups: Arena::new(groups),
+            word_store,
+            tag_store,
+            casing: Casing::default(),
+            frequencies: DefaultHashMap::default(),
+            frequency_total: 0,
+            folding: None,
+            folded: DefaultHashMap::default(),
+            tagset: TagSet::default(),
+            custom_words: RwLock::default(),
+            #[cfg(feature = "mmap")]
+            mapped: None,
+        }
+    }
+
+    /// Adds a reading of `word` with the given lemma and part-of-speech tag at runtime, e. g. for a custom
+    /// dictionary of product names. The tag must be known to the tagger. The reading is used for all subsequent
+    /// lookups, including the tagging of a tokenizer which shares this tagger, but is not serialized and does not
+    /// change the [group members][Tagger::get_group_members] of the lemma.
+    pub fn add_word(&self, word: &str, lemma: &str, pos: &str) -> Result<(), Error> {
+        self.extend(std::iter::once((word, lemma, pos)))
+    }
+
+    /// Adds multiple readings as tuples of `(word, lemma, pos)` at runtime, see [add_word][Tagger::add_word].
+    /// No reading is added if any of the tags is unknown.
+    pub fn extend<'a>(
+        &self,
+        entries: impl IntoIterator<Item = (&'a str, &'a str, &'a str)>,
+    ) -> Result<(), Error> {
+        let entries = entries
+            .into_iter()
+            .map(|(word, lemma, pos)| match self.tag_store.id(pos) {
+                Some(tag) => Ok((word, (lemma.to_string(), tag.1))),
+                None => Err(Error::UnknownTag(pos.to_string())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut custom_words = self.custom_words.write().unwrap();
+        for (word, reading) in entries {
+            let custom = custom_words.entry(word.to_string()).or_default();
+
+            if !custom.readings.contains(&reading) {
+                custom.readings.push(reading);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces all readings of `word` at runtime with the given readings, which must be created with the IDs of
+    /// this tagger (e. g. with [id_word][Tagger::id_word] and [TagStore::id]). The readings from the dictionary and
+    /// the ones added with [add_word][Tagger::add_word] are not used for the word anymore.
+    pub fn override_word(&self, word: &str, readings: Vec<WordData>) -> Result<(), Error> {
+        let mut output = Vec::new();
+
+        for data in readings {
+            if self.tag_store.as_str(*data.pos.id()) != Some(data.pos.as_str()) {
+                return Err(Error::UnknownTag(data.pos.as_str().to_string()));
+            }
+
+            let reading = (data.lemma.as_ref().to_string(), *data.pos.id());
+            if !output.contains(&reading) {
+                output.push(reading);
+            }
+        }
+
+        self.custom_words.write().unwrap().insert(
+            word.to_string(),
+            CustomWord {
+                readings: output,
+                removed: true,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Removes all readings of `word` at runtime, both the ones from the dictionary and the ones
+    /// added with [add_word][Tagger::add_word].
+    pub fn remove_word(&self, word: &str) {
+        self.custom_words.write().unwrap().insert(
+            word.to_string(),
+            CustomWord {
+                readings: Vec::new(),
+                removed: true,
+            },
+        );
+    }
+
+    /// Writes the tagger in a binary format which loads much faster than the dumps, see [from_reader][Tagger::from_reader].
+    /// The binary starts with a header containing the format version and a checksum of the data. The dictionary is
+    /// stored in fixed-width records which can be used without deserializing them, so the binary can also be
+    /// memory-mapped with `Tagger::from_mmap` (with the `mmap` feature).
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> Result<(), Error> {
+        let data = binary::write(self)?;
+
+        writer.write_all(BINARY_MAGIC)?;
+        writer.write_all(&BINARY_VERSION.to_le_bytes())?;
+        writer.write_all(&checksum(&data).to_le_bytes())?;
+        writer.write_all(&data)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Reads a tagger written by [to_writer][Tagger::to_writer].
+    /// Fails with [Error::VersionMismatch] if the binary was written in another format version and with
+    /// [Error::CorruptedBinary] if it is not a tagger binary or the data does not match the checksum.
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, Error> {
+        let mut header = [0u8; 16];
+        reader.read_exact(&mut header).map_err(|_| {
+            Error::CorruptedBinary("the header of the tagger binary is incomplete".into())
+        })?;
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Tagger::check_binary(&header, &data)?;
+
+        binary::read(&data)
+    }
+
+    /// Reads a tagger written by [to_writer][Tagger::to_writer] from a memory-mapped file. Only the tags, the
+    /// frequencies and the diacritics-insensitive index are deserialized onto the heap, the words and their readings
+    /// are read from the mapped file on each lookup without copies. The pages of the file are shared by all processes
+    /// which map it, so multiple processes with the same tagger need its memory only once.
+    ///
+    /// The checksum of the data is verified, which reads the whole file once. Fails like
+    /// [from_reader][Tagger::from_reader], and also if the file can not be mapped.
+    /// The tagger can be written with [to_writer][Tagger::to_writer], but serializing it with serde (e. g. as part
+    /// of a tokenizer) fails since its dictionary is not part of the serialized fields.
+    #[cfg(feature = "mmap")]
+    pub fn from_mmap<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        // SAFETY: the mapped file must not be modified while it is mapped. This can not be guaranteed for files,
+        // so modifying the binary of a running process is undefined behavior, like for other users of `mmap`.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+
+        if mmap.len() < 16 {
+            return Err(Error::CorruptedBinary(
+                "the header of the tagger binary is incomplete".into(),
+            ));
+        }
+        Tagger::check_binary(&mmap[..16], &mmap[16..])?;
+
+        binary::read_mapped(mmap, 16)
+    }
+
+    /// Checks the magic bytes, the version and the checksum in the header of a binary.
+    fn check_binary(header: &[u8], data: &[u8]) -> Result<(), Error> {
+        if &header[..4] != BINARY_MAGIC {
+            return Err(Error::CorruptedBinary("not a tagger binary".into()));
+        }
+
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&header[4..8]);
+        let version = u32::from_le_bytes(version);
+        if version != BINARY_VERSION {
+            return Err(Error::VersionMismatch {
+                found: version,
+                expected: BINARY_VERSION,
+            });
+        }
+
+        let mut expected = [0u8; 8];
+        expected.copy_from_slice(&header[8..16]);
+
+        if checksum(data) != u64::from_le_bytes(expected) {
+            return Err(Error::CorruptedBinary(
+                "the data of the tagger binary does not match its checksum".into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Creates a tagger from raw files like [from_dumps][Tagger::from_dumps], but reads it from the binary at
+    /// `cache_path` instead if the binary is newer than all of the files. Otherwise (or if the binary can not be
+    /// read) the tagger is created from the files and written to `cache_path`.
+    /// Changes of `extra_tags` or `common_words` are not detected, the binary has to be removed in that case.
+    pub fn from_dumps_cached<S1: AsRef<str>, S2: AsRef<str>, S3: AsRef<str>, P: AsRef<Path>>(
+        paths: &[S1],
+        remove_paths: &[S2],
+        extra_tags: &[S3],
+        common_words: &HashSet<String>,
+        cache_path: P,
+    ) -> Result<Self, Error> {
+        let cache_path = cache_path.as_ref();
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|x| x.modified()).ok();
+
+        if let Some(cache_modified) = modified(cache_path) {
+            let is_fresh = paths
+                .iter()
+                .map(|x| x.as_ref())
+                .chain(remove_paths.iter().map(|x| x.as_ref()))
+                .all(|path| modified(Path::new(path)).map_or(false, |x| x <= cache_modified));
+
+            if is_fresh {
+                if let Ok(tagger) = File::open(cache_path)
+                    .map_err(Error::from)
+                    .and_then(|file| Tagger::from_reader(BufReader::new(file)))
+                {
+                    return Ok(tagger);
+                }
+            }
+        }
+
+        let tagger = Tagger::from_dumps(paths, remove_paths, extra_tags, common_words)?;
+        tagger.to_writer(BufWriter::new(File::create(cache_path)?))?;
+
+        Ok(tagger)
+    }
+
+    /// Sets the casing rules used to look up the lowercase variant of words. Should match the language of the dumps.
+    pub fn with_casing(mut self, casing: Casing) -> Self {
+        self.casing = casing;
+        self
+    }
+
+    /// The casing rules used to look up the lowercase variant of words.
+    pub fn casing(&self) -> Casing {
+        self.casing
+    }
+
+    /// Enables the diacritics-insensitive fallback lookup with the given folding, or disables it if `None`.
+    /// If neither the word nor its lowercase variant is found, the readings of the dictionary words whose folded
+    /// lowercase variant equals the folded word are used, marked as [approximate][WordData::approximate].
+    /// The folded variants of all dictionary words are computed here, so the fallback is a single lookup.
+    /// Must be called after [with_casing][Tagger::with_casing].
+    pub fn with_folding(mut self, folding: Option<Folding>) -> Self {
+        self.folded = DefaultHashMap::default();
+
+        if let Some(folding) = folding {
+            for word_id in self.word_ids() {
+                let word = self.word_store.word(word_id).unwrap();
+
+                for key in folding.keys(&self.casing.lowercase(word)) {
+                    // unchanged words are already found by the exact or lowercase lookup
+                    if key != word {
+                        self.folded.entry(key).or_default().push(word_id);
+                    }
+                }
+            }
+
+            // the ids are in arbitrary order, sort them so the readings are deterministic
+            for ids in self.folded.values_mut() {
+                ids.sort_unstable();
+                ids.dedup();
+            }
+        }
+
+        self.folding = folding;
+        self
+    }
+
+    /// The folding of the diacritics-insensitive fallback lookup, if enabled.
+    pub fn folding(&self) -> Option<Folding> {
+        self.folding
+    }
+
+    /// Loads word frequencies from a file with one word and its number of occurrences (or a frequency class)
+    /// separated by whitespace per line, like the frequency lists in the LanguageTool dump directories.
+    /// The file can be compressed like the dumps. Occurrences of the same word are summed up. A missing file
+    /// is not an error since many languages have no frequency list, it only leaves the frequencies unchanged.
+    /// Malformed lines are skipped with a warning.
+    pub fn load_frequencies<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        if !path.exists() {
+            warn!("frequency file {} does not exist", path.display());
+            return Ok(());
+        }
+
+        let path_str = path.to_string_lossy().into_owned();
+        let reader = Tagger::open_dump(&path_str).map_err(|source| Error::DumpUnreadable {
+            path: path_str.clone(),
+            source,
+        })?;
+
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.rsplitn(2, char::is_whitespace);
+            match (
+                parts.next().and_then(|x| x.parse::<u32>().ok()),
+                parts.next().map(str::trim),
+            ) {
+                (Some(count), Some(word)) if !word.is_empty() => {
+                    let frequency = self.frequencies.entry(word.to_string()).or_insert(0);
+                    *frequency = frequency.saturating_add(count);
+                    self.frequency_total += count as u64;
+                }
+                _ => warn!(
+                    "skipping malformed frequency line {} in {}: {:?}",
+                    i + 1,
+                    path_str,
+                    line
+                ),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Loads descriptions of the tags from a tagset file, see [TagSet::from_reader]. The file can be compressed
+    /// like the dumps. Like for [load_frequencies][Tagger::load_frequencies], a missing file only leaves the
+    /// descriptions unchanged. Descriptions of tags which are already described are replaced.
+    pub fn load_tagset<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        if !path.exists() {
+            warn!("tagset file {} does not exist", path.display());
+            return Ok(());
+        }
+
+        let path_str = path.to_string_lossy().into_owned();
+        let reader = Tagger::open_dump(&path_str).map_err(|source| Error::DumpUnreadable {
+            path: path_str.clone(),
+            source,
+        })?;
+
+        self.tagset
+            .descriptions
+            .extend(TagSet::from_reader(reader)?.descriptions);
+        Ok(())
+    }
+
+    /// The descriptions of the tags.
+    pub fn tagset(&self) -> &TagSet {
+        &self.tagset
+    }
+
+    /// The human-readable description of the tag, see [TagSet::describe].
+    pub fn describe(&self, tag: &str) -> Option<&str> {
+        self.tagset.describe(tag)
+    }
+
+    /// Counts the words, readings, lemmas and tags of the dictionary and estimates the heap memory used by the
+    /// tagger. Computed on demand, so this iterates over the whole dictionary. Words changed at runtime
+    /// (e. g. with [add_word][Tagger::add_word]) are not counted.
+    pub fn stats(&self) -> TaggerStats {
+        let size = std::mem::size_of::<u32>();
+        let mut readings = 0;
+        let mut lemmas = HashSet::new();
+        let mut heap_bytes = self.tags.heap_bytes() + self.groups.heap_bytes();
+
+        let word_ids = self.word_ids();
+        for word_id in &word_ids {
+            self.for_each_reading(*word_id, |lemma_id, _| {
+                lemmas.insert(lemma_id);
+                readings += 1;
+            });
+        }
+
+        heap_bytes += table_bytes::<String, u32>(self.frequencies.capacity())
+            + self.frequencies.keys().map(|x| x.capacity()).sum::<usize>();
+        heap_bytes += table_bytes::<String, Vec<u32>>(self.folded.capacity())
+            + self
+                .folded
+                .iter()
+                .map(|(key, ids)| key.capacity() + ids.capacity() * size)
+                .sum::<usize>();
+        heap_bytes += table_bytes::<String, String>(self.tagset.descriptions.capacity())
+            + self
+                .tagset
+                .descriptions
+                .iter()
+                .map(|(tag, description)| tag.capacity() + description.capacity())
+                .sum::<usize>();
+        heap_bytes += self.word_store.heap_bytes() + self.tag_store.heap_bytes();
+
+        TaggerStats {
+            words: word_ids.len(),
+            readings,
+            lemmas: lemmas.len(),
+            tags: self.tag_store.len(),
+            heap_bytes,
+        }
+    }
+
+    /// Suggests up to `limit` known words within the Levenshtein distance `max_distance` of `word`, e. g. "receive"
+    /// for "recieve". Suggestions with a smaller distance come first, then the ones with a higher
+    /// [frequency][Tagger::frequency] if frequencies are loaded, then alphabetically. The word itself is not
+    /// suggested. Casing is handled like in [get_tags][Tagger::get_tags]: the lowercase variant of a title- or
+    /// uppercase word is searched as well and its suggestions are title- or uppercased, so "Recieve" gets "Receive".
+    /// Only words in the dictionary are suggested, not the ones added with [add_word][Tagger::add_word].
+    pub fn suggest(&self, word: &str, max_distance: usize, limit: usize) -> Vec<String> {
+        let mut candidates: Vec<(String, usize)> = Vec::new();
+        let mut add = |candidate: String, distance: usize| {
+            if candidate == word {
+                return;
+            }
+
+            match candidates.iter_mut().find(|(x, _)| *x == candidate) {
+                Some((_, prev)) => *prev = (*prev).min(distance),
+                None => candidates.push((candidate, distance)),
+            }
+        };
+
+        let lower = self.casing.lowercase(word);
+        let is_title = crate::utils::is_title_case(word);
+        let is_cased = word != lower && (is_title || crate::utils::is_uppercase(word));
+
+        for (candidate, distance) in self.word_store.similar(word, max_distance) {
+            // lowercase words are suggested with the casing of `word` by the search for the lowercase variant
+            if is_cased && candidate.starts_with(char::is_lowercase) {
+                continue;
+            }
+
+            if !self.get_raw(candidate).is_empty() {
+                add(candidate.to_string(), distance);
+            }
+        }
+
+        if is_cased {
+            for (candidate, distance) in self.word_store.similar(&lower, max_distance) {
+                if self.get_raw(candidate).is_empty() {
+                    continue;
+                }
+
+                let candidate = if is_title {
+                    crate::utils::apply_to_first(candidate, |c| c.to_uppercase().collect())
+                } else {
+                    candidate.to_uppercase()
+                };
+                add(candidate, distance);
+            }
+        }
+
+        candidates.sort_by(|(a, a_distance), (b, b_distance)| {
+            a_distance
+                .cmp(b_distance)
+                .then_with(|| self.frequency(b).cmp(&self.frequency(a)))
+                .then_with(|| a.cmp(b))
+        });
+
+        candidates
+            .into_iter()
+            .take(limit)
+            .map(|(candidate, _)| candidate)
+            .collect()
+    }
+
+    /// The frequency of the word as loaded by [load_frequencies][Tagger::load_frequencies]. Falls back to the
+    /// frequency of the lowercase variant if the word itself has none, since frequency lists are often lowercase.
+    pub fn frequency(&self, word: &str) -> Option<u32> {
+        if self.frequencies.is_empty() {
+            return None;
+        }
+
+        self.frequencies
+            .get(word)
+            .or_else(|| self.frequencies.get(&self.casing.lowercase(word)))
+            .copied()
+    }
+
+    /// The sum of all loaded frequencies, to compute relative frequencies. Zero if no frequencies are loaded.
+    pub fn frequency_total(&self) -> u64 {
+        self.frequency_total
+    }
+
+    /// The tags of the word as stored in the tagger. The data borrows the strings of the tagger and the IDs are
+    /// already known, so no strings are allocated and no additional lookups are needed.
+    fn get_raw(&self, word: &str) -> Vec<WordData<'_>> {
+        let custom_words = self.custom_words.read().unwrap();
+
+        match custom_words.get(word) {
+            Some(custom) => {
+                let mut output = if custom.removed {
+                    Vec::new()
+                } else {
+                    self.get_dictionary(word)
+                };
+
+                for (lemma, tag_id) in &custom.readings {
+                    let data = WordData::new(
+                        WordId(lemma.clone().into(), self.word_store.id(lemma)),
+                        self.tag_store.pos(*tag_id),
+                    );
+
+                    if !output.contains(&data) {
+                        output.push(data);
+                    }
+                }
 
-        let punct = "!\"#$%&\\'()*+,-./:;<=>?@[\\]^_`{|}~";
-        for i in 0..punct.len() {
-            word_store.insert(&punct[i..(i + 1)]);
+                output
+            }
+            None => self.get_dictionary(word),
         }
+    }
 
-        word_store.extend(common_words.iter().map(|x| x.as_str()));
+    fn get_dictionary(&self, word: &str) -> Vec<WordData<'_>> {
+        let mut output = Vec::new();
 
-        for (word, inflection, tag) in lines.iter() {
-            word_store.insert(word);
-            word_store.insert(inflection);
-            tag_store.insert(tag);
+        if let Some(word_id) = self.word_store.id(word) {
+            self.for_each_reading(word_id, |lemma_id, tag_id| {
+                output.push(WordData::new(
+                    WordId(
+                        self.word_store.word(lemma_id).unwrap().into(),
+                        Some(lemma_id),
+                    ),
+                    self.tag_store.pos(tag_id),
+                ))
+            });
         }
 
-        // word store ids should be consistent across runs
-        let mut word_store: Vec<_> = word_store.iter().collect();
-        word_store.sort();
-
-        //  tag store ids should be consistent across runs
-        let mut tag_store: Vec<_> = tag_store.iter().collect();
-        tag_store.sort();
+        output
+    }
 
-        let word_store: BiMap<_, _> = word_store
-            .iter()
-            .enumerate()
-            .map(|(i, x)| (x.to_string(), i as u32))
-            .collect();
-        let tag_store: BiMap<_, _> = tag_store
-            .iter()
-            .enumerate()
-            .map(|(i, x)| (x.to_string(), i as u16))
-            .collect();
+    /// Calls `f` with the lemma ID and tag ID of each reading of the word in the dictionary, in order.
+    fn for_each_reading(&self, word_id: u32, mut f: impl FnMut(u32, u16)) {
+        #[cfg(feature = "mmap")]
+        {
+            if let Some(mapped) = &self.mapped {
+                return mapped
+                    .sections()
+                    .readings(word_id)
+                    .for_each(|(lemma_id, tag_id)| f(lemma_id, tag_id));
+            }
+        }
 
-        for (word, inflection, tag) in lines.iter() {
-            let word_id = word_store.get_by_left(word).unwrap();
-            let inflection_id = word_store.get_by_left(inflection).unwrap();
-            let tag_id = tag_store.get_by_left(tag).unwrap();
+        for (lemma_id, tag_id) in self.tags.get(word_id) {
+            f(*lemma_id, *tag_id);
+        }
+    }
 
-            let group = groups.entry(*inflection_id).or_insert_with(Vec::new);
-            if !group.contains(word_id) {
-                group.push(*word_id);
+    /// Calls `f` with the ID of each word with the lemma in the dictionary, in order.
+    fn for_each_group_member(&self, lemma_id: u32, mut f: impl FnMut(u32)) {
+        #[cfg(feature = "mmap")]
+        {
+            if let Some(mapped) = &self.mapped {
+                return mapped.sections().group(lemma_id).for_each(f);
             }
-
-            tags.entry(*word_id)
-                .or_insert_with(IndexMap::new)
-                .entry(*inflection_id)
-                .or_insert_with(Vec::new)
-                .push(*tag_id);
         }
 
-        Ok(Tagger {
-            tags,
-            groups,
-            word_store,
-            tag_store,
-        })
+        for word_id in self.groups.get(lemma_id) {
+            f(*word_id);
+        }
     }
 
-    #[allow(clippy::clippy::ptr_arg)]
-    fn get_raw(&self, word: &String) -> Vec<WordData> {
-        if let Some(map) = self
-            .word_store
-            .get_by_left(word)
-            .and_then(|x| self.tags.get(x))
+    /// The IDs of the words with readings in the dictionary, in arbitrary order.
+    fn word_ids(&self) -> Vec<u32> {
+        #[cfg(feature = "mmap")]
         {
-            let mut output = Vec::new();
-
-            for (key, value) in map.iter() {
-                for tag_id in value {
-                    output.push(WordData::new(
-                        self.id_word(self.word_store.get_by_right(key).unwrap().as_str().into()),
-                        self.id_tag(self.tag_store.get_by_right(tag_id).unwrap().as_str()),
-                    ))
-                }
+            if let Some(mapped) = &self.mapped {
+                let sections = mapped.sections();
+                return (0..sections.len() as u32)
+                    .filter(|id| sections.readings(*id).next().is_some())
+                    .collect();
             }
-
-            output
-        } else {
-            Vec::new()
         }
+
+        self.tags.ids().collect()
     }
 
     fn get_strict_tags(
@@ -183,52 +1871,112 @@ impl Tagger {
         word: &str,
         add_lower: bool,
         add_lower_if_empty: bool,
-    ) -> Vec<WordData> {
-        let mut tags = self.get_raw(&word.to_string());
-        let lower = word.to_lowercase();
+        add_title: bool,
+    ) -> Vec<(WordData<'_>, Lookup)> {
+        let mut tags: Vec<_> = self
+            .get_raw(word)
+            .into_iter()
+            .map(|x| (x, Lookup::Exact))
+            .collect();
+        let lower = self.casing.lowercase(word);
 
         if (add_lower || (add_lower_if_empty && tags.is_empty()))
             && (word != lower
                 && (crate::utils::is_title_case(word) || crate::utils::is_uppercase(word)))
         {
-            tags.extend(self.get_raw(&lower));
+            tags.extend(
+                self.get_raw(&lower)
+                    .into_iter()
+                    .map(|x| (x, Lookup::Lowercase)),
+            );
+        }
+
+        if add_title && word == lower && word.chars().next().map_or(false, char::is_lowercase) {
+            let title = crate::utils::apply_to_first(word, |c| c.to_uppercase().collect());
+            tags.extend(
+                self.get_raw(&title)
+                    .into_iter()
+                    .map(|x| (x, Lookup::Titlecase)),
+            );
         }
 
         tags
     }
 
-    pub fn tag_store(&self) -> &BiMap<String, u16> {
+    pub fn tag_store(&self) -> &TagStore {
         &self.tag_store
     }
 
-    pub fn word_store(&self) -> &BiMap<String, u32> {
+    pub fn word_store(&self) -> &WordStore {
         &self.word_store
     }
 
     pub fn id_tag<'a>(&self, tag: &'a str) -> PosId<'a> {
-        PosId(tag, *self.tag_store.get_by_left(tag).unwrap())
+        PosId(tag, self.tag_store.id(tag).unwrap().1)
     }
 
     pub fn id_word<'t>(&'t self, text: Cow<'t, str>) -> WordId<'t> {
-        let id = self.word_store.get_by_left(text.as_ref()).copied();
+        let id = self.word_store.id(text.as_ref());
         WordId(text, id)
     }
 
     /// Get the tags and lemmas (as [WordData][crate::types::WordData]) for the given word.
+    /// Each (lemma, tag) pair occurs once. The pairs are ordered by the first occurrence of the lemma for the word,
+    /// then by the first occurrence of the tag for the lemma, in the dumps sorted by file name.
     ///
     /// # Arguments
     /// * `word`: The word to lookup data for.
     /// * `add_lower`: Whether to add data for the lowercase variant of the word.
     /// * `use_compound_split_heuristic`: Whether to use a heuristic to split compound words.
-    /// If true, will attempt to find tags for words which are longer than some cutoff and unknown by looking up tags
-    /// for substrings from left to right until tags are found or a minimum length reached.
+    ///   If true, will attempt to find tags for words which are longer than some cutoff and unknown by looking up tags
+    ///   for substrings from left to right until tags are found or a minimum length reached.
     pub fn get_tags(
         &self,
         word: &str,
         add_lower: bool,
         use_compound_split_heuristic: bool,
-    ) -> Vec<WordData> {
-        let mut tags = self.get_strict_tags(word, add_lower, true);
+    ) -> Vec<WordData<'_>> {
+        self.get_tags_with_lookup(word, add_lower, false, use_compound_split_heuristic)
+            .into_iter()
+            .map(|x| x.0)
+            .collect()
+    }
+
+    /// Get the tags and lemmas for the given word like [get_tags][Tagger::get_tags] together with the [Lookup]
+    /// which found them. The word is looked up with its exact case first. Then the readings of the lowercase
+    /// variant are added if `add_lower` is true (or no readings were found) and the word is title- or uppercase.
+    /// Then the readings of the titlecased variant are added if `add_title` is true and the word is lowercase.
+    /// The diacritics-insensitive fallback (see [with_folding][Tagger::with_folding]) and then the compound splitting
+    /// heuristic are only used if none of these lookups found readings.
+    ///
+    /// # Arguments
+    /// * `word`: The word to lookup data for.
+    /// * `add_lower`: Whether to add data for the lowercase variant of the word.
+    /// * `add_title`: Whether to add data for the titlecased variant of a lowercase word,
+    ///   e. g. for a sentence-initial word which should be capitalized.
+    /// * `use_compound_split_heuristic`: Whether to use a heuristic to split compound words.
+    pub fn get_tags_with_lookup(
+        &self,
+        word: &str,
+        add_lower: bool,
+        add_title: bool,
+        use_compound_split_heuristic: bool,
+    ) -> Vec<(WordData<'_>, Lookup)> {
+        let mut tags = self.get_strict_tags(word, add_lower, true, add_title);
+
+        if tags.is_empty() && self.folding.is_some() {
+            let key = Folding::strip(&self.casing.lowercase(word));
+
+            for id in self.folded.get(&key).into_iter().flatten() {
+                for mut data in self.get_raw(self.word_store.word(*id).unwrap()) {
+                    data.approximate = true;
+
+                    if !tags.iter().any(|(x, _)| *x == data) {
+                        tags.push((data, Lookup::Approximate));
+                    }
+                }
+            }
+        }
 
         // compound splitting heuristic, seems to work reasonably well
         if use_compound_split_heuristic && tags.is_empty() {
@@ -248,17 +1996,21 @@ impl Tagger {
                         word[i..].to_string()
                     };
 
-                    let next_tags = self.get_strict_tags(&next, add_lower, false);
+                    let next_tags = self.get_strict_tags(&next, add_lower, false, false);
 
                     if !next_tags.is_empty() {
                         tags = next_tags
                             .into_iter()
-                            .map(|mut x| {
+                            .map(|(mut x, _)| {
                                 x.lemma = self.id_word(
-                                    format!("{}{}", &word[..i], x.lemma.as_ref().to_lowercase())
-                                        .into(),
+                                    format!(
+                                        "{}{}",
+                                        &word[..i],
+                                        self.casing.lowercase(x.lemma.as_ref())
+                                    )
+                                    .into(),
                                 );
-                                x
+                                (x, Lookup::Compound)
                             })
                             .collect();
                         break;
@@ -270,17 +2022,1100 @@ impl Tagger {
         tags
     }
 
+    /// Whether the part of a compound is a known word, in its title- or lowercase variant.
+    fn is_known_part(&self, part: &str) -> bool {
+        let title = crate::utils::apply_to_first(part, |c| c.to_uppercase().collect());
+
+        !self.get_raw(&title).is_empty() || !self.get_raw(&self.casing.lowercase(part)).is_empty()
+    }
+
+    /// Whether the text can be split into known parts with at least `min_part_length` chars each,
+    /// optionally followed by one of the linking elements.
+    fn is_compound_modifier(&self, text: &str, splitting: &CompoundSplitting) -> bool {
+        let stripped = splitting
+            .linking_elements
+            .iter()
+            .filter_map(|x| text.strip_suffix(x.as_str()));
+
+        std::iter::once(text).chain(stripped).any(|text| {
+            let n_chars = text.chars().count();
+
+            if n_chars < splitting.min_part_length {
+                return false;
+            }
+            if self.is_known_part(text) {
+                return true;
+            }
+
+            text.char_indices()
+                .skip(splitting.min_part_length)
+                .take((n_chars + 1).saturating_sub(2 * splitting.min_part_length))
+                .any(|(i, _)| {
+                    self.is_known_part(&text[i..])
+                        && self.is_compound_modifier(&text[..i], splitting)
+                })
+        })
+    }
+
+    /// Get the tags for an unknown capitalized compound like "Datenbankverbindungsfehler" from its head.
+    /// The word is split greedily from the right such that the rightmost part is a noun known to the tagger and
+    /// the rest consists of known words, each optionally followed by a linking element. All parts must have at least
+    /// `min_part_length` chars, so words which only happen to end in a noun are not split. The head's readings
+    /// are assigned to the word with the lemma of the head prefixed by the other parts e. g. "Datenbankverbindungsfehler"
+    /// for "Datenbankverbindungsfehlern".
+    ///
+    /// Returns no readings if the word is not capitalized or no split is found.
+    pub fn get_compound_tags(
+        &self,
+        word: &str,
+        splitting: &CompoundSplitting,
+    ) -> Vec<WordData<'_>> {
+        if !word.chars().next().map_or(false, char::is_uppercase) {
+            return Vec::new();
+        }
+
+        let n_chars = word.chars().count();
+        let mut starts: Vec<_> = word
+            .char_indices()
+            .skip(splitting.min_part_length)
+            .take((n_chars + 1).saturating_sub(2 * splitting.min_part_length))
+            .map(|x| x.0)
+            .collect();
+        // greedy from the right i. e. the shortest head first
+        starts.reverse();
+
+        for i in starts {
+            let head = crate::utils::apply_to_first(&word[i..], |c| c.to_uppercase().collect());
+            let tags: Vec<_> = self
+                .get_raw(&head)
+                .into_iter()
+                .filter(|x| x.pos.0.starts_with(splitting.head_tag_prefix.as_str()))
+                .collect();
+
+            if !tags.is_empty() && self.is_compound_modifier(&word[..i], splitting) {
+                return tags
+                    .into_iter()
+                    .map(|mut x| {
+                        x.lemma = self.id_word(
+                            format!("{}{}", &word[..i], self.casing.lowercase(x.lemma.as_ref()))
+                                .into(),
+                        );
+                        x
+                    })
+                    .collect();
+            }
+        }
+
+        Vec::new()
+    }
+
     /// Get the words with the same lemma as the given lemma.
-    #[allow(clippy::clippy::ptr_arg)]
-    pub fn get_group_members(&self, lemma: &String) -> Vec<&str> {
-        self.word_store
-            .get_by_left(lemma)
-            .and_then(|x| self.groups.get(x))
-            .map(|vec| {
-                vec.iter()
-                    .map(|x| self.word_store.get_by_right(x).unwrap().as_str())
-                    .collect()
-            })
-            .unwrap_or_else(Vec::new)
+    pub fn get_group_members(&self, lemma: &str) -> Vec<&str> {
+        let mut output = Vec::new();
+
+        if let Some(lemma_id) = self.word_store.id(lemma) {
+            self.for_each_group_member(lemma_id, |word_id| {
+                output.push(self.word_store.word(word_id).unwrap())
+            });
+        }
+
+        output
+    }
+
+    /// Get the distinct lemmas of the word in the order of its readings, looking up the word with its exact case.
+    /// Readings added with [add_word][Tagger::add_word] are not included.
+    pub fn get_lemmas(&self, word: &str) -> Vec<&str> {
+        if let Some(custom) = self.custom_words.read().unwrap().get(word) {
+            if custom.removed {
+                return Vec::new();
+            }
+        }
+
+        let mut lemmas = Vec::new();
+
+        if let Some(word_id) = self.word_store.id(word) {
+            self.for_each_reading(word_id, |lemma_id, _| {
+                let lemma = self.word_store.word(lemma_id).unwrap();
+                if !lemmas.contains(&lemma) {
+                    lemmas.push(lemma);
+                }
+            });
+        }
+
+        lemmas
+    }
+
+    /// Get the readings of all words with the given lemma as pairs of word and part-of-speech tag,
+    /// i. e. the inverse of [get_tags][Tagger::get_tags]. Words added with [add_word][Tagger::add_word]
+    /// are not included.
+    pub fn get_group(&self, lemma: &str) -> Vec<(&str, PosId<'_>)> {
+        let lemma_id = match self.word_store.id(lemma) {
+            Some(id) => id,
+            None => return Vec::new(),
+        };
+
+        let mut output = Vec::new();
+        self.for_each_group_member(lemma_id, |word_id| {
+            let word = self.word_store.word(word_id).unwrap();
+
+            self.for_each_reading(word_id, |x, tag_id| {
+                if x == lemma_id {
+                    output.push((word, self.tag_store.pos(tag_id)));
+                }
+            });
+        });
+
+        output
+    }
+
+    /// Get the words with the given lemma and a part-of-speech tag matching the regular expression `pos`,
+    /// like the synthesizer of LanguageTool. The expression must match the entire tag.
+    pub fn synthesize(&self, lemma: &str, pos: &str) -> Result<Vec<&str>, Error> {
+        let regex = onig::Regex::new(&format!(r"\A(?:{})\z", pos))?;
+        let mut output = Vec::new();
+
+        for (word, tag) in self.get_group(lemma) {
+            if regex.find(tag.as_ref()).is_some() && !output.contains(&word) {
+                output.push(word);
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bimap::BiMap;
+    use std::fs;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("nlprule_tagger_{}_{}", name, std::process::id()))
+    }
+
+    const DUMP: &str = "Haus\tHaus\tSUB:NOM:SIN:NEU\nHaus\tHaus\tSUB:AKK:SIN:NEU\nHäuser\tHaus\tSUB:NOM:PLU:NEU\nlief\tlaufen\tVER:3:SIN\n";
+
+    fn tagger(name: &str) -> (Tagger, String) {
+        let dump = temp_path(name);
+        std::fs::write(&dump, DUMP).unwrap();
+        let dump = dump.to_str().unwrap().to_string();
+
+        let tagger = Tagger::from_dumps(&[&dump], &[] as &[&str], &["ZAL"], &HashSet::new())
+            .unwrap()
+            .with_casing(Casing::Turkic);
+
+        (tagger, dump)
+    }
+
+    fn assert_same_tags(a: &Tagger, b: &Tagger) {
+        for word in &[
+            "Haus",
+            "haus",
+            "HAUS",
+            "Häuser",
+            "lief",
+            "Lief",
+            "unbekannt",
+            ".",
+        ] {
+            assert_eq!(
+                a.get_tags(word, true, true),
+                b.get_tags(word, true, true),
+                "{}",
+                word
+            );
+        }
+        assert_eq!(a.get_group_members("Haus"), b.get_group_members("Haus"));
+        assert_eq!(a.casing(), b.casing());
+    }
+
+    #[test]
+    fn readers_match_dumps() {
+        let (tagger, dump) = tagger("readers");
+        let remove = temp_path("readers_remove");
+        std::fs::write(&remove, "# removed\nlief\tlaufen\tVER:3:SIN\n").unwrap();
+
+        let from_readers = Tagger::from_readers(
+            vec![std::io::Cursor::new(DUMP.as_bytes())],
+            Vec::<&[u8]>::new(),
+            &["ZAL"],
+            &HashSet::new(),
+        )
+        .unwrap()
+        .with_casing(Casing::Turkic);
+        assert_same_tags(&tagger, &from_readers);
+
+        let removed = Tagger::from_dumps(
+            &[&dump],
+            &[remove.to_str().unwrap()],
+            &["ZAL"],
+            &HashSet::new(),
+        )
+        .unwrap();
+        let removed_from_readers = Tagger::from_readers(
+            vec![DUMP.as_bytes()],
+            vec![&b"# removed\nlief\tlaufen\tVER:3:SIN\n"[..]],
+            &["ZAL"],
+            &HashSet::new(),
+        )
+        .unwrap();
+        assert!(removed.get_tags("lief", true, true).is_empty());
+        assert_same_tags(&removed, &removed_from_readers);
+    }
+
+    fn fixture(name: &str) -> String {
+        format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn compressed_dumps() {
+        let load = |name: &str| {
+            Tagger::from_dumps(&[fixture(name)], &[] as &[&str], &["ZAL"], &HashSet::new())
+                .unwrap()
+                .with_casing(Casing::Turkic)
+        };
+
+        let plain = load("tagger_dump.txt");
+        assert_same_tags(&plain, &load("tagger_dump.txt.gz"));
+
+        let (tagger, _) = tagger("fixture");
+        assert_same_tags(&tagger, &plain);
+
+        let compressed = fs::read(fixture("tagger_dump.txt.gz")).unwrap();
+        let truncated = temp_path("truncated.gz");
+        fs::write(&truncated, &compressed[..compressed.len() / 2]).unwrap();
+        let truncated = truncated.to_str().unwrap();
+
+        match Tagger::from_dumps(&[truncated], &[] as &[&str], &["ZAL"], &HashSet::new()) {
+            Err(Error::DumpUnreadable { path, .. }) => assert_eq!(path, truncated),
+            x => panic!("unexpected result: {:?}", x.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn morfologik_dictionaries() {
+        let plain = Tagger::from_dumps(
+            &[fixture("morfologik_dump.txt")],
+            &[] as &[&str],
+            &["ZAL"],
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        // the order of the entries depends on the encoding of the lemmas, so the tags are compared sorted
+        let tags = |tagger: &Tagger, word: &str| {
+            let mut tags: Vec<_> = tagger
+                .get_tags(word, true, true)
+                .iter()
+                .map(|x| (x.lemma.as_ref().to_string(), x.pos.as_ref().to_string()))
+                .collect();
+            tags.sort();
+            tags
+        };
+        let members = |tagger: &Tagger, lemma: &str| {
+            let mut members: Vec<_> = tagger
+                .get_group_members(lemma)
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect();
+            members.sort();
+            members
+        };
+
+        assert_eq!(
+            tags(&plain, "huset"),
+            vec![("hus".to_string(), "N:SG:DEF".to_string())]
+        );
+        assert_eq!(members(&plain, "gå"), vec!["gik", "gå", "går"]);
+
+        for name in &["morfologik_fsa5.dict", "morfologik_cfsa2.dict"] {
+            let tagger = Tagger::from_morfologik(fixture(name), &["ZAL"], &HashSet::new()).unwrap();
+
+            for word in &[
+                "hus", "Hus", "huse", "huset", "husene", "gik", "går", "gå", "bedre", "god",
+                "ugift", "uheldig", "ukendt",
+            ] {
+                assert_eq!(
+                    tags(&plain, word),
+                    tags(&tagger, word),
+                    "{} in {}",
+                    word,
+                    name
+                );
+            }
+            for lemma in &["hus", "gå", "god", "gift", "heldig"] {
+                assert_eq!(members(&plain, lemma), members(&tagger, lemma), "{}", lemma);
+            }
+        }
+
+        assert!(matches!(
+            Tagger::from_morfologik(fixture("missing.dict"), &["ZAL"], &HashSet::new()),
+            Err(Error::DumpUnreadable { .. })
+        ));
+    }
+
+    #[test]
+    fn malformed_lines() {
+        let path = fixture("malformed_dump.txt");
+        let tagger =
+            Tagger::from_dumps(&[&path], &[] as &[&str], &["ZAL"], &HashSet::new()).unwrap();
+
+        assert_eq!(tagger.get_tags("Haus", true, true).len(), 2);
+        assert_eq!(tagger.get_tags("lief", true, true).len(), 1);
+        assert!(tagger.get_tags("Häuser", true, true).is_empty());
+
+        match Tagger::from_dumps_strict(&[&path], &[] as &[&str], &["ZAL"], &HashSet::new()) {
+            Err(Error::MalformedDumpLine {
+                path: error_path,
+                line,
+                content,
+            }) => {
+                assert_eq!(error_path, path);
+                assert_eq!(line, 5);
+                assert_eq!(content, "Häuser\tHaus");
+            }
+            x => panic!("unexpected result: {:?}", x.map(|_| ())),
+        }
+
+        let from_readers = Tagger::from_readers(
+            vec![fs::read(&path).unwrap().as_slice()],
+            Vec::<&[u8]>::new(),
+            &["ZAL"],
+            &HashSet::new(),
+        )
+        .unwrap();
+        assert_same_tags(&tagger, &from_readers);
+    }
+
+    #[test]
+    fn dump_order_does_not_matter() {
+        let a = temp_path("order_a.txt");
+        let b = temp_path("order_b.txt");
+        fs::write(&a, "Haus\tHaus\tSUB:NOM:SIN:NEU\nlief\tlaufen\tVER:3:SIN\n").unwrap();
+        fs::write(
+            &b,
+            "Haus\tHaus\tSUB:AKK:SIN:NEU\nHaus\tHaus\tSUB:NOM:SIN:NEU\nlief\tlaufen\tVER:1:SIN\n",
+        )
+        .unwrap();
+        let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+
+        let load = |paths: &[&str]| {
+            Tagger::from_dumps(paths, &[] as &[&str], &["ZAL"], &HashSet::new()).unwrap()
+        };
+        let pos = |tagger: &Tagger, word: &str| {
+            tagger
+                .get_tags(word, true, true)
+                .iter()
+                .map(|x| x.pos.as_ref().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let tagger = load(&[a, b]);
+        assert_same_tags(&tagger, &load(&[b, a]));
+        assert_eq!(pos(&tagger, "lief"), vec!["VER:3:SIN", "VER:1:SIN"]);
+
+        // duplicates are collapsed
+        assert_eq!(
+            pos(&tagger, "Haus"),
+            vec!["SUB:NOM:SIN:NEU", "SUB:AKK:SIN:NEU"]
+        );
+        assert_eq!(pos(&load(&[a, a]), "Haus"), vec!["SUB:NOM:SIN:NEU"]);
+    }
+
+    #[test]
+    fn overrides() {
+        let dir = temp_path("overrides");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(
+            dir.join("a.dump"),
+            "Apple\tapplen\tVER:INF\nApple\tApple\tEIG\nBirne\tBirne\tSUB:NOM\nBirne\tBirne\tSUB:AKK\n\
+             Kiwi\tKiwi\tSUB:NOM\nHaus\tHaus\tSUB:NOM\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("overrides.txt"),
+            "Apple\tApple\tEIG\nBirne\tBirne\tSUB:AKK\nBirne\nBirne\tBirne\tSUB:DAT\nKiwi\n",
+        )
+        .unwrap();
+
+        let tagger = Tagger::from_dump_dir(&dir, &["SUB:DAT"], &HashSet::new()).unwrap();
+        let tags = |word: &str| {
+            tagger
+                .get_tags(word, false, false)
+                .iter()
+                .map(|x| (x.lemma.as_ref().to_string(), x.pos.as_ref().to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        // the spurious verb reading is removed
+        assert_eq!(tags("Apple"), vec![("Apple".into(), "EIG".into())]);
+        // clearing discards the readings given before
+        assert_eq!(tags("Birne"), vec![("Birne".into(), "SUB:DAT".into())]);
+        assert!(tags("Kiwi").is_empty());
+        assert_eq!(tags("Haus"), vec![("Haus".into(), "SUB:NOM".into())]);
+
+        tagger
+            .override_word(
+                "Haus",
+                vec![
+                    WordData::new(tagger.id_word("Haus".into()), tagger.id_tag("SUB:DAT")),
+                    WordData::new(tagger.id_word("Gehäuse".into()), tagger.id_tag("SUB:NOM")),
+                ],
+            )
+            .unwrap();
+        // a reading with an unknown tag can not be added, the overridden readings are kept
+        assert!(matches!(
+            tagger.add_word("Haus", "Haus", "SUB:AKK"),
+            Err(Error::UnknownTag(_))
+        ));
+        assert_eq!(
+            tags("Haus"),
+            vec![
+                ("Haus".into(), "SUB:DAT".into()),
+                ("Gehäuse".into(), "SUB:NOM".into())
+            ]
+        );
+
+        assert!(matches!(
+            tagger.override_word(
+                "Haus",
+                vec![WordData::new(
+                    tagger.id_word("Haus".into()),
+                    PosId("SUB:GEN", 1000)
+                )]
+            ),
+            Err(Error::UnknownTag(_))
+        ));
+    }
+
+    #[test]
+    fn multiple_dump_dirs() {
+        let base = temp_path("dump_dirs_base");
+        let overlay = temp_path("dump_dirs_overlay");
+        for dir in &[&base, &overlay] {
+            let _ = fs::remove_dir_all(dir);
+            fs::create_dir_all(dir).unwrap();
+        }
+
+        fs::write(base.join("a.dump"), DUMP).unwrap();
+        fs::write(base.join("b.dump"), "geht\tgehen\tVER:3:SIN\n").unwrap();
+        fs::write(
+            overlay.join("custom.dump"),
+            "Haus\tHaus\tSUB:NOM:SIN:NEU\nHaus\tHaus\tSUB:DAT:SIN:NEU\nNlprule\tNlprule\tEIG:NOM:SIN:NEU\n",
+        )
+        .unwrap();
+        fs::write(overlay.join("removed.txt"), "geht\tgehen\t*\n").unwrap();
+
+        let pos = |dirs: &[&std::path::PathBuf], word: &str| {
+            Tagger::from_dump_dirs(dirs, &["ZAL"], &HashSet::new())
+                .unwrap()
+                .get_tags(word, false, false)
+                .iter()
+                .map(|x| x.pos.as_ref().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // entries of later directories are appended, duplicates are only added once
+        assert_eq!(
+            pos(&[&base, &overlay], "Haus"),
+            vec!["SUB:NOM:SIN:NEU", "SUB:AKK:SIN:NEU", "SUB:DAT:SIN:NEU"]
+        );
+        assert_eq!(pos(&[&base, &overlay], "Nlprule"), vec!["EIG:NOM:SIN:NEU"]);
+        assert_eq!(pos(&[&base, &overlay], "Häuser"), vec!["SUB:NOM:PLU:NEU"]);
+        // removals shadow the entries of earlier directories only
+        assert!(pos(&[&base, &overlay], "geht").is_empty());
+        assert_eq!(pos(&[&overlay, &base], "geht"), vec!["VER:3:SIN"]);
+    }
+
+    #[test]
+    fn overlays() {
+        let dir = temp_path("overlays");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(dir.join("a.dump"), DUMP).unwrap();
+        fs::write(
+            dir.join("b.dump"),
+            "lief\tlaufen\tVER:1:SIN\ngeht\tgehen\tVER:3:SIN\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("removed.txt"),
+            "# comment\nlief\tlaufen\t*\nHaus\tHaus\tSUB:AKK:SIN:NEU\ngeht\tgehen\tVER:3:SIN\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("added.txt"),
+            "Smartphone\tSmartphone\tSUB:NOM:SIN:NEU\ngeht\tgehen\tVER:3:SIN\n",
+        )
+        .unwrap();
+
+        let tagger = Tagger::from_dump_dir(&dir, &["ZAL"], &HashSet::new()).unwrap();
+        let pos = |word: &str| {
+            tagger
+                .get_tags(word, true, true)
+                .iter()
+                .map(|x| x.pos.as_ref().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(pos("lief").is_empty());
+        assert_eq!(pos("Haus"), vec!["SUB:NOM:SIN:NEU"]);
+        assert_eq!(pos("Häuser"), vec!["SUB:NOM:PLU:NEU"]);
+        assert_eq!(pos("Smartphone"), vec!["SUB:NOM:SIN:NEU"]);
+        // additions are applied after removals
+        assert_eq!(pos("geht"), vec!["VER:3:SIN"]);
+
+        // the overlays are optional
+        fs::remove_file(dir.join("removed.txt")).unwrap();
+        fs::remove_file(dir.join("added.txt")).unwrap();
+        let tagger = Tagger::from_dump_dir(&dir, &["ZAL"], &HashSet::new()).unwrap();
+        assert_eq!(tagger.get_tags("lief", true, true).len(), 2);
+        assert!(tagger.get_tags("Smartphone", true, true).is_empty());
+    }
+
+    #[test]
+    fn custom_words() {
+        let (tagger, _) = tagger("custom");
+        let tags = |word: &str| {
+            tagger
+                .get_tags(word, true, true)
+                .iter()
+                .map(|x| (x.lemma.as_ref().to_string(), x.pos.as_ref().to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        assert!(tags("Smartphone").is_empty());
+        tagger
+            .add_word("Smartphone", "Smartphone", "SUB:NOM:SIN:NEU")
+            .unwrap();
+        assert_eq!(
+            tags("Smartphone"),
+            vec![("Smartphone".to_string(), "SUB:NOM:SIN:NEU".to_string())]
+        );
+
+        tagger
+            .extend(vec![
+                ("Smartphones", "Smartphone", "SUB:NOM:PLU:NEU"),
+                ("Haus", "Haus", "SUB:NOM:PLU:NEU"),
+                ("Haus", "Haus", "SUB:NOM:SIN:NEU"),
+            ])
+            .unwrap();
+        assert_eq!(tags("Smartphones").len(), 1);
+        assert_eq!(
+            tags("Haus"),
+            vec![
+                ("Haus".to_string(), "SUB:NOM:SIN:NEU".to_string()),
+                ("Haus".to_string(), "SUB:AKK:SIN:NEU".to_string()),
+                ("Haus".to_string(), "SUB:NOM:PLU:NEU".to_string()),
+            ]
+        );
+
+        assert!(matches!(
+            tagger.add_word("Tablet", "Tablet", "NOT:A:TAG"),
+            Err(Error::UnknownTag(_))
+        ));
+        assert!(matches!(
+            tagger.extend(vec![
+                ("Tablet", "Tablet", "SUB:NOM:SIN:NEU"),
+                ("x", "x", "?")
+            ]),
+            Err(Error::UnknownTag(_))
+        ));
+        assert!(tags("Tablet").is_empty());
+
+        tagger.remove_word("Smartphone");
+        tagger.remove_word("Haus");
+        assert!(tags("Smartphone").is_empty());
+        assert!(tags("Haus").is_empty());
+        assert_eq!(tags("Häuser").len(), 1);
+    }
+
+    #[test]
+    fn lookup_respects_case() {
+        let tagger = Tagger::from_readers(
+            vec![&b"Laufen\tLaufen\tSUB:NOM:SIN:NEU\nlaufen\tlaufen\tVER:INF\nMay\tMay\tNNP\nmay\tmay\tMD\n"[..]],
+            Vec::<&[u8]>::new(),
+            &[] as &[&str],
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        let lookup = |word: &str, add_lower: bool, add_title: bool| {
+            tagger
+                .get_tags_with_lookup(word, add_lower, add_title, false)
+                .into_iter()
+                .map(|(data, lookup)| (data.pos.as_ref().to_string(), lookup))
+                .collect::<Vec<_>>()
+        };
+        let pos = |x: &str| x.to_string();
+
+        // within a sentence, the readings depend on the case
+        assert_eq!(
+            lookup("Laufen", false, false),
+            vec![(pos("SUB:NOM:SIN:NEU"), Lookup::Exact)]
+        );
+        assert_eq!(
+            lookup("laufen", false, false),
+            vec![(pos("VER:INF"), Lookup::Exact)]
+        );
+        assert_eq!(
+            lookup("May", false, false),
+            vec![(pos("NNP"), Lookup::Exact)]
+        );
+        assert_eq!(
+            lookup("may", false, false),
+            vec![(pos("MD"), Lookup::Exact)]
+        );
+        assert_eq!(tagger.get_tags("May", false, false).len(), 1);
+
+        // at the start of a sentence, the other case is added after the exact readings
+        assert_eq!(
+            lookup("Laufen", true, true),
+            vec![
+                (pos("SUB:NOM:SIN:NEU"), Lookup::Exact),
+                (pos("VER:INF"), Lookup::Lowercase)
+            ]
+        );
+        assert_eq!(
+            lookup("may", true, true),
+            vec![(pos("MD"), Lookup::Exact), (pos("NNP"), Lookup::Titlecase)]
+        );
+        assert_eq!(
+            lookup("MAY", false, false),
+            vec![(pos("MD"), Lookup::Lowercase)]
+        );
+    }
+
+    #[test]
+    fn synthesis() {
+        let tagger = Tagger::from_readers(
+            vec![
+                DUMP.as_bytes(),
+                &b"go\tgo\tVB\nwent\tgo\tVBD\ngone\tgo\tVBN\ngoes\tgo\tVBZ\n"[..],
+            ],
+            Vec::<&[u8]>::new(),
+            &[] as &[&str],
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            tagger.synthesize("Haus", "SUB:.*:PLU:.*").unwrap(),
+            vec!["Häuser"]
+        );
+        assert_eq!(
+            tagger.synthesize("Haus", "SUB:.*:SIN:.*").unwrap(),
+            vec!["Haus"]
+        );
+        assert_eq!(tagger.synthesize("go", "VBN").unwrap(), vec!["gone"]);
+        assert_eq!(
+            tagger.synthesize("go", "VB[DN]").unwrap(),
+            vec!["went", "gone"]
+        );
+        // the expression must match the entire tag
+        assert_eq!(tagger.synthesize("go", "VB").unwrap(), vec!["go"]);
+        assert!(tagger.synthesize("gone", "VBN").unwrap().is_empty());
+        assert!(tagger.synthesize("go", "(").is_err());
+
+        let group: Vec<_> = tagger
+            .get_group("Haus")
+            .into_iter()
+            .map(|(word, pos)| (word, pos.as_ref().to_string()))
+            .collect();
+        assert_eq!(
+            group,
+            vec![
+                ("Haus", "SUB:NOM:SIN:NEU".to_string()),
+                ("Haus", "SUB:AKK:SIN:NEU".to_string()),
+                ("Häuser", "SUB:NOM:PLU:NEU".to_string()),
+            ]
+        );
+        assert!(tagger.get_group("unbekannt").is_empty());
+    }
+
+    #[test]
+    fn lemmas() {
+        let tagger = Tagger::from_readers(
+            vec![&b"saw\tsee\tVBD\nsaw\tsaw\tNN\nsaw\tsaw\tVB\nsaw\tsee\tVBN\nSaw\tSaw\tNNP\n"[..]],
+            Vec::<&[u8]>::new(),
+            &[] as &[&str],
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        assert_eq!(tagger.get_lemmas("saw"), vec!["see", "saw"]);
+        assert_eq!(tagger.get_lemmas("Saw"), vec!["Saw"]);
+        assert!(tagger.get_lemmas("seen").is_empty());
+
+        tagger.remove_word("saw");
+        assert!(tagger.get_lemmas("saw").is_empty());
+    }
+
+    #[test]
+    fn tag_store() {
+        let (tagger, _) = tagger("tag_store");
+        let store = tagger.tag_store();
+
+        assert_eq!(store.id("SENT_START"), Some(PosId::SENT_START));
+        assert_eq!(store.id("UNKNOWN"), Some(PosId::UNKNOWN));
+        assert_eq!(store.as_str(*PosId::SENT_END.id()), Some("SENT_END"));
+        assert_eq!(store.id("VBZ"), None);
+        assert_eq!(store.as_str(store.len() as u16), None);
+
+        for pos in store.iter() {
+            assert_eq!(store.id(pos.as_str()), Some(pos.clone()));
+            assert_eq!(store.as_str(*pos.id()), Some(pos.as_str()));
+            assert_eq!(pos.is_special(), SPECIAL_TAGS.contains(&pos));
+        }
+        assert!(store.id("ZAL").is_some());
+        assert!(store.id("SUB:NOM:PLU:NEU").is_some());
+    }
+
+    #[test]
+    fn frequencies() {
+        let (mut tagger, _) = tagger("frequencies");
+        assert_eq!(tagger.frequency("Haus"), None);
+        assert_eq!(tagger.frequency_total(), 0);
+
+        tagger
+            .load_frequencies(temp_path("frequencies_missing.txt"))
+            .unwrap();
+        assert_eq!(tagger.frequency_total(), 0);
+
+        tagger.load_frequencies(fixture("frequencies.txt")).unwrap();
+
+        assert_eq!(tagger.frequency("haus"), Some(120));
+        // falls back to the lowercase variant
+        assert_eq!(tagger.frequency("Haus"), Some(120));
+        assert_eq!(tagger.frequency("New York"), Some(7));
+        // occurrences of the same word are summed up, malformed lines are skipped
+        assert_eq!(tagger.frequency("lief"), Some(40));
+        assert_eq!(tagger.frequency("unbekannt"), None);
+        assert_eq!(tagger.frequency_total(), 167);
+    }
+
+    #[test]
+    fn diacritics_folding() {
+        let dump =
+            "über\tüber\tAPPR\nStraße\tStraße\tSUB\nrésumé\trésumé\tNN\nresume\tresume\tVB\n\
+            Café\tCafé\tSUB\n";
+        let tagger = || {
+            Tagger::from_readers(
+                vec![dump.as_bytes()],
+                Vec::<&[u8]>::new(),
+                &[] as &[&str],
+                &HashSet::new(),
+            )
+            .unwrap()
+        };
+        let tags = |tagger: &Tagger, word: &str| {
+            tagger
+                .get_tags_with_lookup(word, false, false, false)
+                .into_iter()
+                .map(|(x, lookup)| {
+                    assert_eq!(x.approximate, lookup == Lookup::Approximate);
+                    (x.lemma.as_ref().to_string(), lookup)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let german = tagger().with_folding(Some(Folding::for_language("de")));
+        assert_eq!(
+            tags(&german, "uber"),
+            vec![("über".into(), Lookup::Approximate)]
+        );
+        assert_eq!(
+            tags(&german, "ueber"),
+            vec![("über".into(), Lookup::Approximate)]
+        );
+        assert_eq!(
+            tags(&german, "Strasse"),
+            vec![("Straße".into(), Lookup::Approximate)]
+        );
+        assert_eq!(
+            tags(&german, "cafe"),
+            vec![("Café".into(), Lookup::Approximate)]
+        );
+        assert_eq!(tags(&german, "über"), vec![("über".into(), Lookup::Exact)]);
+
+        // no fallback unless enabled
+        let english = tagger();
+        assert!(tags(&english, "resumé").is_empty());
+        let english = english.with_folding(Some(Folding::for_language("en")));
+        assert_eq!(
+            tags(&english, "resumé"),
+            vec![("résumé".into(), Lookup::Approximate)]
+        );
+        // exact matches are never approximate
+        assert_eq!(
+            tags(&english, "resume"),
+            vec![("resume".into(), Lookup::Exact)]
+        );
+        // the two-letter spellings are German only
+        assert!(tags(&english, "ueber").is_empty());
+        assert_eq!(
+            tags(&english, "uber"),
+            vec![("über".into(), Lookup::Approximate)]
+        );
+
+        assert!(tags(&english.with_folding(None), "uber").is_empty());
+    }
+
+    #[test]
+    fn tagset() {
+        let mut tagger = Tagger::default();
+        tagger.load_tagset(fixture("tagset.txt")).unwrap();
+
+        assert_eq!(
+            tagger.describe("VER:3:SIN:PRÄ"),
+            Some("Verb, 3. Person Singular, Präsens")
+        );
+        // the later description wins
+        assert_eq!(
+            tagger.describe("SUB:NOM:SIN:NEU"),
+            Some("Substantiv, Nominativ Singular Neutrum")
+        );
+        assert_eq!(tagger.describe("SUB:AKK:SIN:NEU"), None);
+        // the line without a tab is skipped
+        assert_eq!(tagger.tagset().len(), 3);
+
+        // a missing file leaves the descriptions unchanged
+        tagger.load_tagset(fixture("missing_tagset.txt")).unwrap();
+        assert_eq!(tagger.tagset().len(), 3);
+    }
+
+    #[test]
+    fn suggestions() {
+        let dump = "receive\treceive\tVB\nrecipe\trecipe\tNN\nbelieve\tbelieve\tVB\ndeceive\tdeceive\tVB\n\
+                    Rome\tRome\tNNP\n";
+        let mut tagger = Tagger::from_readers(
+            vec![std::io::Cursor::new(dump.as_bytes())],
+            Vec::<&[u8]>::new(),
+            &[] as &[&str],
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            tagger.suggest("recieve", 2, 10),
+            vec!["believe", "receive", "recipe"]
+        );
+        assert_eq!(tagger.suggest("recieve", 2, 1), vec!["believe"]);
+        // "deceive" has distance 3
+        assert!(tagger.suggest("recieve", 1, 10).is_empty());
+        // known words are not suggested for themselves
+        assert_eq!(tagger.suggest("receive", 1, 10), vec!["deceive"]);
+
+        assert_eq!(
+            tagger.suggest("Recieve", 2, 10),
+            vec!["Believe", "Receive", "Recipe"]
+        );
+        assert_eq!(tagger.suggest("RECIPES", 1, 10), vec!["RECIPE"]);
+        assert_eq!(tagger.suggest("Rone", 1, 10), vec!["Rome"]);
+
+        // frequencies break ties between suggestions with the same distance
+        tagger.frequencies.insert("receive".into(), 20);
+        tagger.frequencies.insert("recipe".into(), 10);
+        assert_eq!(
+            tagger.suggest("recieve", 2, 10),
+            vec!["receive", "recipe", "believe"]
+        );
+    }
+
+    #[test]
+    fn stats() {
+        let tagger = Tagger::from_dumps(
+            &[fixture("tagger_dump.txt")],
+            &[] as &[&str],
+            &["ZAL"],
+            &HashSet::new(),
+        )
+        .unwrap();
+        let stats = tagger.stats();
+
+        assert_eq!(
+            (stats.words, stats.readings, stats.lemmas, stats.tags),
+            // the special tags and ZAL are counted as tags
+            (3, 4, 2, 4 + 1 + 4)
+        );
+        assert!(stats.heap_bytes > 0);
+        assert!(stats
+            .to_string()
+            .starts_with("words:     3\nreadings:  4\n"));
+
+        // the estimate grows with the dictionary
+        let larger = Tagger::from_dumps(
+            &[fixture("tagger_dump.txt"), fixture("morfologik_dump.txt")],
+            &[] as &[&str],
+            &["ZAL"],
+            &HashSet::new(),
+        )
+        .unwrap();
+        assert!(larger.stats().heap_bytes > stats.heap_bytes);
+    }
+
+    #[test]
+    fn arena_is_indexed_by_id() {
+        let arena = Arena::new(vec![
+            (3, vec!['c']),
+            (0, vec!['a', 'b']),
+            (5, vec![]),
+            (4, vec![]),
+        ]);
+
+        assert_eq!(arena.get(0), &['a', 'b']);
+        assert!(arena.get(1).is_empty());
+        assert_eq!(arena.get(3), &['c']);
+        assert!(arena.get(5).is_empty());
+        assert!(arena.get(100).is_empty());
+        assert_eq!(arena.ids().collect::<Vec<_>>(), vec![0, 3]);
+        // the empty lists after the last word with items are not stored
+        assert_eq!(arena.ends, vec![2, 2, 2, 3]);
+
+        assert!(Arena::<char>::new(vec![(2, vec![])]).ends.is_empty());
+    }
+
+    #[test]
+    fn compound_splitting() {
+        let dump = "Daten\tDaten\tSUB:NOM:PLU:NEU\nBank\tBank\tSUB:NOM:SIN:FEM\n\
+            Verbindung\tVerbindung\tSUB:NOM:SIN:FEM\nFehler\tFehler\tSUB:NOM:SIN:MAS\n\
+            Fehlern\tFehler\tSUB:DAT:PLU:MAS\nOrt\tOrt\tSUB:NOM:SIN:MAS\nlief\tlaufen\tVER:3:SIN\n";
+        let tagger = Tagger::from_readers(
+            vec![dump.as_bytes()],
+            Vec::<&[u8]>::new(),
+            &[] as &[&str],
+            &HashSet::new(),
+        )
+        .unwrap();
+        let splitting = CompoundSplitting::default();
+        let tags = |word| -> Vec<(String, String)> {
+            tagger
+                .get_compound_tags(word, &splitting)
+                .into_iter()
+                .map(|x| (x.lemma.as_ref().to_string(), x.pos.0.to_string()))
+                .collect()
+        };
+
+        assert_eq!(
+            tags("Bankfehler"),
+            vec![("Bankfehler".to_string(), "SUB:NOM:SIN:MAS".to_string())]
+        );
+        // three parts with a linking element, the head is inflected
+        assert_eq!(
+            tags("Datenbankverbindungsfehlern"),
+            vec![(
+                "Datenbankverbindungsfehler".to_string(),
+                "SUB:DAT:PLU:MAS".to_string()
+            )]
+        );
+
+        // "Exp" is not a word and "Sp" is too short, so the words are not compounds of "Ort"
+        assert!(tags("Export").is_empty());
+        assert!(tags("Sport").is_empty());
+        // the head must be a noun and the word capitalized
+        assert!(tags("Banklief").is_empty());
+        assert!(tags("bankfehler").is_empty());
+    }
+
+    #[test]
+    fn binary_roundtrip() {
+        let (tagger, _) = tagger("roundtrip");
+        let mut binary = Vec::new();
+        tagger.to_writer(&mut binary).unwrap();
+
+        assert_same_tags(&tagger, &Tagger::from_reader(binary.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn invalid_binaries_are_rejected() {
+        let (tagger, _) = tagger("invalid");
+        let mut binary = Vec::new();
+        tagger.to_writer(&mut binary).unwrap();
+
+        let mut corrupted = binary.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+        assert!(matches!(
+            Tagger::from_reader(corrupted.as_slice()),
+            Err(Error::CorruptedBinary(_))
+        ));
+
+        let mut other_version = binary.clone();
+        other_version[4..8].copy_from_slice(&(BINARY_VERSION + 1).to_le_bytes());
+        match Tagger::from_reader(other_version.as_slice()) {
+            Err(Error::VersionMismatch { found, expected }) => {
+                assert_eq!((found, expected), (BINARY_VERSION + 1, BINARY_VERSION))
+            }
+            x => panic!("unexpected result: {:?}", x.map(|_| ())),
+        }
+
+        assert!(matches!(
+            Tagger::from_reader(&b"NLP"[..]),
+            Err(Error::CorruptedBinary(_))
+        ));
+        assert!(matches!(
+            Tagger::from_reader(&binary[1..]),
+            Err(Error::CorruptedBinary(_))
+        ));
+    }
+
+    #[test]
+    fn cached_binary_is_reused() {
+        let (tagger, dump) = tagger("cached");
+        let cache = temp_path("cached.bin");
+        let _ = std::fs::remove_file(&cache);
+
+        let common_words = HashSet::new();
+        let build = || {
+            Tagger::from_dumps_cached(&[&dump], &[] as &[&str], &["ZAL"], &common_words, &cache)
+                .unwrap()
+                .with_casing(Casing::Turkic)
+        };
+
+        assert_same_tags(&tagger, &build());
+        let written = std::fs::metadata(&cache).unwrap().modified().unwrap();
+
+        assert_same_tags(&tagger, &build());
+        assert_eq!(
+            std::fs::metadata(&cache).unwrap().modified().unwrap(),
+            written
+        );
+
+        // a corrupted binary is replaced
+        std::fs::write(&cache, b"NLPT").unwrap();
+        assert_same_tags(&tagger, &build());
+        assert!(Tagger::from_reader(File::open(&cache).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn word_store_is_serialized_like_a_bimap() {
+        let words: Vec<_> = ["Haus", "a", "b", "ä"]
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (x.to_string(), i as u32))
+            .collect();
+        let store = WordStore::new(words.clone());
+
+        assert_eq!(store.len(), 4);
+        assert_eq!(store.id("ä"), Some(3));
+        assert_eq!(store.id("c"), None);
+        assert_eq!(store.word(0), Some("Haus"));
+        assert_eq!(store.word(3), Some("ä"));
+        assert_eq!(store.word(4), None);
+
+        let bimap: BiMap<String, u32> = words.into_iter().collect();
+        let from_bimap: WordStore =
+            bincode::deserialize(&bincode::serialize(&bimap).unwrap()).unwrap();
+        let to_bimap: BiMap<String, u32> =
+            bincode::deserialize(&bincode::serialize(&store).unwrap()).unwrap();
+
+        let mut words: Vec<_> = from_bimap.iter().collect();
+        words.sort_unstable();
+        assert_eq!(words, vec![("Haus", 0), ("a", 1), ("b", 2), ("ä", 3)]);
+        assert_eq!(to_bimap, bimap);
     }
 }
@@ -0,0 +1,449 @@
+//! The data of the binary written by [Tagger::to_writer]. The dictionary is stored in sections of fixed-width records
+//! so it can be used directly from a memory-mapped file (see `Tagger::from_mmap` with the `mmap` feature) instead of
+//! being deserialized onto the heap. The other fields of the tagger are serialized with bincode.
+//!
+//! The data starts with a table of the start and end of each section in bytes, relative to the start of the data.
+//! Sections start at multiples of 8 bytes. All integers are little-endian and are decoded from byte slices with
+//! `from_le_bytes`, so the records do not have to be aligned in memory and binaries are the same on all platforms.
+//!
+//! | section | content |
+//! | --- | --- |
+//! | meta | the tag store, casing, frequencies, folding and tagset, serialized with bincode |
+//! | text | all words concatenated in the order of their IDs |
+//! | ends | the end of each word in the text as `u32`, indexed by word ID |
+//! | sorted | the word IDs as `u32` sorted by the bytes of the words, to find a word by binary search |
+//! | reading offsets | the index of the first reading of each word as `u32`, indexed by word ID, plus the number of readings |
+//! | readings | the readings as records of lemma ID (`u32`), tag ID (`u16`) and two bytes of padding |
+//! | group offsets | the index of the first member of the group of each lemma as `u32`, indexed by word ID, plus the number of members |
+//! | groups | the word IDs of the group members as `u32` |
+
+use super::{Arena, Casing, Folding, TagSet, TagStore, Tagger, WordStore};
+use crate::{types::DefaultHashMap, Error};
+#[cfg(feature = "mmap")]
+use serde::Serializer;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "mmap")]
+use std::sync::Arc;
+
+const META: usize = 0;
+const TEXT: usize = 1;
+const ENDS: usize = 2;
+const SORTED: usize = 3;
+const READING_OFFSETS: usize = 4;
+const READINGS: usize = 5;
+const GROUP_OFFSETS: usize = 6;
+const GROUPS: usize = 7;
+const N_SECTIONS: usize = 8;
+
+/// The size of the table of sections at the start of the data.
+const TABLE_SIZE: usize = N_SECTIONS * 16;
+/// The size of a record in the readings section.
+const READING_SIZE: usize = 8;
+
+#[derive(Serialize)]
+struct MetaRef<'a> {
+    tag_store: &'a TagStore,
+    casing: Casing,
+    frequencies: &'a DefaultHashMap<String, u32>,
+    frequency_total: u64,
+    folding: Option<Folding>,
+    folded: &'a DefaultHashMap<String, Vec<u32>>,
+    tagset: &'a TagSet,
+}
+
+#[derive(Deserialize)]
+struct Meta {
+    tag_store: TagStore,
+    casing: Casing,
+    frequencies: DefaultHashMap<String, u32>,
+    frequency_total: u64,
+    folding: Option<Folding>,
+    folded: DefaultHashMap<String, Vec<u32>>,
+    tagset: TagSet,
+}
+
+fn u32_at(bytes: &[u8], index: usize) -> Option<u32> {
+    let bytes = bytes.get(index * 4..index * 4 + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn u64_at(bytes: &[u8], index: usize) -> Option<u64> {
+    let bytes = bytes.get(index * 8..index * 8 + 8)?;
+    let mut output = [0u8; 8];
+    output.copy_from_slice(bytes);
+    Some(u64::from_le_bytes(output))
+}
+
+/// Serializes the data of the tagger, i. e. the binary without the header.
+pub(super) fn write(tagger: &Tagger) -> Result<Vec<u8>, Error> {
+    let word_store = tagger.word_store();
+    let n_words = word_store.len() as u32;
+    let mut sections = vec![Vec::new(); N_SECTIONS];
+
+    sections[META] = bincode::serialize(&MetaRef {
+        tag_store: &tagger.tag_store,
+        casing: tagger.casing,
+        frequencies: &tagger.frequencies,
+        frequency_total: tagger.frequency_total,
+        folding: tagger.folding,
+        folded: &tagger.folded,
+        tagset: &tagger.tagset,
+    })?;
+
+    let mut words = Vec::with_capacity(n_words as usize);
+    let mut n_readings = 0u32;
+    let mut n_members = 0u32;
+
+    for id in 0..n_words {
+        let word = word_store.word(id).unwrap();
+        words.push((word, id));
+
+        sections[TEXT].extend_from_slice(word.as_bytes());
+        let end = sections[TEXT].len() as u32;
+        sections[ENDS].extend_from_slice(&end.to_le_bytes());
+
+        sections[READING_OFFSETS].extend_from_slice(&n_readings.to_le_bytes());
+        let readings = &mut sections[READINGS];
+        tagger.for_each_reading(id, |lemma_id, tag_id| {
+            readings.extend_from_slice(&lemma_id.to_le_bytes());
+            readings.extend_from_slice(&tag_id.to_le_bytes());
+            readings.extend_from_slice(&[0, 0]);
+            n_readings += 1;
+        });
+
+        sections[GROUP_OFFSETS].extend_from_slice(&n_members.to_le_bytes());
+        let groups = &mut sections[GROUPS];
+        tagger.for_each_group_member(id, |word_id| {
+            groups.extend_from_slice(&word_id.to_le_bytes());
+            n_members += 1;
+        });
+    }
+    sections[READING_OFFSETS].extend_from_slice(&n_readings.to_le_bytes());
+    sections[GROUP_OFFSETS].extend_from_slice(&n_members.to_le_bytes());
+
+    words.sort_unstable_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+    for (_, id) in words {
+        sections[SORTED].extend_from_slice(&id.to_le_bytes());
+    }
+
+    let mut table = Vec::with_capacity(TABLE_SIZE);
+    let mut content = Vec::new();
+    for section in sections {
+        let start = TABLE_SIZE + content.len();
+        table.extend_from_slice(&(start as u64).to_le_bytes());
+        table.extend_from_slice(&((start + section.len()) as u64).to_le_bytes());
+
+        content.extend_from_slice(&section);
+        // the next section starts at a multiple of 8 bytes
+        content.resize((content.len() + 7) / 8 * 8, 0);
+    }
+    table.extend(content);
+
+    Ok(table)
+}
+
+/// The sections of the data of a binary, see the [module documentation][self].
+#[derive(Clone, Copy)]
+pub(super) struct Sections<'a> {
+    data: &'a [u8],
+    ranges: [(usize, usize); N_SECTIONS],
+}
+
+impl<'a> Sections<'a> {
+    /// Reads the table of sections and checks that the sections are within the data and have consistent lengths.
+    fn new(data: &'a [u8]) -> Result<Self, Error> {
+        let corrupted = |message: &str| Error::CorruptedBinary(message.into());
+        let mut ranges = [(0, 0); N_SECTIONS];
+
+        for (i, range) in ranges.iter_mut().enumerate() {
+            let start = u64_at(data, 2 * i).ok_or_else(|| corrupted("the table is incomplete"))?;
+            let end =
+                u64_at(data, 2 * i + 1).ok_or_else(|| corrupted("the table is incomplete"))?;
+
+            if start > end || end > data.len() as u64 {
+                return Err(corrupted("a section is out of bounds"));
+            }
+            *range = (start as usize, end as usize);
+        }
+
+        let sections = Sections { data, ranges };
+        let length = |i: usize| sections.section(i).len();
+        let n_words = length(ENDS) / 4;
+
+        if length(ENDS) % 4 != 0
+            || length(SORTED) != 4 * n_words
+            || length(READING_OFFSETS) != 4 * (n_words + 1)
+            || length(READINGS) % READING_SIZE != 0
+            || length(GROUP_OFFSETS) != 4 * (n_words + 1)
+            || length(GROUPS) % 4 != 0
+        {
+            return Err(corrupted("the lengths of the sections do not match"));
+        }
+
+        Ok(sections)
+    }
+
+    fn section(&self, i: usize) -> &'a [u8] {
+        let (start, end) = self.ranges[i];
+        &self.data[start..end]
+    }
+
+    /// The records of the word or lemma with the ID in the section `records` according to the section `offsets`.
+    fn records(&self, offsets: usize, records: usize, size: usize, id: u32) -> &'a [u8] {
+        let offsets = self.section(offsets);
+
+        match (
+            u32_at(offsets, id as usize),
+            u32_at(offsets, id as usize + 1),
+        ) {
+            (Some(start), Some(end)) => self
+                .section(records)
+                .get(start as usize * size..end as usize * size)
+                .unwrap_or(&[]),
+            _ => &[],
+        }
+    }
+
+    /// The number of words.
+    pub(super) fn len(&self) -> usize {
+        self.section(ENDS).len() / 4
+    }
+
+    /// The word with the ID.
+    pub(super) fn word(&self, id: u32) -> Option<&'a str> {
+        let ends = self.section(ENDS);
+        let end = u32_at(ends, id as usize)? as usize;
+        let start = match id {
+            0 => 0,
+            _ => u32_at(ends, id as usize - 1)? as usize,
+        };
+
+        std::str::from_utf8(self.section(TEXT).get(start..end)?).ok()
+    }
+
+    /// The ID of the word, found by binary search.
+    #[cfg(any(feature = "mmap", test))]
+    pub(super) fn id(&self, word: &str) -> Option<u32> {
+        let sorted = self.section(SORTED);
+        let (mut low, mut high) = (0, self.len());
+
+        while low < high {
+            let middle = (low + high) / 2;
+            let id = u32_at(sorted, middle)?;
+
+            match self.word(id)?.as_bytes().cmp(word.as_bytes()) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Some(id),
+            }
+        }
+
+        None
+    }
+
+    /// The lemma ID and tag ID of each reading of the word with the ID, in order.
+    pub(super) fn readings(&self, word_id: u32) -> impl Iterator<Item = (u32, u16)> + 'a {
+        self.records(READING_OFFSETS, READINGS, READING_SIZE, word_id)
+            .chunks_exact(READING_SIZE)
+            .map(|x| {
+                (
+                    u32::from_le_bytes([x[0], x[1], x[2], x[3]]),
+                    u16::from_le_bytes([x[4], x[5]]),
+                )
+            })
+    }
+
+    /// The IDs of the words in the group of the lemma with the ID, in order.
+    pub(super) fn group(&self, lemma_id: u32) -> impl Iterator<Item = u32> + 'a {
+        self.records(GROUP_OFFSETS, GROUPS, 4, lemma_id)
+            .chunks_exact(4)
+            .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+    }
+
+    fn meta(&self) -> Result<Meta, Error> {
+        Ok(bincode::deserialize(self.section(META))?)
+    }
+}
+
+/// Deserializes a tagger from the data of a binary onto the heap.
+pub(super) fn read(data: &[u8]) -> Result<Tagger, Error> {
+    let sections = Sections::new(data)?;
+    let meta = sections.meta()?;
+    let n_words = sections.len() as u32;
+
+    let mut words = Vec::with_capacity(n_words as usize);
+    for id in 0..n_words {
+        let word = sections
+            .word(id)
+            .ok_or_else(|| Error::CorruptedBinary(format!("word {} is not valid UTF-8", id)))?;
+        words.push((word.to_string(), id));
+    }
+
+    Ok(Tagger {
+        tags: Arena::new((0..n_words).map(|id| (id, sections.readings(id)))),
+        tag_store: meta.tag_store,
+        word_store: WordStore::new(words),
+        groups: Arena::new((0..n_words).map(|id| (id, sections.group(id)))),
+        casing: meta.casing,
+        frequencies: meta.frequencies,
+        frequency_total: meta.frequency_total,
+        folding: meta.folding,
+        folded: meta.folded,
+        tagset: meta.tagset,
+        ..Tagger::default()
+    })
+}
+
+/// A memory-mapped binary. The dictionary is read from the mapped bytes on each lookup.
+#[cfg(feature = "mmap")]
+pub(super) struct Mapped {
+    mmap: memmap2::Mmap,
+    /// The offset of the data in the binary, i. e. the length of the header.
+    offset: usize,
+    ranges: [(usize, usize); N_SECTIONS],
+}
+
+#[cfg(feature = "mmap")]
+impl Mapped {
+    pub(super) fn sections(&self) -> Sections<'_> {
+        Sections {
+            data: &self.mmap[self.offset..],
+            ranges: self.ranges,
+        }
+    }
+}
+
+/// Creates a tagger which uses the dictionary in the data of a memory-mapped binary starting at `offset`. Only the
+/// fields in the meta section are deserialized onto the heap.
+#[cfg(feature = "mmap")]
+pub(super) fn read_mapped(mmap: memmap2::Mmap, offset: usize) -> Result<Tagger, Error> {
+    let (meta, ranges) = {
+        let sections = Sections::new(&mmap[offset..])?;
+        (sections.meta()?, sections.ranges)
+    };
+    let mapped = Arc::new(Mapped {
+        mmap,
+        offset,
+        ranges,
+    });
+
+    Ok(Tagger {
+        tag_store: meta.tag_store,
+        word_store: WordStore::mapped(mapped.clone()),
+        casing: meta.casing,
+        frequencies: meta.frequencies,
+        frequency_total: meta.frequency_total,
+        folding: meta.folding,
+        folded: meta.folded,
+        tagset: meta.tagset,
+        mapped: Some(mapped),
+        ..Tagger::default()
+    })
+}
+
+/// Fails for memory-mapped taggers since their dictionary is not part of the serialized fields.
+#[cfg(feature = "mmap")]
+pub(super) fn serialize_mapped<S: Serializer>(
+    mapped: &Option<Arc<Mapped>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match mapped {
+        Some(_) => Err(serde::ser::Error::custom(
+            "a memory-mapped tagger can only be written with Tagger::to_writer",
+        )),
+        None => serializer.serialize_unit(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn tagger() -> Tagger {
+        let fixture = |name| format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+
+        Tagger::from_dumps(
+            &[fixture("tagger_dump.txt"), fixture("morfologik_dump.txt")],
+            &[] as &[&str],
+            &["ZAL"],
+            &HashSet::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn sections_match_tagger() {
+        let tagger = tagger();
+        let data = write(&tagger).unwrap();
+        let sections = Sections::new(&data).unwrap();
+
+        assert_eq!(sections.len(), tagger.word_store().len());
+        for (word, id) in tagger.word_store().iter() {
+            assert_eq!(sections.word(id), Some(word));
+            assert_eq!(sections.id(word), Some(id));
+
+            let mut readings = Vec::new();
+            tagger.for_each_reading(id, |lemma_id, tag_id| readings.push((lemma_id, tag_id)));
+            assert_eq!(sections.readings(id).collect::<Vec<_>>(), readings);
+
+            let mut members = Vec::new();
+            tagger.for_each_group_member(id, |word_id| members.push(word_id));
+            assert_eq!(sections.group(id).collect::<Vec<_>>(), members);
+        }
+        assert_eq!(sections.id("unbekannt"), None);
+        assert_eq!(sections.word(sections.len() as u32), None);
+
+        // each section starts at a multiple of 8 bytes
+        assert!(sections.ranges.iter().all(|(start, _)| start % 8 == 0));
+        assert!(matches!(
+            Sections::new(&data[..TABLE_SIZE - 1]),
+            Err(Error::CorruptedBinary(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn mmap_matches_reader() {
+        let tagger = tagger();
+        let path = std::env::temp_dir().join(format!("nlprule_mmap_{}", std::process::id()));
+        tagger
+            .to_writer(std::fs::File::create(&path).unwrap())
+            .unwrap();
+
+        let in_memory = Tagger::from_reader(std::fs::File::open(&path).unwrap()).unwrap();
+        let mapped = Tagger::from_mmap(&path).unwrap();
+
+        let words: Vec<_> = tagger
+            .word_store()
+            .iter()
+            .map(|x| x.0.to_string())
+            .collect();
+        for word in words
+            .iter()
+            .map(|x| x.as_str())
+            .chain(vec!["unbekannt", "HAUS"])
+        {
+            assert_eq!(
+                in_memory.get_tags(word, true, true),
+                mapped.get_tags(word, true, true),
+                "{}",
+                word
+            );
+            assert_eq!(in_memory.get_lemmas(word), mapped.get_lemmas(word));
+            assert_eq!(in_memory.get_group(word), mapped.get_group(word));
+        }
+        assert_eq!(in_memory.stats().readings, mapped.stats().readings);
+
+        // the mapped tagger can be written again, but not serialized with serde
+        let mut binary = Vec::new();
+        mapped.to_writer(&mut binary).unwrap();
+        assert_eq!(
+            Tagger::from_reader(binary.as_slice())
+                .unwrap()
+                .get_tags("Häuser", false, false),
+            in_memory.get_tags("Häuser", false, false)
+        );
+        assert!(bincode::serialize(&mapped).is_err());
+    }
+}
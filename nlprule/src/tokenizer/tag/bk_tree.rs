@@ -0,0 +1,159 @@
+//! A [BK-tree](https://en.wikipedia.org/wiki/BK-tree) to find the words within a Levenshtein distance of a word
+//! without computing the distance to every word. Every child of a node is keyed by its distance to the node,
+//! since the distance is a metric only children with a key within `max_distance` of the distance between the
+//! query and the node can contain matches.
+
+use crate::utils::edit_distance;
+
+/// A node of the tree. The words are not stored in the tree, only their IDs in the [WordStore][super::WordStore].
+#[derive(Debug)]
+struct Node {
+    id: u32,
+    // the distance to this node and the index of the child in the nodes
+    children: Vec<(u32, u32)>,
+}
+
+fn distance(a: &str, b: &str) -> usize {
+    edit_distance(a, b, usize::MAX).expect("distance is not bounded")
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct BkTree {
+    nodes: Vec<Node>,
+}
+
+impl BkTree {
+    /// Builds a tree of the `ids`, `word` must return the word of each ID.
+    pub fn new<'a>(ids: impl IntoIterator<Item = u32>, word: impl Fn(u32) -> &'a str) -> Self {
+        let mut nodes: Vec<Node> = Vec::new();
+
+        for id in ids {
+            let text = word(id);
+            let new_index = nodes.len() as u32;
+            let mut index = 0;
+
+            // the first word is the root, every other word is appended to the children of a node
+            while index < nodes.len() {
+                let key = distance(text, word(nodes[index].id)) as u32;
+                if key == 0 {
+                    // the word is already in the tree
+                    break;
+                }
+
+                match nodes[index].children.iter().find(|(x, _)| *x == key) {
+                    Some((_, child)) => index = *child as usize,
+                    None => {
+                        nodes[index].children.push((key, new_index));
+                        index = nodes.len();
+                    }
+                }
+            }
+
+            if index == nodes.len() {
+                nodes.push(Node {
+                    id,
+                    children: Vec::new(),
+                });
+            }
+        }
+
+        for node in nodes.iter_mut() {
+            node.children.shrink_to_fit();
+        }
+
+        BkTree { nodes }
+    }
+
+    /// The IDs of the words within the Levenshtein distance `max_distance` of `query` and their distance,
+    /// in arbitrary order. `word` must return the word of each ID like when building the tree.
+    pub fn find<'a>(
+        &self,
+        query: &str,
+        max_distance: usize,
+        word: impl Fn(u32) -> &'a str,
+    ) -> Vec<(u32, usize)> {
+        let mut output = Vec::new();
+        let mut stack = if self.nodes.is_empty() {
+            Vec::new()
+        } else {
+            vec![0]
+        };
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let d = distance(query, word(node.id));
+
+            if d <= max_distance {
+                output.push((node.id, d));
+            }
+
+            let min_key = d.saturating_sub(max_distance);
+            let max_key = d.saturating_add(max_distance);
+            stack.extend(
+                node.children
+                    .iter()
+                    .filter(|(key, _)| (min_key..=max_key).contains(&(*key as usize)))
+                    .map(|(_, child)| *child as usize),
+            );
+        }
+
+        output
+    }
+
+    /// An estimate of the heap memory used by the tree in bytes.
+    pub fn heap_bytes(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<Node>()
+            + self
+                .nodes
+                .iter()
+                .map(|x| x.children.capacity() * std::mem::size_of::<(u32, u32)>())
+                .sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck_macros::quickcheck;
+
+    fn find(words: &[String], query: &str, max_distance: usize) -> Vec<(u32, usize)> {
+        let tree = BkTree::new(0..words.len() as u32, |id| words[id as usize].as_str());
+        let mut output = tree.find(query, max_distance, |id| words[id as usize].as_str());
+        output.sort_unstable();
+        output
+    }
+
+    #[test]
+    fn finds_similar_words() {
+        let words: Vec<String> = ["book", "books", "cake", "boo", "cape", "cart", "boon"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+
+        assert_eq!(find(&words, "bool", 1), vec![(0, 1), (3, 1), (6, 1)]);
+        assert_eq!(find(&words, "cake", 0), vec![(2, 0)]);
+        assert_eq!(find(&words, "xyz", 1), vec![]);
+        assert_eq!(find(&[], "book", 2), vec![]);
+    }
+
+    #[quickcheck]
+    fn finds_same_words_as_scan(words: Vec<String>, query: String, max_distance: u8) -> bool {
+        // the words of a store are unique
+        let words: Vec<String> = words
+            .into_iter()
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let max_distance = max_distance as usize % 4;
+
+        let scan: Vec<_> = words
+            .iter()
+            .enumerate()
+            .filter_map(|(id, word)| {
+                edit_distance(&query, word, max_distance).map(|distance| (id as u32, distance))
+            })
+            .collect();
+
+        find(&words, &query, max_distance) == scan
+    }
+}
@@ -0,0 +1,105 @@
+//! The lines of the dumps while a tagger is built. Dumps like the German one have millions of lines, but only a few
+//! hundred thousand distinct words and lemmas and about a thousand distinct tags, so the strings are interned and
+//! each line is stored as three IDs instead of three strings.
+
+use indexmap::IndexSet;
+
+/// The ID of an interned word or lemma. Lemmas are words of the dictionary as well, so both share the IDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) struct LemmaId(pub u32);
+
+/// The ID of an interned part-of-speech tag. Not the same as the ID of the tag in the [TagStore][super::TagStore].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) struct PosId(pub u32);
+
+fn intern(strings: &mut IndexSet<String>, string: &str) -> u32 {
+    match strings.get_index_of(string) {
+        Some(index) => index as u32,
+        None => strings.insert_full(string.to_string()).0 as u32,
+    }
+}
+
+/// Lines of the form `(word, lemma, tag)` with interned strings, in the order they were added.
+#[derive(Debug, Default)]
+pub(super) struct Lines {
+    words: IndexSet<String>,
+    tags: IndexSet<String>,
+    entries: Vec<(LemmaId, (LemmaId, PosId))>,
+}
+
+impl Lines {
+    pub fn push(&mut self, word: &str, lemma: &str, tag: &str) {
+        let word = LemmaId(intern(&mut self.words, word));
+        let lemma = LemmaId(intern(&mut self.words, lemma));
+        let tag = PosId(intern(&mut self.tags, tag));
+
+        self.entries.push((word, (lemma, tag)));
+    }
+
+    pub fn word(&self, id: LemmaId) -> &str {
+        &self.words[id.0 as usize]
+    }
+
+    pub fn tag(&self, id: PosId) -> &str {
+        &self.tags[id.0 as usize]
+    }
+
+    /// The number of interned words and lemmas, including the ones of removed lines.
+    pub fn word_count(&self) -> usize {
+        self.words.len()
+    }
+
+    /// The number of interned tags, including the ones of removed lines.
+    pub fn tag_count(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// The word, lemma and tag IDs of the lines.
+    pub fn entries(&self) -> &[(LemmaId, (LemmaId, PosId))] {
+        &self.entries
+    }
+
+    /// Removes the lines for which `keep` returns false. The strings stay interned.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str, &str, &str) -> bool) {
+        let (words, tags) = (&self.words, &self.tags);
+
+        self.entries.retain(|(word, (lemma, tag))| {
+            keep(
+                &words[word.0 as usize],
+                &words[lemma.0 as usize],
+                &tags[tag.0 as usize],
+            )
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_are_interned() {
+        let mut lines = Lines::default();
+        lines.push("Häuser", "Haus", "SUB:NOM:PLU:NEU");
+        lines.push("Haus", "Haus", "SUB:NOM:SIN:NEU");
+        lines.push("Häuser", "Haus", "SUB:AKK:PLU:NEU");
+
+        assert_eq!((lines.word_count(), lines.tag_count()), (2, 3));
+        assert_eq!(lines.entries()[0].1 .0, lines.entries()[1].0);
+        assert_eq!(lines.word(lines.entries()[2].0), "Häuser");
+
+        lines.retain(|word, _, tag| word != "Häuser" || tag.contains("NOM"));
+        let resolved: Vec<_> = lines
+            .entries()
+            .iter()
+            .map(|(word, (lemma, tag))| (lines.word(*word), lines.word(*lemma), lines.tag(*tag)))
+            .collect();
+        assert_eq!(
+            resolved,
+            vec![
+                ("Häuser", "Haus", "SUB:NOM:PLU:NEU"),
+                ("Haus", "Haus", "SUB:NOM:SIN:NEU")
+            ]
+        );
+    }
+}
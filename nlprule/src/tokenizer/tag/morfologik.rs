@@ -0,0 +1,548 @@
+//! Reads the binary dictionaries of [morfologik](https://github.com/morfologik/morfologik-stemming) which
+//! LanguageTool uses for its taggers. A dictionary consists of a `.dict` file with a finite state automaton
+//! (in the FSA5 or CFSA2 format) and a `.info` file with the settings needed to decode its sequences.
+//! Every sequence accepted by the automaton is one entry of the form `word<sep>encoded lemma<sep>tag`.
+
+use super::intern::Lines;
+use crate::Error;
+
+const MAGIC: &[u8; 4] = b"\\fsa";
+const VERSION_FSA5: u8 = 5;
+const VERSION_CFSA2: u8 = 0xc6;
+
+/// A byte of an encoded lemma is the number of bytes to remove from the word plus this offset.
+const ENCODED_OFFSET: u8 = b'A';
+/// Marks that the entire word is removed when decoding the lemma.
+const REMOVE_EVERYTHING: usize = 255;
+
+fn corrupted(message: &str) -> Error {
+    Error::CorruptedBinary(format!("morfologik dictionary: {}", message))
+}
+
+/// How the lemma is encoded relative to the word.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoder {
+    /// The lemma is stored as is.
+    None,
+    /// Removes bytes from the end of the word, then appends the rest.
+    Suffix,
+    /// Removes bytes from the start and the end of the word, then appends the rest.
+    Prefix,
+    /// Removes bytes from the middle and the end of the word, then appends the rest.
+    Infix,
+}
+
+impl Encoder {
+    fn decode(self, word: &[u8], encoded: &[u8]) -> Result<Vec<u8>, Error> {
+        let n_header = match self {
+            Encoder::None => return Ok(encoded.to_vec()),
+            Encoder::Suffix => 1,
+            Encoder::Prefix => 2,
+            Encoder::Infix => 3,
+        };
+
+        if encoded.len() < n_header {
+            return Err(corrupted("encoded lemma is too short"));
+        }
+
+        let counts: Vec<usize> = encoded[..n_header]
+            .iter()
+            .map(|x| x.wrapping_sub(ENCODED_OFFSET) as usize)
+            .collect();
+
+        // the bytes of the word which are kept are `keep` without `infix`
+        let (keep, infix) = if counts.contains(&REMOVE_EVERYTHING) {
+            (0..0, 0..0)
+        } else {
+            let suffix = counts[n_header - 1];
+            let end = word
+                .len()
+                .checked_sub(suffix)
+                .ok_or_else(|| corrupted("encoded lemma removes more than the word"))?;
+
+            match self {
+                Encoder::Suffix => (0..end, 0..0),
+                Encoder::Prefix => (counts[0]..end, 0..0),
+                _ => (0..end, counts[0]..counts[0] + counts[1]),
+            }
+        };
+
+        if keep.start > keep.end || infix.end > keep.end {
+            return Err(corrupted("encoded lemma removes more than the word"));
+        }
+
+        let mut lemma = Vec::with_capacity(keep.len() + encoded.len() - n_header);
+        lemma.extend_from_slice(&word[keep.start..infix.start.max(keep.start)]);
+        lemma.extend_from_slice(&word[infix.end.max(keep.start)..keep.end]);
+        lemma.extend_from_slice(&encoded[n_header..]);
+        Ok(lemma)
+    }
+}
+
+/// The character encoding of the sequences.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+    Utf8,
+    Latin1,
+}
+
+impl Encoding {
+    fn decode(self, bytes: &[u8]) -> Result<String, Error> {
+        match self {
+            Encoding::Utf8 => String::from_utf8(bytes.to_vec())
+                .map_err(|_| corrupted("sequence is not valid UTF-8")),
+            Encoding::Latin1 => Ok(bytes.iter().map(|x| *x as char).collect()),
+        }
+    }
+}
+
+/// The settings from the `.info` file.
+#[derive(Debug, Clone, PartialEq)]
+struct Info {
+    separator: u8,
+    encoding: Encoding,
+    encoder: Encoder,
+}
+
+impl Info {
+    /// Parses the `.info` file, which is in the Java properties format.
+    fn parse(text: &str) -> Result<Self, Error> {
+        let mut separator = None;
+        let mut encoding = None;
+        let mut encoder = None;
+        let mut uses_prefixes = false;
+        let mut uses_infixes = false;
+
+        for line in text.lines().map(|x| x.trim()) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+                continue;
+            }
+
+            let index = line.find(&['=', ':'][..]).unwrap_or(line.len());
+            let key = line[..index].trim();
+            let value = line.get(index + 1..).unwrap_or("").trim();
+
+            match key {
+                "fsa.dict.separator" => separator = Some(value.to_string()),
+                "fsa.dict.encoding" => encoding = Some(value.to_lowercase()),
+                "fsa.dict.encoder" => encoder = Some(value.to_lowercase()),
+                "fsa.dict.uses-prefixes" => uses_prefixes = value == "true",
+                "fsa.dict.uses-infixes" => uses_infixes = value == "true",
+                _ => {}
+            }
+        }
+
+        let encoding = match encoding.as_deref() {
+            Some("utf-8") | Some("utf8") => Encoding::Utf8,
+            Some("iso-8859-1") | Some("iso8859-1") | Some("latin1") => Encoding::Latin1,
+            Some(x) => {
+                return Err(Error::Unimplemented(format!(
+                    "morfologik dictionaries with encoding {}",
+                    x
+                )))
+            }
+            None => {
+                return Err(Error::Unexpected(
+                    "morfologik info does not set fsa.dict.encoding".into(),
+                ))
+            }
+        };
+
+        let separator = match separator.as_deref().map(|x| x.as_bytes()) {
+            Some([x]) => *x,
+            _ => {
+                return Err(Error::Unexpected(
+                    "morfologik info must set fsa.dict.separator to a single byte".into(),
+                ))
+            }
+        };
+
+        // older dictionaries set the flags instead of the encoder
+        let encoder = match encoder.as_deref() {
+            Some("none") => Encoder::None,
+            Some("suffix") => Encoder::Suffix,
+            Some("prefix") => Encoder::Prefix,
+            Some("infix") => Encoder::Infix,
+            Some(x) => {
+                return Err(Error::Unimplemented(format!(
+                    "morfologik dictionaries with encoder {}",
+                    x
+                )))
+            }
+            None if uses_infixes => Encoder::Infix,
+            None if uses_prefixes => Encoder::Prefix,
+            None => Encoder::Suffix,
+        };
+
+        Ok(Info {
+            separator,
+            encoding,
+            encoder,
+        })
+    }
+}
+
+/// The variants of the automaton. Nodes and arcs are identified by their offset in `arcs`.
+/// An arc offset of zero marks that there is no further arc.
+#[derive(Debug)]
+enum Fsa<'a> {
+    Fsa5 {
+        arcs: &'a [u8],
+        node_data_length: usize,
+        goto_length: usize,
+    },
+    Cfsa2 {
+        arcs: &'a [u8],
+        label_mapping: &'a [u8],
+        has_numbers: bool,
+    },
+}
+
+impl<'a> Fsa<'a> {
+    // FSA5: each arc is a label followed by the flags in the lowest bits of the goto address.
+    const FSA5_FINAL: u8 = 1 << 0;
+    const FSA5_LAST: u8 = 1 << 1;
+    const FSA5_NEXT: u8 = 1 << 2;
+
+    // CFSA2: each arc starts with a byte of flags and the index of its label in the label mapping.
+    const CFSA2_NEXT: u8 = 1 << 7;
+    const CFSA2_LAST: u8 = 1 << 6;
+    const CFSA2_FINAL: u8 = 1 << 5;
+    const CFSA2_LABEL_INDEX_MASK: u8 = (1 << 5) - 1;
+    const CFSA2_NUMBERS: u16 = 1 << 8;
+
+    fn new(data: &'a [u8]) -> Result<Self, Error> {
+        if data.len() < 5 || &data[..4] != MAGIC {
+            return Err(corrupted("not a finite state automaton"));
+        }
+
+        match data[4] {
+            VERSION_FSA5 => {
+                if data.len() < 8 {
+                    return Err(corrupted("incomplete FSA5 header"));
+                }
+
+                // data[5] is the filler and data[6] the annotation byte which are not needed
+                Ok(Fsa::Fsa5 {
+                    node_data_length: (data[7] >> 4) as usize,
+                    goto_length: (data[7] & 0x0f) as usize,
+                    arcs: &data[8..],
+                })
+            }
+            VERSION_CFSA2 => {
+                if data.len() < 8 || data.len() < 8 + data[7] as usize {
+                    return Err(corrupted("incomplete CFSA2 header"));
+                }
+
+                let flags = u16::from_be_bytes([data[5], data[6]]);
+                let mapping_end = 8 + data[7] as usize;
+
+                Ok(Fsa::Cfsa2 {
+                    has_numbers: flags & Fsa::CFSA2_NUMBERS != 0,
+                    label_mapping: &data[8..mapping_end],
+                    arcs: &data[mapping_end..],
+                })
+            }
+            x => Err(Error::Unimplemented(format!(
+                "morfologik automata with version {}",
+                x
+            ))),
+        }
+    }
+
+    fn arcs(&self) -> &'a [u8] {
+        match self {
+            Fsa::Fsa5 { arcs, .. } | Fsa::Cfsa2 { arcs, .. } => arcs,
+        }
+    }
+
+    /// Reads a byte, out of bounds reads are caught by [sequences][Fsa::sequences].
+    fn byte(&self, offset: usize) -> u8 {
+        self.arcs().get(offset).copied().unwrap_or(0)
+    }
+
+    fn flags(&self, arc: usize) -> u8 {
+        match self {
+            Fsa::Fsa5 { .. } => self.byte(arc + 1),
+            Fsa::Cfsa2 { .. } => self.byte(arc),
+        }
+    }
+
+    fn is_final(&self, arc: usize) -> bool {
+        match self {
+            Fsa::Fsa5 { .. } => self.flags(arc) & Fsa::FSA5_FINAL != 0,
+            Fsa::Cfsa2 { .. } => self.flags(arc) & Fsa::CFSA2_FINAL != 0,
+        }
+    }
+
+    fn is_last(&self, arc: usize) -> bool {
+        match self {
+            Fsa::Fsa5 { .. } => self.flags(arc) & Fsa::FSA5_LAST != 0,
+            Fsa::Cfsa2 { .. } => self.flags(arc) & Fsa::CFSA2_LAST != 0,
+        }
+    }
+
+    fn is_next_set(&self, arc: usize) -> bool {
+        match self {
+            Fsa::Fsa5 { .. } => self.flags(arc) & Fsa::FSA5_NEXT != 0,
+            Fsa::Cfsa2 { .. } => self.flags(arc) & Fsa::CFSA2_NEXT != 0,
+        }
+    }
+
+    fn label(&self, arc: usize) -> u8 {
+        match self {
+            Fsa::Fsa5 { .. } => self.byte(arc),
+            Fsa::Cfsa2 { label_mapping, .. } => {
+                match (self.byte(arc) & Fsa::CFSA2_LABEL_INDEX_MASK) as usize {
+                    0 => self.byte(arc + 1),
+                    index => label_mapping.get(index).copied().unwrap_or(0),
+                }
+            }
+        }
+    }
+
+    /// Reads a variable length integer, returning the value and the offset after it.
+    fn read_vint(&self, mut offset: usize) -> (usize, usize) {
+        let mut value = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.byte(offset);
+            offset += 1;
+            if shift < std::mem::size_of::<usize>() * 8 {
+                value |= ((byte & 0x7f) as usize) << shift;
+            }
+            shift += 7;
+
+            if byte & 0x80 == 0 || offset >= self.arcs().len() {
+                return (value, offset);
+            }
+        }
+    }
+
+    fn skip_arc(&self, arc: usize) -> usize {
+        match self {
+            Fsa::Fsa5 { goto_length, .. } => {
+                arc + if self.is_next_set(arc) {
+                    2
+                } else {
+                    1 + goto_length
+                }
+            }
+            Fsa::Cfsa2 { .. } => {
+                let mut offset = arc + 1;
+                if self.byte(arc) & Fsa::CFSA2_LABEL_INDEX_MASK == 0 {
+                    offset += 1;
+                }
+                if !self.is_next_set(arc) {
+                    offset = self.read_vint(offset).1;
+                }
+                offset
+            }
+        }
+    }
+
+    fn first_arc(&self, node: usize) -> usize {
+        match self {
+            Fsa::Fsa5 {
+                node_data_length, ..
+            } => node + node_data_length,
+            Fsa::Cfsa2 {
+                has_numbers: true, ..
+            } => self.read_vint(node).1,
+            Fsa::Cfsa2 { .. } => node,
+        }
+    }
+
+    fn next_arc(&self, arc: usize) -> usize {
+        if self.is_last(arc) {
+            0
+        } else {
+            self.skip_arc(arc)
+        }
+    }
+
+    /// The node the arc points to, zero for terminal arcs.
+    fn destination(&self, mut arc: usize) -> usize {
+        match self {
+            Fsa::Fsa5 { goto_length, .. } => {
+                if self.is_next_set(arc) {
+                    self.skip_arc(arc)
+                } else {
+                    let address = (0..*goto_length)
+                        .rev()
+                        .fold(0, |acc, i| acc << 8 | self.byte(arc + 1 + i) as usize);
+                    address >> 3
+                }
+            }
+            Fsa::Cfsa2 { .. } => {
+                if self.is_next_set(arc) {
+                    // the destination follows the last arc of this node
+                    while !self.is_last(arc) && arc < self.arcs().len() {
+                        arc = self.next_arc(arc);
+                    }
+                    self.skip_arc(arc)
+                } else {
+                    let label_length = if self.byte(arc) & Fsa::CFSA2_LABEL_INDEX_MASK == 0 {
+                        2
+                    } else {
+                        1
+                    };
+                    self.read_vint(arc + label_length).0
+                }
+            }
+        }
+    }
+
+    fn root(&self) -> usize {
+        match self {
+            // the first node is a dummy node, the epsilon node after it points to the root
+            Fsa::Fsa5 { .. } => {
+                let epsilon = self.skip_arc(self.first_arc(0));
+                self.destination(self.first_arc(epsilon))
+            }
+            Fsa::Cfsa2 { .. } => self.destination(self.first_arc(0)),
+        }
+    }
+
+    /// Returns all sequences accepted by the automaton.
+    fn sequences(&self) -> Result<Vec<Vec<u8>>, Error> {
+        let n_bytes = self.arcs().len();
+        let mut output = Vec::new();
+        let mut sequence = Vec::new();
+        let mut stack = vec![(self.first_arc(self.root()), 0)];
+
+        while let Some((arc, depth)) = stack.pop() {
+            if arc == 0 {
+                continue;
+            }
+            // a valid automaton is acyclic, so no sequence can be longer than the number of bytes
+            if arc >= n_bytes || depth > n_bytes {
+                return Err(corrupted("arc is out of bounds"));
+            }
+
+            stack.push((self.next_arc(arc), depth));
+
+            sequence.truncate(depth);
+            sequence.push(self.label(arc));
+
+            if self.is_final(arc) {
+                output.push(sequence.clone());
+            }
+
+            let destination = self.destination(arc);
+            if destination != 0 {
+                stack.push((self.first_arc(destination), depth + 1));
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+/// Reads the entries of a morfologik dictionary as lines of `(word, lemma, tag)`.
+///
+/// # Arguments
+/// * `dict`: The content of the `.dict` file.
+/// * `info`: The content of the `.info` file.
+pub(crate) fn read_entries(dict: &[u8], info: &str) -> Result<Lines, Error> {
+    let info = Info::parse(info)?;
+    let mut lines = Lines::default();
+
+    for sequence in Fsa::new(dict)?.sequences()? {
+        let mut parts = sequence.splitn(3, |x| *x == info.separator);
+        let word = parts.next().unwrap_or_default();
+        let encoded = parts
+            .next()
+            .ok_or_else(|| corrupted("sequence without separator"))?;
+        let tag = parts.next().unwrap_or_default();
+
+        let lemma = info.encoder.decode(word, encoded)?;
+
+        lines.push(
+            &info.encoding.decode(word)?,
+            &info.encoding.decode(&lemma)?,
+            &info.encoding.decode(tag)?,
+        );
+    }
+
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lemmas_are_decoded() {
+        let decode = |encoder: Encoder, word: &str, encoded: &str| {
+            String::from_utf8(encoder.decode(word.as_bytes(), encoded.as_bytes()).unwrap()).unwrap()
+        };
+
+        assert_eq!(decode(Encoder::None, "huse", "hus"), "hus");
+        assert_eq!(decode(Encoder::Suffix, "huse", "B"), "hus");
+        assert_eq!(decode(Encoder::Suffix, "gik", "Cå"), "gå");
+        assert_eq!(decode(Encoder::Suffix, "bedre", "Fgod"), "god");
+        assert_eq!(decode(Encoder::Prefix, "ugift", "BA"), "gift");
+        assert_eq!(decode(Encoder::Prefix, "uheldige", "BB"), "heldig");
+        assert_eq!(decode(Encoder::Infix, "aufgegessen", "DDA"), "aufessen");
+        assert_eq!(decode(Encoder::Infix, "ugift", "ABA"), "gift");
+        assert_eq!(decode(Encoder::Infix, "ugift", "\u{40}AAgift"), "gift");
+
+        assert!(Encoder::Suffix.decode(b"hus", b"E").is_err());
+        assert!(Encoder::Infix.decode(b"hus", b"CCA").is_err());
+    }
+
+    #[test]
+    fn info_is_parsed() {
+        let info = Info::parse(
+            "# comment\nfsa.dict.separator=+\nfsa.dict.encoding = UTF-8\nfsa.dict.encoder: prefix\n",
+        )
+        .unwrap();
+        assert_eq!(
+            info,
+            Info {
+                separator: b'+',
+                encoding: Encoding::Utf8,
+                encoder: Encoder::Prefix
+            }
+        );
+
+        let legacy = Info::parse(
+            "fsa.dict.separator=+\nfsa.dict.encoding=iso-8859-1\nfsa.dict.uses-infixes=true\n",
+        )
+        .unwrap();
+        assert_eq!(legacy.encoder, Encoder::Infix);
+        assert_eq!(legacy.encoding, Encoding::Latin1);
+
+        assert!(Info::parse("fsa.dict.encoding=utf-8\n").is_err());
+        assert!(Info::parse("fsa.dict.separator=+\nfsa.dict.encoding=iso-8859-2\n").is_err());
+    }
+
+    #[test]
+    fn invalid_automata_are_rejected() {
+        assert!(read_entries(
+            b"\\fsb\x05",
+            "fsa.dict.separator=+\nfsa.dict.encoding=utf-8"
+        )
+        .is_err());
+        assert!(read_entries(
+            b"\\fsa\x05\x5f",
+            "fsa.dict.separator=+\nfsa.dict.encoding=utf-8"
+        )
+        .is_err());
+        assert!(matches!(
+            read_entries(
+                b"\\fsa\x09\0\0\0",
+                "fsa.dict.separator=+\nfsa.dict.encoding=utf-8"
+            ),
+            Err(Error::Unimplemented(_))
+        ));
+        // the root points past the end of the data
+        assert!(read_entries(
+            b"\\fsa\x05_+\x03\0\x02\0\0\0\x02\xff\0",
+            "fsa.dict.separator=+\nfsa.dict.encoding=utf-8"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,284 @@
+//! Checks dumps for problems which are silently ignored (or silently accepted) when building a tagger,
+//! see [Tagger::validate].
+
+use super::{DumpDir, Tagger};
+use crate::{types::DefaultHashMap, Error};
+use std::collections::HashSet;
+use std::io::BufRead;
+use std::path::Path;
+
+/// How severe a [ValidationIssue] is, e. g. to only fail CI on errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Purely informative, like the statistics of a file.
+    Info,
+    /// Likely a mistake, but the tagger can be built.
+    Warning,
+    /// The entry is dropped or wrong when building the tagger.
+    Error,
+}
+
+/// The kind of a [ValidationIssue].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IssueKind {
+    /// A line with less than three tab-separated fields or an empty word or tag.
+    MalformedLine,
+    /// A (word, lemma, tag) entry which already occurred at the given path and line.
+    Duplicate { path: String, line: usize },
+    /// A tag which is not in the known tags of the language.
+    UnknownTag(String),
+    /// An entry with an empty lemma.
+    EmptyLemma,
+    /// An entry whose word contains whitespace, so it can never match a token.
+    WhitespaceInWord,
+    /// Statistics of a file: the number of lines, of entries and of distinct words and tags.
+    Statistics {
+        lines: usize,
+        entries: usize,
+        words: usize,
+        tags: usize,
+    },
+}
+
+impl IssueKind {
+    /// The severity of issues of this kind.
+    pub fn severity(&self) -> Severity {
+        match self {
+            IssueKind::MalformedLine | IssueKind::UnknownTag(_) => Severity::Error,
+            IssueKind::Duplicate { .. } | IssueKind::EmptyLemma | IssueKind::WhitespaceInWord => {
+                Severity::Warning
+            }
+            IssueKind::Statistics { .. } => Severity::Info,
+        }
+    }
+}
+
+/// A problem with a dump found by [Tagger::validate].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub kind: IssueKind,
+    /// The path of the file.
+    pub path: String,
+    /// The 1-based line in the file. `None` for issues which concern the whole file.
+    pub line: Option<usize>,
+    /// The content of the line. Empty for issues which concern the whole file.
+    pub content: String,
+}
+
+impl ValidationIssue {
+    /// The severity of the issue, see [IssueKind::severity].
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl Tagger {
+    /// Checks the dumps at the path, which is either a single dump or a directory in the layout of
+    /// [from_dump_dir][Tagger::from_dump_dir], and reports malformed lines, duplicate entries, tags which are not in
+    /// `known_tags` (not checked if `known_tags` is empty), empty lemmas and words containing whitespace.
+    /// The statistics of each file are reported as issues with [Severity::Info]. A `*` as lemma or tag in
+    /// `removed.txt` and a line with only a word in `overrides.txt` are not reported.
+    ///
+    /// Fails if the path or one of the files can not be read.
+    pub fn validate<P: AsRef<Path>, S: AsRef<str>>(
+        path: P,
+        known_tags: &[S],
+    ) -> Result<Vec<ValidationIssue>, Error> {
+        let path = path.as_ref();
+        let known_tags: HashSet<&str> = known_tags.iter().map(|x| x.as_ref()).collect();
+
+        let (paths, removed, overrides) = if path.is_dir() {
+            let dir = DumpDir::read(path)?;
+            let mut paths = dir.dumps;
+            paths.sort_by_key(|x| (Path::new(x).file_name().map(|x| x.to_owned()), x.clone()));
+            paths.extend(dir.removed.iter().cloned());
+            paths.extend(dir.added);
+            paths.extend(dir.overrides.iter().cloned());
+            (paths, dir.removed, dir.overrides)
+        } else {
+            (
+                vec![path.to_string_lossy().into_owned()],
+                Vec::new(),
+                Vec::new(),
+            )
+        };
+
+        let mut issues = Vec::new();
+        // the first occurrence of each entry per kind of file, e. g. removals are not duplicates of additions
+        let mut seen: DefaultHashMap<((bool, bool), String), (String, usize)> =
+            DefaultHashMap::default();
+
+        for path in paths {
+            let is_removal = removed.contains(&path);
+            let is_override = overrides.contains(&path);
+            let reader = Tagger::open_dump(&path).map_err(|source| Error::DumpUnreadable {
+                path: path.clone(),
+                source,
+            })?;
+
+            let mut n_lines = 0;
+            let mut n_entries = 0;
+            let mut words = HashSet::new();
+            let mut tags = HashSet::new();
+
+            for (i, line) in reader.lines().enumerate() {
+                let line = line.map_err(|source| Error::DumpUnreadable {
+                    path: path.clone(),
+                    source,
+                })?;
+                n_lines += 1;
+
+                if line.trim().is_empty() || line.starts_with('#') {
+                    continue;
+                }
+
+                let mut issue = |kind| {
+                    issues.push(ValidationIssue {
+                        kind,
+                        path: path.clone(),
+                        line: Some(i + 1),
+                        content: line.clone(),
+                    })
+                };
+
+                let parts: Vec<_> = line.split('\t').collect();
+                let (word, lemma, tag) = match parts.as_slice() {
+                    // clears the readings of the word
+                    [word] if is_override && !word.is_empty() => continue,
+                    [word, lemma, tag, ..] if !word.is_empty() && !tag.is_empty() => {
+                        (*word, *lemma, *tag)
+                    }
+                    _ => {
+                        issue(IssueKind::MalformedLine);
+                        continue;
+                    }
+                };
+
+                if lemma.is_empty() {
+                    issue(IssueKind::EmptyLemma);
+                }
+                if word.contains(char::is_whitespace) {
+                    issue(IssueKind::WhitespaceInWord);
+                }
+                let is_known_tag =
+                    known_tags.is_empty() || known_tags.contains(tag) || (is_removal && tag == "*");
+                if !is_known_tag {
+                    issue(IssueKind::UnknownTag(tag.to_string()));
+                }
+
+                let key = (
+                    (is_removal, is_override),
+                    format!("{}\t{}\t{}", word, lemma, tag),
+                );
+                match seen.get(&key) {
+                    Some((path, line)) => issue(IssueKind::Duplicate {
+                        path: path.clone(),
+                        line: *line,
+                    }),
+                    None => {
+                        seen.insert(key, (path.clone(), i + 1));
+                    }
+                }
+
+                n_entries += 1;
+                words.insert(word.to_string());
+                tags.insert(tag.to_string());
+            }
+
+            issues.push(ValidationIssue {
+                kind: IssueKind::Statistics {
+                    lines: n_lines,
+                    entries: n_entries,
+                    words: words.len(),
+                    tags: tags.len(),
+                },
+                path: path.clone(),
+                line: None,
+                content: String::new(),
+            });
+        }
+
+        Ok(issues)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn broken_dumps() {
+        let dir = format!("{}/tests/fixtures/broken_dumps", env!("CARGO_MANIFEST_DIR"));
+        let issues = Tagger::validate(
+            &dir,
+            &["SUB:NOM:SIN:NEU", "SUB:NOM:PLU:NEU", "VER:3:SIN", "EIG"],
+        )
+        .unwrap();
+
+        let find = |kind: IssueKind| {
+            let matches: Vec<_> = issues.iter().filter(|x| x.kind == kind).collect();
+            assert_eq!(matches.len(), 1, "{:?} in {:?}", kind, issues);
+            (
+                Path::new(&matches[0].path)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned(),
+                matches[0].line,
+                matches[0].severity(),
+            )
+        };
+        let a = format!("{}/a.dump", dir);
+
+        assert_eq!(
+            find(IssueKind::Duplicate { path: a, line: 1 }),
+            ("a.dump".into(), Some(2), Severity::Warning)
+        );
+        assert_eq!(
+            find(IssueKind::MalformedLine),
+            ("a.dump".into(), Some(3), Severity::Error)
+        );
+        assert_eq!(
+            find(IssueKind::EmptyLemma),
+            ("a.dump".into(), Some(4), Severity::Warning)
+        );
+        assert_eq!(
+            find(IssueKind::WhitespaceInWord),
+            ("a.dump".into(), Some(5), Severity::Warning)
+        );
+        assert_eq!(
+            find(IssueKind::UnknownTag("VRB:3:SIN".into())),
+            ("b.dump".into(), Some(3), Severity::Error)
+        );
+
+        let statistics: Vec<_> = issues
+            .iter()
+            .filter(|x| x.severity() == Severity::Info)
+            .map(|x| x.kind.clone())
+            .collect();
+        assert_eq!(
+            statistics,
+            vec![
+                IssueKind::Statistics {
+                    lines: 5,
+                    entries: 4,
+                    words: 3,
+                    tags: 3
+                },
+                IssueKind::Statistics {
+                    lines: 3,
+                    entries: 2,
+                    words: 2,
+                    tags: 2
+                },
+                IssueKind::Statistics {
+                    lines: 1,
+                    entries: 1,
+                    words: 1,
+                    tags: 1
+                },
+            ]
+        );
+        assert_eq!(issues.len(), 8);
+    }
+}
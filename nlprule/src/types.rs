@@ -14,14 +14,16 @@ pub(crate) type DefaultHashSet<T> = HashSet<T>;
 pub(crate) type DefaultHasher = hash_map::DefaultHasher;
 
 /// Owned versions of the types for use in longer-living structures not bound to the `'t` lifetime e.g. rule tests.
+/// All owned types implement [Serialize] and [Deserialize].
 pub mod owned {
+    use crate::tokenizer::tag::Tagger;
     use serde::{Deserialize, Serialize};
 
-    #[derive(Debug, Serialize, Deserialize, Hash, Eq, PartialEq)]
+    #[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
     pub struct WordId(pub String, pub Option<u32>);
 
     impl WordId {
-        pub fn as_ref_id(&self) -> super::WordId {
+        pub fn as_ref_id(&self) -> super::WordId<'_> {
             super::WordId(self.0.as_str().into(), self.1)
         }
     }
@@ -32,11 +34,11 @@ pub mod owned {
         }
     }
 
-    #[derive(Debug, Serialize, Deserialize, Hash, Eq, PartialEq)]
+    #[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
     pub struct PosId(pub String, pub u16);
 
     impl PosId {
-        pub fn as_ref_id(&self) -> super::PosId {
+        pub fn as_ref_id(&self) -> super::PosId<'_> {
             super::PosId(self.0.as_str(), self.1)
         }
     }
@@ -47,32 +49,201 @@ pub mod owned {
         }
     }
 
-    #[derive(Debug, Serialize, Deserialize, Hash, Eq, PartialEq)]
+    #[derive(Debug, Clone, Serialize, Deserialize, Hash, Eq, PartialEq)]
     pub struct WordData {
         pub lemma: WordId,
         pub pos: PosId,
+        #[serde(default)]
+        pub approximate: bool,
     }
 
     impl WordData {
         pub fn new(lemma: WordId, pos_id: PosId) -> Self {
-            WordData { lemma, pos: pos_id }
+            WordData {
+                lemma,
+                pos: pos_id,
+                approximate: false,
+            }
+        }
+
+        pub fn as_ref_word_data(&self) -> super::WordData<'_> {
+            super::WordData {
+                approximate: self.approximate,
+                ..super::WordData::new(self.lemma.as_ref_id(), self.pos.as_ref_id())
+            }
         }
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
     pub struct Word {
         pub text: WordId,
         pub tags: Vec<WordData>,
     }
 
-    #[derive(Debug, Serialize, Deserialize)]
+    impl Word {
+        pub fn as_ref_word(&self) -> super::Word<'_> {
+            super::Word::new_with_tags(
+                self.text.as_ref_id(),
+                self.tags.iter().map(|x| x.as_ref_word_data()).collect(),
+            )
+        }
+    }
+
+    #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
     pub struct Token {
         pub word: Word,
         pub char_span: (usize, usize),
+        pub grapheme_span: (usize, usize),
+        pub utf16_span: (usize, usize),
         pub byte_span: (usize, usize),
+        pub kind: super::TokenKind,
+        pub case: super::Case,
+        pub token_index: usize,
+        pub sentence_index: usize,
+        #[serde(default)]
+        pub has_lowercase_readings: bool,
+        #[serde(default)]
+        pub frequency: Option<u32>,
         pub has_space_before: bool,
+        pub space_before: String,
         pub chunks: Vec<String>,
     }
+
+    impl Token {
+        /// Borrows this token as a [Token][super::Token] again. `text` must be the text this token was created from,
+        /// `tagger` must be the tagger of the tokenizer which created this token.
+        pub fn as_ref_token<'t>(&'t self, text: &'t str, tagger: &'t Tagger) -> super::Token<'t> {
+            super::Token {
+                word: self.word.as_ref_word(),
+                char_span: self.char_span,
+                grapheme_span: self.grapheme_span,
+                utf16_span: self.utf16_span,
+                byte_span: self.byte_span,
+                kind: self.kind,
+                case: self.case,
+                token_index: self.token_index,
+                sentence_index: self.sentence_index,
+                has_lowercase_readings: self.has_lowercase_readings,
+                has_space_before: self.has_space_before,
+                space_before: &self.space_before,
+                chunks: self.chunks.clone(),
+                text,
+                tagger,
+            }
+        }
+    }
+}
+
+/// The kind of a token, determined during tokenization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TokenKind {
+    /// Any regular token e. g. a word or an abbreviation.
+    Word,
+    /// Punctuation and other symbols e. g. ",", "..." or "€".
+    Punctuation,
+    /// A URL like "https://example.com". Rules usually should not suggest changes to URLs.
+    Url,
+    /// An email address like "foo@example.com". Rules usually should not suggest changes to email addresses.
+    Email,
+    /// A number, date or ordinal e. g. "3.14", "3.10.2021" or "3.".
+    Number,
+    /// A file path like "/usr/bin/env" or "C:\\Windows". Rules usually should not suggest changes to paths.
+    Path,
+    /// A hashtag like "#nlproc".
+    Hashtag,
+    /// A mention of a user like "@username".
+    Mention,
+    /// Whitespace between tokens. Only created by [tokenize_lossless][crate::tokenizer::Tokenizer::tokenize_lossless].
+    Whitespace,
+    /// The special SENT_START token inserted before every sentence.
+    SentStart,
+}
+
+/// The case of the letters in the text of a token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Case {
+    /// All letters are lowercase e. g. "house".
+    Lower,
+    /// The first letter is uppercase and all other letters are lowercase e. g. "Berlin" or "A".
+    Title,
+    /// There are multiple letters and all of them are uppercase e. g. "NASA" or "EU".
+    Upper,
+    /// Any other combination of uppercase and lowercase letters e. g. "iPhone" or "McDonald".
+    Mixed,
+    /// The text contains no letters with case e. g. "123", "," or "東京".
+    NonAlpha,
+}
+
+impl Case {
+    /// Classifies the case of the text. Characters without case like digits are ignored.
+    pub fn of(text: &str) -> Self {
+        let mut cased = text
+            .chars()
+            .filter(|c| c.is_uppercase() || c.is_lowercase());
+
+        let first_upper = match cased.next() {
+            Some(c) => c.is_uppercase(),
+            None => return Case::NonAlpha,
+        };
+        let (upper, lower) = cased.fold((0, 0), |(upper, lower), c| {
+            if c.is_uppercase() {
+                (upper + 1, lower)
+            } else {
+                (upper, lower + 1)
+            }
+        });
+
+        match (first_upper, upper, lower) {
+            (false, 0, _) => Case::Lower,
+            (true, 0, _) => Case::Title,
+            (true, _, 0) => Case::Upper,
+            _ => Case::Mixed,
+        }
+    }
+}
+
+/// The kind of whitespace before a token. Non-breaking and thin spaces are common in French and German
+/// typography e. g. before ":" or as thousands separator, so they are distinguished from ordinary spaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SpaceKind {
+    /// There is no whitespace before the token.
+    None,
+    /// An ordinary space or tab.
+    Space,
+    /// A non-breaking space (U+00A0) or figure space (U+2007).
+    NoBreak,
+    /// A narrow non-breaking space (U+202F).
+    NarrowNoBreak,
+    /// A thin space (U+2009) or hair space (U+200A).
+    Thin,
+    /// Whitespace containing a line or paragraph break.
+    LineBreak,
+    /// Any other whitespace e. g. an em space (U+2003).
+    Other,
+}
+
+impl SpaceKind {
+    /// Classifies the whitespace before a token. Whitespace containing a line break is a [LineBreak][SpaceKind::LineBreak],
+    /// otherwise the whitespace character directly before the token decides the kind.
+    pub fn of(space: &str) -> Self {
+        if space.chars().any(|c| {
+            matches!(
+                c,
+                '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+            )
+        }) {
+            return SpaceKind::LineBreak;
+        }
+
+        match space.chars().last() {
+            None => SpaceKind::None,
+            Some(' ') | Some('\t') => SpaceKind::Space,
+            Some('\u{A0}') | Some('\u{2007}') => SpaceKind::NoBreak,
+            Some('\u{202F}') => SpaceKind::NarrowNoBreak,
+            Some('\u{2009}') | Some('\u{200A}') => SpaceKind::Thin,
+            Some(_) => SpaceKind::Other,
+        }
+    }
 }
 
 /// A potentially identified word. If it is identified as a known word, many optimizations can be applied.
@@ -96,9 +267,22 @@ impl<'t> AsRef<str> for WordId<'t> {
 }
 
 /// An identified part-of-speech tag. POS tags are treated as a closed set so every POS tag is identified.
-#[derive(Debug, Clone, PartialEq)]
+/// Tags are compared by their ID in the [TagStore][crate::tokenizer::tag::TagStore] of the tagger, so only tags
+/// of the same tagger can be compared.
+#[derive(Debug, Clone)]
 pub struct PosId<'t>(pub(crate) &'t str, pub(crate) u16);
 
+impl PosId<'static> {
+    /// The empty tag every finalized token has.
+    pub const EMPTY: PosId<'static> = PosId("", 0);
+    /// The tag of the special SENT_START token.
+    pub const SENT_START: PosId<'static> = PosId("SENT_START", 1);
+    /// The tag of the last token of a sentence.
+    pub const SENT_END: PosId<'static> = PosId("SENT_END", 2);
+    /// The tag of tokens which have no other tags.
+    pub const UNKNOWN: PosId<'static> = PosId("UNKNOWN", 3);
+}
+
 impl<'t> PosId<'t> {
     pub fn to_owned_id(&self) -> owned::PosId {
         owned::PosId(self.0.to_string(), self.1)
@@ -107,6 +291,23 @@ impl<'t> PosId<'t> {
     pub fn id(&self) -> &u16 {
         &self.1
     }
+
+    /// The tag as string.
+    pub fn as_str(&self) -> &'t str {
+        self.0
+    }
+
+    /// Whether this is one of the special tags added by the tokenizer (the empty tag, SENT_START, SENT_END
+    /// and UNKNOWN).
+    pub fn is_special(&self) -> bool {
+        self.1 <= PosId::UNKNOWN.1
+    }
+}
+
+impl<'t> PartialEq for PosId<'t> {
+    fn eq(&self, other: &Self) -> bool {
+        self.1 == other.1
+    }
 }
 
 impl<'t> AsRef<str> for PosId<'t> {
@@ -120,17 +321,25 @@ impl<'t> AsRef<str> for PosId<'t> {
 pub struct WordData<'t> {
     pub lemma: WordId<'t>,
     pub pos: PosId<'t>,
+    /// Whether the reading is from a word which only differs in diacritics, found by the fallback lookup of
+    /// [Tagger::with_folding]. Rules which need exact matches can ignore these readings.
+    pub approximate: bool,
 }
 
 impl<'t> WordData<'t> {
     pub fn new(lemma: WordId<'t>, pos: PosId<'t>) -> Self {
-        WordData { lemma, pos }
+        WordData {
+            lemma,
+            pos,
+            approximate: false,
+        }
     }
 
     pub fn to_owned_word_data(&self) -> owned::WordData {
         owned::WordData {
             lemma: self.lemma.to_owned_id(),
             pos: self.pos.to_owned_id(),
+            approximate: self.approximate,
         }
     }
 }
@@ -163,8 +372,23 @@ pub struct IncompleteToken<'t> {
     pub word: Word<'t>,
     pub byte_span: (usize, usize),
     pub char_span: (usize, usize),
+    /// The span in extended grapheme clusters i. e. user-perceived characters. An emoji sequence like "👩‍👩‍👦"
+    /// or a letter with combining marks counts as one grapheme but as multiple chars.
+    pub grapheme_span: (usize, usize),
+    /// The span in UTF-16 code units, as used by e. g. JavaScript and the Language Server Protocol.
+    pub utf16_span: (usize, usize),
+    pub kind: TokenKind,
+    /// The case of the token text, see [Case::of].
+    pub case: Case,
+    pub is_sentence_start: bool,
     pub is_sentence_end: bool,
+    /// Whether the tags include readings of the lowercase variant of the token text which were added because
+    /// the token starts a sentence, e. g. the verb reading of "Run" in "Run the tests.".
+    pub has_lowercase_readings: bool,
+    /// Whether there is whitespace before this token. Also true for the first token if the text starts with whitespace.
     pub has_space_before: bool,
+    /// The whitespace between this token and the previous one (or the start of the text) e. g. " ", "\t" or "\n\n".
+    pub space_before: &'t str,
     pub chunks: Vec<String>,
     pub text: &'t str,
     #[derivative(PartialEq = "ignore", Debug = "ignore")]
@@ -177,8 +401,27 @@ pub struct IncompleteToken<'t> {
 pub struct Token<'t> {
     pub word: Word<'t>,
     pub char_span: (usize, usize),
+    /// The span in extended grapheme clusters i. e. user-perceived characters.
+    pub grapheme_span: (usize, usize),
+    /// The span in UTF-16 code units.
+    pub utf16_span: (usize, usize),
     pub byte_span: (usize, usize),
+    pub kind: TokenKind,
+    /// The case of the token text, see [Case::of].
+    pub case: Case,
+    /// The index of this token in its sentence. The SENT_START token has index 0, the first token of the sentence
+    /// index 1. Whitespace tokens are not counted and have the index of the previous token, so the indices are the
+    /// same whether or not whitespace is kept as tokens.
+    pub token_index: usize,
+    /// The index of the sentence of this token in the text.
+    pub sentence_index: usize,
+    /// Whether the tags include readings of the lowercase variant of the token text which were added because
+    /// the token starts a sentence.
+    pub has_lowercase_readings: bool,
+    /// Whether there is whitespace before this token. Also true for the first token if the text starts with whitespace.
     pub has_space_before: bool,
+    /// The whitespace between this token and the previous one (or the start of the text).
+    pub space_before: &'t str,
     pub chunks: Vec<String>,
     pub text: &'t str,
     #[derivative(Debug = "ignore")]
@@ -191,28 +434,116 @@ impl<'t> Token<'t> {
         Token {
             word: Word::new_with_tags(
                 tagger.id_word("".into()),
-                vec![WordData::new(
-                    tagger.id_word("".into()),
-                    tagger.id_tag("SENT_START"),
-                )]
-                .into_iter()
-                .collect(),
+                vec![WordData::new(tagger.id_word("".into()), PosId::SENT_START)]
+                    .into_iter()
+                    .collect(),
             ),
             char_span: (0, 0),
+            grapheme_span: (0, 0),
+            utf16_span: (0, 0),
             byte_span: (0, 0),
+            kind: TokenKind::SentStart,
+            case: Case::NonAlpha,
+            token_index: 0,
+            sentence_index: 0,
+            has_lowercase_readings: false,
             has_space_before: false,
+            space_before: "",
             chunks: Vec::new(),
             text,
             tagger,
         }
     }
 
+    /// Whether this is the special SENT_START token inserted before every sentence.
+    pub fn is_sentence_start(&self) -> bool {
+        self.kind == TokenKind::SentStart
+    }
+
+    /// The text of this token as it appears in the input. Can differ from the text of the [Word] if the tokenizer
+    /// normalizes e. g. quotes or Unicode composition.
+    pub fn original_text(&self) -> &'t str {
+        &self.text[self.byte_span.0..self.byte_span.1]
+    }
+
+    /// The text of this token in lowercase according to the [Casing][crate::tokenizer::tag::Casing] of the tagger,
+    /// so e. g. Turkish "ISPARTA" becomes "ısparta".
+    pub fn lower(&self) -> String {
+        self.tagger.casing().lowercase(self.word.text.as_ref())
+    }
+
+    /// The frequency of the token text according to the [Tagger::frequency], if frequencies are loaded.
+    pub fn frequency(&self) -> Option<u32> {
+        self.tagger.frequency(self.word.text.as_ref())
+    }
+
+    /// The kind of the whitespace before this token, see [SpaceKind::of].
+    pub fn space_kind(&self) -> SpaceKind {
+        SpaceKind::of(self.space_before)
+    }
+
+    /// The descriptions of the distinct tags of this token according to the [tagset][crate::tokenizer::tag::TagSet]
+    /// of the tagger, joined by "; " for display. Tags without a description are included as they are, the special
+    /// tags (e. g. SENT_END) are ignored.
+    pub fn describe_tags(&self) -> String {
+        let mut descriptions = Vec::new();
+
+        for data in &self.word.tags {
+            if data.pos.is_special() {
+                continue;
+            }
+
+            let description = self
+                .tagger
+                .describe(data.pos.as_str())
+                .unwrap_or_else(|| data.pos.as_str());
+            if !descriptions.contains(&description) {
+                descriptions.push(description);
+            }
+        }
+
+        descriptions.join("; ")
+    }
+
+    /// The distinct lemmas of the readings of this token in the order of the readings. The special tags added
+    /// when finalizing a token (e. g. UNKNOWN or SENT_END) have the token text as lemma and are ignored,
+    /// so the token text is only returned if the token has no other readings.
+    pub fn lemmas(&self) -> Vec<&str> {
+        let mut lemmas = Vec::new();
+
+        for data in &self.word.tags {
+            if data.pos.is_special() {
+                continue;
+            }
+
+            let lemma = data.lemma.as_ref();
+            if !lemmas.contains(&lemma) {
+                lemmas.push(lemma);
+            }
+        }
+
+        if lemmas.is_empty() {
+            lemmas.push(self.word.text.as_ref());
+        }
+
+        lemmas
+    }
+
     pub fn to_owned_token(&self) -> owned::Token {
         owned::Token {
             word: self.word.to_owned_word(),
             char_span: self.char_span,
+            grapheme_span: self.grapheme_span,
+            utf16_span: self.utf16_span,
             byte_span: self.byte_span,
+            kind: self.kind,
+            case: self.case,
+            token_index: self.token_index,
+            sentence_index: self.sentence_index,
+            has_lowercase_readings: self.has_lowercase_readings,
+            frequency: self.frequency(),
             has_space_before: self.has_space_before,
+            space_before: self.space_before.to_string(),
             chunks: self.chunks.clone(),
         }
     }
@@ -220,32 +551,34 @@ impl<'t> Token<'t> {
 
 impl<'t> From<IncompleteToken<'t>> for Token<'t> {
     fn from(data: IncompleteToken<'t>) -> Self {
-        let mut word = data.word.clone();
-
-        word.tags.push(WordData::new(
-            data.word.text.clone(),
-            data.tagger.id_tag(""),
-        ));
-
-        if word.tags.iter().all(|x| x.pos.0.is_empty()) {
-            word.tags.push(WordData::new(
-                data.word.text.clone(),
-                data.tagger.id_tag("UNKNOWN"),
-            ));
+        let mut word = data.word;
+
+        word.tags
+            .push(WordData::new(word.text.clone(), PosId::EMPTY));
+
+        if word.tags.iter().all(|x| x.pos == PosId::EMPTY) {
+            word.tags
+                .push(WordData::new(word.text.clone(), PosId::UNKNOWN));
         }
 
         if data.is_sentence_end {
-            word.tags.push(WordData::new(
-                data.word.text,
-                data.tagger.id_tag("SENT_END"),
-            ));
+            word.tags
+                .push(WordData::new(word.text.clone(), PosId::SENT_END));
         }
 
         Token {
             word,
             byte_span: data.byte_span,
             char_span: data.char_span,
+            grapheme_span: data.grapheme_span,
+            utf16_span: data.utf16_span,
+            kind: data.kind,
+            case: data.case,
+            token_index: 0,
+            sentence_index: 0,
+            has_lowercase_readings: data.has_lowercase_readings,
             has_space_before: data.has_space_before,
+            space_before: data.space_before,
             chunks: data.chunks,
             text: data.text,
             tagger: data.tagger,
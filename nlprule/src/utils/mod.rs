@@ -1,6 +1,7 @@
 use lazy_static::lazy_static;
 use onig::{Captures, Regex};
 
+#[cfg(feature = "rayon")]
 pub mod parallelism;
 pub mod regex;
 
@@ -26,6 +27,36 @@ pub fn is_uppercase(string: &str) -> bool {
     !string.chars().any(|x| x.is_lowercase())
 }
 
+/// The Levenshtein distance between the chars of `a` and `b` if it is at most `max_distance`.
+pub fn edit_distance(a: &str, b: &str, max_distance: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    if a.len().abs_diff(b.len()) > max_distance {
+        return None;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, x) in a.iter().enumerate() {
+        current[0] = i + 1;
+
+        for (j, y) in b.iter().enumerate() {
+            let substitution = prev[j] + if x == y { 0 } else { 1 };
+            current[j + 1] = substitution.min(prev[j + 1] + 1).min(current[j] + 1);
+        }
+
+        // the distance can not decrease in later rows
+        if current.iter().all(|x| *x > max_distance) {
+            return None;
+        }
+        std::mem::swap(&mut prev, &mut current);
+    }
+
+    Some(prev[b.len()]).filter(|x| *x <= max_distance)
+}
+
 // see https://github.com/rust-onig/rust-onig/issues/59#issuecomment-340160520
 pub fn dollar_replace(mut replacement: String, caps: &Captures) -> String {
     for i in 1..caps.len() {
@@ -1,27 +1,14 @@
 //!
 //! This module defines helpers to allow optional Rayon usage.
-//! Copied from huggingface/tokenizers v0.1.1. Only change is "TOKENIZERS_PARALLELISM" to "NLPRULE_PARALLELISM"
+//! Copied from huggingface/tokenizers v0.1.1. Changes are "TOKENIZERS_PARALLELISM" to "NLPRULE_PARALLELISM"
+//! and the removal of the helpers nlprule does not use.
 //!
 
-use rayon::iter::IterBridge;
 use rayon::prelude::*;
 use rayon_cond::CondIterator;
 
 pub const ENV_VARIABLE: &str = "NLPRULE_PARALLELISM";
 
-// Reading/Writing this variable should always happen on the main thread
-static mut USED_PARALLELISM: bool = false;
-
-/// Check if the NLPRULE_PARALLELISM env variable has been explicitly set
-pub fn _is_parallelism_configured() -> bool {
-    std::env::var(ENV_VARIABLE).is_ok()
-}
-
-/// Check if at some point we used a parallel iterator
-pub fn _has_parallelism_been_used() -> bool {
-    unsafe { USED_PARALLELISM }
-}
-
 /// Get the currently set value for `NLPRULE_PARALLELISM` env variable
 pub fn get_parallelism() -> bool {
     match std::env::var(ENV_VARIABLE) {
@@ -33,11 +20,6 @@ pub fn get_parallelism() -> bool {
     }
 }
 
-/// Set the value for `NLPRULE_PARALLELISM` for the current process
-pub fn _set_parallelism(val: bool) {
-    std::env::set_var(ENV_VARIABLE, if val { "true" } else { "false" })
-}
-
 /// Allows to convert into an iterator that can be executed either parallelly or serially.
 ///
 /// The choice is made according to the currently set `NLPRULE_PARALLELISM` environment variable.
@@ -53,10 +35,6 @@ where
     /// Convert ourself in a CondIterator, that will be executed either in parallel or serially,
     /// based solely on the `NLPRULE_PARALLELISM` environment variable
     fn into_maybe_par_iter(self) -> CondIterator<P, S>;
-    /// Convert ourself in a CondIterator, that will be executed either in parallel or serially,
-    /// based on both the `NLPRULE_PARALLELISM` environment variable and the provided bool.
-    /// Both must be true to run with parallelism activated.
-    fn into_maybe_par_iter_cond(self, cond: bool) -> CondIterator<P, S>;
 }
 
 impl<P, S, I> MaybeParallelIterator<P, S> for I
@@ -66,19 +44,7 @@ where
     S: Iterator<Item = P::Item>,
 {
     fn into_maybe_par_iter(self) -> CondIterator<P, S> {
-        let parallelism = get_parallelism();
-        if parallelism {
-            unsafe { USED_PARALLELISM = true };
-        }
-        CondIterator::new(self, parallelism)
-    }
-
-    fn into_maybe_par_iter_cond(self, cond: bool) -> CondIterator<P, S> {
-        if cond {
-            self.into_maybe_par_iter()
-        } else {
-            CondIterator::from_serial(self)
-        }
+        CondIterator::new(self, get_parallelism())
     }
 }
 
@@ -91,7 +57,6 @@ where
     P::Item: 'data,
 {
     fn maybe_par_iter(&'data self) -> CondIterator<P, S>;
-    fn maybe_par_iter_cond(&'data self, cond: bool) -> CondIterator<P, S>;
 }
 
 impl<'data, P, S, I: 'data + ?Sized> MaybeParallelRefIterator<'data, P, S> for I
@@ -104,73 +69,6 @@ where
     fn maybe_par_iter(&'data self) -> CondIterator<P, S> {
         self.into_maybe_par_iter()
     }
-
-    fn maybe_par_iter_cond(&'data self, cond: bool) -> CondIterator<P, S> {
-        self.into_maybe_par_iter_cond(cond)
-    }
-}
-
-/// Exclusive reference version of MaybeParallelIterator, works the same but returns an iterator
-/// over mutable references, does not consume self
-pub trait MaybeParallelRefMutIterator<'data, P, S>
-where
-    P: ParallelIterator,
-    S: Iterator<Item = P::Item>,
-    P::Item: 'data,
-{
-    fn maybe_par_iter_mut(&'data mut self) -> CondIterator<P, S>;
-    fn maybe_par_iter_mut_cond(&'data mut self, cond: bool) -> CondIterator<P, S>;
-}
-
-impl<'data, P, S, I: 'data + ?Sized> MaybeParallelRefMutIterator<'data, P, S> for I
-where
-    &'data mut I: MaybeParallelIterator<P, S>,
-    P: ParallelIterator,
-    S: Iterator<Item = P::Item>,
-    P::Item: 'data,
-{
-    fn maybe_par_iter_mut(&'data mut self) -> CondIterator<P, S> {
-        self.into_maybe_par_iter()
-    }
-
-    fn maybe_par_iter_mut_cond(&'data mut self, cond: bool) -> CondIterator<P, S> {
-        self.into_maybe_par_iter_cond(cond)
-    }
-}
-
-/// Converts any serial iterator into a CondIterator, that can either run parallelly or serially.
-pub trait MaybeParallelBridge<T, S>
-where
-    S: Iterator<Item = T> + Send,
-    T: Send,
-{
-    fn maybe_par_bridge(self) -> CondIterator<IterBridge<S>, S>;
-    fn maybe_par_bridge_cond(self, cond: bool) -> CondIterator<IterBridge<S>, S>;
-}
-
-impl<T, S> MaybeParallelBridge<T, S> for S
-where
-    S: Iterator<Item = T> + Send,
-    T: Send,
-{
-    fn maybe_par_bridge(self) -> CondIterator<IterBridge<S>, S> {
-        let iter = CondIterator::from_serial(self);
-
-        if get_parallelism() {
-            unsafe { USED_PARALLELISM = true };
-            CondIterator::from_parallel(iter.into_parallel().right().unwrap())
-        } else {
-            iter
-        }
-    }
-
-    fn maybe_par_bridge_cond(self, cond: bool) -> CondIterator<IterBridge<S>, S> {
-        if cond {
-            self.maybe_par_bridge()
-        } else {
-            CondIterator::from_serial(self)
-        }
-    }
 }
 
 #[cfg(test)]
@@ -179,19 +77,10 @@ mod tests {
 
     #[test]
     fn test_maybe_parallel_iterator() {
-        let mut v = vec![1u32, 2, 3, 4, 5, 6];
+        let v = vec![1u32, 2, 3, 4, 5, 6];
 
         assert_eq!(v.maybe_par_iter().sum::<u32>(), 21);
-        assert_eq!(
-            v.maybe_par_iter_mut()
-                .map(|v| {
-                    *v *= 2;
-                    *v
-                })
-                .sum::<u32>(),
-            42
-        );
-        assert_eq!(v.maybe_par_iter().sum::<u32>(), 42);
-        assert_eq!(v.into_maybe_par_iter().sum::<u32>(), 42);
+        assert_eq!(v.maybe_par_iter().map(|v| v * 2).sum::<u32>(), 42);
+        assert_eq!(v.into_maybe_par_iter().sum::<u32>(), 21);
     }
 }
@@ -97,7 +97,7 @@ impl SerializeRegex {
 
         Ok(SerializeRegex {
             regex: SerializeRegex::compile(&fixed, case_sensitive)
-                .map_err(|x| Error::Unexpected(format!("{}", x)))?,
+                .map_err(|x| Error::Unexpected(format!("invalid regex {:?}: {}", regex_str, x)))?,
             regex_str: fixed,
             case_sensitive,
         })
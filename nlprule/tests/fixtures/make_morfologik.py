@@ -0,0 +1,155 @@
+"""Writes the morfologik test dictionaries from `morfologik_dump.txt`.
+
+The automata are unminimized tries, which are valid FSA5 / CFSA2 automata. FSA5 uses the suffix encoder
+and CFSA2 the infix encoder for the lemmas.
+"""
+
+from pathlib import Path
+
+HERE = Path(__file__).parent
+SEPARATOR = b"+"
+REMOVE_EVERYTHING = 255
+
+
+def common_prefix(a, b):
+    n = 0
+    while n < min(len(a), len(b)) and a[n] == b[n]:
+        n += 1
+    return n
+
+
+def encode_suffix(word, lemma):
+    shared = common_prefix(word, lemma)
+    remove = len(word) - shared
+    if remove >= REMOVE_EVERYTHING:
+        remove, shared = REMOVE_EVERYTHING, 0
+    return bytes([(remove + ord("A")) & 0xFF]) + lemma[shared:]
+
+
+def encode_infix(word, lemma):
+    best = None
+    for index in range(len(word) + 1):
+        for length in range(len(word) - index + 1):
+            for suffix in range(len(word) - index - length + 1):
+                kept = word[:index] + word[index + length : len(word) - suffix]
+                if lemma.startswith(kept):
+                    encoded = bytes(
+                        [x + ord("A") for x in (index, length, suffix)]
+                    ) + lemma[len(kept) :]
+                    if best is None or len(encoded) < len(best):
+                        best = encoded
+    return best
+
+
+def sequences(encode):
+    entries = []
+    for line in (HERE / "morfologik_dump.txt").read_text().splitlines():
+        word, lemma, tag = (x.encode("utf-8") for x in line.split("\t"))
+        entries.append(word + SEPARATOR + encode(word, lemma) + SEPARATOR + tag)
+    return sorted(set(entries))
+
+
+def trie(seqs):
+    """Returns the nodes of the trie, each node is a list of (label, final, child) arcs."""
+    nodes = [{}]
+    finals = [set()]
+    for seq in seqs:
+        node = 0
+        for i, label in enumerate(seq):
+            if label not in nodes[node]:
+                nodes.append({})
+                finals.append(set())
+                nodes[node][label] = len(nodes) - 1
+            if i == len(seq) - 1:
+                finals[node].add(label)
+            node = nodes[node][label]
+    return [
+        [(label, label in finals[i], child) for label, child in sorted(node.items())]
+        for i, node in enumerate(nodes)
+    ]
+
+
+def fsa5(seqs, goto_length=3):
+    nodes = trie(seqs)
+    arc_size = 1 + goto_length
+
+    # the dummy node and the epsilon node take one arc each, then the nodes follow in order
+    offsets = {}
+    offset = 2 * arc_size
+    for i, node in enumerate(nodes):
+        offsets[i] = offset
+        offset += len(node) * arc_size
+
+    def arc(label, final, last, target):
+        flags = (1 if final else 0) | (2 if last else 0)
+        return bytes([label]) + ((target << 3) | flags).to_bytes(goto_length, "little")
+
+    data = arc(0, False, True, 0) + arc(0, False, True, offsets[0])
+    for node in nodes:
+        for i, (label, final, child) in enumerate(node):
+            target = offsets[child] if nodes[child] else 0
+            data += arc(label, final, i == len(node) - 1, target)
+
+    header = b"\\fsa" + bytes([5, ord("_"), SEPARATOR[0], goto_length])
+    return header + data
+
+
+def vint(value):
+    out = b""
+    while True:
+        byte = value & 0x7F
+        value >>= 7
+        if value:
+            out += bytes([byte | 0x80])
+        else:
+            return out + bytes([byte])
+
+
+def cfsa2(seqs):
+    nodes = trie(seqs)
+    # labels in the mapping are stored as an index in the flags byte, index zero means an explicit label
+    mapping = [0] + list(b"eus+")
+
+    def arc(label, final, last, target):
+        index = mapping.index(label) if label in mapping[1:] else 0
+        flags = (0x40 if last else 0) | (0x20 if final else 0) | index
+        return bytes([flags]) + (bytes([label]) if index == 0 else b"") + vint(target)
+
+    # the nodes are written children first so the targets are known, the root arc at offset zero points
+    # to the root which is written last. Its size depends on the address, so the layout is repeated until stable.
+    order = []
+
+    def visit(i):
+        for _, _, child in nodes[i]:
+            visit(child)
+        order.append(i)
+
+    visit(0)
+
+    root_size = 1
+    while True:
+        offsets = {}
+        data = b""
+        for i in order:
+            offsets[i] = root_size + len(data)
+            for j, (label, final, child) in enumerate(nodes[i]):
+                target = offsets[child] if nodes[child] else 0
+                data += arc(label, final, j == len(nodes[i]) - 1, target)
+        root = arc(0, False, True, offsets[0])
+        if len(root) == root_size:
+            break
+        root_size = len(root)
+
+    flags = 0x1 | 0x2 | 0x4  # FLEXIBLE, STOPBIT, NEXTBIT
+    header = b"\\fsa" + bytes([0xC6]) + flags.to_bytes(2, "big") + bytes([len(mapping)])
+    return header + bytes(mapping) + root + data
+
+
+def info(encoder):
+    return f"fsa.dict.separator=+\nfsa.dict.encoding=utf-8\nfsa.dict.encoder={encoder}\n"
+
+
+(HERE / "morfologik_fsa5.dict").write_bytes(fsa5(sequences(encode_suffix)))
+(HERE / "morfologik_fsa5.info").write_text(info("SUFFIX"))
+(HERE / "morfologik_cfsa2.dict").write_bytes(cfsa2(sequences(encode_infix)))
+(HERE / "morfologik_cfsa2.info").write_text(info("INFIX"))